@@ -0,0 +1,256 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use bindgen::callbacks::{EnumVariantValue, ParseCallbacks};
+use regex::Regex;
+
+/// Bindgen callbacks collecting the variants of the C enums seen while generating the bindings.
+///
+/// They must be registered with [`bindgen::Builder::parse_callbacks`], and are paired with the
+/// [`EnumValuesGenerator`] returned by [`EnumValuesCallbacks::new()`].
+///
+/// Safe wrappers usually mirror C enums with Rust enums, converted back from the raw values with `TryFrom`.
+/// The generator writes the list of the values of every C enum as a constant of the bindings (e.g. `CFG_Region_VALUES`
+/// for the `CFG_Region` enum), so that the wrappers can check at compile time that they handle all of them,
+/// and stay in sync when new variants are added to the headers.
+///
+/// # Example
+///
+/// In the build script of a `-sys` crate:
+///
+/// ```no_run
+/// use binding_helpers::EnumValuesCallbacks;
+///
+/// let (callbacks, generator) = EnumValuesCallbacks::new();
+///
+/// bindgen::Builder::default()
+///     .header("wrapper.h")
+///     .prepend_enum_name(false)
+///     .parse_callbacks(Box::new(callbacks))
+///     .generate()
+///     .expect("unable to generate bindings")
+///     .write_to_file("bindings.rs")
+///     .expect("unable to write bindings");
+///
+/// generator
+///     .allowlist_enum("CFG_.*")
+///     .generate_values("enum_values.rs")
+///     .expect("unable to generate enum values");
+/// ```
+///
+/// The generated file is then included next to the bindings.
+#[derive(Debug)]
+pub struct EnumValuesCallbacks(Rc<EnumValuesGenerator>);
+
+impl EnumValuesCallbacks {
+    /// Creates the callbacks, and the generator which will use the variants they collect.
+    pub fn new() -> (Self, Rc<EnumValuesGenerator>) {
+        let generator = Rc::new(EnumValuesGenerator::new());
+        (Self(Rc::clone(&generator)), generator)
+    }
+}
+
+impl ParseCallbacks for EnumValuesCallbacks {
+    fn enum_variant_name(
+        &self,
+        enum_name: Option<&str>,
+        original_variant_name: &str,
+        _variant_value: EnumVariantValue,
+    ) -> Option<String> {
+        let mut variants = self.0.variants.borrow_mut();
+        if !variants
+            .iter()
+            .any(|(_, variant)| variant == original_variant_name)
+        {
+            // Anonymous enums are named after their location (e.g. `(unnamed enum at result.h:12:1)`),
+            // so they can only be part of constant tables.
+            let enum_name = enum_name.map(|name| name.strip_prefix("enum ").unwrap_or(name));
+            variants.push((
+                enum_name.map(str::to_string),
+                original_variant_name.to_string(),
+            ));
+        }
+
+        None
+    }
+}
+
+/// Generator of the lists of values of C enums, configured like a builder.
+///
+/// The enums must be generated as constants (bindgen's default), and only the allowlisted enums are written.
+#[derive(Debug)]
+pub struct EnumValuesGenerator {
+    allowlist: RefCell<Vec<Regex>>,
+    tables: RefCell<Vec<(String, String)>>,
+    variants: RefCell<Vec<(Option<String>, String)>>,
+    prepend_enum_name: RefCell<bool>,
+}
+
+impl EnumValuesGenerator {
+    fn new() -> Self {
+        Self {
+            allowlist: RefCell::default(),
+            tables: RefCell::default(),
+            variants: RefCell::default(),
+            prepend_enum_name: RefCell::new(false),
+        }
+    }
+
+    /// Writes the values of the enums whose name fully matches the regex `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `pattern` isn't a valid regex.
+    pub fn allowlist_enum(&self, pattern: &str) -> &Self {
+        self.allowlist
+            .borrow_mut()
+            .push(Regex::new(&format!("^({pattern})$")).unwrap());
+        self
+    }
+
+    /// Writes a table named `table_name` of the names and values of all the enum constants starting with `prefix`,
+    /// including the ones of anonymous enums.
+    ///
+    /// The table is a `&[(&str, u32)]`, in the order of the headers, and the names are in lowercase without the prefix
+    /// (e.g. `("out_of_range", RD_OUT_OF_RANGE as u32)` for the prefix `RD_`).
+    /// The constants are expected to be named as in the headers, i.e. with [`bindgen::Builder::prepend_enum_name`] disabled.
+    pub fn constant_table(&self, prefix: &str, table_name: &str) -> &Self {
+        self.tables
+            .borrow_mut()
+            .push((prefix.to_string(), table_name.to_string()));
+        self
+    }
+
+    /// Sets whether the constants of the variants are prefixed with the name of their enum,
+    /// which must match [`bindgen::Builder::prepend_enum_name`]. Defaults to `false`.
+    pub fn prepend_enum_name(&self, prepend: bool) -> &Self {
+        *self.prepend_enum_name.borrow_mut() = prepend;
+        self
+    }
+
+    /// Writes the lists of values to `output_path`.
+    ///
+    /// This must be called after the bindings were generated, so that the callbacks saw all the enums.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be written.
+    pub fn generate_values(&self, output_path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(output_path, self.build_values())?;
+
+        Ok(())
+    }
+
+    fn build_values(&self) -> String {
+        let allowlist = self.allowlist.borrow();
+        let prepend_enum_name = *self.prepend_enum_name.borrow();
+        let variants = self.variants.borrow();
+        let mut output = String::new();
+
+        let mut enums: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (enum_name, variant) in variants.iter() {
+            if let Some(enum_name) = enum_name {
+                enums.entry(enum_name).or_default().push(variant);
+            }
+        }
+
+        // Writing to a `String` can't fail.
+        for (enum_name, variants) in enums {
+            if !allowlist.iter().any(|pattern| pattern.is_match(enum_name)) {
+                continue;
+            }
+
+            let values = variants
+                .iter()
+                .map(|variant| {
+                    if prepend_enum_name {
+                        format!("{enum_name}_{variant}")
+                    } else {
+                        variant.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let _ = writeln!(output, "/// Values of the `{enum_name}` enum.");
+            let _ = writeln!(
+                output,
+                "pub const {enum_name}_VALUES: &[{enum_name}] = &[{values}];"
+            );
+        }
+
+        for (prefix, table_name) in self.tables.borrow().iter() {
+            let entries = variants
+                .iter()
+                .filter_map(|(_, variant)| {
+                    let name = variant.strip_prefix(prefix.as_str())?;
+                    Some(format!(
+                        "(\"{}\", {variant} as u32)",
+                        name.to_ascii_lowercase()
+                    ))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let _ = writeln!(output, "/// Names and values of the `{prefix}*` constants.");
+            let _ = writeln!(
+                output,
+                "pub const {table_name}: &[(&str, u32)] = &[{entries}];"
+            );
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enum_values() {
+        let (callbacks, generator) = EnumValuesCallbacks::new();
+
+        for variant in ["CFG_REGION_JPN", "CFG_REGION_USA", "CFG_REGION_JPN"] {
+            callbacks.enum_variant_name(Some("CFG_Region"), variant, EnumVariantValue::Unsigned(0));
+        }
+        callbacks.enum_variant_name(
+            Some("enum GSPGPU_Event"),
+            "GSPGPU_EVENT_PSC0",
+            EnumVariantValue::Unsigned(0),
+        );
+        for variant in ["RD_SUCCESS", "RD_OUT_OF_RANGE", "RL_SUCCESS"] {
+            callbacks.enum_variant_name(
+                Some("(unnamed enum at result.h:12:1)"),
+                variant,
+                EnumVariantValue::Unsigned(0),
+            );
+        }
+
+        generator.allowlist_enum("CFG_Region");
+        assert_eq!(
+            generator.build_values(),
+            "/// Values of the `CFG_Region` enum.\n\
+             pub const CFG_Region_VALUES: &[CFG_Region] = &[CFG_REGION_JPN, CFG_REGION_USA];\n"
+        );
+
+        generator.constant_table("RD_", "RESULT_DESCRIPTIONS");
+        assert!(generator.build_values().ends_with(
+            "/// Names and values of the `RD_*` constants.\n\
+             pub const RESULT_DESCRIPTIONS: &[(&str, u32)] = \
+             &[(\"success\", RD_SUCCESS as u32), (\"out_of_range\", RD_OUT_OF_RANGE as u32)];\n"
+        ));
+
+        generator
+            .allowlist_enum("GSPGPU_.*")
+            .prepend_enum_name(true);
+        assert!(generator.build_values().contains(
+            "pub const GSPGPU_Event_VALUES: &[GSPGPU_Event] = &[GSPGPU_Event_GSPGPU_EVENT_PSC0];"
+        ));
+    }
+}
@@ -0,0 +1,533 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+use bindgen::callbacks::{
+    DeriveInfo, DeriveTrait, FieldInfo, ImplementsTrait, ParseCallbacks, TypeKind,
+};
+use bindgen::FieldVisibilityKind;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, TokenStreamExt};
+use regex::Regex;
+use rust_format::{Formatter, RustFmt};
+
+// Names given by bindgen to anonymous nested structs/unions (`{parent}__bindgen_ty_{n}`) and to the fields storing them.
+const ANONYMOUS_TYPE_SEPARATOR: &str = "__bindgen_ty_";
+const ANONYMOUS_FIELD_PREFIX: &str = "__bindgen_anon_";
+
+/// How the generated tests report layout mismatches.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AssertionStyle {
+    /// Every size, alignment and offset is checked with `assert_eq!`, so each test stops at the first mismatch of its type.
+    #[default]
+    AssertEq,
+    /// All the mismatches of a type are collected, and its test fails once with the full list.
+    CollectMismatches,
+}
+
+/// Language of the headers the bindings were generated from.
+///
+/// The tests are always compiled as C++, but C headers which aren't guarded by `extern "C"`
+/// must be included accordingly.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HeaderLanguage {
+    /// C headers, which are wrapped in an `extern "C"` block.
+    C,
+    /// C++ headers, or C headers guarding themselves, which are included as-is.
+    #[default]
+    Cpp,
+}
+
+/// Bindgen callbacks collecting the types, fields and headers seen while generating the bindings.
+///
+/// They must be registered with [`bindgen::Builder::parse_callbacks`], and are paired with the
+/// [`LayoutTestGenerator`] returned by [`LayoutTestCallbacks::new()`].
+///
+/// The layout tests generated by bindgen use the sizes and offsets computed by libclang at
+/// generation time, which may not match the ABI used by the real toolchain (e.g. devkitARM).
+/// Instead, the generated tests compile helper functions with the [`cpp`](https://docs.rs/cpp)
+/// crate, returning the real `sizeof`/`alignof`/`offsetof` of every type and field, and compare them to the
+/// ones of the Rust bindings.
+///
+/// # Example
+///
+/// In the build script of a `-sys` crate, register a [`LayoutTestCallbacks`] with the bindgen builder,
+/// then configure the paired [`LayoutTestGenerator`] and write the tests once the bindings are generated:
+///
+/// ```no_run
+/// use binding_helpers::{AssertionStyle, HeaderLanguage, LayoutTestCallbacks};
+///
+/// let (callbacks, generator) = LayoutTestCallbacks::new();
+///
+/// bindgen::Builder::default()
+///     .header("wrapper.h")
+///     .parse_callbacks(Box::new(callbacks))
+///     .generate()
+///     .expect("unable to generate bindings")
+///     .write_to_file("bindings.rs")
+///     .expect("unable to write bindings");
+///
+/// generator
+///     .language(HeaderLanguage::C)
+///     .assertion_style(AssertionStyle::CollectMismatches)
+///     .prologue("#define __3DS__")
+///     // Bitfields can't be checked:
+///     .blocklist_field("my_struct", "bitfield_.*")
+///     .generate_layout_tests("generated_layout_test.rs")
+///     .expect("unable to generate layout tests");
+/// ```
+///
+/// The generated file must then be compiled with `cpp_build` and included in a test module,
+/// which must have these items in scope:
+///
+/// - the `cpp!` macro of the `cpp` crate, and the bindings being tested;
+/// - `size_of!` and `align_of!` macros accepting a type or a `Type::field` path, where `field` may be
+///   a nested path through anonymous members (e.g. `Type::__bindgen_anon_1.field`, possibly inside a union),
+///   and `offset_of!` (usually `std::mem::offset_of!`, which supports nested fields).
+///
+/// See `ctru-sys/tests/layout_test.rs` for a complete example of such a module.
+#[derive(Debug)]
+pub struct LayoutTestCallbacks(Rc<LayoutTestGenerator>);
+
+impl LayoutTestCallbacks {
+    /// Creates the callbacks, and the generator which will use the information they collect.
+    pub fn new() -> (Self, Rc<LayoutTestGenerator>) {
+        let generator = Rc::new(LayoutTestGenerator::new());
+        (Self(Rc::clone(&generator)), generator)
+    }
+}
+
+impl ParseCallbacks for LayoutTestCallbacks {
+    fn header_file(&self, filename: &str) {
+        self.0.headers.borrow_mut().push(filename.to_string());
+    }
+
+    fn add_derives(&self, info: &DeriveInfo<'_>) -> Vec<String> {
+        if let TypeKind::Union = info.kind {
+            // layout tests don't handle unions for now, just skip it
+            println!(
+                "cargo:warning=Skipping layout tests for union {}",
+                info.name,
+            );
+            self.0.blocklist_type(info.name);
+        }
+
+        Vec::new()
+    }
+
+    fn blocklisted_type_implements_trait(
+        &self,
+        name: &str,
+        _derive_trait: DeriveTrait,
+    ) -> Option<ImplementsTrait> {
+        self.0.blocklist_type(name);
+        None
+    }
+
+    fn field_visibility(&self, info: FieldInfo<'_>) -> Option<FieldVisibilityKind> {
+        self.0
+            .struct_fields
+            .borrow_mut()
+            .entry(info.type_name.to_string())
+            .or_default()
+            .insert(info.field_name.to_string());
+
+        None
+    }
+}
+
+/// Generator of the layout tests, configured like a builder.
+///
+/// Types blocklisted by bindgen and unions are skipped automatically, and the fields of anonymous nested structs/unions
+/// are checked through the parent type, where C and C++ see them. Other types or fields which can't be checked
+/// (opaque types, bitfields, variable-length arrays...) must be skipped with [`blocklist_type()`](Self::blocklist_type)
+/// or [`blocklist_field()`](Self::blocklist_field).
+#[derive(Debug)]
+pub struct LayoutTestGenerator {
+    blocklist: RefCell<Vec<(Regex, Option<Regex>)>>,
+    headers: RefCell<Vec<String>>,
+    renames: RefCell<BTreeMap<String, String>>,
+    struct_fields: RefCell<BTreeMap<String, BTreeSet<String>>>,
+    assertion_style: RefCell<AssertionStyle>,
+    language: RefCell<HeaderLanguage>,
+    prologue: RefCell<Vec<String>>,
+}
+
+impl LayoutTestGenerator {
+    fn new() -> Self {
+        Self {
+            blocklist: RefCell::default(),
+            headers: RefCell::default(),
+            renames: RefCell::default(),
+            struct_fields: RefCell::default(),
+            assertion_style: RefCell::default(),
+            language: RefCell::default(),
+            prologue: RefCell::default(),
+        }
+    }
+
+    /// Skips the types whose name fully matches the regex `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `pattern` isn't a valid regex.
+    pub fn blocklist_type(&self, pattern: &str) -> &Self {
+        self.blocklist
+            .borrow_mut()
+            .push((Regex::new(&format!("^({pattern})$")).unwrap(), None));
+        self
+    }
+
+    /// Skips the fields whose name fully matches the regex `field_pattern`, in the types whose name fully matches `struct_pattern`.
+    ///
+    /// The size and alignment of the types themselves are still checked.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if one of the patterns isn't a valid regex.
+    pub fn blocklist_field(&self, struct_pattern: &str, field_pattern: &str) -> &Self {
+        self.blocklist.borrow_mut().push((
+            Regex::new(&format!("^({struct_pattern})$")).unwrap(),
+            Some(Regex::new(&format!("^({field_pattern})$")).unwrap()),
+        ));
+        self
+    }
+
+    /// Maps a field renamed by bindgen (e.g. `type_`, since `type` is a Rust keyword) to its name in the headers.
+    pub fn rename_field(&self, cpp_name: &str, rust_name: &str) -> &Self {
+        self.renames
+            .borrow_mut()
+            .insert(rust_name.to_string(), cpp_name.to_string());
+        self
+    }
+
+    /// Sets how the tests report mismatches. Defaults to [`AssertionStyle::AssertEq`].
+    pub fn assertion_style(&self, style: AssertionStyle) -> &Self {
+        *self.assertion_style.borrow_mut() = style;
+        self
+    }
+
+    /// Sets the language of the headers. Defaults to [`HeaderLanguage::Cpp`].
+    pub fn language(&self, language: HeaderLanguage) -> &Self {
+        *self.language.borrow_mut() = language;
+        self
+    }
+
+    /// Adds C++ code (e.g. `#define` directives, or additional `#include`s) before the headers of the bindings.
+    ///
+    /// Can be called several times, in which case the code is added in order.
+    pub fn prologue(&self, code: &str) -> &Self {
+        self.prologue.borrow_mut().push(code.to_string());
+        self
+    }
+
+    /// Writes the tests to `output_path`.
+    ///
+    /// This must be called after the bindings were generated, so that the callbacks saw all the types.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be written, or if the tests couldn't be formatted.
+    pub fn generate_layout_tests(
+        &self,
+        output_path: impl AsRef<Path>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut file = File::create(output_path)?;
+
+        // Since quote! tokenizes its input, it would result in invalid C++ for
+        // the `#include` directives (they would be missing whitespace/newlines),
+        // so we basically need to drop in the include headers here "manually" by
+        // writing them into the cpp! macro invocation.
+        file.write_all("cpp! {{\n".as_bytes())?;
+        for code in self.prologue.borrow().iter() {
+            writeln!(file, "    {code}")?;
+        }
+
+        let language = *self.language.borrow();
+        if language == HeaderLanguage::C {
+            writeln!(file, "    extern \"C\" {{")?;
+        }
+        for included_file in self.headers.borrow().iter() {
+            writeln!(file, "    #include \"{included_file}\"")?;
+        }
+        if language == HeaderLanguage::C {
+            writeln!(file, "    }}")?;
+        }
+        file.write_all("}}\n".as_bytes())?;
+
+        let test_tokens = RustFmt::default().format_tokens(self.build_tests())?;
+        file.write_all(test_tokens.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn build_tests(&self) -> TokenStream {
+        let mut output = TokenStream::new();
+
+        for struct_name in self.struct_fields.borrow().keys() {
+            // Anonymous types can't be named in C++, their fields are checked in the tests of their parent instead.
+            if struct_name.contains(ANONYMOUS_TYPE_SEPARATOR) {
+                continue;
+            }
+
+            if self
+                .blocklist
+                .borrow()
+                .iter()
+                .any(|(pat, field)| field.is_none() && pat.is_match(struct_name))
+            {
+                println!("cargo:warning=Skipping layout tests for {struct_name}",);
+                continue;
+            }
+
+            output.append_all(self.build_struct_test(struct_name));
+        }
+
+        output
+    }
+
+    // Collects the fields of `type_name` to check in the test of `struct_name`, with their path in the Rust bindings.
+    //
+    // The fields of anonymous nested structs/unions are members of the parent in C/C++, but bindgen generates them
+    // in separate types (`{parent}__bindgen_ty_{n}`), stored in `__bindgen_anon_{n}` fields of the parent.
+    fn collect_fields(
+        &self,
+        struct_name: &str,
+        type_name: &str,
+        path: &mut Vec<String>,
+        output: &mut Vec<(Vec<String>, String)>,
+    ) {
+        let struct_fields = self.struct_fields.borrow();
+        let Some(fields) = struct_fields.get(type_name) else {
+            return;
+        };
+
+        let anonymous_types = self.anonymous_members(type_name);
+
+        for field in fields {
+            if field.starts_with(ANONYMOUS_FIELD_PREFIX) {
+                match anonymous_types.as_ref().and_then(|types| types.get(field)) {
+                    Some(anonymous_type) => {
+                        path.push(field.clone());
+                        self.collect_fields(struct_name, anonymous_type, path, output);
+                        path.pop();
+                    }
+                    None => println!(
+                        "cargo:warning=Skipping layout tests for anonymous member {type_name}::{field}",
+                    ),
+                }
+                continue;
+            }
+
+            if self
+                .blocklist
+                .borrow()
+                .iter()
+                .any(|(struct_pat, field_pat)| match field_pat {
+                    Some(field_pat) => {
+                        struct_pat.is_match(struct_name) && field_pat.is_match(field)
+                    }
+                    None => false,
+                })
+            {
+                println!("cargo:warning=Skipping layout tests for {struct_name}::{field}",);
+                continue;
+            }
+
+            let mut field_path = path.clone();
+            field_path.push(field.clone());
+            output.push((field_path, field.clone()));
+        }
+    }
+
+    // Maps the anonymous members of a type to the types generated for them.
+    //
+    // Bindgen numbers anonymous members and anonymous types separately, so they can only be paired when every
+    // anonymous type of the parent is used by an anonymous member (and not e.g. by a named field of an anonymous type).
+    fn anonymous_members(&self, type_name: &str) -> Option<BTreeMap<String, String>> {
+        let struct_fields = self.struct_fields.borrow();
+        let type_prefix = format!("{type_name}{ANONYMOUS_TYPE_SEPARATOR}");
+
+        let mut members: Vec<(u32, &String)> = struct_fields
+            .get(type_name)?
+            .iter()
+            .filter_map(|field| Some((anonymous_index(field, ANONYMOUS_FIELD_PREFIX)?, field)))
+            .collect();
+        let mut types: Vec<(u32, &String)> = struct_fields
+            .keys()
+            .filter_map(|name| Some((anonymous_index(name, &type_prefix)?, name)))
+            .collect();
+
+        if members.is_empty() || members.len() != types.len() {
+            return None;
+        }
+
+        members.sort();
+        types.sort();
+
+        Some(
+            members
+                .into_iter()
+                .zip(types)
+                .map(|((_, field), (_, name))| (field.clone(), name.clone()))
+                .collect(),
+        )
+    }
+
+    fn build_struct_test(&self, struct_name: &str) -> proc_macro2::TokenStream {
+        let name = format_ident!("{struct_name}");
+
+        let mut checks = Vec::new();
+        checks.push((quote!(size_of!(#name)), quote!(sizeof(#name))));
+        checks.push((quote!(align_of!(#name)), quote!(alignof(#name))));
+
+        let mut fields = Vec::new();
+        self.collect_fields(struct_name, struct_name, &mut Vec::new(), &mut fields);
+
+        for (path, field) in fields {
+            let rust_path = path.iter().map(|member| format_ident!("{member}"));
+            let rust_field = quote!(#(#rust_path).*);
+            let cpp_field =
+                format_ident!("{}", self.renames.borrow().get(&field).unwrap_or(&field));
+
+            checks.push((
+                quote!(size_of!(#name::#rust_field)),
+                quote!(sizeof(#name::#cpp_field)),
+            ));
+            checks.push((
+                quote!(align_of!(#name::#rust_field)),
+                quote!(alignof(#name::#cpp_field)),
+            ));
+            checks.push((
+                quote!(offset_of!(#name, #rust_field)),
+                quote!(offsetof(#name, #cpp_field)),
+            ));
+        }
+
+        let body = match *self.assertion_style.borrow() {
+            AssertionStyle::AssertEq => {
+                let asserts = checks
+                    .iter()
+                    .map(|(rust_lhs, cpp_rhs)| build_assert_eq(rust_lhs, cpp_rhs));
+
+                quote!(#(#asserts);*)
+            }
+            AssertionStyle::CollectMismatches => {
+                let checks = checks
+                    .iter()
+                    .map(|(rust_lhs, cpp_rhs)| build_check(rust_lhs, cpp_rhs));
+
+                quote! {
+                    let mut mismatches: Vec<String> = Vec::new();
+                    #(#checks)*
+                    assert!(
+                        mismatches.is_empty(),
+                        "layout mismatches:\n{}",
+                        mismatches.join("\n"),
+                    );
+                }
+            }
+        };
+
+        quote! {
+            #[test]
+            fn #name() {
+                #body
+            }
+        }
+    }
+}
+
+// Returns `n` if `name` is `{prefix}{n}`.
+fn anonymous_index(name: &str, prefix: &str) -> Option<u32> {
+    name.strip_prefix(prefix)?.parse().ok()
+}
+
+fn cpp_value(cpp_expr: &TokenStream) -> TokenStream {
+    quote!(cpp!(unsafe [] -> usize as "size_t" { return #cpp_expr; }))
+}
+
+fn build_assert_eq(rust_lhs: &TokenStream, cpp_rhs: &TokenStream) -> TokenStream {
+    let cpp_value = cpp_value(cpp_rhs);
+
+    quote! {
+        assert_eq!(
+            #rust_lhs,
+            #cpp_value,
+            "{} != {}",
+            stringify!(#rust_lhs),
+            stringify!(#cpp_rhs),
+        );
+    }
+}
+
+fn build_check(rust_lhs: &TokenStream, cpp_rhs: &TokenStream) -> TokenStream {
+    let cpp_value = cpp_value(cpp_rhs);
+
+    quote! {
+        let (rust, cpp) = (#rust_lhs, #cpp_value);
+        if rust != cpp {
+            mismatches.push(format!(
+                "{} ({}) != {} ({})",
+                stringify!(#rust_lhs),
+                rust,
+                stringify!(#cpp_rhs),
+                cpp,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_fields(generator: &LayoutTestGenerator, type_name: &str, fields: &[&str]) {
+        generator.struct_fields.borrow_mut().insert(
+            type_name.to_string(),
+            fields.iter().map(|field| field.to_string()).collect(),
+        );
+    }
+
+    #[test]
+    fn anonymous_members() {
+        let (_, generator) = LayoutTestCallbacks::new();
+
+        add_fields(
+            &generator,
+            "Foo",
+            &["a", "__bindgen_anon_1", "__bindgen_anon_2"],
+        );
+        add_fields(&generator, "Foo__bindgen_ty_1", &["b", "c"]);
+        add_fields(&generator, "Foo__bindgen_ty_2", &["__bindgen_anon_1"]);
+        add_fields(&generator, "Foo__bindgen_ty_2__bindgen_ty_1", &["d"]);
+        // A named field with an anonymous type can't be paired with the anonymous members.
+        add_fields(&generator, "Bar", &["__bindgen_anon_1", "named"]);
+        add_fields(&generator, "Bar__bindgen_ty_1", &["e"]);
+        add_fields(&generator, "Bar__bindgen_ty_2", &["f"]);
+
+        generator.blocklist_field("Foo", "c");
+
+        let tests: String = generator
+            .build_tests()
+            .to_string()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+
+        assert!(tests.contains("offset_of!(Foo,a)"));
+        assert!(tests.contains("offset_of!(Foo,__bindgen_anon_1.b)"));
+        assert!(tests.contains("offsetof(Foo,b)"));
+        assert!(tests.contains("size_of!(Foo::__bindgen_anon_2.__bindgen_anon_1.d)"));
+        assert!(tests.contains("sizeof(Foo::d)"));
+        assert!(tests.contains("offset_of!(Bar,named)"));
+
+        assert!(!tests.contains("offsetof(Foo,c)"));
+        assert!(!tests.contains("offsetof(Bar,e)"));
+        assert!(!tests.contains("__bindgen_ty"));
+    }
+}
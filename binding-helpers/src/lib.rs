@@ -0,0 +1,17 @@
+//! Build-time helpers for `-sys` crates generating their bindings with [`bindgen`].
+//!
+//! The helpers are bindgen callbacks, registered with [`bindgen::Builder::parse_callbacks`], paired with a generator
+//! writing additional code from what the callbacks saw once the bindings are generated:
+//!
+//! - [`EnumValuesCallbacks`] lists the values of C enums, so that safe wrappers can check that they handle all of them,
+//!   and writes tables of the names of related constants (e.g. result codes).
+//! - `LayoutTestCallbacks` generates layout tests comparing the bindings to the C types as compiled by the real toolchain.
+//!   It requires the `layout-tests` feature.
+
+mod enums;
+#[cfg(feature = "layout-tests")]
+mod layout;
+
+pub use enums::{EnumValuesCallbacks, EnumValuesGenerator};
+#[cfg(feature = "layout-tests")]
+pub use layout::{AssertionStyle, HeaderLanguage, LayoutTestCallbacks, LayoutTestGenerator};
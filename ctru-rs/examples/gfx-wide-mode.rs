@@ -3,14 +3,16 @@
 //! This example demonstrates the wide-mode capability of the top screen
 //! which doubles the horizontal resolution of the screen by merging the 2 stereoscopic 3D sides.
 //!
-//! Beware, wide-mode doesn't work on Old 2DS consoles.
+//! Beware, wide-mode doesn't work on Old 2DS consoles: on those, the screen stays in the regular mode.
 
 use ctru::prelude::*;
+use ctru::services::cfgu::Cfgu;
 
 fn main() {
     let apt = Apt::new().unwrap();
     let mut hid = Hid::new().unwrap();
     let gfx = Gfx::new().unwrap();
+    let cfgu = Cfgu::new().unwrap();
     let mut console = Console::new(gfx.top_screen.borrow_mut());
 
     println!("Press A to enable/disable wide screen mode.");
@@ -30,7 +32,10 @@ fn main() {
 
             // Switch the state of the wide-mode.
             let wide_mode = gfx.top_screen.borrow().is_wide();
-            gfx.top_screen.borrow_mut().set_wide_mode(!wide_mode);
+            gfx.top_screen
+                .borrow_mut()
+                .try_set_wide_mode(!wide_mode, &cfgu)
+                .unwrap();
 
             console = Console::new(gfx.top_screen.borrow_mut());
             println!("Press A to enable/disable wide screen mode.");
@@ -0,0 +1,55 @@
+//! Network Gamepad example.
+//!
+//! This example turns the console into a wireless gamepad, streaming its input state over UDP to any receiver on the local network.
+//! Have a look at the documentation of the `ctru::input::netpad` module for the packet format and a receiver to run on a PC.
+
+use ctru::input::netpad::{Streamer, DEFAULT_PORT};
+use ctru::prelude::*;
+
+fn main() {
+    let gfx = Gfx::new().unwrap();
+    let mut hid = Hid::new().unwrap();
+    let apt = Apt::new().unwrap();
+
+    // Owning a living handle to the `Soc` service is required to use network functionalities.
+    let soc = Soc::new().unwrap();
+
+    let _console = Console::new(gfx.top_screen.borrow_mut());
+
+    // The motion sensors are only streamed while enabled.
+    hid.set_accelerometer(true)
+        .expect("Couldn't activate accelerometer");
+    hid.set_gyroscope(true)
+        .expect("Couldn't activate gyroscope");
+
+    // Broadcast the packets, so that the address of the receiver doesn't need to be typed in.
+    let mut streamer = Streamer::new(("255.255.255.255", DEFAULT_PORT)).unwrap();
+    streamer.set_rate(30);
+
+    println!(
+        "Streaming from {} to port {DEFAULT_PORT}",
+        soc.host_address()
+    );
+    println!("\x1b[29;6HPress Start + Select to exit");
+
+    let mut sent = 0u32;
+
+    while apt.main_loop() {
+        hid.scan_input();
+
+        // Start alone is a valid button to stream, so a combination is used to exit.
+        if hid.keys_held().contains(KeyPad::START | KeyPad::SELECT) {
+            break;
+        }
+
+        match streamer.update(&hid) {
+            Ok(true) => sent += 1,
+            Ok(false) => {}
+            Err(e) => println!("\x1b[5;0HCouldn't send packet: {e}"),
+        }
+
+        println!("\x1b[3;0HPackets sent: {sent}");
+
+        gfx.wait_for_vblank();
+    }
+}
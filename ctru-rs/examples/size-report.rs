@@ -0,0 +1,51 @@
+//! Size Report example.
+//!
+//! This example showcases the optional subsystem features of `ctru-rs`, which can be disabled to reduce the size of the final binary.
+//! It prints the list of subsystems compiled in the application, while only using the core services itself.
+//!
+//! To see the difference in code size, build it with and without the default features and compare the resulting executables:
+//!
+//! ```bash
+//! cargo 3ds build --release --example size-report
+//! cargo 3ds build --release --example size-report --no-default-features --features big-stack
+//! ```
+
+use ctru::prelude::*;
+
+/// Optional subsystems of `ctru-rs`, together with whether they have been compiled in.
+const SUBSYSTEMS: [(&str, bool); 5] = [
+    ("applets", cfg!(feature = "applets")),
+    ("audio", cfg!(feature = "audio")),
+    ("camera", cfg!(feature = "camera")),
+    ("ir", cfg!(feature = "ir")),
+    ("network", cfg!(feature = "network")),
+];
+
+fn main() {
+    let gfx = Gfx::new().expect("Couldn't obtain GFX controller");
+    let mut hid = Hid::new().expect("Couldn't obtain HID controller");
+    let apt = Apt::new().expect("Couldn't obtain APT controller");
+    let _console = Console::new(gfx.top_screen.borrow_mut());
+
+    println!("Optional subsystems:\n");
+
+    for (name, enabled) in SUBSYSTEMS {
+        let status = if enabled { "enabled" } else { "disabled" };
+        println!("  {name:<10}{status}");
+    }
+
+    println!("\nCompare the size of the executable built");
+    println!("with and without the default features.");
+
+    println!("\x1b[29;16HPress Start to exit");
+
+    while apt.main_loop() {
+        hid.scan_input();
+
+        if hid.keys_down().contains(KeyPad::START) {
+            break;
+        }
+
+        gfx.wait_for_vblank();
+    }
+}
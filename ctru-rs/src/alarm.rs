@@ -0,0 +1,392 @@
+//! Alarms and reminders.
+//!
+//! The system can't run an application at a given time, and notifications added via the [`News`] service are shown right away.
+//! [`Scheduler`] keeps a list of [`Alarm`]s (which can be saved to a file, to survive restarts), and posts each of them as a
+//! notification once the clock of the console reaches its time. Reminder-style applications keep it running in their main loop
+//! (for example as a [headless](crate::headless) daemon), sleeping until the next alarm with [`Scheduler::time_until_next()`].
+//!
+//! # Notes
+//!
+//! Times are read from the real-time clock of the console (via [`SystemTime::now()`]), so they're in the local time set in the
+//! System Settings. Applications aren't running while the console is asleep: alarms missed that way fire as soon as the
+//! scheduler runs again.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # let _runner = test_runner::GdbRunner::default();
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #
+//! use std::time::{Duration, SystemTime};
+//!
+//! use ctru::alarm::{Alarm, Scheduler};
+//! use ctru::prelude::*;
+//! use ctru::services::news::News;
+//!
+//! let apt = Apt::new()?;
+//! let mut news = News::new()?;
+//!
+//! let mut scheduler = Scheduler::new();
+//! scheduler.load("sdmc:/3ds/reminders/alarms.txt")?;
+//!
+//! if scheduler.alarms().is_empty() {
+//!     scheduler.schedule(
+//!         Alarm::new(
+//!             SystemTime::now() + Duration::from_secs(60 * 60),
+//!             "Stretch",
+//!             "Time to stand up and stretch a bit!",
+//!         )
+//!         .repeat_every(Duration::from_secs(60 * 60)),
+//!     );
+//! }
+//!
+//! while apt.main_loop() {
+//!     if scheduler.fire_due(&mut news)? > 0 {
+//!         scheduler.save("sdmc:/3ds/reminders/alarms.txt")?;
+//!     }
+//!
+//!     let wait = scheduler.time_until_next().unwrap_or(Duration::MAX);
+//!     std::thread::sleep(wait.min(Duration::from_secs(1)));
+//! }
+//! #
+//! # Ok(())
+//! # }
+//! ```
+#![doc(alias = "reminder")]
+#![doc(alias = "notification")]
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::services::news::News;
+
+/// Notification to post at a given time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alarm {
+    /// Time at which the notification is posted.
+    pub time: SystemTime,
+    /// Period after which the alarm fires again, if any.
+    pub repeat: Option<Duration>,
+    /// Title of the notification (see [`MAX_TITLE_LEN`](crate::services::news::MAX_TITLE_LEN)).
+    pub title: String,
+    /// Message of the notification (see [`MAX_MESSAGE_LEN`](crate::services::news::MAX_MESSAGE_LEN)).
+    pub message: String,
+}
+
+/// List of pending [`Alarm`]s.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Scheduler {
+    alarms: Vec<Alarm>,
+}
+
+impl Alarm {
+    /// Creates an alarm firing once at `time`.
+    pub fn new(time: SystemTime, title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            time,
+            repeat: None,
+            title: title.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Makes the alarm fire again every `period` (e.g. daily). A zero `period` is ignored.
+    pub fn repeat_every(mut self, period: Duration) -> Self {
+        self.repeat = (!period.is_zero()).then_some(period);
+        self
+    }
+
+    // Moves a repeating alarm to its next time after `now`, returning `false` if it shouldn't fire again
+    // (including when its next time is out of the range of `SystemTime`).
+    fn advance(&mut self, now: SystemTime) -> bool {
+        let Some(period) = self.repeat else {
+            return false;
+        };
+
+        // Occurrences missed while the application wasn't running are skipped.
+        let late = now.duration_since(self.time).unwrap_or_default();
+        let next = (late.as_nanos() / period.as_nanos())
+            .checked_add(1)
+            .and_then(|occurrences| period.as_nanos().checked_mul(occurrences))
+            .and_then(|nanos| {
+                let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+                self.time
+                    .checked_add(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+            });
+
+        match next {
+            Some(next) => {
+                self.time = next;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Scheduler {
+    /// Creates a scheduler without any alarm.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an alarm.
+    pub fn schedule(&mut self, alarm: Alarm) {
+        self.alarms.push(alarm);
+    }
+
+    /// Removes all the alarms titled `title`, returning `true` if there were any.
+    pub fn cancel(&mut self, title: &str) -> bool {
+        let len = self.alarms.len();
+        self.alarms.retain(|alarm| alarm.title != title);
+
+        self.alarms.len() != len
+    }
+
+    /// Returns the pending alarms, in the order they were scheduled.
+    pub fn alarms(&self) -> &[Alarm] {
+        &self.alarms
+    }
+
+    /// Returns the time of the next alarm, if any.
+    pub fn next_alarm(&self) -> Option<SystemTime> {
+        self.alarms.iter().map(|alarm| alarm.time).min()
+    }
+
+    /// Returns how long to wait until the next alarm (zero if one is already due), or `None` if there are no alarms.
+    pub fn time_until_next(&self) -> Option<Duration> {
+        let next = self.next_alarm()?;
+
+        Some(
+            next.duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO),
+        )
+    }
+
+    /// Posts a notification for every alarm which is due, returning the number of notifications posted.
+    ///
+    /// Alarms which fired are removed, unless they repeat (and their next time is within the range of [`SystemTime`]).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a notification couldn't be posted, in which case its alarm
+    /// (and the following ones) stay due.
+    #[doc(alias = "NEWS_AddNotification")]
+    pub fn fire_due(&mut self, news: &mut News) -> crate::Result<usize> {
+        self.process_due(SystemTime::now(), |alarm| {
+            news.add_notification(&alarm.title, &alarm.message)
+        })
+    }
+
+    /// Calls `handler` for every alarm due at `now`, returning the number of alarms handled.
+    ///
+    /// This works like [`Scheduler::fire_due()`], for applications which show the alarms themselves.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the first error returned by `handler`, in which case the alarm (and the following ones) stay due.
+    pub fn process_due(
+        &mut self,
+        now: SystemTime,
+        mut handler: impl FnMut(&Alarm) -> crate::Result<()>,
+    ) -> crate::Result<usize> {
+        let mut handled = 0;
+        let mut result = Ok(());
+
+        self.alarms.retain_mut(|alarm| {
+            if result.is_err() || alarm.time > now {
+                return true;
+            }
+
+            if let Err(e) = handler(alarm) {
+                result = Err(e);
+                return true;
+            }
+
+            handled += 1;
+            alarm.advance(now)
+        });
+
+        result.map(|()| handled)
+    }
+
+    /// Loads the alarms saved in a file by [`Scheduler::save()`], replacing the current ones.
+    ///
+    /// Nothing is changed if the file doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be read, or if it's malformed (in which case nothing is changed).
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                self.alarms = parse(&contents)?;
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Saves the alarms to a file, which can be read back by [`Scheduler::load()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_file())
+    }
+
+    // File format, with one alarm per line: `<time> <repeat period> <title>\t<message>`.
+    // Times and periods are in seconds (since the epoch for times, and 0 for alarms which don't repeat).
+    // Backslashes, tabs and line breaks are escaped in the title and message.
+    fn to_file(&self) -> String {
+        let mut file = String::new();
+
+        for alarm in &self.alarms {
+            let time = alarm
+                .time
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            let repeat = alarm.repeat.unwrap_or_default();
+
+            // Writing to a `String` can't fail.
+            let _ = writeln!(
+                file,
+                "{} {} {}\t{}",
+                time.as_secs(),
+                repeat.as_secs(),
+                escape(&alarm.title),
+                escape(&alarm.message)
+            );
+        }
+
+        file
+    }
+}
+
+fn parse(file: &str) -> io::Result<Vec<Alarm>> {
+    file.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {reason}", i + 1),
+                )
+            };
+            let seconds = |value: &str| {
+                value
+                    .parse()
+                    .map(Duration::from_secs)
+                    .map_err(|_| invalid("expected a number of seconds"))
+            };
+
+            let mut fields = line.splitn(3, ' ');
+            let (Some(time), Some(repeat), Some(text)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid("expected `<time> <repeat> <title>\\t<message>`"));
+            };
+            let (title, message) = text
+                .split_once('\t')
+                .ok_or_else(|| invalid("expected a tab between the title and the message"))?;
+
+            Ok(Alarm {
+                time: SystemTime::UNIX_EPOCH
+                    .checked_add(seconds(time)?)
+                    .ok_or_else(|| invalid("time is out of range"))?,
+                repeat: Some(seconds(repeat)?).filter(|repeat| !repeat.is_zero()),
+                title: unescape(title).ok_or_else(|| invalid("invalid escape sequence"))?,
+                message: unescape(message).ok_or_else(|| invalid("invalid escape sequence"))?,
+            })
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn due_alarms() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let hour = Duration::from_secs(60 * 60);
+
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule(Alarm::new(start + hour, "Once", "Line 1\nLine 2\t\\"));
+        scheduler.schedule(Alarm::new(start, "Hourly", "").repeat_every(hour));
+        assert_eq!(scheduler.next_alarm(), Some(start));
+
+        // The saved file can be loaded back.
+        let mut loaded = Scheduler::new();
+        loaded.alarms = parse(&scheduler.to_file()).unwrap();
+        assert_eq!(loaded, scheduler);
+
+        // Missed occurrences of repeating alarms are skipped.
+        let mut fired = Vec::new();
+        let now = start + hour * 3 + Duration::from_secs(1);
+        let handled = scheduler.process_due(now, |alarm| {
+            fired.push(alarm.title.clone());
+            Ok(())
+        });
+
+        assert_eq!(handled.unwrap(), 2);
+        assert_eq!(fired, ["Once", "Hourly"]);
+        assert_eq!(scheduler.alarms().len(), 1);
+        assert_eq!(scheduler.next_alarm(), Some(start + hour * 4));
+
+        assert!(scheduler.cancel("Hourly"));
+        assert_eq!(scheduler.next_alarm(), None);
+
+        // Repeating alarms are removed once their next time is out of range.
+        scheduler.schedule(Alarm::new(now, "Forever", "").repeat_every(Duration::MAX));
+        assert_eq!(scheduler.process_due(now, |_| Ok(())).unwrap(), 1);
+        assert_eq!(scheduler.next_alarm(), None);
+
+        assert!(parse(&format!("{} 0 Late\t", u64::MAX)).is_err());
+    }
+}
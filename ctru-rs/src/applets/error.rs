@@ -69,6 +69,13 @@ impl PopUp {
         }
     }
 
+    /// Sets whether the applet offers to open the System Settings once the message is closed,
+    /// so that the user can fix the configuration which caused the error (e.g. the Internet Settings after a connection error).
+    #[doc(alias = "appJump")]
+    pub fn set_settings_jump(&mut self, enabled: bool) {
+        self.state.appJump = enabled;
+    }
+
     /// Launches the error applet.
     #[doc(alias = "errorDisp")]
     pub fn launch(&mut self, _apt: &Apt, _gfx: &Gfx) -> Result<(), Error> {
@@ -25,6 +25,7 @@ pub struct SoftwareKeyboard {
     state: Box<SwkbdState>,
     filter_callback: Option<Box<CallbackFunction>>,
     initial_text: Option<Cow<'static, str>>,
+    dictionary: Vec<SwkbdDictWord>,
 }
 
 /// Configuration structure to setup the Parental Lock applet.
@@ -47,7 +48,8 @@ pub enum Kind {
     Normal = ctru_sys::SWKBD_TYPE_NORMAL,
     /// Only QWERTY keyboard.
     Qwerty = ctru_sys::SWKBD_TYPE_QWERTY,
-    /// Only number pad.
+    /// Only number pad (also known as the "ten-key" layout).
+    #[doc(alias = "ten-key")]
     Numpad = ctru_sys::SWKBD_TYPE_NUMPAD,
     /// On JPN systems: a keyboard without japanese input capabilities.
     ///
@@ -243,6 +245,7 @@ impl SoftwareKeyboard {
                 state,
                 filter_callback: None,
                 initial_text: None,
+                dictionary: Vec::new(),
             }
         }
     }
@@ -293,7 +296,7 @@ impl SoftwareKeyboard {
     ///
     /// let mut keyboard = SoftwareKeyboard::default();
     ///
-    /// let features = Features::DARKEN_TOP_SCREEN & Features::MULTILINE;
+    /// let features = Features::DARKEN_TOP_SCREEN | Features::MULTILINE;
     /// keyboard.set_features(features);
     /// #
     /// # }
@@ -317,10 +320,12 @@ impl SoftwareKeyboard {
     /// let validation = ValidInput::NotEmptyNotBlank;
     ///
     /// // Disallow the use of numerical digits and profanity.
-    /// let filters = Filters::DIGITS & Filters::PROFANITY;
+    /// let filters = Filters::DIGITS | Filters::PROFANITY;
     /// keyboard.set_validation(validation, filters);
     /// #
     /// # }
+    /// ```
+    #[doc(alias = "swkbdSetValidation")]
     pub fn set_validation(&mut self, validation: ValidInput, filters: Filters) {
         self.state.valid_input = validation.into();
         self.state.filter_flags = filters.bits().into();
@@ -356,6 +361,8 @@ impl SoftwareKeyboard {
     /// })));
     /// #
     /// # }
+    /// ```
+    #[doc(alias = "swkbdSetFilterCallback")]
     pub fn set_filter_callback(&mut self, callback: Option<Box<CallbackFunction>>) {
         self.filter_callback = callback;
     }
@@ -382,6 +389,7 @@ impl SoftwareKeyboard {
     /// keyboard.set_max_digits(3);
     /// #
     /// # }
+    /// ```
     pub fn set_max_digits(&mut self, digits: u16) {
         self.state.max_digits = digits;
     }
@@ -586,6 +594,80 @@ impl SoftwareKeyboard {
         self.state.valid_input = ValidInput::FixedLen.into();
     }
 
+    /// Allow the user to write multiple lines of text, separated by `'\n'`.
+    ///
+    /// This is the same as setting [`Features::MULTILINE`], without changing the other features.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # fn main() {
+    /// #
+    /// use ctru::applets::swkbd::SoftwareKeyboard;
+    /// let mut keyboard = SoftwareKeyboard::default();
+    ///
+    /// keyboard.set_multiline(true);
+    /// keyboard.set_max_text_len(500);
+    /// #
+    /// # }
+    /// ```
+    pub fn set_multiline(&mut self, enabled: bool) {
+        self.state.multiline = enabled;
+    }
+
+    /// Set custom words suggested by the predictive input, as `(reading, word)` pairs.
+    ///
+    /// The word is suggested when the user writes its reading (e.g. a game-specific term in its full form when writing an abbreviation).
+    /// Both are truncated to 40 UTF-16 code units. This enables [`Features::PREDICTIVE_INPUT`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # fn main() {
+    /// #
+    /// use ctru::applets::swkbd::SoftwareKeyboard;
+    /// let mut keyboard = SoftwareKeyboard::default();
+    ///
+    /// keyboard.set_dictionary([("lvl", "level up"), ("hp", "health potion")]);
+    /// #
+    /// # }
+    /// ```
+    #[doc(alias = "swkbdSetDictionary", alias = "swkbdSetDictWord")]
+    pub fn set_dictionary<'a>(&mut self, words: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        fn encode(text: &str, out: &mut [u16]) {
+            for (idx, code_unit) in text
+                .encode_utf16()
+                .take(out.len() - 1)
+                .chain(once(0))
+                .enumerate()
+            {
+                out[idx] = code_unit;
+            }
+        }
+
+        self.dictionary = words
+            .into_iter()
+            .map(|(reading, word)| {
+                let mut dict_word = SwkbdDictWord {
+                    reading: [0; 41],
+                    word: [0; 41],
+                    language: 0,
+                    all_languages: true,
+                };
+
+                encode(reading, &mut dict_word.reading);
+                encode(word, &mut dict_word.word);
+
+                dict_word
+            })
+            .collect();
+
+        self.state.dict_word_count = self.dictionary.len() as _;
+        self.state.predictive_input |= !self.dictionary.is_empty();
+    }
+
     // A reimplementation of `swkbdInputText` from `libctru/source/applets/swkbd.c`. Allows us to fix various
     // API nits and get rid of awkward type conversions when interacting with the Software Keyboard.
     fn swkbd_input_text(&mut self, output: &mut String, _apt: &Apt, _gfx: &Gfx) -> SwkbdButton {
@@ -686,13 +768,13 @@ impl SoftwareKeyboard {
             }
         }
 
-        if !extra.dict.is_null() {
+        if !self.dictionary.is_empty() {
             swkbd.dict_offset = dict_off as _;
             unsafe {
                 std::ptr::copy_nonoverlapping(
-                    extra.dict,
+                    self.dictionary.as_ptr(),
                     swkbd_shared_mem_ptr.add(dict_off).cast(),
-                    swkbd.dict_word_count as _,
+                    self.dictionary.len(),
                 )
             };
         }
@@ -0,0 +1,76 @@
+//! Base64 encoding shared by the crate and the test runner.
+//!
+//! This isn't part of the public API, and may change at any time.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `data` in base64, with padding.
+pub fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        // A chunk of `n` bytes is encoded by `n + 1` characters, and padded to 4.
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3F;
+                encoded.push(char::from(ALPHABET[index as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decodes padded base64, returning `None` if `encoded` isn't valid.
+pub fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.as_bytes();
+    if encoded.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut data = Vec::with_capacity(encoded.len() / 4 * 3);
+
+    for chunk in encoded.chunks(4) {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+
+        let mut group = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = ALPHABET.iter().position(|&a| a == c)?;
+            group = (group << 6) | value as u32;
+        }
+        group <<= 6 * padding;
+
+        data.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(encode(b"Man"), "TWFu");
+        assert_eq!(encode(b"Ma"), "TWE=");
+        assert_eq!(encode(b"M"), "TQ==");
+        assert_eq!(encode(b"hello"), "aGVsbG8=");
+
+        assert_eq!(decode("TWE=").unwrap(), b"Ma");
+        assert_eq!(decode("TWE"), None);
+        assert_eq!(decode("T==="), None);
+    }
+}
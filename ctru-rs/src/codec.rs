@@ -0,0 +1,643 @@
+//! Message codec for network communication.
+//!
+//! This module provides a compact binary format to exchange messages between consoles (via UDS local networking)
+//! or with a PC (via sockets), without depending on the endianness or the word size of either side:
+//!
+//! - bytes are encoded as-is;
+//! - other unsigned integers are encoded as LEB128 variable-length integers, and signed integers are zigzag-encoded first;
+//! - floating point numbers are encoded in little-endian order;
+//! - strings, byte slices and vectors are prefixed by their length.
+//!
+//! Messages are built by implementing [`Encode`] and [`Decode`] on top of the implementations provided for the primitive types.
+//! [`Hello`] messages allow the two sides of a session to agree on a protocol version before exchanging any other message.
+//!
+//! Stream-based transports (such as TCP sockets) don't preserve message boundaries: use [`frame()`] and [`FrameReader`]
+//! to prefix every message with its length.
+#![doc(alias = "serialize")]
+#![doc(alias = "varint")]
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Error returned when decoding a message.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The message ended before the value was complete.
+    UnexpectedEnd,
+    /// A variable-length integer was too large for its type.
+    Overflow,
+    /// A string wasn't valid UTF-8.
+    InvalidUtf8,
+    /// A value was invalid for its type (e.g. a `bool` which isn't `0` or `1`).
+    InvalidValue,
+    /// A [`Hello`] message was sent by a different protocol.
+    WrongMagic,
+    /// The two sides of the session don't support any common protocol version.
+    IncompatibleVersion {
+        /// Versions supported locally.
+        local: RangeInclusive<u16>,
+        /// Versions supported by the other side.
+        remote: RangeInclusive<u16>,
+    },
+}
+
+/// Types which can be encoded in a message.
+pub trait Encode {
+    /// Appends the encoded value to the encoder.
+    fn encode(&self, encoder: &mut Encoder);
+}
+
+/// Types which can be decoded from a message.
+pub trait Decode: Sized {
+    /// Reads a value from the decoder.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the data doesn't contain a valid value.
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error>;
+}
+
+/// Message being encoded.
+///
+/// # Example
+///
+/// ```
+/// use ctru::codec::{Decode, Decoder, Encode, Encoder};
+///
+/// let mut encoder = Encoder::new();
+/// 42u32.encode(&mut encoder);
+/// "player".encode(&mut encoder);
+///
+/// let bytes = encoder.into_bytes();
+/// let mut decoder = Decoder::new(&bytes);
+///
+/// assert_eq!(u32::decode(&mut decoder), Ok(42));
+/// assert_eq!(String::decode(&mut decoder).as_deref(), Ok("player"));
+/// assert!(decoder.is_empty());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+}
+
+/// Message being decoded.
+#[derive(Clone, Debug)]
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+/// Handshake message used to negotiate the protocol version of a session.
+///
+/// Both sides send their [`Hello`] as the first message of the session, and use the highest version supported by both.
+///
+/// # Example
+///
+/// ```
+/// use ctru::codec::Hello;
+///
+/// let local = Hello::new(*b"GAME", 1..=3);
+///
+/// // Received from the other side.
+/// let remote = Hello::from_bytes(&Hello::new(*b"GAME", 2..=5).to_bytes())?;
+///
+/// assert_eq!(local.negotiate(&remote)?, 3);
+/// # Ok::<(), ctru::codec::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hello {
+    magic: [u8; 4],
+    versions: RangeInclusive<u16>,
+}
+
+/// Splits a byte stream into the messages written via [`frame()`].
+///
+/// # Example
+///
+/// ```
+/// use ctru::codec::{frame, FrameReader};
+///
+/// let mut stream = frame(b"hello");
+/// stream.extend(frame(b"world"));
+///
+/// let mut reader = FrameReader::new();
+///
+/// // Data may arrive in arbitrary chunks.
+/// reader.push(&stream[..4]);
+/// assert_eq!(reader.next_frame()?, None);
+///
+/// reader.push(&stream[4..]);
+/// assert_eq!(reader.next_frame()?.as_deref(), Some(&b"hello"[..]));
+/// assert_eq!(reader.next_frame()?.as_deref(), Some(&b"world"[..]));
+/// # Ok::<(), ctru::codec::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FrameReader {
+    buffer: Vec<u8>,
+    max_len: Option<usize>,
+}
+
+impl Encoder {
+    /// Creates a new empty message.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends raw bytes to the message, without any length prefix.
+    pub fn write_raw(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Appends an unsigned variable-length integer to the message.
+    pub fn write_varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+
+            self.bytes.push(byte | 0x80);
+        }
+    }
+
+    /// Returns the encoded message.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Consumes the encoder, returning the encoded message.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<'a> Decoder<'a> {
+    /// Creates a new decoder reading the specified message.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Returns the bytes which haven't been decoded yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns `true` if the whole message has been decoded.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Reads the specified amount of raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the message is too short.
+    pub fn read_raw(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.bytes.len() {
+            return Err(Error::UnexpectedEnd);
+        }
+
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(bytes)
+    }
+
+    /// Reads an unsigned variable-length integer.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the message is too short or the value doesn't fit in 64 bits.
+    pub fn read_varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let &[byte] = self.read_raw(1)? else {
+                unreachable!()
+            };
+
+            let bits = u64::from(byte & 0x7F);
+            if bits << shift >> shift != bits {
+                return Err(Error::Overflow);
+            }
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(Error::Overflow)
+    }
+
+    fn read_len(&mut self) -> Result<usize, Error> {
+        let len = usize::try_from(self.read_varint()?).map_err(|_| Error::Overflow)?;
+
+        // Every element takes at least one byte, so longer lengths can't be valid.
+        if len > self.bytes.len() {
+            return Err(Error::UnexpectedEnd);
+        }
+
+        Ok(len)
+    }
+}
+
+impl Hello {
+    const MAGIC: &'static [u8; 4] = b"CTRH";
+
+    /// Creates a new handshake message for the protocol identified by `magic`, supporting the specified versions.
+    pub fn new(magic: [u8; 4], versions: RangeInclusive<u16>) -> Self {
+        Self { magic, versions }
+    }
+
+    /// Returns the versions supported by the sender.
+    pub fn versions(&self) -> RangeInclusive<u16> {
+        self.versions.clone()
+    }
+
+    /// Encodes the handshake message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        self.encode(&mut encoder);
+        encoder.into_bytes()
+    }
+
+    /// Decodes a handshake message.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `bytes` doesn't contain a valid handshake message.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode(&mut Decoder::new(bytes))
+    }
+
+    /// Returns the highest protocol version supported by both sides.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the messages are for different protocols, or if there is no common version.
+    pub fn negotiate(&self, remote: &Hello) -> Result<u16, Error> {
+        if self.magic != remote.magic {
+            return Err(Error::WrongMagic);
+        }
+
+        let highest = *self.versions.end().min(remote.versions.end());
+        let lowest = *self.versions.start().max(remote.versions.start());
+
+        if lowest > highest {
+            return Err(Error::IncompatibleVersion {
+                local: self.versions(),
+                remote: remote.versions(),
+            });
+        }
+
+        Ok(highest)
+    }
+}
+
+impl Encode for Hello {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_raw(Self::MAGIC);
+        encoder.write_raw(&self.magic);
+        self.versions.start().encode(encoder);
+        self.versions.end().encode(encoder);
+    }
+}
+
+impl Decode for Hello {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        if decoder.read_raw(4)? != Self::MAGIC {
+            return Err(Error::WrongMagic);
+        }
+
+        let magic = <[u8; 4]>::decode(decoder)?;
+        let start = u16::decode(decoder)?;
+        let end = u16::decode(decoder)?;
+
+        Ok(Self::new(magic, start..=end))
+    }
+}
+
+/// Prefixes a message with its length, to send it over a stream-based transport.
+pub fn frame(message: &[u8]) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+    message.encode(&mut encoder);
+    encoder.into_bytes()
+}
+
+impl FrameReader {
+    /// Creates a new empty reader.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum length of a frame. Longer frames are reported as [`Error::Overflow`].
+    ///
+    /// By default, frames can have any length, which lets the other side make this reader allocate arbitrary amounts of memory.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Appends data received from the stream.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next complete frame, or [`None`] if more data is needed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the length of the frame is invalid or too large.
+    /// The stream can't be recovered after an error.
+    pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        let mut decoder = Decoder::new(&self.buffer);
+
+        let len = match decoder.read_varint() {
+            Ok(len) => usize::try_from(len).map_err(|_| Error::Overflow)?,
+            Err(Error::UnexpectedEnd) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if self.max_len.is_some_and(|max_len| len > max_len) {
+            return Err(Error::Overflow);
+        }
+
+        let header = self.buffer.len() - decoder.remaining().len();
+        if decoder.remaining().len() < len {
+            return Ok(None);
+        }
+
+        let frame = self.buffer[header..header + len].to_vec();
+        self.buffer.drain(..header + len);
+
+        Ok(Some(frame))
+    }
+}
+
+macro_rules! impl_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, encoder: &mut Encoder) {
+                    encoder.write_varint(u64::from(*self));
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+                    <$ty>::try_from(decoder.read_varint()?).map_err(|_| Error::Overflow)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, encoder: &mut Encoder) {
+                    let value = i64::from(*self);
+                    encoder.write_varint(((value << 1) ^ (value >> 63)) as u64);
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+                    let value = decoder.read_varint()?;
+                    let value = (value >> 1) as i64 ^ -((value & 1) as i64);
+
+                    <$ty>::try_from(value).map_err(|_| Error::Overflow)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_float {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, encoder: &mut Encoder) {
+                    encoder.write_raw(&self.to_le_bytes());
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+                    let bytes = decoder.read_raw(std::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned!(u16, u32, u64);
+impl_signed!(i8, i16, i32, i64);
+impl_float!(f32, f64);
+
+impl Encode for u8 {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_raw(&[*self]);
+    }
+}
+
+impl Decode for u8 {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        Ok(decoder.read_raw(1)?[0])
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_raw(&[u8::from(*self)]);
+    }
+}
+
+impl Decode for bool {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        match decoder.read_raw(1)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(Error::InvalidValue),
+        }
+    }
+}
+
+impl<const N: usize> Encode for [u8; N] {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_raw(self);
+    }
+}
+
+impl<const N: usize> Decode for [u8; N] {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        Ok(decoder.read_raw(N)?.try_into().unwrap())
+    }
+}
+
+impl Encode for [u8] {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_varint(self.len() as u64);
+        encoder.write_raw(self);
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.as_bytes().encode(encoder);
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.as_str().encode(encoder);
+    }
+}
+
+impl Decode for String {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        let len = decoder.read_len()?;
+        let bytes = decoder.read_raw(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| Error::InvalidUtf8)
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_varint(self.len() as u64);
+
+        for item in self {
+            item.encode(encoder);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        let len = decoder.read_len()?;
+
+        (0..len).map(|_| T::decode(decoder)).collect()
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            None => false.encode(encoder),
+            Some(value) => {
+                true.encode(encoder);
+                value.encode(encoder);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        if bool::decode(decoder)? {
+            T::decode(decoder).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, encoder: &mut Encoder) {
+        (**self).encode(encoder);
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "the message ended unexpectedly"),
+            Self::Overflow => write!(f, "a value is too large for its type"),
+            Self::InvalidUtf8 => write!(f, "a string is not valid UTF-8"),
+            Self::InvalidValue => write!(f, "a value is invalid for its type"),
+            Self::WrongMagic => write!(f, "the message is for a different protocol"),
+            Self::IncompatibleVersion { local, remote } => write!(
+                f,
+                "no common protocol version (local: {}..={}, remote: {}..={})",
+                local.start(),
+                local.end(),
+                remote.start(),
+                remote.end()
+            ),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut encoder = Encoder::new();
+        300u16.encode(&mut encoder);
+        (-2i32).encode(&mut encoder);
+        i64::MIN.encode(&mut encoder);
+        u64::MAX.encode(&mut encoder);
+        1.5f32.encode(&mut encoder);
+        Some("héllo").encode(&mut encoder);
+        vec![true, false].encode(&mut encoder);
+
+        let bytes = encoder.into_bytes();
+        assert_eq!(bytes[..3], [0xAC, 0x02, 0x03]);
+
+        let mut decoder = Decoder::new(&bytes);
+        assert_eq!(u16::decode(&mut decoder), Ok(300));
+        assert_eq!(i32::decode(&mut decoder), Ok(-2));
+        assert_eq!(i64::decode(&mut decoder), Ok(i64::MIN));
+        assert_eq!(u64::decode(&mut decoder), Ok(u64::MAX));
+        assert_eq!(f32::decode(&mut decoder), Ok(1.5));
+        assert_eq!(
+            Option::<String>::decode(&mut decoder),
+            Ok(Some("héllo".to_owned()))
+        );
+        assert_eq!(Vec::<bool>::decode(&mut decoder), Ok(vec![true, false]));
+        assert!(decoder.is_empty());
+
+        assert_eq!(
+            Vec::<u8>::decode(&mut Decoder::new(&frame(&[1, 200]))),
+            Ok(vec![1, 200])
+        );
+        assert_eq!(
+            u16::decode(&mut Decoder::new(&[0xFF, 0xFF, 0x04])),
+            Err(Error::Overflow)
+        );
+        assert_eq!(
+            String::decode(&mut Decoder::new(&[5, b'a'])),
+            Err(Error::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn version_negotiation() {
+        let local = Hello::new(*b"TEST", 2..=4);
+
+        let remote = Hello::from_bytes(&Hello::new(*b"TEST", 1..=3).to_bytes()).unwrap();
+        assert_eq!(local.negotiate(&remote), Ok(3));
+
+        let remote = Hello::new(*b"TEST", 5..=6);
+        assert!(matches!(
+            local.negotiate(&remote),
+            Err(Error::IncompatibleVersion { .. })
+        ));
+
+        let remote = Hello::new(*b"ELSE", 2..=4);
+        assert_eq!(local.negotiate(&remote), Err(Error::WrongMagic));
+        assert_eq!(Hello::from_bytes(b"nope"), Err(Error::WrongMagic));
+    }
+}
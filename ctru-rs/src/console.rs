@@ -6,13 +6,24 @@
 //! Have a look at [`Soc::redirect_to_3dslink()`](crate::services::soc::Soc::redirect_to_3dslink) for a better alternative when debugging applications.
 
 use std::cell::{RefMut, UnsafeCell};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::Mutex;
 
 use ctru_sys::{consoleClear, consoleInit, consoleSelect, consoleSetWindow, PrintConsole};
 
 use crate::services::gfx::{Flush, Screen, Swap};
+use crate::services::hid::{InputSource, KeyPad};
 
 static mut EMPTY_CONSOLE: PrintConsole = unsafe { std::mem::zeroed::<PrintConsole>() };
 
+// Scrollback buffers of the consoles which enabled them, along with the address of their context.
+// libctru only passes the context to the print callback, so it's used to find the matching buffer.
+static SCROLLBACKS: Mutex<Vec<(usize, Scrollback)>> = Mutex::new(Vec::new());
+
+// Address of the context of the last console created on each screen (top and bottom), or 0 if there's none.
+static SCREEN_CONSOLES: Mutex<[usize; 2]> = Mutex::new([0; 2]);
+
 /// Error enum for generic errors within [`Console`].
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Error {
@@ -66,6 +77,28 @@ pub struct Console<'screen> {
     screen: RefMut<'screen, dyn ConsoleScreen>,
 }
 
+/// Region of a [`Console`], with its own cursor.
+///
+/// This struct can be created via [`Console::window()`].
+#[doc(alias = "PrintConsole")]
+pub struct ConsoleWindow<'console> {
+    context: Box<UnsafeCell<PrintConsole>>,
+    parent: &'console UnsafeCell<PrintConsole>,
+}
+
+// Text printed to a console, split in lines as wide as the console window.
+struct Scrollback {
+    lines: VecDeque<Vec<u8>>,
+    current: Vec<u8>,
+    capacity: usize,
+    width: usize,
+    tab_size: usize,
+    // Amount of lines scrolled up from the bottom.
+    offset: usize,
+    // Disabled while the console is redrawn from the buffer.
+    recording: bool,
+}
+
 impl<'screen> Console<'screen> {
     /// Initialize a console on the chosen screen.
     ///
@@ -110,6 +143,14 @@ impl<'screen> Console<'screen> {
 
         unsafe { consoleInit(screen.as_raw(), context.get()) };
 
+        if let Some(slot) = SCREEN_CONSOLES
+            .lock()
+            .unwrap()
+            .get_mut(screen.as_raw() as usize)
+        {
+            *slot = context.get() as usize;
+        }
+
         Console { context, screen }
     }
 
@@ -228,22 +269,7 @@ impl<'screen> Console<'screen> {
     /// ```
     #[doc(alias = "consoleSetWindow")]
     pub fn set_window(&mut self, x: u8, y: u8, width: u8, height: u8) -> Result<(), Error> {
-        let height_limit = 30;
-        let length_limit = self.max_width();
-
-        if x >= length_limit {
-            return Err(Error::CoordinateOutOfBounds(Axis::X));
-        }
-        if y >= height_limit {
-            return Err(Error::CoordinateOutOfBounds(Axis::Y));
-        }
-
-        if (x + width) > length_limit {
-            return Err(Error::DimensionOutOfBounds(Dimension::Width));
-        }
-        if (y + height) > height_limit {
-            return Err(Error::DimensionOutOfBounds(Dimension::Height));
-        }
+        self.check_window(x, y, width, height)?;
 
         unsafe {
             consoleSetWindow(
@@ -291,6 +317,199 @@ impl<'screen> Console<'screen> {
         self.set_window(0, 0, width, 30).unwrap();
     }
 
+    /// Create a sub-window of the console, with its own cursor, which can be printed to and cleared independently.
+    ///
+    /// This allows a single screen to host multiple regions, such as a scrolling log and a fixed status bar.
+    /// Windows use the same coordinates as [`set_window()`](Console::set_window()), and may overlap.
+    ///
+    /// # Notes
+    ///
+    /// Just like the console itself, a window must be [selected](ConsoleWindow::select()) to be printed to.
+    /// When a selected window is dropped, its parent console is selected instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// # use ctru::services::gfx::Gfx;
+    /// # let gfx = Gfx::new()?;
+    /// #
+    /// use ctru::console::Console;
+    ///
+    /// let top_console = Console::new(gfx.top_screen.borrow_mut());
+    ///
+    /// // A status bar on the first line, and a log on the rest of the screen.
+    /// let status = top_console.window(0, 0, 50, 1)?;
+    /// let log = top_console.window(0, 2, 50, 28)?;
+    ///
+    /// status.select();
+    /// print!("Connected");
+    ///
+    /// log.select();
+    /// println!("Received 42 bytes");
+    ///
+    /// // Only the status bar is cleared.
+    /// status.clear();
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "consoleSetWindow")]
+    pub fn window(&self, x: u8, y: u8, width: u8, height: u8) -> Result<ConsoleWindow<'_>, Error> {
+        self.check_window(x, y, width, height)?;
+
+        // The window shares the screen and font of the console, but has its own cursor.
+        let context = Box::new(UnsafeCell::new(unsafe {
+            std::ptr::read(self.context.get())
+        }));
+
+        unsafe {
+            consoleSetWindow(
+                context.get(),
+                x.into(),
+                y.into(),
+                width.into(),
+                height.into(),
+            )
+        };
+
+        Ok(ConsoleWindow {
+            context,
+            parent: &self.context,
+        })
+    }
+
+    /// Keep the last `lines` lines printed to the console in memory, so that they can be scrolled back to.
+    ///
+    /// Scrolling is done via [`Console::scroll()`], or with the D-Pad via [`Console::handle_scroll_input()`].
+    /// While scrolled up, new output is retained but not shown until the console is scrolled back to the bottom.
+    ///
+    /// # Notes
+    ///
+    /// Only the text is retained: colors and cursor movements (via ANSI codes) are lost when scrolling.
+    /// The lines are wrapped to the current size of the window, so it should be set before enabling the scrollback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// # use ctru::services::gfx::Gfx;
+    /// # use ctru::services::hid::Hid;
+    /// # use ctru::services::apt::Apt;
+    /// # let gfx = Gfx::new()?;
+    /// # let mut hid = Hid::new()?;
+    /// # let apt = Apt::new()?;
+    /// #
+    /// use ctru::console::Console;
+    ///
+    /// let mut top_console = Console::new(gfx.top_screen.borrow_mut());
+    /// top_console.enable_scrollback(500);
+    ///
+    /// for i in 0..100 {
+    ///     println!("Log line {i}");
+    /// }
+    ///
+    /// while apt.main_loop() {
+    ///     hid.scan_input();
+    ///
+    ///     // Page through the log with the D-Pad.
+    ///     top_console.handle_scroll_input(&hid);
+    /// #   break;
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn enable_scrollback(&mut self, lines: usize) {
+        let context = unsafe { &mut *self.context.get() };
+
+        let scrollback = Scrollback {
+            lines: VecDeque::new(),
+            current: Vec::new(),
+            capacity: lines,
+            width: context.windowWidth as usize,
+            tab_size: context.tabSize as usize,
+            offset: 0,
+            recording: true,
+        };
+
+        let mut scrollbacks = SCROLLBACKS.lock().unwrap();
+        scrollbacks.retain(|(address, _)| *address != self.context.get() as usize);
+        scrollbacks.push((self.context.get() as usize, scrollback));
+
+        context.PrintChar = Some(record_char);
+    }
+
+    /// Stop retaining the output of the console, discarding the lines kept in memory.
+    pub fn disable_scrollback(&mut self) {
+        let address = self.context.get() as usize;
+        let was_scrolled = self.with_scrollback(|scrollback| scrollback.offset > 0);
+
+        unsafe { (*self.context.get()).PrintChar = None };
+        SCROLLBACKS
+            .lock()
+            .unwrap()
+            .retain(|(other, _)| *other != address);
+
+        if was_scrolled == Some(true) {
+            self.clear_self();
+        }
+    }
+
+    /// Scroll the console by `lines` lines, up if negative or down if positive.
+    ///
+    /// Does nothing if the scrollback isn't enabled (see [`Console::enable_scrollback()`]).
+    pub fn scroll(&mut self, lines: isize) {
+        let changed = self.with_scrollback(|scrollback| {
+            let max_offset = (scrollback.lines.len() + 1).saturating_sub(self.height());
+            // The offset counts lines from the bottom, so scrolling up increases it.
+            let offset = if lines < 0 {
+                scrollback.offset.saturating_add(lines.unsigned_abs())
+            } else {
+                scrollback.offset.saturating_sub(lines.unsigned_abs())
+            }
+            .min(max_offset);
+
+            std::mem::replace(&mut scrollback.offset, offset) != offset
+        });
+
+        if changed == Some(true) {
+            self.redraw();
+        }
+    }
+
+    /// Scroll the console by one page with the Up and Down buttons of the D-Pad, or back to the bottom with Left.
+    ///
+    /// This should be called once per frame, after scanning the input. Returns `true` if a button was handled.
+    pub fn handle_scroll_input(&mut self, input: &impl InputSource) -> bool {
+        let page = self.height() as isize;
+        let keys = input.keys_down();
+
+        if keys.contains(KeyPad::DPAD_UP) {
+            self.scroll(-page);
+        } else if keys.contains(KeyPad::DPAD_DOWN) {
+            self.scroll(page);
+        } else if keys.contains(KeyPad::DPAD_LEFT) {
+            self.scroll(isize::MAX);
+        } else {
+            return false;
+        }
+
+        true
+    }
+
+    /// Returns `true` if the console is scrolled up, hiding the latest output.
+    pub fn is_scrolled(&self) -> bool {
+        self.with_scrollback(|scrollback| scrollback.offset > 0)
+            .unwrap_or(false)
+    }
+
     /// Returns this [`Console`]'s maximum character width depending on the screen used.
     ///
     /// # Example
@@ -325,6 +544,226 @@ impl<'screen> Console<'screen> {
             _ => unreachable!(),
         }
     }
+
+    fn check_window(&self, x: u8, y: u8, width: u8, height: u8) -> Result<(), Error> {
+        let height_limit = 30;
+        let length_limit = self.max_width();
+
+        if x >= length_limit {
+            return Err(Error::CoordinateOutOfBounds(Axis::X));
+        }
+        if y >= height_limit {
+            return Err(Error::CoordinateOutOfBounds(Axis::Y));
+        }
+
+        if (x + width) > length_limit {
+            return Err(Error::DimensionOutOfBounds(Dimension::Width));
+        }
+        if (y + height) > height_limit {
+            return Err(Error::DimensionOutOfBounds(Dimension::Height));
+        }
+
+        Ok(())
+    }
+
+    fn height(&self) -> usize {
+        unsafe { (*self.context.get()).windowHeight as usize }
+    }
+
+    fn with_scrollback<T>(&self, f: impl FnOnce(&mut Scrollback) -> T) -> Option<T> {
+        let address = self.context.get() as usize;
+        let mut scrollbacks = SCROLLBACKS.lock().unwrap();
+
+        scrollbacks
+            .iter_mut()
+            .find(|(other, _)| *other == address)
+            .map(|(_, scrollback)| f(scrollback))
+    }
+
+    // Clears the console, even if it isn't the selected one.
+    fn clear_self(&self) {
+        unsafe {
+            let previous = consoleSelect(self.context.get());
+            consoleClear();
+            consoleSelect(previous);
+        }
+    }
+
+    // Draws the lines of the scrollback visible at the current offset.
+    fn redraw(&mut self) {
+        let height = self.height();
+        let Some(visible) = self.with_scrollback(|scrollback| {
+            scrollback.recording = false;
+            scrollback.visible_lines(height)
+        }) else {
+            return;
+        };
+
+        unsafe {
+            let previous = consoleSelect(self.context.get());
+            consoleClear();
+
+            let mut stdout = std::io::stdout().lock();
+            for (row, line) in visible.iter().enumerate() {
+                let _ = write!(stdout, "\x1b[{};1H", row + 1);
+                let _ = stdout.write_all(line);
+            }
+            let _ = stdout.flush();
+            drop(stdout);
+
+            consoleSelect(previous);
+        }
+
+        self.with_scrollback(|scrollback| scrollback.recording = true);
+    }
+}
+
+impl Scrollback {
+    // The console prints byte by byte, each byte being a character of its font.
+    fn push_char(&mut self, c: u8) {
+        match c {
+            b'\n' => self.push_line(),
+            b'\r' => self.current.clear(),
+            b'\t' => {
+                let tab_size = self.tab_size.max(1);
+                for _ in 0..tab_size - self.current.len() % tab_size {
+                    self.push_char(b' ');
+                }
+            }
+            0x08 => {
+                self.current.pop();
+            }
+            c => {
+                // Like the console, wrap long lines.
+                if self.current.len() >= self.width {
+                    self.push_line();
+                }
+                self.current.push(c);
+            }
+        }
+    }
+
+    fn push_line(&mut self) {
+        let line = std::mem::take(&mut self.current);
+        self.lines.push_back(line);
+
+        if self.lines.len() > self.capacity {
+            self.lines.pop_front();
+        }
+
+        // Keep showing the same lines while scrolled up.
+        if self.offset > 0 {
+            self.offset = (self.offset + 1).min(self.lines.len());
+        }
+    }
+
+    fn visible_lines(&self, height: usize) -> Vec<Vec<u8>> {
+        let end = self.lines.len() + 1 - self.offset.min(self.lines.len());
+        let start = end.saturating_sub(height);
+
+        self.lines
+            .iter()
+            .chain(std::iter::once(&self.current))
+            .skip(start)
+            .take(end - start)
+            .cloned()
+            .collect()
+    }
+}
+
+// Runs `f` with the console of `screen` selected, then selects the previous console again.
+// Returns `false` if there's no console on the screen.
+#[cfg(feature = "logger")]
+pub(crate) fn with_screen_console(screen: ctru_sys::gfxScreen_t, f: impl FnOnce()) -> bool {
+    // The lock is held until the end, so that the console can't be dropped in the meantime.
+    let consoles = SCREEN_CONSOLES.lock().unwrap();
+
+    let Some(&address) = consoles
+        .get(screen as usize)
+        .filter(|&&address| address != 0)
+    else {
+        return false;
+    };
+
+    unsafe {
+        let previous = consoleSelect(address as *mut PrintConsole);
+        f();
+        consoleSelect(previous);
+    }
+
+    true
+}
+
+// Print callback of the consoles with a scrollback, called by libctru for every printed character.
+// Returns `true` if the character shouldn't be drawn.
+unsafe extern "C" fn record_char(console: *mut libc::c_void, c: libc::c_int) -> bool {
+    let Ok(mut scrollbacks) = SCROLLBACKS.try_lock() else {
+        return false;
+    };
+
+    let Some((_, scrollback)) = scrollbacks
+        .iter_mut()
+        .find(|(address, _)| *address == console as usize)
+    else {
+        return false;
+    };
+
+    if !scrollback.recording {
+        return false;
+    }
+
+    scrollback.push_char(c as u8);
+
+    // While scrolled up, new output is only shown when scrolling back to the bottom.
+    scrollback.offset > 0
+}
+
+impl ConsoleWindow<'_> {
+    /// Select this window as the current target for standard output.
+    ///
+    /// Any previously selected console or window will be unhooked, but keeps its cursor position.
+    #[doc(alias = "consoleSelect")]
+    pub fn select(&self) {
+        unsafe {
+            // The parent console may have swapped its framebuffers since the window was created.
+            (*self.context.get()).frameBuffer = (*self.parent.get()).frameBuffer;
+
+            consoleSelect(self.context.get());
+        }
+    }
+
+    /// Clear all text from the window, leaving the rest of the screen untouched.
+    ///
+    /// The cursor is moved back to the top left corner of the window.
+    #[doc(alias = "consoleClear")]
+    pub fn clear(&self) {
+        unsafe {
+            let previous = consoleSelect(self.context.get());
+            consoleClear();
+            consoleSelect(previous);
+        }
+    }
+
+    /// Move the cursor of the window, with coordinates relative to its top left corner.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the position is outside of the window.
+    pub fn set_cursor_position(&self, x: u8, y: u8) -> Result<(), Error> {
+        let context = unsafe { &mut *self.context.get() };
+
+        if i32::from(x) >= context.windowWidth {
+            return Err(Error::CoordinateOutOfBounds(Axis::X));
+        }
+        if i32::from(y) >= context.windowHeight {
+            return Err(Error::CoordinateOutOfBounds(Axis::Y));
+        }
+
+        context.cursorX = x.into();
+        context.cursorY = y.into();
+
+        Ok(())
+    }
 }
 
 impl Swap for Console<'_> {
@@ -356,6 +795,16 @@ impl Flush for Console<'_> {
 
 impl Drop for Console<'_> {
     fn drop(&mut self) {
+        let address = self.context.get() as usize;
+        if let Ok(mut scrollbacks) = SCROLLBACKS.lock() {
+            scrollbacks.retain(|(other, _)| *other != address);
+        }
+        if let Ok(mut consoles) = SCREEN_CONSOLES.lock() {
+            for slot in consoles.iter_mut().filter(|slot| **slot == address) {
+                *slot = 0;
+            }
+        }
+
         unsafe {
             // Safety: We are about to deallocate the PrintConsole data pointed
             // to by libctru. Without this drop code libctru would have a
@@ -380,6 +829,19 @@ impl Drop for Console<'_> {
     }
 }
 
+impl Drop for ConsoleWindow<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            // Safety: same as for the `Console`, libctru must not keep a dangling pointer to the window.
+            let current_console = consoleSelect(self.parent.get());
+
+            if !std::ptr::eq(current_console, self.context.get()) {
+                consoleSelect(current_console);
+            }
+        }
+    }
+}
+
 impl std::fmt::Display for Axis {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -412,3 +874,41 @@ impl std::fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrollback(capacity: usize) -> Scrollback {
+        Scrollback {
+            lines: VecDeque::new(),
+            current: Vec::new(),
+            capacity,
+            width: 4,
+            tab_size: 4,
+            offset: 0,
+            recording: true,
+        }
+    }
+
+    #[test]
+    fn scrollback_wraps_and_pages() {
+        let mut scrollback = scrollback(4);
+
+        for &c in b"abcdef\nx\ty\nz" {
+            scrollback.push_char(c);
+        }
+
+        // Long lines and tabs are wrapped to the width of the window.
+        assert_eq!(scrollback.lines, [&b"abcd"[..], b"ef", b"x   ", b"y"]);
+        assert_eq!(scrollback.visible_lines(2), [&b"y"[..], b"z"]);
+
+        scrollback.offset = 2;
+        assert_eq!(scrollback.visible_lines(2), [&b"ef"[..], b"x   "]);
+
+        // New lines don't move the visible ones while scrolled up, even when the oldest line is dropped.
+        scrollback.push_char(b'\n');
+        assert_eq!(scrollback.lines.len(), 4);
+        assert_eq!(scrollback.visible_lines(2), [&b"ef"[..], b"x   "]);
+    }
+}
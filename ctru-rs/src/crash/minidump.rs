@@ -0,0 +1,288 @@
+//! Minidump writer.
+//!
+//! Writes the crash state in the [minidump](https://learn.microsoft.com/en-us/windows/win32/debug/minidump-files) format,
+//! using the ARM thread context defined by Breakpad. The resulting files can be read by host-side tools such as
+//! `minidump-stackwalk` or `minidump_stackwalk`, and symbolicated against the application's ELF file
+//! (3DSX executables are loaded at the same addresses as in the ELF file).
+
+use std::io::{self, Write};
+
+const SIGNATURE: u32 = u32::from_le_bytes(*b"MDMP");
+const VERSION: u32 = 0xa793;
+
+const THREAD_LIST_STREAM: u32 = 3;
+const MODULE_LIST_STREAM: u32 = 4;
+const MEMORY_LIST_STREAM: u32 = 5;
+const EXCEPTION_STREAM: u32 = 6;
+const SYSTEM_INFO_STREAM: u32 = 7;
+const STREAM_COUNT: u32 = 5;
+
+const PROCESSOR_ARCHITECTURE_ARM: u16 = 5;
+// Breakpad's generic Unix platform ID, the closest match for Horizon.
+const PLATFORM_UNIX: u32 = 0x8000;
+const CONTEXT_ARM_INTEGER: u32 = 0x4000_0002;
+
+const HEADER_LEN: u32 = 32;
+const DIRECTORY_LEN: u32 = 12 * STREAM_COUNT;
+const SYSTEM_INFO_LEN: u32 = 56;
+// Empty string used as the OS service pack name.
+const EMPTY_STRING_LEN: u32 = 6;
+const EXCEPTION_LEN: u32 = 168;
+const CONTEXT_LEN: u32 = 368;
+const THREAD_LIST_LEN: u32 = 4 + 48;
+const MODULE_LIST_LEN: u32 = 4 + 108;
+const MEMORY_LIST_LEN: u32 = 4 + 16;
+
+/// Exception codes, matching the POSIX signals raised for the same faults on other platforms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub(super) enum ExceptionCode {
+    IllegalInstruction = 4,
+    FloatingPoint = 8,
+    SegmentationFault = 11,
+}
+
+/// Crash state to write to the minidump.
+pub(super) struct Crash<'a> {
+    pub thread_id: u32,
+    pub exception_code: ExceptionCode,
+    pub exception_address: u32,
+    /// `r0`-`r12`, `sp`, `lr`, `pc` and `cpsr`.
+    pub registers: [u32; 17],
+    pub stack_start: u32,
+    pub stack: &'a [u8],
+    pub module_base: u32,
+    pub module_size: u32,
+    /// UTF-16 encoded name of the executable.
+    pub module_name: &'a [u16],
+}
+
+// Location of a block of data in the file.
+#[derive(Clone, Copy)]
+struct Location {
+    len: u32,
+    rva: u32,
+}
+
+/// Writes the minidump of `crash` to `out`.
+///
+/// All offsets are computed beforehand so that the file can be written sequentially, without any allocation.
+pub(super) fn write(out: &mut impl Write, crash: &Crash) -> io::Result<()> {
+    let mut rva = HEADER_LEN + DIRECTORY_LEN;
+    let mut next = |len: u32| {
+        let location = Location { len, rva };
+        rva += len.next_multiple_of(4);
+        location
+    };
+
+    let system_info = next(SYSTEM_INFO_LEN);
+    let empty_string = next(EMPTY_STRING_LEN);
+    let exception = next(EXCEPTION_LEN);
+    let context = next(CONTEXT_LEN);
+    let thread_list = next(THREAD_LIST_LEN);
+    let module_list = next(MODULE_LIST_LEN);
+    let module_name = next(4 + 2 * crash.module_name.len() as u32 + 2);
+    let memory_list = next(MEMORY_LIST_LEN);
+    let stack = next(crash.stack.len() as u32);
+
+    let mut out = Writer { out, written: 0 };
+
+    // Header
+    out.u32(SIGNATURE)?;
+    out.u32(VERSION)?;
+    out.u32(STREAM_COUNT)?;
+    out.u32(HEADER_LEN)?;
+    out.u32(0)?; // Checksum
+    out.u32(0)?; // Timestamp
+    out.u64(0)?; // Flags
+
+    // Stream directory
+    for (stream_type, location) in [
+        (SYSTEM_INFO_STREAM, system_info),
+        (EXCEPTION_STREAM, exception),
+        (THREAD_LIST_STREAM, thread_list),
+        (MODULE_LIST_STREAM, module_list),
+        (MEMORY_LIST_STREAM, memory_list),
+    ] {
+        out.u32(stream_type)?;
+        out.location(location)?;
+    }
+
+    // System info
+    out.align_to(system_info)?;
+    out.u16(PROCESSOR_ARCHITECTURE_ARM)?;
+    out.u16(6)?; // Processor level (ARMv6)
+    out.u16(0)?; // Processor revision
+    out.bytes(&[2, 0])?; // Number of processors, product type
+    out.u32(0)?; // Major version
+    out.u32(0)?; // Minor version
+    out.u32(0)?; // Build number
+    out.u32(PLATFORM_UNIX)?;
+    out.u32(empty_string.rva)?;
+    out.u16(0)?; // Suite mask
+    out.u16(0)?; // Reserved
+    out.bytes(&[0; 24])?; // CPU information
+
+    out.align_to(empty_string)?;
+    out.u32(0)?;
+    out.u16(0)?;
+
+    // Exception
+    out.align_to(exception)?;
+    out.u32(crash.thread_id)?;
+    out.u32(0)?; // Alignment
+    out.u32(crash.exception_code as u32)?;
+    out.u32(0)?; // Flags
+    out.u64(0)?; // Nested exception record
+    out.u64(crash.exception_address.into())?;
+    out.u32(0)?; // Number of parameters
+    out.u32(0)?; // Alignment
+    out.bytes(&[0; 15 * 8])?; // Parameters
+    out.location(context)?;
+
+    // Thread context
+    out.align_to(context)?;
+    out.u32(CONTEXT_ARM_INTEGER)?;
+    for register in crash.registers {
+        out.u32(register)?;
+    }
+    // The floating point registers aren't saved by the kernel.
+    out.bytes(&[0; 8 + 32 * 8 + 8 * 4])?;
+
+    // Thread list
+    out.align_to(thread_list)?;
+    out.u32(1)?;
+    out.u32(crash.thread_id)?;
+    out.u32(0)?; // Suspend count
+    out.u32(0)?; // Priority class
+    out.u32(0)?; // Priority
+    out.u64(0)?; // Thread environment block
+    out.u64(crash.stack_start.into())?;
+    out.location(stack)?;
+    out.location(context)?;
+
+    // Module list
+    out.align_to(module_list)?;
+    out.u32(1)?;
+    out.u64(crash.module_base.into())?;
+    out.u32(crash.module_size)?;
+    out.u32(0)?; // Checksum
+    out.u32(0)?; // Timestamp
+    out.u32(module_name.rva)?;
+    out.bytes(&[0; 13 * 4])?; // Version info
+    out.location(Location { len: 0, rva: 0 })?; // CodeView record
+    out.location(Location { len: 0, rva: 0 })?; // Misc record
+    out.u64(0)?; // Reserved
+    out.u64(0)?; // Reserved
+
+    out.align_to(module_name)?;
+    out.u32(2 * crash.module_name.len() as u32)?;
+    for &unit in crash.module_name {
+        out.u16(unit)?;
+    }
+    out.u16(0)?;
+
+    // Memory list
+    out.align_to(memory_list)?;
+    out.u32(1)?;
+    out.u64(crash.stack_start.into())?;
+    out.location(stack)?;
+
+    out.align_to(stack)?;
+    out.bytes(crash.stack)
+}
+
+struct Writer<'a, W> {
+    out: &'a mut W,
+    written: u32,
+}
+
+impl<W: Write> Writer<'_, W> {
+    fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.out.write_all(bytes)?;
+        self.written += bytes.len() as u32;
+        Ok(())
+    }
+
+    fn u16(&mut self, value: u16) -> io::Result<()> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn u32(&mut self, value: u32) -> io::Result<()> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn u64(&mut self, value: u64) -> io::Result<()> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn location(&mut self, location: Location) -> io::Result<()> {
+        self.u32(location.len)?;
+        self.u32(location.rva)
+    }
+
+    // Pads the file up to the start of the block.
+    fn align_to(&mut self, location: Location) -> io::Result<()> {
+        debug_assert!(self.written <= location.rva);
+
+        while self.written < location.rva {
+            self.bytes(&[0])?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn minidump_layout() {
+        let stack = [0xaa; 10];
+        let name: Vec<u16> = "app.3dsx".encode_utf16().collect();
+        let crash = Crash {
+            thread_id: 7,
+            exception_code: ExceptionCode::SegmentationFault,
+            exception_address: 0x1234,
+            registers: [1; 17],
+            stack_start: 0x0ffe_0000,
+            stack: &stack,
+            module_base: 0x0010_0000,
+            module_size: 0x1000,
+            module_name: &name,
+        };
+
+        let mut data = Vec::new();
+        write(&mut data, &crash).unwrap();
+
+        assert_eq!(&data[..4], b"MDMP");
+        assert_eq!(u32_at(&data, 8), STREAM_COUNT);
+
+        // Every stream must be found at the offset listed in the directory.
+        for i in 0..STREAM_COUNT as usize {
+            let entry = HEADER_LEN as usize + 12 * i;
+            let (stream_type, len, rva) = (
+                u32_at(&data, entry),
+                u32_at(&data, entry + 4) as usize,
+                u32_at(&data, entry + 8) as usize,
+            );
+
+            assert!(rva + len <= data.len());
+            match stream_type {
+                THREAD_LIST_STREAM | MODULE_LIST_STREAM | MEMORY_LIST_STREAM => {
+                    assert_eq!(u32_at(&data, rva), 1)
+                }
+                EXCEPTION_STREAM => assert_eq!(u32_at(&data, rva + 8), 11),
+                SYSTEM_INFO_STREAM => assert_eq!(data[rva], 5),
+                _ => unreachable!(),
+            }
+        }
+
+        assert_eq!(&data[data.len() - stack.len()..], stack);
+    }
+}
@@ -0,0 +1,450 @@
+//! Crash reports.
+//!
+//! When a thread triggers an ARM exception (e.g. by dereferencing an invalid pointer), the kernel stops the whole application,
+//! leaving no trace of what went wrong once the console has been rebooted. [`install()`] registers an exception handler which
+//! writes a plain text report to the SD card instead, containing:
+//!
+//! * the exception type and fault status,
+//! * the CPU registers of the faulting thread,
+//! * a dump of the top of its stack,
+//! * the memory regions mapped by the application,
+//! * the last lines passed to [`record_line()`].
+//!
+//! A [minidump](set_minidump_path) of the crash can also be written, to be analyzed with host-side tools.
+//! When the `symbols` feature is enabled, addresses are also resolved with the installed symbol table.
+//!
+//! After the report is written, the application breaks as it would have without the handler,
+//! so that debuggers and custom firmwares can still catch the exception.
+//!
+//! # Notes
+//!
+//! Exception handlers are registered per thread: [`install()`] must be called from every thread which should be covered.
+//!
+//! Panics don't trigger exceptions, and are not reported by this module. Have a look at
+//! [`set_panic_hook()`](crate::applets::error::set_panic_hook) to display them.
+#![doc(alias = "exception")]
+#![doc(alias = "threadOnException")]
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::fmt::{self, Write};
+use std::io;
+use std::ops::Range;
+use std::ptr::NonNull;
+use std::sync::{Mutex, OnceLock};
+
+mod minidump;
+
+// Number of lines kept by `record_line()`.
+const RECENT_LINES: usize = 32;
+// Number of bytes dumped from the top of the faulting stack.
+const STACK_DUMP_LEN: u32 = 0x200;
+// Maximum number of bytes of the faulting stack included in minidumps.
+const MINIDUMP_STACK_LEN: u32 = 0x10000;
+// Start and end of the user-space address range walked to list the mapped memory regions.
+const USER_SPACE: (u32, u32) = (0x0010_0000, 0x4000_0000);
+const HANDLER_STACK_LEN: usize = 0x4000;
+
+// The handler can't rely on the stack of the faulting thread, which may be the cause of the exception.
+#[repr(C, align(8))]
+struct HandlerStack([u8; HANDLER_STACK_LEN]);
+
+static REPORT_PATH: OnceLock<CString> = OnceLock::new();
+static MINIDUMP_PATH: OnceLock<(CString, Vec<u16>)> = OnceLock::new();
+static LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+thread_local! {
+    // Top of the handler stack of the current thread. Every thread gets its own stack, so that threads faulting
+    // at the same time don't overwrite each other's handler frames.
+    static HANDLER_STACK_TOP: Cell<Option<NonNull<u8>>> = const { Cell::new(None) };
+}
+
+/// Installs the crash report handler for the current thread.
+///
+/// Reports are written to `path`, replacing any previous report. The path is set by the first call to this function,
+/// and is ignored by following calls (e.g. made to install the handler on other threads).
+///
+/// # Notes
+///
+/// The handler of each thread runs on its own stack of 16 KiB, which is allocated by the first call from that thread
+/// and never freed (the handler may still run while the thread is exiting).
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// #
+/// ctru::crash::install("sdmc:/3ds/my-app/crash.txt");
+///
+/// ctru::crash::record_line("loading level 1");
+/// ```
+#[doc(alias = "threadOnException")]
+pub fn install(path: &str) {
+    // Interior NUL bytes can't be part of a valid path anyway.
+    REPORT_PATH.get_or_init(|| CString::new(path.replace('\0', "")).unwrap());
+
+    let stack_top = HANDLER_STACK_TOP.with(|top| {
+        let stack_top = top.get().unwrap_or_else(|| {
+            // SAFETY: the stack is plain bytes, for which zeroes are valid.
+            let stack = unsafe { Box::<HandlerStack>::new_zeroed().assume_init() };
+
+            // SAFETY: the pointer is one past the end of the same (non-null) allocation.
+            unsafe {
+                NonNull::new_unchecked(Box::leak(stack).0.as_mut_ptr().add(HANDLER_STACK_LEN))
+            }
+        });
+
+        top.set(Some(stack_top));
+        stack_top
+    });
+
+    // SAFETY: the handler stack is leaked, so it stays valid for as long as the handler is installed.
+    unsafe {
+        ctru_sys::threadOnException(
+            Some(exception_handler),
+            stack_top.as_ptr().cast(),
+            // Write the exception data to the handler stack.
+            std::ptr::null_mut(),
+        );
+    }
+}
+
+/// Sets the path where a minidump is written when a crash occurs, in addition to the text report.
+///
+/// Minidumps contain the registers and the stack of the faulting thread, and can be analyzed with tools
+/// such as `minidump-stackwalk` together with the application's ELF file to get a symbolicated stack trace.
+///
+/// Like the text report path, the minidump path can only be set once. The crash handler must still be installed via [`install()`].
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// #
+/// ctru::crash::set_minidump_path("sdmc:/3ds/my-app/crash.dmp");
+/// ctru::crash::install("sdmc:/3ds/my-app/crash.txt");
+/// ```
+#[doc(alias = "breakpad")]
+pub fn set_minidump_path(path: &str) {
+    MINIDUMP_PATH.get_or_init(|| {
+        let module_name = std::env::args()
+            .next()
+            .unwrap_or_else(|| "application".to_owned());
+
+        (
+            CString::new(path.replace('\0', "")).unwrap(),
+            module_name.encode_utf16().collect(),
+        )
+    });
+}
+
+/// Records a line of text to be included in the next crash report.
+///
+/// Only the last few lines are kept. This is meant to leave breadcrumbs of what the application was doing before crashing.
+pub fn record_line(line: impl Into<String>) {
+    let mut lines = LINES.lock().unwrap_or_else(|e| e.into_inner());
+
+    if lines.len() == RECENT_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(line.into());
+}
+
+// Writes directly to a file descriptor, without allocating memory (the allocator may be in an inconsistent state).
+struct FdWriter(libc::c_int);
+
+impl Write for FdWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut bytes = s.as_bytes();
+
+        while !bytes.is_empty() {
+            let written = unsafe { libc::write(self.0, bytes.as_ptr().cast(), bytes.len()) };
+            if written <= 0 {
+                return Err(fmt::Error);
+            }
+            bytes = &bytes[written as usize..];
+        }
+
+        Ok(())
+    }
+}
+
+impl io::Write for FdWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = unsafe { libc::write(self.0, buf.as_ptr().cast(), buf.len()) };
+
+        if written < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(written as usize)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn exception_handler(
+    info: *mut ctru_sys::ERRF_ExceptionInfo,
+    regs: *mut ctru_sys::CpuRegisters,
+) {
+    // SAFETY: libctru passes the exception data and the registers it saved on the handler stack,
+    // which stay valid until the handler returns.
+    let (info, regs) = unsafe { (&*info, &*regs) };
+
+    if let Some(path) = REPORT_PATH.get() {
+        if let Some(fd) = create_file(path) {
+            let _ = write_report(&mut FdWriter(fd), info, regs);
+            // SAFETY: the descriptor was opened above, and isn't used afterwards.
+            unsafe { libc::close(fd) };
+        }
+    }
+
+    if let Some((path, module_name)) = MINIDUMP_PATH.get() {
+        if let Some(fd) = create_file(path) {
+            let _ = write_minidump(&mut FdWriter(fd), info, regs, module_name);
+            // SAFETY: the descriptor was opened above, and isn't used afterwards.
+            unsafe { libc::close(fd) };
+        }
+    }
+
+    // SAFETY: breaking is always allowed, and is what the kernel would have done without the handler.
+    unsafe { ctru_sys::svcBreak(ctru_sys::USERBREAK_PANIC) };
+}
+
+// Opens (and truncates) a file for writing, returning its descriptor.
+fn create_file(path: &CStr) -> Option<libc::c_int> {
+    // SAFETY: `path` is a valid NUL-terminated string.
+    let fd = unsafe {
+        libc::open(
+            path.as_ptr(),
+            libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC,
+            0o644,
+        )
+    };
+
+    (fd >= 0).then_some(fd)
+}
+
+// Returns the addresses of the (up to) `len` bytes starting at the stack pointer `sp`, if they're mapped and readable.
+// The handler must never read outside of this range, or it would fault itself.
+fn stack_range(sp: u32, len: u32) -> Option<Range<u32>> {
+    let region = query_memory(sp)?;
+    if region.perm & ctru_sys::MEMPERM_READ == 0 {
+        return None;
+    }
+
+    Some(sp..(region.base_addr + region.size).min(sp.saturating_add(len)))
+}
+
+fn write_report(
+    out: &mut FdWriter,
+    info: &ctru_sys::ERRF_ExceptionInfo,
+    regs: &ctru_sys::CpuRegisters,
+) -> fmt::Result {
+    writeln!(out, "ctru-rs crash report")?;
+    writeln!(out)?;
+
+    writeln!(out, "Exception: {}", exception_name(info.type_))?;
+    writeln!(out, "FSR: {:08x}  FAR: {:08x}", info.fsr, info.far)?;
+    writeln!(
+        out,
+        "FPEXC: {:08x}  FPINST: {:08x}  FPINST2: {:08x}",
+        info.fpexc, info.fpinst, info.fpinst2
+    )?;
+    writeln!(out)?;
+
+    writeln!(out, "Registers:")?;
+    for (i, r) in regs.r.iter().enumerate() {
+        write!(
+            out,
+            "r{i:<2}: {r:08x}{}",
+            if i % 4 == 3 { "\n" } else { "  " }
+        )?;
+    }
+    writeln!(out, "sp : {:08x}  lr : {:08x}", regs.sp, regs.lr)?;
+    writeln!(out, "pc : {:08x}  cpsr: {:08x}", regs.pc, regs.cpsr)?;
+    writeln!(out)?;
+
+    #[cfg(feature = "symbols")]
+    if let Some(table) = crate::symbols::SymbolTable::installed() {
+        write_symbols(out, table, regs)?;
+    }
+
+    writeln!(out, "Stack:")?;
+    if let Some(stack) = stack_range(regs.sp, STACK_DUMP_LEN) {
+        for row in (stack.start & !3..stack.end).step_by(16) {
+            write!(out, "{row:08x}:")?;
+            for word in (row..(row + 16).min(stack.end)).step_by(4) {
+                // SAFETY: the word is part of a mapped and readable region, since the region starts on a page boundary.
+                let value = unsafe { (word as *const u32).read_volatile() };
+                write!(out, " {value:08x}")?;
+            }
+            writeln!(out)?;
+        }
+    } else {
+        writeln!(out, "stack pointer is not mapped")?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "Memory regions:")?;
+    let mut addr = USER_SPACE.0;
+    while addr < USER_SPACE.1 {
+        let Some(region) = query_memory(addr) else {
+            break;
+        };
+
+        if region.state != ctru_sys::MEMSTATE_FREE {
+            writeln!(
+                out,
+                "{:08x}-{:08x} perm {:x} state {:x}",
+                region.base_addr,
+                region.base_addr + region.size,
+                region.perm,
+                region.state
+            )?;
+        }
+
+        if region.size == 0 {
+            break;
+        }
+        addr = region.base_addr + region.size;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "Recent lines:")?;
+    // The lock may be held by the faulting thread.
+    match LINES.try_lock() {
+        Ok(lines) => {
+            for line in lines.iter() {
+                writeln!(out, "{line}")?;
+            }
+        }
+        Err(_) => writeln!(out, "unavailable")?,
+    }
+
+    Ok(())
+}
+
+// Resolves the program counter, the link register, and the words found on the stack which point into known functions
+// (which are likely return addresses).
+#[cfg(feature = "symbols")]
+fn write_symbols(
+    out: &mut FdWriter,
+    table: &crate::symbols::SymbolTable,
+    regs: &ctru_sys::CpuRegisters,
+) -> fmt::Result {
+    writeln!(out, "Symbols:")?;
+
+    for (name, address) in [("pc", regs.pc), ("lr", regs.lr)] {
+        if let Some(location) = table.resolve(address) {
+            writeln!(out, "{name} : {address:08x} {location}")?;
+        }
+    }
+
+    if let Some(stack) = stack_range(regs.sp, STACK_DUMP_LEN) {
+        for word in (stack.start & !3..stack.end).step_by(4) {
+            // SAFETY: the word is part of a mapped and readable region, since the region starts on a page boundary.
+            let value = unsafe { (word as *const u32).read_volatile() };
+
+            if let Some(location) = table.resolve(value) {
+                writeln!(out, "[{word:08x}]: {value:08x} {location}")?;
+            }
+        }
+    }
+
+    writeln!(out)
+}
+
+fn write_minidump(
+    out: &mut FdWriter,
+    info: &ctru_sys::ERRF_ExceptionInfo,
+    regs: &ctru_sys::CpuRegisters,
+    module_name: &[u16],
+) -> io::Result<()> {
+    let mut thread_id = 0;
+    // SAFETY: the pseudo-handle of the current thread is always valid.
+    let _ = unsafe { ctru_sys::svcGetThreadId(&mut thread_id, ctru_sys::CUR_THREAD_HANDLE) };
+
+    let (exception_code, exception_address) = match info.type_ {
+        ctru_sys::ERRF_EXCEPTION_DATA_ABORT => {
+            (minidump::ExceptionCode::SegmentationFault, info.far)
+        }
+        ctru_sys::ERRF_EXCEPTION_UNDEFINED => {
+            (minidump::ExceptionCode::IllegalInstruction, regs.pc)
+        }
+        ctru_sys::ERRF_EXCEPTION_VFP => (minidump::ExceptionCode::FloatingPoint, regs.pc),
+        _ => (minidump::ExceptionCode::SegmentationFault, regs.pc),
+    };
+
+    let stack = match stack_range(regs.sp, MINIDUMP_STACK_LEN) {
+        // SAFETY: `stack_range()` only returns ranges inside a single mapped and readable region, which stays mapped
+        // while the handler runs (the faulting thread is stopped, and the other threads don't unmap stacks).
+        Some(stack) => unsafe { std::slice::from_raw_parts(stack.start as *const u8, stack.len()) },
+        None => &[],
+    };
+
+    // The executable is mapped first, followed by its data, in contiguous regions.
+    let mut module_end = USER_SPACE.0;
+    while let Some(region) = query_memory(module_end) {
+        if region.state == ctru_sys::MEMSTATE_FREE || region.size == 0 {
+            break;
+        }
+        module_end = region.base_addr + region.size;
+    }
+
+    let mut registers = [0; 17];
+    registers[..13].copy_from_slice(&regs.r);
+    registers[13..].copy_from_slice(&[regs.sp, regs.lr, regs.pc, regs.cpsr]);
+
+    minidump::write(
+        out,
+        &minidump::Crash {
+            thread_id,
+            exception_code,
+            exception_address,
+            registers,
+            stack_start: regs.sp,
+            stack,
+            module_base: USER_SPACE.0,
+            module_size: module_end - USER_SPACE.0,
+            module_name,
+        },
+    )
+}
+
+fn query_memory(addr: u32) -> Option<ctru_sys::MemInfo> {
+    let mut info = ctru_sys::MemInfo::default();
+    let mut page = ctru_sys::PageInfo::default();
+
+    let res = unsafe { ctru_sys::svcQueryMemory(&mut info, &mut page, addr) };
+    (res >= 0).then_some(info)
+}
+
+fn exception_name(exception_type: ctru_sys::ERRF_ExceptionType) -> &'static str {
+    match exception_type {
+        ctru_sys::ERRF_EXCEPTION_PREFETCH_ABORT => "prefetch abort",
+        ctru_sys::ERRF_EXCEPTION_DATA_ABORT => "data abort",
+        ctru_sys::ERRF_EXCEPTION_UNDEFINED => "undefined instruction",
+        ctru_sys::ERRF_EXCEPTION_VFP => "VFP (floating point) exception",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_lines() {
+        for i in 0..RECENT_LINES + 2 {
+            record_line(format!("line {i}"));
+        }
+
+        let lines = LINES.lock().unwrap();
+        assert_eq!(lines.len(), RECENT_LINES);
+        assert_eq!(lines[0], "line 2");
+    }
+}
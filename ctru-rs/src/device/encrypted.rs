@@ -0,0 +1,311 @@
+//! Encrypted container backend.
+//!
+//! The SD card can be freely read by any other application (or by removing it from the console),
+//! which makes it unsuitable to store sensitive data such as user credentials or authentication tokens.
+//! [`EncryptedDevice`] stores each file in an encrypted container on another device (usually the SD card),
+//! using keys that never leave the console's security processor via the [`Ps`] service.
+//!
+//! # Container format
+//!
+//! Every container starts with a header holding the random nonce used for the file, followed by the file contents
+//! encrypted and authenticated together with the header using AES-CCM, and the resulting MAC.
+//!
+//! Containers which were modified or truncated, encrypted with a different key, or copied from another console
+//! (when using a console-unique keyslot) fail to open.
+#![doc(alias = "crypto")]
+
+use std::fs;
+use std::io::{self, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use super::{DirEntry, Metadata, OpenOptions};
+use crate::services::ps::{AESKeyType, Ps};
+
+const MAGIC: [u8; 4] = *b"CENC";
+const VERSION: u32 = 1;
+
+// Plain header data, authenticated together with the contents.
+const HEADER_LEN: usize = 48;
+// Length of the MAC following the encrypted contents.
+const MAC_LEN: usize = 16;
+
+/// [`Device`](super::Device) storing encrypted files in a directory of another device.
+///
+/// Files are decrypted in memory when opened, and encrypted back when closed if they were modified.
+/// As such, this backend is meant for small files only.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::device::{self, encrypted::EncryptedDevice};
+/// use ctru::services::ps::AESKeyType;
+///
+/// let secure = EncryptedDevice::new("sdmc:/3ds/my-app/secure", AESKeyType::Keyslot0D)?;
+/// let _registration = device::register("secure", secure)?;
+///
+/// std::fs::write("secure:/token", "my-auth-token")?;
+/// assert_eq!(std::fs::read_to_string("secure:/token")?, "my-auth-token");
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct EncryptedDevice {
+    root: PathBuf,
+    key_type: AESKeyType,
+    ps: Ps,
+}
+
+/// File opened on an [`EncryptedDevice`].
+pub struct EncryptedFile {
+    path: PathBuf,
+    data: Vec<u8>,
+    position: usize,
+    options: OpenOptions,
+    modified: bool,
+}
+
+struct Header {
+    nonce: [u8; 12],
+}
+
+impl EncryptedDevice {
+    /// Creates a new backend storing its containers in the `root` directory, encrypted with the key of the specified keyslot.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the [`Ps`] service couldn't be initialized.
+    pub fn new(root: impl Into<PathBuf>, key_type: AESKeyType) -> crate::Result<Self> {
+        Ok(Self {
+            root: root.into(),
+            key_type,
+            ps: Ps::new()?,
+        })
+    }
+
+    fn host_path(&self, path: &str) -> io::Result<PathBuf> {
+        let path = Path::new(path.trim_start_matches('/'));
+
+        // Containers must not escape the root directory.
+        if path
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "path escapes the device root",
+            ));
+        }
+
+        Ok(self.root.join(path))
+    }
+
+    pub(crate) fn seal(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut nonce = [0; 12];
+        self.ps
+            .generate_random_bytes(&mut nonce)
+            .map_err(io::Error::other)?;
+
+        let header = Header { nonce }.to_bytes();
+
+        let sealed = self
+            .ps
+            .encrypt_sign_aes_ccm(&header, data, self.key_type, &nonce)
+            .map_err(io::Error::other)?;
+
+        Ok([&header[..], &sealed[..]].concat())
+    }
+
+    pub(crate) fn unseal(&self, container: &[u8]) -> io::Result<Vec<u8>> {
+        if container.len() < HEADER_LEN + MAC_LEN {
+            return Err(invalid_container("container is truncated"));
+        }
+
+        let (header_bytes, sealed) = container.split_at(HEADER_LEN);
+        let header = Header::parse(header_bytes)?;
+
+        self.ps
+            .decrypt_verify_aes_ccm(header_bytes, sealed, self.key_type, &header.nonce)
+            .map_err(|_| invalid_container("container authentication failed"))
+    }
+}
+
+impl super::Device for EncryptedDevice {
+    type File = EncryptedFile;
+    type Dir = fs::ReadDir;
+
+    fn open(&self, path: &str, options: OpenOptions) -> io::Result<EncryptedFile> {
+        let path = self.host_path(path)?;
+
+        let (data, modified) = match fs::read(&path) {
+            Ok(_) if options.create_new => return Err(io::ErrorKind::AlreadyExists.into()),
+            Ok(_) if options.truncate => (Vec::new(), true),
+            Ok(container) => (self.unseal(&container)?, false),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && options.create => (Vec::new(), true),
+            Err(e) => return Err(e),
+        };
+
+        Ok(EncryptedFile {
+            path,
+            data,
+            position: 0,
+            options,
+            modified,
+        })
+    }
+
+    fn read(&self, file: &mut EncryptedFile, buf: &mut [u8]) -> io::Result<usize> {
+        if !file.options.read {
+            return Err(io::ErrorKind::PermissionDenied.into());
+        }
+
+        let remaining = file.data.get(file.position..).unwrap_or_default();
+        let len = remaining.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&remaining[..len]);
+        file.position += len;
+
+        Ok(len)
+    }
+
+    fn write(&self, file: &mut EncryptedFile, buf: &[u8]) -> io::Result<usize> {
+        if !file.options.write {
+            return Err(io::ErrorKind::PermissionDenied.into());
+        }
+
+        if file.options.append {
+            file.position = file.data.len();
+        }
+
+        let end = file
+            .position
+            .checked_add(buf.len())
+            .ok_or(io::ErrorKind::InvalidInput)?;
+        if end > file.data.len() {
+            file.data.resize(end, 0);
+        }
+
+        file.data[file.position..end].copy_from_slice(buf);
+        file.position = end;
+        file.modified = true;
+
+        Ok(buf.len())
+    }
+
+    fn seek(&self, file: &mut EncryptedFile, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => (file.position as u64).checked_add_signed(offset),
+            SeekFrom::End(offset) => (file.data.len() as u64).checked_add_signed(offset),
+        };
+
+        let position = position.ok_or(io::ErrorKind::InvalidInput)?;
+        file.position = position
+            .try_into()
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+        Ok(position)
+    }
+
+    fn close(&self, file: EncryptedFile) -> io::Result<()> {
+        if file.modified {
+            fs::write(&file.path, self.seal(&file.data)?)?;
+        }
+
+        Ok(())
+    }
+
+    fn file_metadata(&self, file: &mut EncryptedFile) -> io::Result<Metadata> {
+        Ok(Metadata {
+            is_dir: false,
+            len: file.data.len() as u64,
+        })
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        Ok(host_metadata(&fs::metadata(self.host_path(path)?)?))
+    }
+
+    fn open_dir(&self, path: &str) -> io::Result<fs::ReadDir> {
+        fs::read_dir(self.host_path(path)?)
+    }
+
+    fn dir_next(&self, dir: &mut fs::ReadDir) -> io::Result<Option<DirEntry>> {
+        let Some(entry) = dir.next().transpose()? else {
+            return Ok(None);
+        };
+
+        Ok(Some(DirEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            metadata: host_metadata(&entry.metadata()?),
+        }))
+    }
+}
+
+impl Header {
+    fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4..8].copy_from_slice(&VERSION.to_le_bytes());
+        bytes[8..20].copy_from_slice(&self.nonce);
+
+        bytes
+    }
+
+    fn parse(bytes: &[u8]) -> io::Result<Self> {
+        if bytes[0..4] != MAGIC {
+            return Err(invalid_container("not an encrypted container"));
+        }
+
+        if bytes[4..8] != VERSION.to_le_bytes() {
+            return Err(invalid_container("unsupported container version"));
+        }
+
+        Ok(Self {
+            nonce: bytes[8..20].try_into().unwrap(),
+        })
+    }
+}
+
+// Metadata of a container, as seen from the encrypted device.
+fn host_metadata(metadata: &fs::Metadata) -> Metadata {
+    if metadata.is_dir() {
+        Metadata {
+            is_dir: true,
+            len: 0,
+        }
+    } else {
+        Metadata {
+            is_dir: false,
+            len: metadata.len().saturating_sub((HEADER_LEN + MAC_LEN) as u64),
+        }
+    }
+}
+
+fn invalid_container(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_round_trip() {
+        let header = Header { nonce: [2; 12] };
+        let bytes = header.to_bytes();
+
+        let parsed = Header::parse(&bytes).unwrap();
+        assert_eq!(parsed.nonce, header.nonce);
+
+        let mut corrupted = bytes;
+        corrupted[0] = b'X';
+        assert_eq!(
+            Header::parse(&corrupted).err().unwrap().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
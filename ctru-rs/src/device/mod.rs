@@ -0,0 +1,636 @@
+//! Custom filesystem devices.
+//!
+//! The C standard library used on the Nintendo 3DS (`newlib`) routes all path-based operations through a table of devices,
+//! each identified by the prefix of the path (e.g. `sdmc:/` or `romfs:/`).
+//! Registering a new device makes its paths accessible from the standard library, such as [`std::fs`].
+//!
+//! Custom backends (e.g. network filesystems or encrypted containers) can be implemented via the [`Device`] trait,
+//! and then made available with [`register()`].
+#![doc(alias = "devoptab")]
+
+pub mod encrypted;
+
+use std::any::Any;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::io::{self, SeekFrom};
+use std::mem;
+use std::sync::Arc;
+
+use crate::{Error, Result};
+
+/// Registration of a custom device in the `newlib` device table.
+///
+/// The device is removed from the table when this struct is dropped.
+///
+/// This struct can be retrieved via [`register()`] or [`register_device()`].
+#[must_use = "the device is unregistered when dropped"]
+pub struct DeviceRegistration {
+    // Name of the device, including the trailing colon.
+    prefix: CString,
+    // Operation table and device data, if owned by the registration.
+    _owned: Option<Box<dyn Any>>,
+}
+
+impl DeviceRegistration {
+    /// Returns the name of the registered device (without the trailing colon).
+    pub fn name(&self) -> &str {
+        let prefix = self.prefix.to_str().unwrap();
+        &prefix[..prefix.len() - 1]
+    }
+}
+
+/// Options used to open a file on a [`Device`].
+///
+/// These mirror the options of [`std::fs::OpenOptions`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpenOptions {
+    /// The file should be readable.
+    pub read: bool,
+    /// The file should be writable.
+    pub write: bool,
+    /// Writes should always happen at the end of the file.
+    pub append: bool,
+    /// The file should be truncated to length 0 when opened.
+    pub truncate: bool,
+    /// The file should be created if it doesn't exist.
+    pub create: bool,
+    /// The file must not exist already and should be created.
+    pub create_new: bool,
+}
+
+/// Metadata about a file or directory stored on a [`Device`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// Whether the entry is a directory.
+    pub is_dir: bool,
+    /// Size of the entry (in bytes).
+    pub len: u64,
+}
+
+/// Entry of a directory stored on a [`Device`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    /// Name of the entry (without its parent path).
+    pub name: String,
+    /// Metadata of the entry.
+    pub metadata: Metadata,
+}
+
+/// Filesystem backend which can be registered as a `newlib` device.
+///
+/// Paths passed to the trait functions are relative to the device root, i.e. they don't include the `name:` prefix.
+///
+/// The functions may be called from any thread that accesses the device, so implementors must be thread-safe.
+/// Optional operations are reported as [unsupported](io::ErrorKind::Unsupported) by default.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::io;
+///
+/// use ctru::device::{self, Device, OpenOptions};
+///
+/// // Device on which every file is an endless stream of zeroes.
+/// struct Zero;
+///
+/// impl Device for Zero {
+///     type File = ();
+///     type Dir = ();
+///
+///     fn open(&self, _path: &str, _options: OpenOptions) -> io::Result<()> {
+///         Ok(())
+///     }
+///
+///     fn read(&self, _file: &mut (), buf: &mut [u8]) -> io::Result<usize> {
+///         buf.fill(0);
+///         Ok(buf.len())
+///     }
+/// }
+///
+/// let _registration = device::register("zero", Zero)?;
+///
+/// let mut buf = [1; 16];
+/// io::Read::read_exact(&mut std::fs::File::open("zero:/anything")?, &mut buf)?;
+/// assert_eq!(buf, [0; 16]);
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub trait Device: Send + Sync + 'static {
+    /// Handle of an open file.
+    type File: Send;
+
+    /// Handle of an open directory.
+    type Dir: Send;
+
+    /// Opens the file at the specified path.
+    fn open(&self, path: &str, options: OpenOptions) -> io::Result<Self::File>;
+
+    /// Reads from the file into the specified buffer, returning how many bytes were read.
+    fn read(&self, file: &mut Self::File, buf: &mut [u8]) -> io::Result<usize> {
+        let _ = (file, buf);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Writes the specified buffer into the file, returning how many bytes were written.
+    fn write(&self, file: &mut Self::File, buf: &[u8]) -> io::Result<usize> {
+        let _ = (file, buf);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Moves the cursor of the file, returning the new position from the start of the file.
+    fn seek(&self, file: &mut Self::File, pos: SeekFrom) -> io::Result<u64> {
+        let _ = (file, pos);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Closes the file.
+    ///
+    /// The file is dropped right after this call, even if an error is returned.
+    fn close(&self, file: Self::File) -> io::Result<()> {
+        drop(file);
+        Ok(())
+    }
+
+    /// Returns the metadata of an open file.
+    fn file_metadata(&self, file: &mut Self::File) -> io::Result<Metadata> {
+        let _ = file;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Returns the metadata of the entry at the specified path.
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let _ = path;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Opens the directory at the specified path.
+    fn open_dir(&self, path: &str) -> io::Result<Self::Dir> {
+        let _ = path;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Returns the next entry of the directory, or [`None`] if all entries were already returned.
+    fn dir_next(&self, dir: &mut Self::Dir) -> io::Result<Option<DirEntry>> {
+        let _ = dir;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Closes the directory.
+    ///
+    /// The directory is dropped right after this call, even if an error is returned.
+    fn close_dir(&self, dir: Self::Dir) -> io::Result<()> {
+        drop(dir);
+        Ok(())
+    }
+}
+
+/// Registers a [`Device`] in the `newlib` device table.
+///
+/// Once registered, paths starting with the device name (e.g. `name:/file.txt`) are handled by `device`,
+/// which makes them accessible via [`std::fs`].
+///
+/// # Notes
+///
+/// Files and directories still open once the registration is dropped can't be accessed anymore, but are never freed.
+///
+/// # Errors
+///
+/// This function will return an error if the device name is invalid, already in use, or if the device table is full.
+#[doc(alias = "AddDevice")]
+pub fn register<D: Device>(name: &str, device: D) -> Result<DeviceRegistration> {
+    let name =
+        CString::new(name).map_err(|_| Error::Other("device name contains a nul byte".into()))?;
+    let device = Box::new(Arc::new(device));
+
+    let optab = Box::new(ctru_sys::devoptab_t {
+        name: name.as_ptr(),
+        structSize: mem::size_of::<*mut OpenFile<D>>(),
+        open_r: Some(open_r::<D>),
+        close_r: Some(close_r::<D>),
+        write_r: Some(write_r::<D>),
+        read_r: Some(read_r::<D>),
+        seek_r: Some(seek_r::<D>),
+        fstat_r: Some(fstat_r::<D>),
+        stat_r: Some(stat_r::<D>),
+        dirStateSize: mem::size_of::<*mut OpenDir<D>>(),
+        diropen_r: Some(diropen_r::<D>),
+        dirnext_r: Some(dirnext_r::<D>),
+        dirclose_r: Some(dirclose_r::<D>),
+        deviceData: (&*device as *const Arc<D>).cast_mut().cast(),
+        ..Default::default()
+    });
+
+    // SAFETY: the operation table and its data live as long as the registration.
+    let prefix = unsafe { add_device(&optab)? };
+
+    Ok(DeviceRegistration {
+        prefix,
+        _owned: Some(Box::new((optab, device, name))),
+    })
+}
+
+/// Registers a raw device operation table in the `newlib` device table.
+///
+/// Once registered, paths starting with the device name (e.g. `name:/file.txt`) are handled by the callbacks of `optab`.
+/// Prefer using [`register()`] unless full control over the device callbacks is needed.
+///
+/// # Errors
+///
+/// This function will return an error if the device name is invalid, already in use, or if the device table is full.
+///
+/// # Safety
+///
+/// The `name` field of `optab` must point to a valid nul-terminated string, and all its callbacks must behave as
+/// `newlib` expects (i.e. return `-1` and set the `errno` field of the reentrancy structure on failure).
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::device::register_device;
+///
+/// static NULL_DEVICE: ctru_sys::devoptab_t = ctru_sys::devoptab_t {
+///     name: c"null".as_ptr(),
+///     // ...
+/// #   ..unsafe { std::mem::zeroed() }
+/// };
+///
+/// let registration = unsafe { register_device(&NULL_DEVICE)? };
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "AddDevice")]
+pub unsafe fn register_device(optab: &'static ctru_sys::devoptab_t) -> Result<DeviceRegistration> {
+    // SAFETY: the caller guarantees the validity of the operation table.
+    let prefix = unsafe { add_device(optab)? };
+
+    Ok(DeviceRegistration {
+        prefix,
+        _owned: None,
+    })
+}
+
+// Adds the operation table to the device table, returning the path prefix of the device.
+//
+// SAFETY: the operation table must be valid until it's removed from the device table.
+unsafe fn add_device(optab: &ctru_sys::devoptab_t) -> Result<CString> {
+    let name = unsafe { CStr::from_ptr(optab.name) };
+    let name = name
+        .to_str()
+        .map_err(|_| Error::Other("device name is not valid UTF-8".into()))?;
+
+    if name.is_empty() || name.contains(['/', ':']) {
+        return Err(Error::Other(format!("invalid device name: {name:?}")));
+    }
+
+    // `newlib` looks up devices using the full path prefix.
+    let prefix = CString::new(format!("{name}:")).unwrap();
+
+    // `AddDevice` silently replaces any device with the same name, so we must check beforehand.
+    if unsafe { ctru_sys::FindDevice(prefix.as_ptr()) } >= 0 {
+        return Err(Error::Other(format!(
+            "device {name:?} is already registered"
+        )));
+    }
+
+    if unsafe { ctru_sys::AddDevice(optab) } < 0 {
+        return Err(Error::Other("the device table is full".into()));
+    }
+
+    Ok(prefix)
+}
+
+impl Drop for DeviceRegistration {
+    #[doc(alias = "RemoveDevice")]
+    fn drop(&mut self) {
+        unsafe {
+            ctru_sys::RemoveDevice(self.prefix.as_ptr());
+        }
+    }
+}
+
+impl OpenOptions {
+    fn from_flags(flags: c_int) -> Self {
+        let access = flags & (libc::O_WRONLY | libc::O_RDWR);
+
+        Self {
+            read: access != libc::O_WRONLY,
+            write: access != libc::O_RDONLY,
+            append: flags & libc::O_APPEND != 0,
+            truncate: flags & libc::O_TRUNC != 0,
+            create: flags & libc::O_CREAT != 0,
+            create_new: flags & libc::O_CREAT != 0 && flags & libc::O_EXCL != 0,
+        }
+    }
+}
+
+// `newlib` provides a buffer of `NAME_MAX + 1` bytes for directory entry names.
+const NAME_MAX: usize = 255;
+
+// State stored by `newlib` for each open file.
+struct OpenFile<D: Device> {
+    device: Arc<D>,
+    file: D::File,
+}
+
+// State stored by `newlib` for each open directory.
+struct OpenDir<D: Device> {
+    device: Arc<D>,
+    dir: D::Dir,
+}
+
+// Callbacks invoked by `newlib`.
+//
+// Callbacks receiving a path retrieve the device via the operation table it was registered with,
+// while the others find it in the state of the open file or directory.
+// Errors are reported by setting `errno` and returning `-1` (or a null pointer).
+
+unsafe fn device_for_path<'a, D: Device>(path: *const c_char) -> (&'a Arc<D>, &'a str) {
+    unsafe {
+        let optab = ctru_sys::GetDeviceOpTab(path);
+        let device = &*(*optab).deviceData.cast::<Arc<D>>();
+
+        // Device paths are always valid UTF-8, since they come from Rust strings.
+        let path = CStr::from_ptr(path).to_str().unwrap_or_default();
+        let path = path.split_once(':').map_or(path, |(_, path)| path);
+
+        (device, path)
+    }
+}
+
+fn set_errno(err: &io::Error) {
+    let code = err.raw_os_error().unwrap_or(match err.kind() {
+        io::ErrorKind::NotFound => libc::ENOENT,
+        io::ErrorKind::PermissionDenied => libc::EACCES,
+        io::ErrorKind::AlreadyExists => libc::EEXIST,
+        io::ErrorKind::InvalidInput => libc::EINVAL,
+        io::ErrorKind::Unsupported => libc::ENOSYS,
+        _ => libc::EIO,
+    });
+
+    unsafe { *ctru_sys::__errno() = code };
+}
+
+fn metadata_to_stat(metadata: Metadata, st: *mut libc::stat) {
+    // SAFETY: `newlib` always passes a valid `stat` structure.
+    unsafe {
+        *st = mem::zeroed();
+        (*st).st_mode = if metadata.is_dir {
+            libc::S_IFDIR
+        } else {
+            libc::S_IFREG
+        } | 0o777;
+        (*st).st_size = metadata.len as libc::off_t;
+    }
+}
+
+unsafe extern "C" fn open_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    file_struct: *mut c_void,
+    path: *const c_char,
+    flags: c_int,
+    _mode: c_int,
+) -> c_int {
+    let (device, path) = unsafe { device_for_path::<D>(path) };
+
+    match device.open(path, OpenOptions::from_flags(flags)) {
+        Ok(file) => {
+            let open = Box::new(OpenFile {
+                device: Arc::clone(device),
+                file,
+            });
+            unsafe {
+                file_struct
+                    .cast::<*mut OpenFile<D>>()
+                    .write(Box::into_raw(open))
+            };
+            0
+        }
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+unsafe fn open_file<'a, D: Device>(fd: *mut c_void) -> &'a mut OpenFile<D> {
+    unsafe { &mut **fd.cast::<*mut OpenFile<D>>() }
+}
+
+unsafe extern "C" fn close_r<D: Device>(_r: *mut ctru_sys::_reent, fd: *mut c_void) -> c_int {
+    let open = unsafe { Box::from_raw(*fd.cast::<*mut OpenFile<D>>()) };
+
+    match open.device.close(open.file) {
+        Ok(()) => 0,
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn write_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    fd: *mut c_void,
+    ptr: *const c_char,
+    len: usize,
+) -> libc::ssize_t {
+    let open = unsafe { open_file::<D>(fd) };
+    let buf = unsafe { std::slice::from_raw_parts(ptr.cast::<u8>(), len) };
+
+    match open.device.write(&mut open.file, buf) {
+        Ok(written) => written as libc::ssize_t,
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn read_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    fd: *mut c_void,
+    ptr: *mut c_char,
+    len: usize,
+) -> libc::ssize_t {
+    let open = unsafe { open_file::<D>(fd) };
+    let buf = unsafe { std::slice::from_raw_parts_mut(ptr.cast::<u8>(), len) };
+
+    match open.device.read(&mut open.file, buf) {
+        Ok(read) => read as libc::ssize_t,
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn seek_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    fd: *mut c_void,
+    pos: libc::off_t,
+    dir: c_int,
+) -> libc::off_t {
+    let open = unsafe { open_file::<D>(fd) };
+
+    let pos = match dir {
+        libc::SEEK_SET if pos >= 0 => SeekFrom::Start(pos as u64),
+        libc::SEEK_CUR => SeekFrom::Current(pos.into()),
+        libc::SEEK_END => SeekFrom::End(pos.into()),
+        _ => {
+            set_errno(&io::ErrorKind::InvalidInput.into());
+            return -1;
+        }
+    };
+
+    match open.device.seek(&mut open.file, pos) {
+        Ok(pos) => pos as libc::off_t,
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn fstat_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    fd: *mut c_void,
+    st: *mut libc::stat,
+) -> c_int {
+    let open = unsafe { open_file::<D>(fd) };
+
+    match open.device.file_metadata(&mut open.file) {
+        Ok(metadata) => {
+            metadata_to_stat(metadata, st);
+            0
+        }
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn stat_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    path: *const c_char,
+    st: *mut libc::stat,
+) -> c_int {
+    let (device, path) = unsafe { device_for_path::<D>(path) };
+
+    match device.metadata(path) {
+        Ok(metadata) => {
+            metadata_to_stat(metadata, st);
+            0
+        }
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn diropen_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    dir_state: *mut ctru_sys::DIR_ITER,
+    path: *const c_char,
+) -> *mut ctru_sys::DIR_ITER {
+    let (device, path) = unsafe { device_for_path::<D>(path) };
+
+    match device.open_dir(path) {
+        Ok(dir) => {
+            let open = Box::new(OpenDir {
+                device: Arc::clone(device),
+                dir,
+            });
+            unsafe {
+                (*dir_state)
+                    .dirStruct
+                    .cast::<*mut OpenDir<D>>()
+                    .write(Box::into_raw(open))
+            };
+            dir_state
+        }
+        Err(e) => {
+            set_errno(&e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+unsafe extern "C" fn dirnext_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    dir_state: *mut ctru_sys::DIR_ITER,
+    filename: *mut c_char,
+    filestat: *mut libc::stat,
+) -> c_int {
+    let open = unsafe { &mut **(*dir_state).dirStruct.cast::<*mut OpenDir<D>>() };
+
+    match open.device.dir_next(&mut open.dir) {
+        Ok(Some(entry)) => {
+            let name = entry.name.as_bytes();
+            let len = name.len().min(NAME_MAX);
+
+            unsafe {
+                std::ptr::copy_nonoverlapping(name.as_ptr(), filename.cast::<u8>(), len);
+                filename.add(len).write(0);
+            }
+            metadata_to_stat(entry.metadata, filestat);
+            0
+        }
+        // The end of the directory is reported with `ENOENT`.
+        Ok(None) => {
+            set_errno(&io::ErrorKind::NotFound.into());
+            -1
+        }
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+unsafe extern "C" fn dirclose_r<D: Device>(
+    _r: *mut ctru_sys::_reent,
+    dir_state: *mut ctru_sys::DIR_ITER,
+) -> c_int {
+    let open = unsafe { Box::from_raw(*(*dir_state).dirStruct.cast::<*mut OpenDir<D>>()) };
+
+    match open.device.close_dir(open.dir) {
+        Ok(()) => 0,
+        Err(e) => {
+            set_errno(&e);
+            -1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_flags() {
+        let options = OpenOptions::from_flags(libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC);
+
+        assert_eq!(
+            options,
+            OpenOptions {
+                write: true,
+                create: true,
+                truncate: true,
+                ..Default::default()
+            }
+        );
+
+        let options = OpenOptions::from_flags(libc::O_RDWR | libc::O_APPEND);
+        assert!(options.read && options.write && options.append);
+    }
+}
@@ -0,0 +1,271 @@
+//! System event subscriptions.
+//!
+//! Lifecycle events of the application come from different services: APT notifies suspensions and sleep mode,
+//! PTM keeps track of the shell (the lid of the console) and of the charger, and SRV delivers notifications from the rest of the system.
+//! [`SystemEvents`] gathers all of them in a single queue of [`SystemEvent`]s, so that they can be handled in one place.
+//!
+//! # Notes
+//!
+//! APT events are queued when they happen (during [`Apt::main_loop()`](crate::services::apt::Apt::main_loop)),
+//! while the state of the shell and of the charger is checked every time the queue is polled.
+#![doc(alias = "notification")]
+#![doc(alias = "lifecycle")]
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::error::ResultCode;
+use crate::resources::{self, Registration, Transition};
+use crate::services::ServiceReference;
+
+static PTMU_ACTIVE: Mutex<()> = Mutex::new(());
+
+/// Event reported by [`SystemEvents`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SystemEvent {
+    /// The application was suspended (e.g. the HOME Menu was opened).
+    Suspended,
+    /// The application regained control after being suspended.
+    Restored,
+    /// The console entered sleep mode.
+    Sleep,
+    /// The console woke up from sleep mode.
+    WakeUp,
+    /// The application is being closed.
+    Exit,
+    /// The HOME button was pressed while it was disabled (see [`Apt::set_home_allowed()`](crate::services::apt::Apt::set_home_allowed)).
+    HomePressed,
+    /// The shell of the console was opened.
+    ShellOpened,
+    /// The shell of the console was closed.
+    ShellClosed,
+    /// The charger was plugged in.
+    ChargerConnected,
+    /// The charger was unplugged.
+    ChargerDisconnected,
+    /// The battery started charging.
+    ChargingStarted,
+    /// The battery stopped charging (e.g. because it's full).
+    ChargingStopped,
+    /// A notification subscribed via [`SystemEvents::subscribe()`] was received.
+    Notification(u32),
+}
+
+/// Queue of the events of the system.
+///
+/// See the [module documentation](self) for more information.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::events::{SystemEvent, SystemEvents};
+/// use ctru::prelude::*;
+///
+/// let apt = Apt::new()?;
+/// let mut events = SystemEvents::new()?;
+///
+/// while apt.main_loop() {
+///     for event in events.drain() {
+///         match event {
+///             SystemEvent::ShellClosed => println!("Good night!"),
+///             SystemEvent::ChargerConnected => println!("Charging..."),
+///             SystemEvent::Exit => println!("Saving before exiting"),
+///             _ => {}
+///         }
+///     }
+/// #   break;
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct SystemEvents {
+    queue: Arc<Mutex<VecDeque<SystemEvent>>>,
+    shell_open: bool,
+    adapter_connected: bool,
+    charging: bool,
+    notifications: Option<ctru_sys::Handle>,
+    subscriptions: Vec<u32>,
+    _registration: Registration,
+    _service_handler: ServiceReference,
+}
+
+impl SystemEvents {
+    /// Starts listening to the events of the system.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the PTM service couldn't be initialized, or if another [`SystemEvents`] is active.
+    #[doc(alias = "ptmuInit")]
+    pub fn new() -> crate::Result<Self> {
+        let _service_handler = ServiceReference::new(
+            &PTMU_ACTIVE,
+            || {
+                ResultCode(unsafe { ctru_sys::ptmuInit() })?;
+
+                Ok(())
+            },
+            || unsafe {
+                ctru_sys::ptmuExit();
+            },
+        )?;
+
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+
+        let _registration = resources::register({
+            let queue = queue.clone();
+
+            move |transition| {
+                let event = match transition {
+                    Transition::Suspend => SystemEvent::Suspended,
+                    Transition::Restore => SystemEvent::Restored,
+                    Transition::Sleep => SystemEvent::Sleep,
+                    Transition::WakeUp => SystemEvent::WakeUp,
+                    Transition::Exit => SystemEvent::Exit,
+                };
+
+                queue.lock().unwrap().push_back(event);
+            }
+        });
+
+        let mut events = Self {
+            queue,
+            shell_open: true,
+            adapter_connected: false,
+            charging: false,
+            notifications: None,
+            subscriptions: Vec::new(),
+            _registration,
+            _service_handler,
+        };
+
+        // Read the initial state, so that only the changes are reported.
+        let (shell_open, adapter_connected, charging) = events.power_state()?;
+        events.shell_open = shell_open;
+        events.adapter_connected = adapter_connected;
+        events.charging = charging;
+
+        Ok(events)
+    }
+
+    /// Subscribes to a system notification, reported as [`SystemEvent::Notification`].
+    ///
+    /// See <https://www.3dbrew.org/wiki/NS#Notification_IDs> for the list of notifications.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the subscription failed.
+    #[doc(alias = "srvEnableNotification", alias = "srvSubscribe")]
+    pub fn subscribe(&mut self, notification_id: u32) -> crate::Result<()> {
+        if self.notifications.is_none() {
+            let mut semaphore = 0;
+            ResultCode(unsafe { ctru_sys::srvEnableNotification(&mut semaphore) })?;
+            self.notifications = Some(semaphore);
+        }
+
+        if !self.subscriptions.contains(&notification_id) {
+            ResultCode(unsafe { ctru_sys::srvSubscribe(notification_id) })?;
+            self.subscriptions.push(notification_id);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the next event, if any.
+    ///
+    /// # Notes
+    ///
+    /// Errors while checking the state of the system are ignored, and the corresponding events are reported by a later call instead.
+    #[doc(alias = "srvReceiveNotification", alias = "aptCheckHomePressRejected")]
+    pub fn poll(&mut self) -> Option<SystemEvent> {
+        if let Ok((shell_open, adapter_connected, charging)) = self.power_state() {
+            let mut queue = self.queue.lock().unwrap();
+
+            if shell_open != self.shell_open {
+                queue.push_back(if shell_open {
+                    SystemEvent::ShellOpened
+                } else {
+                    SystemEvent::ShellClosed
+                });
+            }
+
+            if adapter_connected != self.adapter_connected {
+                queue.push_back(if adapter_connected {
+                    SystemEvent::ChargerConnected
+                } else {
+                    SystemEvent::ChargerDisconnected
+                });
+            }
+
+            if charging != self.charging {
+                queue.push_back(if charging {
+                    SystemEvent::ChargingStarted
+                } else {
+                    SystemEvent::ChargingStopped
+                });
+            }
+
+            self.shell_open = shell_open;
+            self.adapter_connected = adapter_connected;
+            self.charging = charging;
+        }
+
+        if unsafe { ctru_sys::aptCheckHomePressRejected() } {
+            self.queue
+                .lock()
+                .unwrap()
+                .push_back(SystemEvent::HomePressed);
+        }
+
+        if let Some(semaphore) = self.notifications {
+            // Every pending notification signals the semaphore once.
+            while unsafe { ctru_sys::svcWaitSynchronization(semaphore, 0) } == 0 {
+                let mut id = 0;
+
+                if ctru_sys::R_SUCCEEDED(unsafe { ctru_sys::srvReceiveNotification(&mut id) }) {
+                    self.queue
+                        .lock()
+                        .unwrap()
+                        .push_back(SystemEvent::Notification(id));
+                }
+            }
+        }
+
+        self.queue.lock().unwrap().pop_front()
+    }
+
+    /// Returns an iterator over the pending events.
+    pub fn drain(&mut self) -> impl Iterator<Item = SystemEvent> + '_ {
+        std::iter::from_fn(move || self.poll())
+    }
+
+    fn power_state(&self) -> crate::Result<(bool, bool, bool)> {
+        let (mut shell, mut adapter, mut charging) = (0, 0, 0);
+
+        unsafe {
+            ResultCode(ctru_sys::PTMU_GetShellState(&mut shell))?;
+            ResultCode(ctru_sys::PTMU_GetAdapterState(&mut adapter))?;
+            ResultCode(ctru_sys::PTMU_GetBatteryChargeState(&mut charging))?;
+        }
+
+        Ok((shell != 0, adapter != 0, charging != 0))
+    }
+}
+
+impl Drop for SystemEvents {
+    #[doc(alias = "srvUnsubscribe")]
+    fn drop(&mut self) {
+        for &id in &self.subscriptions {
+            let _ = unsafe { ctru_sys::srvUnsubscribe(id) };
+        }
+
+        if let Some(semaphore) = self.notifications {
+            let _ = unsafe { ctru_sys::svcCloseHandle(semaphore) };
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Headless mode, for daemon-style applications.
+//!
+//! Background utilities (e.g. a network or file server started by the custom firmware at boot) don't draw anything,
+//! and don't need to pay for the framebuffers and the GPU rights held by [`Gfx`](crate::services::gfx::Gfx).
+//! Such applications can skip creating [`Gfx`](crate::services::gfx::Gfx) and [`Console`](crate::console::Console) altogether,
+//! and run their main loop with [`Headless`] instead of [`Apt::main_loop()`].
+//!
+//! Without the GSP service, the application can't hand the screens over to the HOME Menu, nor get them back:
+//! [`Headless`] keeps the HOME button disabled, and reports presses of it instead (which daemons usually treat as a request to exit).
+//! It can also turn the backlights off while it's running, since nothing is displayed on the screens anyway.
+//!
+//! # Notes
+//!
+//! Output written to `stdout` is discarded without a console, unless it's redirected with [`stdio`](crate::stdio).
+#![doc(alias = "daemon")]
+#![doc(alias = "background")]
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::services::apt::{Apt, EventLoop};
+use crate::services::gsplcd::{GspLcd, LcdScreen};
+
+/// Main loop of an application running without graphics.
+///
+/// The previous settings of the [`Apt`] are restored, and the backlights turned back on, when it's dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::time::Duration;
+///
+/// use ctru::headless::Headless;
+/// use ctru::services::apt::Apt;
+///
+/// let mut apt = Apt::new()?;
+///
+/// let mut headless = Headless::new(&mut apt).screens_off()?;
+///
+/// while headless.main_loop() {
+///     if headless.home_pressed() {
+///         break;
+///     }
+///
+///     // Serve requests, then give some time to the rest of the system.
+///     std::thread::sleep(Duration::from_millis(50));
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "aptMainLoop")]
+pub struct Headless<'apt> {
+    event_loop: EventLoop<'apt>,
+    home_pressed: Rc<Cell<bool>>,
+    lcd: Option<GspLcd>,
+}
+
+impl<'apt> Headless<'apt> {
+    /// Starts running without graphics.
+    ///
+    /// This disables the HOME button (see [`Headless::home_pressed()`]) and sleep mode (see [`Headless::sleep_allowed()`]).
+    pub fn new(apt: &'apt mut Apt) -> Self {
+        let home_pressed = Rc::new(Cell::new(false));
+
+        let event_loop = EventLoop::new(apt).sleep_allowed(false).on_home_pressed({
+            let home_pressed = Rc::clone(&home_pressed);
+            move || {
+                home_pressed.set(true);
+
+                // Jumping to the HOME Menu requires the GPU rights, which the application doesn't hold.
+                false
+            }
+        });
+
+        Self {
+            event_loop,
+            home_pressed,
+            lcd: None,
+        }
+    }
+
+    /// Turns the backlights of both screens off until this is dropped.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the [`GspLcd`] service couldn't be initialized, or if the backlights couldn't be turned off.
+    #[doc(alias = "GSPLCD_PowerOffAllBacklights")]
+    pub fn screens_off(mut self) -> crate::Result<Self> {
+        let mut lcd = GspLcd::new()?;
+        lcd.set_backlight(LcdScreen::Both, false)?;
+
+        self.lcd = Some(lcd);
+        Ok(self)
+    }
+
+    /// Sets if the console is allowed to enter sleep mode. Defaults to `false`, so that the application keeps running
+    /// (and stays connected) when the console is closed.
+    ///
+    /// See [`Apt::set_sleep_allowed()`] for more information.
+    pub fn sleep_allowed(mut self, allowed: bool) -> Self {
+        self.event_loop.apt().set_sleep_allowed(allowed);
+        self
+    }
+
+    /// Returns `true` if the application should keep running.
+    ///
+    /// Unlike [`Gfx`](crate::services::gfx::Gfx)-based applications, the loop doesn't wait for the screens to be refreshed:
+    /// the application should block on its work (e.g. on a socket) or sleep between iterations, so that it doesn't starve the system.
+    ///
+    /// See [`Apt::main_loop()`] for more information.
+    #[doc(alias = "aptMainLoop")]
+    pub fn main_loop(&mut self) -> bool {
+        self.event_loop.main_loop()
+    }
+
+    /// Returns `true` if the HOME button was pressed since the last call.
+    #[doc(alias = "aptCheckHomePressRejected")]
+    pub fn home_pressed(&mut self) -> bool {
+        self.home_pressed.replace(false)
+    }
+
+    /// Returns the underlying [`Apt`] service.
+    pub fn apt(&mut self) -> &mut Apt {
+        self.event_loop.apt()
+    }
+}
+
+impl Drop for Headless<'_> {
+    fn drop(&mut self) {
+        if let Some(lcd) = &mut self.lcd {
+            // The HOME Menu (or the next application) expects the screens to be on.
+            let _ = lcd.set_backlight(LcdScreen::Both, true);
+        }
+    }
+}
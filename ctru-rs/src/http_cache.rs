@@ -0,0 +1,274 @@
+//! HTTP response cache.
+//!
+//! Applications which often fetch the same resources (e.g. catalog listings or icons) can store the responses on the SD card
+//! together with their validation headers (`ETag` and `Last-Modified`). The following requests are then made conditional,
+//! and the server can answer with a body-less `304 Not Modified` response if the cached copy is still valid.
+//!
+//! The cache doesn't depend on a specific HTTP client: [`HttpCache::fetch()`] provides the conditional headers to send,
+//! and the client reports back whether the resource was modified.
+#![doc(alias = "etag")]
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Cache of HTTP responses stored in a directory, keyed by URL.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::http_cache::{Fetched, HttpCache, Validators};
+///
+/// let cache = HttpCache::new("sdmc:/3ds/my-app/cache")?;
+///
+/// let body = cache.fetch("https://example.com/catalog.json", |headers| {
+///     // Perform the request with any HTTP client, adding the conditional `headers`.
+/// #   let status = 304;
+///     if status == 304 {
+///         Ok(Fetched::NotModified)
+///     } else {
+///         Ok(Fetched::Modified {
+///             body: Vec::new(),
+///             validators: Validators {
+///                 etag: Some("\"33a64df5\"".into()),
+///                 last_modified: None,
+///             },
+///         })
+///     }
+/// })?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+/// Validation headers of a cached response.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Validators {
+    /// Value of the `ETag` header.
+    pub etag: Option<String>,
+    /// Value of the `Last-Modified` header.
+    pub last_modified: Option<String>,
+}
+
+/// Response stored in the [`HttpCache`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedResponse {
+    /// Validation headers of the response.
+    pub validators: Validators,
+    /// Body of the response.
+    pub body: Vec<u8>,
+}
+
+/// Outcome of a request made by [`HttpCache::fetch()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fetched {
+    /// The server answered with `304 Not Modified`: the cached response is still valid.
+    NotModified,
+    /// The server sent a new version of the resource.
+    Modified {
+        /// Body of the response.
+        body: Vec<u8>,
+        /// Validation headers of the response. The response isn't cached if none are present.
+        validators: Validators,
+    },
+}
+
+impl HttpCache {
+    /// Opens the cache stored in the specified directory, creating it if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self { dir })
+    }
+
+    /// Fetches a resource, using the cached response if it's still valid.
+    ///
+    /// `request` must perform the HTTP request, adding the provided conditional headers (as name-value pairs).
+    /// The cache is updated with the new response if the resource was modified.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request failed, if the cache couldn't be updated,
+    /// or if the server answered with `304 Not Modified` but the cached response was lost in the meantime.
+    pub fn fetch<F>(&self, url: &str, request: F) -> io::Result<Vec<u8>>
+    where
+        F: FnOnce(&[(&'static str, String)]) -> io::Result<Fetched>,
+    {
+        let cached = self.load(url)?;
+        let headers = cached
+            .as_ref()
+            .map(|c| c.validators.request_headers())
+            .unwrap_or_default();
+
+        match request(&headers)? {
+            Fetched::NotModified => cached.map(|c| c.body).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "resource not modified, but no cached response is available",
+                )
+            }),
+            Fetched::Modified { body, validators } => {
+                if validators.is_empty() {
+                    self.remove(url)?;
+                } else {
+                    self.store(url, &validators, &body)?;
+                }
+
+                Ok(body)
+            }
+        }
+    }
+
+    /// Returns the cached response for the specified URL, if any.
+    pub fn load(&self, url: &str) -> io::Result<Option<CachedResponse>> {
+        let data = match fs::read(self.entry_path(url)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        // Entries with a different URL are hash collisions, and corrupted ones are simply refetched.
+        Ok(decode(&data).and_then(|(entry_url, response)| (entry_url == url).then_some(response)))
+    }
+
+    /// Stores a response in the cache, replacing any previous one for the same URL.
+    pub fn store(&self, url: &str, validators: &Validators, body: &[u8]) -> io::Result<()> {
+        let path = self.entry_path(url);
+        let temp_path = path.with_extension("tmp");
+
+        // Write the entry as a whole, so that an interrupted write can't leave a truncated response.
+        // Renaming fails if the destination already exists, and losing the previous response only means refetching it.
+        fs::write(&temp_path, encode(url, validators, body))?;
+        self.remove(url)?;
+        fs::rename(temp_path, path)
+    }
+
+    /// Removes the cached response for the specified URL, if any.
+    pub fn remove(&self, url: &str) -> io::Result<()> {
+        match fs::remove_file(self.entry_path(url)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes all cached responses.
+    pub fn clear(&self) -> io::Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+
+            if path.extension().is_some_and(|ext| ext == "http") {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.http", fnv1a(url.as_bytes())))
+    }
+}
+
+impl Validators {
+    /// Returns `true` if no validation headers are present.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// Returns the conditional request headers (as name-value pairs) matching these validators.
+    pub fn request_headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match", etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(("If-Modified-Since", last_modified.clone()));
+        }
+
+        headers
+    }
+}
+
+// 64-bit FNV-1a hash, used to name the cache entries.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn encode(url: &str, validators: &Validators, body: &[u8]) -> Vec<u8> {
+    let fields = [
+        url.as_bytes(),
+        validators.etag.as_deref().unwrap_or_default().as_bytes(),
+        validators
+            .last_modified
+            .as_deref()
+            .unwrap_or_default()
+            .as_bytes(),
+        body,
+    ];
+
+    let mut data = Vec::new();
+    for field in fields {
+        data.extend((field.len() as u32).to_le_bytes());
+        data.extend(field);
+    }
+    data
+}
+
+fn decode(mut data: &[u8]) -> Option<(String, CachedResponse)> {
+    let mut next_field = || {
+        let len = u32::from_le_bytes(data.get(..4)?.try_into().unwrap()) as usize;
+        let field = data.get(4..)?.get(..len)?.to_vec();
+        data = &data[4 + len..];
+        Some(field)
+    };
+    let mut next_string = || String::from_utf8(next_field()?).ok();
+
+    let url = next_string()?;
+    let etag = next_string()?;
+    let last_modified = next_string()?;
+    let body = next_field()?;
+
+    Some((
+        url,
+        CachedResponse {
+            validators: Validators {
+                etag: (!etag.is_empty()).then_some(etag),
+                last_modified: (!last_modified.is_empty()).then_some(last_modified),
+            },
+            body,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_round_trip() {
+        let validators = Validators {
+            etag: Some("\"abc\"".into()),
+            last_modified: None,
+        };
+        let data = encode("https://example.com", &validators, b"body");
+
+        let (url, response) = decode(&data).unwrap();
+        assert_eq!(url, "https://example.com");
+        assert_eq!(response.validators, validators);
+        assert_eq!(response.body, b"body");
+
+        assert_eq!(decode(&data[..data.len() - 1]), None);
+    }
+}
@@ -0,0 +1,347 @@
+//! Touch gesture recognition.
+//!
+//! [`GestureRecognizer`] turns the raw touch screen state into higher level gestures (taps, double taps, drags and swipes),
+//! so that touch-based interfaces don't need to track the touch state across frames themselves.
+#![doc(alias = "touch")]
+#![doc(alias = "swipe")]
+
+use std::time::{Duration, Instant};
+
+use crate::services::hid::{InputSource, KeyPad};
+
+/// Gesture recognized by a [`GestureRecognizer`].
+///
+/// Positions are in pixels, as returned by [`InputSource::touch_position()`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Gesture {
+    /// The screen was touched and released without moving.
+    Tap {
+        /// Position of the tap.
+        position: (u16, u16),
+    },
+    /// The screen was tapped twice in quick succession.
+    ///
+    /// The first tap is reported as a [`Gesture::Tap`] as well.
+    DoubleTap {
+        /// Position of the second tap.
+        position: (u16, u16),
+    },
+    /// The touch position started moving.
+    DragStart {
+        /// Position where the screen was first touched.
+        position: (u16, u16),
+    },
+    /// The touch position moved during a drag.
+    Drag {
+        /// Current touch position.
+        position: (u16, u16),
+        /// Movement since the previous frame.
+        delta: (i32, i32),
+    },
+    /// The screen was released at the end of a drag.
+    DragEnd {
+        /// Last touch position.
+        position: (u16, u16),
+    },
+    /// The screen was released while moving quickly, at the end of a drag.
+    Swipe {
+        /// Main direction of the movement.
+        direction: SwipeDirection,
+        /// Velocity of the movement when the screen was released, in pixels per second.
+        velocity: (f32, f32),
+    },
+}
+
+/// Direction of a [`Gesture::Swipe`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SwipeDirection {
+    /// Towards the top of the screen.
+    Up,
+    /// Towards the bottom of the screen.
+    Down,
+    /// Towards the left of the screen.
+    Left,
+    /// Towards the right of the screen.
+    Right,
+}
+
+/// Recognizes gestures from the touch screen state of every frame.
+///
+/// [`GestureRecognizer::update()`] must be called once per frame, after scanning the input.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::input::gestures::{Gesture, GestureRecognizer, SwipeDirection};
+/// use ctru::services::hid::Hid;
+///
+/// let mut hid = Hid::new()?;
+/// let mut gestures = GestureRecognizer::new();
+///
+/// hid.scan_input();
+/// gestures.update(&hid);
+///
+/// for gesture in gestures.gestures() {
+///     match gesture {
+///         Gesture::Tap { position } => println!("Tapped at {position:?}"),
+///         Gesture::Swipe {
+///             direction: SwipeDirection::Left,
+///             ..
+///         } => println!("Next page"),
+///         _ => {}
+///     }
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct GestureRecognizer {
+    tap_slop: u32,
+    tap_timeout: Duration,
+    double_tap_interval: Duration,
+    swipe_velocity: f32,
+    touch: Option<Touch>,
+    last_tap: Option<((u16, u16), Instant)>,
+    gestures: Vec<Gesture>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Touch {
+    start: (u16, u16),
+    started_at: Instant,
+    position: (u16, u16),
+    updated_at: Instant,
+    dragging: bool,
+    velocity: (f32, f32),
+}
+
+impl GestureRecognizer {
+    /// Creates a new recognizer with the default thresholds.
+    pub fn new() -> Self {
+        Self {
+            tap_slop: 8,
+            tap_timeout: Duration::from_millis(300),
+            double_tap_interval: Duration::from_millis(300),
+            swipe_velocity: 300.0,
+            touch: None,
+            last_tap: None,
+            gestures: Vec::new(),
+        }
+    }
+
+    /// Sets the distance (in pixels) the touch position can move before a touch becomes a drag. The default is 8 pixels.
+    pub fn with_tap_slop(mut self, pixels: u32) -> Self {
+        self.tap_slop = pixels;
+        self
+    }
+
+    /// Sets the maximum duration of a tap. The default is 300 milliseconds.
+    pub fn with_tap_timeout(mut self, timeout: Duration) -> Self {
+        self.tap_timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum interval between the two taps of a double tap. The default is 300 milliseconds.
+    pub fn with_double_tap_interval(mut self, interval: Duration) -> Self {
+        self.double_tap_interval = interval;
+        self
+    }
+
+    /// Sets the minimum velocity (in pixels per second) for a drag to end with a swipe. The default is 300 pixels per second.
+    pub fn with_swipe_velocity(mut self, velocity: f32) -> Self {
+        self.swipe_velocity = velocity;
+        self
+    }
+
+    /// Updates the recognizer with the touch screen state of the current frame.
+    pub fn update(&mut self, input: &impl InputSource) {
+        let position = input
+            .keys_held()
+            .contains(KeyPad::TOUCH)
+            .then(|| input.touch_position());
+
+        self.update_at(position, Instant::now());
+    }
+
+    /// Returns the gestures recognized on the current frame.
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    /// Returns `true` if the screen is being touched.
+    pub fn is_touching(&self) -> bool {
+        self.touch.is_some()
+    }
+
+    fn update_at(&mut self, position: Option<(u16, u16)>, now: Instant) {
+        self.gestures.clear();
+
+        match (self.touch.as_mut(), position) {
+            (None, Some(position)) => {
+                self.touch = Some(Touch {
+                    start: position,
+                    started_at: now,
+                    position,
+                    updated_at: now,
+                    dragging: false,
+                    velocity: (0.0, 0.0),
+                });
+            }
+            (Some(touch), Some(position)) => {
+                if !touch.dragging && distance(touch.start, position) > self.tap_slop {
+                    touch.dragging = true;
+                    self.gestures.push(Gesture::DragStart {
+                        position: touch.start,
+                    });
+                }
+
+                let delta = (
+                    i32::from(position.0) - i32::from(touch.position.0),
+                    i32::from(position.1) - i32::from(touch.position.1),
+                );
+                let elapsed = now.duration_since(touch.updated_at).as_secs_f32();
+
+                if elapsed > 0.0 {
+                    // Smooth the velocity, as the touch position is quite noisy.
+                    touch.velocity = (
+                        (touch.velocity.0 + delta.0 as f32 / elapsed) / 2.0,
+                        (touch.velocity.1 + delta.1 as f32 / elapsed) / 2.0,
+                    );
+                }
+
+                if touch.dragging && delta != (0, 0) {
+                    self.gestures.push(Gesture::Drag { position, delta });
+                }
+
+                touch.position = position;
+                touch.updated_at = now;
+            }
+            (Some(touch), None) => {
+                let touch = *touch;
+                self.touch = None;
+                self.release(touch, now);
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn release(&mut self, touch: Touch, now: Instant) {
+        if touch.dragging {
+            self.gestures.push(Gesture::DragEnd {
+                position: touch.position,
+            });
+
+            let (vx, vy) = touch.velocity;
+            if vx.hypot(vy) >= self.swipe_velocity {
+                let direction = if vx.abs() > vy.abs() {
+                    if vx > 0.0 {
+                        SwipeDirection::Right
+                    } else {
+                        SwipeDirection::Left
+                    }
+                } else if vy > 0.0 {
+                    SwipeDirection::Down
+                } else {
+                    SwipeDirection::Up
+                };
+
+                self.gestures.push(Gesture::Swipe {
+                    direction,
+                    velocity: touch.velocity,
+                });
+            }
+        } else if now.duration_since(touch.started_at) <= self.tap_timeout {
+            let position = touch.position;
+            self.gestures.push(Gesture::Tap { position });
+
+            match self.last_tap.take() {
+                Some((last, at))
+                    if now.duration_since(at) <= self.double_tap_interval
+                        && distance(last, position) <= 2 * self.tap_slop =>
+                {
+                    self.gestures.push(Gesture::DoubleTap { position });
+                }
+                _ => self.last_tap = Some((position, now)),
+            }
+        }
+    }
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Chebyshev distance, which is enough to tell whether the touch position moved.
+fn distance(a: (u16, u16), b: (u16, u16)) -> u32 {
+    u32::from(a.0.abs_diff(b.0).max(a.1.abs_diff(b.1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taps_and_swipes() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut recognizer = GestureRecognizer::new();
+
+        recognizer.update_at(Some((100, 100)), at(0));
+        recognizer.update_at(None, at(50));
+        assert_eq!(
+            recognizer.gestures(),
+            [Gesture::Tap {
+                position: (100, 100)
+            }]
+        );
+
+        recognizer.update_at(Some((102, 101)), at(150));
+        recognizer.update_at(None, at(200));
+        assert_eq!(
+            recognizer.gestures(),
+            [
+                Gesture::Tap {
+                    position: (102, 101)
+                },
+                Gesture::DoubleTap {
+                    position: (102, 101)
+                }
+            ]
+        );
+
+        recognizer.update_at(Some((200, 100)), at(1000));
+        recognizer.update_at(Some((180, 100)), at(1016));
+        assert_eq!(
+            recognizer.gestures(),
+            [
+                Gesture::DragStart {
+                    position: (200, 100)
+                },
+                Gesture::Drag {
+                    position: (180, 100),
+                    delta: (-20, 0)
+                }
+            ]
+        );
+
+        recognizer.update_at(Some((150, 100)), at(1032));
+        recognizer.update_at(None, at(1048));
+        assert!(matches!(
+            recognizer.gestures(),
+            [
+                Gesture::DragEnd { .. },
+                Gesture::Swipe {
+                    direction: SwipeDirection::Left,
+                    ..
+                }
+            ]
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+//! Input handling utilities.
+//!
+//! This module contains helpers built on top of the raw input state read by [`Hid`](crate::services::hid::Hid),
+//! for behaviour which is commonly needed by applications but not provided by the system.
+//! All helpers work with any [`InputSource`], so that they can also be driven by mock inputs.
+#![doc(alias = "key repeat")]
+
+pub mod gestures;
+#[cfg(feature = "network")]
+pub mod netpad;
+pub mod remap;
+
+use std::time::{Duration, Instant};
+
+use crate::services::hid::{InputSource, KeyPad};
+
+/// Tracks how long buttons have been held and generates repeated presses, as done by keyboards.
+///
+/// When a button is pressed, it's reported once, then again after `initial_delay` if still held,
+/// and then once every `interval`. This is the usual behaviour expected when scrolling through menus.
+///
+/// [`Repeater::update()`] must be called once per frame, after scanning the input.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::time::Duration;
+///
+/// use ctru::input::Repeater;
+/// use ctru::services::hid::{Hid, KeyPad};
+///
+/// let mut hid = Hid::new()?;
+/// let mut repeater = Repeater::new(Duration::from_millis(400), Duration::from_millis(80))
+///     .with_keys(KeyPad::UP | KeyPad::DOWN);
+/// let mut selection = 0;
+///
+/// hid.scan_input();
+/// repeater.update(&hid);
+///
+/// if repeater.keys_repeated().intersects(KeyPad::DOWN) {
+///     selection += 1;
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Repeater {
+    initial_delay: Duration,
+    interval: Duration,
+    keys: KeyPad,
+    repeated: KeyPad,
+    // State of every key, indexed by the position of its flag.
+    held: [Option<HeldKey>; 32],
+}
+
+#[derive(Clone, Copy, Debug)]
+struct HeldKey {
+    since: Instant,
+    frames: u32,
+    next_repeat: Instant,
+}
+
+impl Repeater {
+    /// Creates a new repeater, applying to all buttons.
+    pub fn new(initial_delay: Duration, interval: Duration) -> Self {
+        Self {
+            initial_delay,
+            interval,
+            keys: KeyPad::all(),
+            repeated: KeyPad::empty(),
+            held: [None; 32],
+        }
+    }
+
+    /// Restricts the repeated presses to the specified buttons.
+    ///
+    /// Other buttons are still reported once when pressed, and their hold duration is still tracked.
+    pub fn with_keys(mut self, keys: KeyPad) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Updates the state of the repeater with the input of the current frame.
+    pub fn update(&mut self, input: &impl InputSource) {
+        self.update_at(input.keys_held(), Instant::now());
+    }
+
+    /// Returns the buttons which have just been pressed on the current frame, or which are repeated on the current frame.
+    pub fn keys_repeated(&self) -> KeyPad {
+        self.repeated
+    }
+
+    /// Returns the number of frames (i.e. calls to [`Repeater::update()`]) during which the button has been held, including the current one.
+    ///
+    /// If `key` contains multiple buttons, the longest hold among them is returned.
+    pub fn held_frames(&self, key: KeyPad) -> u32 {
+        self.held_keys(key).map(|k| k.frames).max().unwrap_or(0)
+    }
+
+    /// Returns for how long the button has been held.
+    ///
+    /// If `key` contains multiple buttons, the longest hold among them is returned.
+    pub fn held_duration(&self, key: KeyPad) -> Duration {
+        self.held_keys(key)
+            .map(|k| k.since.elapsed())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Forgets all held buttons, so that they aren't repeated until pressed again.
+    ///
+    /// This is useful when switching between menus, to avoid carrying over a held button.
+    pub fn reset(&mut self) {
+        self.held = [None; 32];
+        self.repeated = KeyPad::empty();
+    }
+
+    fn update_at(&mut self, keys_held: KeyPad, now: Instant) {
+        self.repeated = KeyPad::empty();
+
+        for (bit, state) in self.held.iter_mut().enumerate() {
+            let key = KeyPad::from_bits_retain(1 << bit);
+
+            if !keys_held.contains(key) {
+                *state = None;
+                continue;
+            }
+
+            match state {
+                None => {
+                    *state = Some(HeldKey {
+                        since: now,
+                        frames: 1,
+                        next_repeat: now + self.initial_delay,
+                    });
+                    self.repeated |= key;
+                }
+                Some(held) => {
+                    held.frames += 1;
+
+                    if self.keys.contains(key) && now >= held.next_repeat {
+                        // Skip missed repeats (e.g. after a long frame) instead of firing them all at once.
+                        while held.next_repeat <= now {
+                            held.next_repeat += self.interval.max(Duration::from_millis(1));
+                        }
+                        self.repeated |= key;
+                    }
+                }
+            }
+        }
+    }
+
+    fn held_keys(&self, key: KeyPad) -> impl Iterator<Item = &HeldKey> {
+        key.keys()
+            .filter_map(move |key| self.held[key.bits().trailing_zeros() as usize].as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_repeat() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let mut repeater = Repeater::new(Duration::from_millis(400), Duration::from_millis(100))
+            .with_keys(KeyPad::DPAD_DOWN);
+
+        repeater.update_at(KeyPad::DPAD_DOWN | KeyPad::A, at(0));
+        assert_eq!(repeater.keys_repeated(), KeyPad::DPAD_DOWN | KeyPad::A);
+
+        repeater.update_at(KeyPad::DPAD_DOWN | KeyPad::A, at(200));
+        assert_eq!(repeater.keys_repeated(), KeyPad::empty());
+
+        repeater.update_at(KeyPad::DPAD_DOWN | KeyPad::A, at(400));
+        assert_eq!(repeater.keys_repeated(), KeyPad::DPAD_DOWN);
+
+        // Missed repeats aren't fired all at once.
+        repeater.update_at(KeyPad::DPAD_DOWN, at(750));
+        assert_eq!(repeater.keys_repeated(), KeyPad::DPAD_DOWN);
+        assert_eq!(repeater.held_frames(KeyPad::DPAD_DOWN), 4);
+        assert_eq!(repeater.held_frames(KeyPad::A), 0);
+
+        repeater.update_at(KeyPad::DPAD_DOWN, at(780));
+        assert_eq!(repeater.keys_repeated(), KeyPad::empty());
+
+        repeater.update_at(KeyPad::empty(), at(800));
+        assert_eq!(repeater.held_frames(KeyPad::DPAD_DOWN), 0);
+    }
+}
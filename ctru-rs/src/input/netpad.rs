@@ -0,0 +1,537 @@
+//! Network gamepad.
+//!
+//! A [`Streamer`] sends the input state of the console (buttons, circle pad, touch screen and motion sensors)
+//! to another device over UDP, turning the console into a wireless gamepad for a PC.
+//!
+//! Conversely, a [`Receiver`] accepts the same packets from another device and injects them into the input of the application
+//! via [`RemoteInput`], so that automation rigs or accessibility controllers can drive it.
+//!
+//! # Packet format
+//!
+//! Every UDP datagram contains a single [`GamepadState`], encoded in [`PACKET_SIZE`] bytes.
+//! All values are little-endian:
+//!
+//! | Offset | Type       | Content                                                                               |
+//! |--------|------------|---------------------------------------------------------------------------------------|
+//! | 0      | `[u8; 4]`  | Magic number, `b"3DGP"`                                                               |
+//! | 4      | `u8`       | Version of the format ([`VERSION`])                                                   |
+//! | 5      | `u8`       | Flags: bit 0 if the screen is touched, bit 1 if the accelerometer is valid, bit 2 if the gyroscope is valid |
+//! | 6      | `u16`      | Sequence number, incremented (and wrapping around) with every packet                  |
+//! | 8      | `u32`      | Held buttons, with the same bits as [`KeyPad`]                                        |
+//! | 12     | `[u16; 2]` | Touch position (x, y) in pixels, or zeroes if the screen isn't touched                |
+//! | 16     | `[i16; 2]` | Circle pad position (x, y)                                                            |
+//! | 20     | `[i16; 3]` | Raw accelerometer reading (x, y, z)                                                   |
+//! | 26     | `[i16; 3]` | Raw gyroscope reading (roll, pitch, yaw)                                              |
+//!
+//! Since UDP doesn't guarantee ordering, receivers should ignore packets with a sequence number older than the last one received.
+//!
+//! # Receiving the packets
+//!
+//! The receiving side doesn't need this crate: any program which can read UDP datagrams can act as a driver.
+//! This is a minimal receiver to run on a PC, using only the standard library:
+//!
+//! ```no_run
+//! use std::net::UdpSocket;
+//!
+//! fn main() -> std::io::Result<()> {
+//!     let socket = UdpSocket::bind("0.0.0.0:4950")?;
+//!     let mut packet = [0; 32];
+//!
+//!     loop {
+//!         let (len, _) = socket.recv_from(&mut packet)?;
+//!         if len != packet.len() || &packet[0..4] != b"3DGP" || packet[4] != 1 {
+//!             continue;
+//!         }
+//!
+//!         let keys = u32::from_le_bytes(packet[8..12].try_into().unwrap());
+//!         let circle_x = i16::from_le_bytes([packet[16], packet[17]]);
+//!         let circle_y = i16::from_le_bytes([packet[18], packet[19]]);
+//!
+//!         println!("keys: {keys:#010x}, circle pad: ({circle_x}, {circle_y})");
+//!     }
+//! }
+//! ```
+#![doc(alias = "controller")]
+#![doc(alias = "joystick")]
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::services::hid::{Hid, InputSource, KeyPad};
+
+/// Size in bytes of an encoded [`GamepadState`].
+pub const PACKET_SIZE: usize = 32;
+
+/// Version of the packet format.
+pub const VERSION: u8 = 1;
+
+/// Default port used by [`Streamer`]s and receivers.
+pub const DEFAULT_PORT: u16 = 4950;
+
+const MAGIC: [u8; 4] = *b"3DGP";
+
+const FLAG_TOUCH: u8 = 1 << 0;
+const FLAG_ACCELEROMETER: u8 = 1 << 1;
+const FLAG_GYROSCOPE: u8 = 1 << 2;
+
+/// Input state sent in every packet.
+///
+/// See the [module documentation](self) for the encoded format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GamepadState {
+    /// Sequence number of the packet.
+    pub sequence: u16,
+    /// Held buttons.
+    pub keys: KeyPad,
+    /// Touch position in pixels, if the screen is touched.
+    pub touch: Option<(u16, u16)>,
+    /// Circle pad position.
+    pub circle_pad: (i16, i16),
+    /// Raw accelerometer reading (x, y, z), if the accelerometer is enabled.
+    pub acceleration: Option<(i16, i16, i16)>,
+    /// Raw gyroscope reading (roll, pitch, yaw), if the gyroscope is enabled.
+    pub angular_rate: Option<(i16, i16, i16)>,
+}
+
+/// Sends the input state to a remote device over UDP.
+///
+/// The state is sent at a fixed rate, and immediately whenever the held buttons change so that short presses aren't lost.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::input::netpad::{Streamer, DEFAULT_PORT};
+/// use ctru::prelude::*;
+///
+/// let apt = Apt::new()?;
+/// let mut hid = Hid::new()?;
+/// let _soc = Soc::new()?;
+///
+/// let mut streamer = Streamer::new(("192.168.1.10", DEFAULT_PORT))?;
+///
+/// while apt.main_loop() {
+///     hid.scan_input();
+///     streamer.update(&hid)?;
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct Streamer {
+    socket: UdpSocket,
+    interval: Duration,
+    last_sent: Option<Instant>,
+    last_keys: KeyPad,
+    sequence: u16,
+}
+
+/// Receives the input state sent by a remote device.
+///
+/// Since any device on the network could send packets, the receiver only accepts packets from a single address, chosen by the application.
+/// The remote input is only used by the application via [`RemoteInput`], which must be explicitly wrapped around its local input.
+///
+/// If no packet is received for [`Receiver::TIMEOUT`], the remote buttons are considered released,
+/// so that they don't stay stuck when the remote device disconnects.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::input::netpad::{Receiver, DEFAULT_PORT};
+/// use ctru::prelude::*;
+/// use ctru::services::hid::InputSource;
+///
+/// let apt = Apt::new()?;
+/// let mut hid = Hid::new()?;
+/// let _soc = Soc::new()?;
+///
+/// let mut receiver = Receiver::bind(DEFAULT_PORT, "192.168.1.10".parse()?)?;
+///
+/// while apt.main_loop() {
+///     hid.scan_input();
+///     receiver.update()?;
+///
+///     let input = receiver.merge(&hid);
+///     if input.keys_down().contains(KeyPad::START) {
+///         break;
+///     }
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct Receiver {
+    socket: UdpSocket,
+    allowed: IpAddr,
+    state: Option<GamepadState>,
+    last_received: Option<Instant>,
+    previous_keys: KeyPad,
+}
+
+/// Input of the application merged with the input received by a [`Receiver`].
+///
+/// Buttons are held if they're held either locally or remotely. The remote touch and circle pad positions
+/// take precedence over the local ones while in use.
+///
+/// This struct can be created via [`Receiver::merge()`].
+pub struct RemoteInput<'a, S: InputSource> {
+    local: &'a S,
+    receiver: &'a Receiver,
+}
+
+impl GamepadState {
+    /// Reads the current state from the HID service.
+    ///
+    /// The motion sensors are only read if they have been enabled via [`Hid::set_accelerometer()`] and [`Hid::set_gyroscope()`].
+    pub fn from_hid(hid: &Hid) -> Self {
+        let keys = hid.keys_held();
+
+        Self {
+            sequence: 0,
+            keys,
+            touch: keys.contains(KeyPad::TOUCH).then(|| hid.touch_position()),
+            circle_pad: hid.circlepad_position(),
+            acceleration: hid
+                .accelerometer_vector()
+                .ok()
+                .map(|vector| (vector.x(), vector.y(), vector.z())),
+            angular_rate: hid
+                .gyroscope_rate()
+                .ok()
+                .map(|rate| (rate.roll(), rate.pitch(), rate.yaw())),
+        }
+    }
+
+    /// Encodes the state in the packet format.
+    pub fn to_bytes(&self) -> [u8; PACKET_SIZE] {
+        let mut packet = [0; PACKET_SIZE];
+        let mut flags = 0;
+
+        let touch = self.touch.unwrap_or_default();
+        let acceleration = self.acceleration.unwrap_or_default();
+        let angular_rate = self.angular_rate.unwrap_or_default();
+
+        if self.touch.is_some() {
+            flags |= FLAG_TOUCH;
+        }
+        if self.acceleration.is_some() {
+            flags |= FLAG_ACCELEROMETER;
+        }
+        if self.angular_rate.is_some() {
+            flags |= FLAG_GYROSCOPE;
+        }
+
+        packet[0..4].copy_from_slice(&MAGIC);
+        packet[4] = VERSION;
+        packet[5] = flags;
+        packet[6..8].copy_from_slice(&self.sequence.to_le_bytes());
+        packet[8..12].copy_from_slice(&self.keys.bits().to_le_bytes());
+
+        let words = [
+            touch.0 as i16,
+            touch.1 as i16,
+            self.circle_pad.0,
+            self.circle_pad.1,
+            acceleration.0,
+            acceleration.1,
+            acceleration.2,
+            angular_rate.0,
+            angular_rate.1,
+            angular_rate.2,
+        ];
+        for (chunk, word) in packet[12..].chunks_exact_mut(2).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        packet
+    }
+
+    /// Decodes a state from a packet.
+    ///
+    /// Returns `None` if the packet isn't valid or uses a different version of the format.
+    pub fn from_bytes(packet: &[u8]) -> Option<Self> {
+        let packet: &[u8; PACKET_SIZE] = packet.try_into().ok()?;
+
+        if packet[0..4] != MAGIC || packet[4] != VERSION {
+            return None;
+        }
+
+        let flags = packet[5];
+        let word = |offset: usize| i16::from_le_bytes([packet[offset], packet[offset + 1]]);
+        let triple = |offset: usize| (word(offset), word(offset + 2), word(offset + 4));
+
+        Some(Self {
+            sequence: u16::from_le_bytes([packet[6], packet[7]]),
+            keys: KeyPad::from_bits_truncate(u32::from_le_bytes([
+                packet[8], packet[9], packet[10], packet[11],
+            ])),
+            touch: (flags & FLAG_TOUCH != 0).then(|| (word(12) as u16, word(14) as u16)),
+            circle_pad: (word(16), word(18)),
+            acceleration: (flags & FLAG_ACCELEROMETER != 0).then(|| triple(20)),
+            angular_rate: (flags & FLAG_GYROSCOPE != 0).then(|| triple(26)),
+        })
+    }
+}
+
+impl Streamer {
+    /// Default rate at which the state is sent, once per frame.
+    pub const DEFAULT_RATE: u32 = 60;
+
+    /// Creates a new streamer sending packets to `target` at [`Streamer::DEFAULT_RATE`].
+    ///
+    /// `target` may be a broadcast address (e.g. `255.255.255.255`) to reach any receiver on the local network.
+    ///
+    /// The [`Soc`](crate::services::soc::Soc) service must be active for as long as the streamer is used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the socket couldn't be created, or if `target` couldn't be resolved.
+    pub fn new(target: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        // Allows sending to the broadcast address, when the address of the receiver isn't known.
+        socket.set_broadcast(true)?;
+        socket.connect(target)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            interval: rate_interval(Self::DEFAULT_RATE),
+            last_sent: None,
+            last_keys: KeyPad::empty(),
+            sequence: 0,
+        })
+    }
+
+    /// Sets the amount of packets sent per second, when the held buttons don't change.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `rate` is zero.
+    pub fn set_rate(&mut self, rate: u32) {
+        self.interval = rate_interval(rate);
+    }
+
+    /// Sends the current input state, if enough time has passed since the last packet or if the held buttons changed.
+    ///
+    /// This should be called once per frame, after [`Hid::scan_input()`].
+    ///
+    /// Returns `true` if a packet was sent.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the packet couldn't be sent.
+    /// Packets dropped because the socket buffer is full aren't considered errors.
+    pub fn update(&mut self, hid: &Hid) -> io::Result<bool> {
+        let now = Instant::now();
+        let keys = hid.keys_held();
+
+        let due = match self.last_sent {
+            Some(last_sent) => now.duration_since(last_sent) >= self.interval,
+            None => true,
+        };
+
+        if !due && keys == self.last_keys {
+            return Ok(false);
+        }
+
+        let mut state = GamepadState::from_hid(hid);
+        state.sequence = self.sequence;
+
+        match self.socket.send(&state.to_bytes()) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+            Err(e) => return Err(e),
+        }
+
+        self.sequence = self.sequence.wrapping_add(1);
+        self.last_sent = Some(now);
+        self.last_keys = keys;
+
+        Ok(true)
+    }
+}
+
+impl Receiver {
+    /// Time after which the remote buttons are released if no packet is received.
+    pub const TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Creates a new receiver listening on `port`, which only accepts packets sent from `allowed`.
+    ///
+    /// The [`Soc`](crate::services::soc::Soc) service must be active for as long as the receiver is used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the socket couldn't be bound.
+    pub fn bind(port: u16, allowed: IpAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            allowed,
+            state: None,
+            last_received: None,
+            previous_keys: KeyPad::empty(),
+        })
+    }
+
+    /// Reads the packets received since the last call, keeping the most recent state.
+    ///
+    /// This should be called once per frame, after [`Hid::scan_input()`].
+    /// Packets which are invalid, out of order or sent from another address are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the socket couldn't be read.
+    pub fn update(&mut self) -> io::Result<()> {
+        self.previous_keys = self.keys_held();
+
+        let mut packet = [0; PACKET_SIZE];
+
+        loop {
+            let (len, sender) = match self.socket.recv_from(&mut packet) {
+                Ok(received) => received,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            };
+
+            if sender.ip() != self.allowed {
+                continue;
+            }
+
+            let Some(state) = GamepadState::from_bytes(&packet[..len]) else {
+                continue;
+            };
+
+            if self.is_active() && !is_newer(state.sequence, self.state.as_ref()) {
+                continue;
+            }
+
+            self.state = Some(state);
+            self.last_received = Some(Instant::now());
+        }
+
+        if !self.is_active() {
+            self.state = None;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the last state received, or `None` if no packet was received for [`Receiver::TIMEOUT`].
+    pub fn state(&self) -> Option<&GamepadState> {
+        self.state.as_ref()
+    }
+
+    /// Merges the remote input with the local input of the application.
+    pub fn merge<'a, S: InputSource>(&'a self, local: &'a S) -> RemoteInput<'a, S> {
+        RemoteInput {
+            local,
+            receiver: self,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.last_received
+            .is_some_and(|last_received| last_received.elapsed() < Self::TIMEOUT)
+    }
+
+    fn keys_held(&self) -> KeyPad {
+        self.state.map_or(KeyPad::empty(), |state| state.keys)
+    }
+}
+
+impl<S: InputSource> InputSource for RemoteInput<'_, S> {
+    fn keys_down(&self) -> KeyPad {
+        let remote = self.receiver.keys_held() - self.receiver.previous_keys;
+        self.local.keys_down() | remote
+    }
+
+    fn keys_held(&self) -> KeyPad {
+        self.local.keys_held() | self.receiver.keys_held()
+    }
+
+    fn keys_up(&self) -> KeyPad {
+        let remote = self.receiver.previous_keys - self.receiver.keys_held();
+        self.local.keys_up() | remote
+    }
+
+    fn touch_position(&self) -> (u16, u16) {
+        self.receiver
+            .state()
+            .and_then(|state| state.touch)
+            .unwrap_or_else(|| self.local.touch_position())
+    }
+
+    fn circlepad_position(&self) -> (i16, i16) {
+        match self.receiver.state() {
+            Some(state) if state.circle_pad != (0, 0) => state.circle_pad,
+            _ => self.local.circlepad_position(),
+        }
+    }
+}
+
+// Sequence numbers wrap around, so a packet is considered newer if it's less than half of the range ahead.
+fn is_newer(sequence: u16, last: Option<&GamepadState>) -> bool {
+    match last {
+        Some(last) => {
+            let ahead = sequence.wrapping_sub(last.sequence);
+            ahead != 0 && ahead < 0x8000
+        }
+        None => true,
+    }
+}
+
+fn rate_interval(rate: u32) -> Duration {
+    assert_ne!(rate, 0, "the packet rate must be positive");
+    Duration::from_secs(1) / rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_round_trip() {
+        let state = GamepadState {
+            sequence: 0xBEEF,
+            keys: KeyPad::A | KeyPad::TOUCH | KeyPad::DPAD_LEFT,
+            touch: Some((319, 239)),
+            circle_pad: (-156, 42),
+            acceleration: None,
+            angular_rate: Some((-1, 0, 32767)),
+        };
+
+        let packet = state.to_bytes();
+
+        assert_eq!(&packet[0..5], b"3DGP\x01");
+        assert_eq!(packet[5], FLAG_TOUCH | FLAG_GYROSCOPE);
+        assert_eq!(GamepadState::from_bytes(&packet), Some(state));
+        assert_eq!(GamepadState::from_bytes(&packet[1..]), None);
+    }
+
+    #[test]
+    fn sequence_wrap_around() {
+        let last = GamepadState {
+            sequence: 0xFFFF,
+            keys: KeyPad::empty(),
+            touch: None,
+            circle_pad: (0, 0),
+            acceleration: None,
+            angular_rate: None,
+        };
+
+        assert!(is_newer(0, Some(&last)));
+        assert!(!is_newer(0xFFFF, Some(&last)));
+        assert!(!is_newer(0xFFF0, Some(&last)));
+        assert!(is_newer(0xFFF0, None));
+    }
+}
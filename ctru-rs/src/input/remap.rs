@@ -0,0 +1,541 @@
+//! User-configurable input remapping.
+//!
+//! An [`InputMap`] binds the logical actions of an application (e.g. `"jump"`) to physical buttons, and adjusts the
+//! Circle Pad (axis inversion and sensitivity). Applications query the actions instead of the buttons, so that users can rebind them,
+//! which is often needed for accessibility (e.g. playing with one hand) or for consoles with worn-out buttons.
+//!
+//! The mapping can be saved to and loaded from a settings file, and edited by users in game via a [`RemapMenu`].
+//!
+//! # Example
+//!
+//! ```
+//! # let _runner = test_runner::GdbRunner::default();
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #
+//! use ctru::input::remap::InputMap;
+//! use ctru::prelude::*;
+//!
+//! let mut hid = Hid::new()?;
+//!
+//! let mut input_map = InputMap::new()
+//!     .with_binding("jump", KeyPad::A)
+//!     .with_binding("pause", KeyPad::START);
+//! input_map.load("sdmc:/3ds/my-game/input.cfg")?;
+//!
+//! hid.scan_input();
+//!
+//! if input_map.pressed(&hid, "jump") {
+//!     println!("Jump!");
+//! }
+//!
+//! let (x, y) = input_map.circlepad_position(&hid);
+//! #
+//! # Ok(())
+//! # }
+//! ```
+#![doc(alias = "rebinding")]
+#![doc(alias = "key mapping")]
+#![doc(alias = "accessibility")]
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::console::Console;
+use crate::services::cfgu::Language;
+use crate::services::hid::{InputSource, KeyPad};
+
+// Range of the Circle Pad position on each axis.
+const CIRCLE_PAD_MAX: f32 = 156.0;
+
+// Bounds and step of the sensitivity, as adjusted by the `RemapMenu`.
+const MIN_SENSITIVITY: f32 = 0.1;
+const MAX_SENSITIVITY: f32 = 3.0;
+const SENSITIVITY_STEP: f32 = 0.1;
+
+// Prefix of the keys of the Circle Pad settings in the settings file.
+const STICK_PREFIX: &str = "stick.";
+
+/// Adjustments of the Circle Pad position.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StickSettings {
+    /// Whether the horizontal axis is inverted.
+    pub invert_x: bool,
+    /// Whether the vertical axis is inverted.
+    pub invert_y: bool,
+    /// Multiplier applied to the position. The result is clamped to the range of the Circle Pad.
+    pub sensitivity: f32,
+}
+
+impl Default for StickSettings {
+    /// Returns settings which leave the position untouched.
+    fn default() -> Self {
+        Self {
+            invert_x: false,
+            invert_y: false,
+            sensitivity: 1.0,
+        }
+    }
+}
+
+/// Bindings of the logical actions of an application to physical buttons, and Circle Pad adjustments.
+///
+/// An action is triggered by any of the buttons it's bound to. Actions are listed in the order they were first bound.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputMap {
+    bindings: Vec<(String, KeyPad)>,
+    stick: StickSettings,
+}
+
+impl InputMap {
+    /// Creates an empty input map, with default Circle Pad settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds an action to buttons, and returns the map. This is handy to declare the default bindings.
+    pub fn with_binding(mut self, action: impl Into<String>, keys: KeyPad) -> Self {
+        self.bind(action, keys);
+        self
+    }
+
+    /// Binds an action to buttons, replacing its previous binding.
+    ///
+    /// Line breaks and `=` characters can't be saved in action names, so they are replaced by spaces.
+    /// Names are also trimmed, and leading `#` characters and `stick.` prefixes (which are reserved by the settings file) are removed.
+    pub fn bind(&mut self, action: impl Into<String>, keys: KeyPad) {
+        let action = action_name(&action.into());
+
+        match self.bindings.iter_mut().find(|(name, _)| *name == action) {
+            Some((_, bound)) => *bound = keys,
+            None => self.bindings.push((action, keys)),
+        }
+    }
+
+    /// Returns the buttons an action is bound to, which are empty if the action isn't bound.
+    pub fn keys(&self, action: &str) -> KeyPad {
+        self.bindings
+            .iter()
+            .find(|(name, _)| name == action)
+            .map_or(KeyPad::empty(), |&(_, keys)| keys)
+    }
+
+    /// Returns an iterator over the actions and the buttons they are bound to.
+    pub fn actions(&self) -> impl Iterator<Item = (&str, KeyPad)> {
+        self.bindings
+            .iter()
+            .map(|(name, keys)| (name.as_str(), *keys))
+    }
+
+    /// Returns the Circle Pad settings.
+    pub fn stick(&self) -> StickSettings {
+        self.stick
+    }
+
+    /// Sets the Circle Pad settings.
+    pub fn set_stick(&mut self, stick: StickSettings) {
+        self.stick = stick;
+    }
+
+    /// Returns `true` if a button bound to the action has just been pressed on the current frame.
+    pub fn pressed(&self, input: &impl InputSource, action: &str) -> bool {
+        input.keys_down().intersects(self.keys(action))
+    }
+
+    /// Returns `true` if a button bound to the action is held during the current frame.
+    pub fn held(&self, input: &impl InputSource, action: &str) -> bool {
+        input.keys_held().intersects(self.keys(action))
+    }
+
+    /// Returns `true` if a button bound to the action has just been released on the current frame.
+    pub fn released(&self, input: &impl InputSource, action: &str) -> bool {
+        input.keys_up().intersects(self.keys(action))
+    }
+
+    /// Returns the Circle Pad position, adjusted by the [`StickSettings`].
+    pub fn circlepad_position(&self, input: &impl InputSource) -> (i16, i16) {
+        let (x, y) = input.circlepad_position();
+        let adjust = |value: i16, invert: bool| {
+            let value = f32::from(value) * self.stick.sensitivity;
+            let value = if invert { -value } else { value };
+
+            value.clamp(-CIRCLE_PAD_MAX, CIRCLE_PAD_MAX) as i16
+        };
+
+        (
+            adjust(x, self.stick.invert_x),
+            adjust(y, self.stick.invert_y),
+        )
+    }
+
+    /// Loads the bindings and settings saved in a settings file by [`InputMap::save()`].
+    ///
+    /// Actions and settings which aren't in the file keep their current value, so defaults should be bound before loading.
+    /// Nothing is changed if the file doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be read, or if it's malformed (in which case nothing is changed).
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        match fs::read_to_string(path) {
+            Ok(contents) => self.parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Saves the bindings and settings to a settings file, which can be read back by [`InputMap::load()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_settings())
+    }
+
+    // Settings file format, with one `key = value` pair per line. Buttons are written as the names of their flags (e.g. `A | DPAD_UP`).
+    fn to_settings(&self) -> String {
+        let mut settings = String::new();
+
+        // Writing to a `String` can't fail.
+        let _ = writeln!(settings, "{STICK_PREFIX}invert_x = {}", self.stick.invert_x);
+        let _ = writeln!(settings, "{STICK_PREFIX}invert_y = {}", self.stick.invert_y);
+        let _ = writeln!(
+            settings,
+            "{STICK_PREFIX}sensitivity = {}",
+            self.stick.sensitivity
+        );
+
+        for (action, keys) in &self.bindings {
+            let _ = write!(settings, "{action} = ");
+            let _ = bitflags::parser::to_writer(keys, &mut settings);
+            settings.push('\n');
+        }
+
+        settings
+    }
+
+    fn parse(&mut self, settings: &str) -> io::Result<()> {
+        let mut parsed = self.clone();
+
+        for (i, line) in settings.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {reason}", i + 1),
+                )
+            };
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("expected `key = value`"))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match key.strip_prefix(STICK_PREFIX) {
+                Some("invert_x") => {
+                    parsed.stick.invert_x =
+                        value.parse().map_err(|_| invalid("expected a boolean"))?
+                }
+                Some("invert_y") => {
+                    parsed.stick.invert_y =
+                        value.parse().map_err(|_| invalid("expected a boolean"))?
+                }
+                Some("sensitivity") => {
+                    parsed.stick.sensitivity = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|sensitivity| sensitivity.is_finite())
+                        .ok_or_else(|| invalid("expected a number"))?
+                        .clamp(MIN_SENSITIVITY, MAX_SENSITIVITY)
+                }
+                Some(_) => return Err(invalid("unknown Circle Pad setting")),
+                None => {
+                    let keys = bitflags::parser::from_str::<KeyPad>(value)
+                        .map_err(|e| invalid(&e.to_string()))?;
+                    parsed.bind(key, keys);
+                }
+            }
+        }
+
+        *self = parsed;
+
+        Ok(())
+    }
+}
+
+/// Configuration menu letting users edit an [`InputMap`], drawn on a [`Console`].
+///
+/// The menu lists the actions and the Circle Pad settings. It's always navigated with the physical buttons,
+/// so that it stays usable whatever the bindings:
+///
+/// - Up and Down on the D-Pad select an entry.
+/// - A rebinds the selected action to the next buttons pressed, or toggles the selected inversion setting.
+/// - Left and Right adjust the sensitivity.
+/// - Y unbinds the selected action.
+/// - B closes the menu.
+///
+/// While rebinding, any button (or combination of buttons) can be pressed, and touching the screen cancels.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::input::remap::{InputMap, RemapMenu};
+/// use ctru::prelude::*;
+///
+/// let apt = Apt::new()?;
+/// let mut hid = Hid::new()?;
+/// let gfx = Gfx::new()?;
+/// let console = Console::new(gfx.bottom_screen.borrow_mut());
+///
+/// let mut input_map = InputMap::new().with_binding("jump", KeyPad::A);
+/// let mut menu = RemapMenu::new();
+///
+/// while apt.main_loop() {
+///     hid.scan_input();
+///
+///     if !menu.update(&mut input_map, &hid) {
+///         input_map.save("sdmc:/3ds/my-game/input.cfg")?;
+///         break;
+///     }
+///
+///     menu.draw(&input_map, &console);
+/// #   break;
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RemapMenu {
+    language: Language,
+    selected: usize,
+    capturing: bool,
+    // Buttons pressed so far while capturing a binding, which is saved once they are all released.
+    captured: KeyPad,
+}
+
+// Entries of the menu after the actions.
+const STICK_ENTRIES: usize = 3;
+
+impl RemapMenu {
+    /// Creates a menu showing the names of the buttons in English.
+    pub fn new() -> Self {
+        Self::with_language(Language::English)
+    }
+
+    /// Creates a menu showing the names of the buttons in a specific language (usually the one of the console).
+    pub fn with_language(language: Language) -> Self {
+        Self {
+            language,
+            selected: 0,
+            capturing: false,
+            captured: KeyPad::empty(),
+        }
+    }
+
+    /// Returns `true` while the menu waits for the user to press the buttons of a new binding.
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    /// Updates the menu with the input of the current frame, editing `input_map` accordingly.
+    ///
+    /// Returns `false` once the user closed the menu.
+    pub fn update(&mut self, input_map: &mut InputMap, input: &impl InputSource) -> bool {
+        let actions = input_map.bindings.len();
+        let keys = input.keys_down();
+
+        if self.capturing {
+            // The binding is saved once the buttons are released, so that combinations can be bound.
+            self.captured |= keys & !KeyPad::TOUCH;
+
+            if keys.contains(KeyPad::TOUCH) {
+                self.capturing = false;
+            } else if !self.captured.is_empty() && !input.keys_held().intersects(self.captured) {
+                input_map.bindings[self.selected].1 = self.captured;
+                self.capturing = false;
+            }
+
+            return true;
+        }
+
+        let entries = actions + STICK_ENTRIES;
+        self.selected = self.selected.min(entries - 1);
+
+        if keys.contains(KeyPad::DPAD_UP) {
+            self.selected = (self.selected + entries - 1) % entries;
+        } else if keys.contains(KeyPad::DPAD_DOWN) {
+            self.selected = (self.selected + 1) % entries;
+        } else if keys.contains(KeyPad::B) {
+            return false;
+        }
+
+        let stick = &mut input_map.stick;
+        match self.selected.checked_sub(actions) {
+            None if keys.contains(KeyPad::A) => {
+                self.capturing = true;
+                self.captured = KeyPad::empty();
+            }
+            None if keys.contains(KeyPad::Y) => {
+                input_map.bindings[self.selected].1 = KeyPad::empty()
+            }
+            Some(0) if keys.contains(KeyPad::A) => stick.invert_x = !stick.invert_x,
+            Some(1) if keys.contains(KeyPad::A) => stick.invert_y = !stick.invert_y,
+            Some(2) if keys.intersects(KeyPad::DPAD_LEFT | KeyPad::DPAD_RIGHT) => {
+                let step = if keys.contains(KeyPad::DPAD_LEFT) {
+                    -SENSITIVITY_STEP
+                } else {
+                    SENSITIVITY_STEP
+                };
+
+                // Rounded to avoid accumulating floating point errors.
+                let sensitivity = ((stick.sensitivity + step) * 10.0).round() / 10.0;
+                stick.sensitivity = sensitivity.clamp(MIN_SENSITIVITY, MAX_SENSITIVITY);
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    /// Draws the menu on a console, replacing its contents.
+    pub fn draw(&self, input_map: &InputMap, console: &Console) {
+        console.select();
+        console.clear();
+
+        println!("Controls\n");
+
+        for (i, (action, keys)) in input_map.actions().enumerate() {
+            let cursor = if i == self.selected { '>' } else { ' ' };
+
+            if self.capturing && i == self.selected {
+                println!("{cursor} {action}: press the new buttons (touch to cancel)");
+            } else {
+                println!("{cursor} {action}: {}", self.key_names(keys));
+            }
+        }
+
+        let stick = input_map.stick();
+        let cursor = |entry: usize| {
+            if input_map.bindings.len() + entry == self.selected {
+                '>'
+            } else {
+                ' '
+            }
+        };
+
+        println!();
+        println!("{} Invert X axis: {}", cursor(0), on_off(stick.invert_x));
+        println!("{} Invert Y axis: {}", cursor(1), on_off(stick.invert_y));
+        println!("{} Sensitivity: < {:.1} >", cursor(2), stick.sensitivity);
+        println!();
+        println!("A: change  Y: unbind  B: back");
+    }
+
+    fn key_names(&self, keys: KeyPad) -> String {
+        if keys.is_empty() {
+            return "-".into();
+        }
+
+        keys.keys()
+            .filter_map(|key| key.display_name(self.language))
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+impl Default for RemapMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+// Returns the name under which an action is bound, so that it's read back unchanged from the settings file.
+fn action_name(action: &str) -> String {
+    let action = action.replace(['\r', '\n', '='], " ");
+    let mut name = action.as_str();
+
+    loop {
+        let stripped = name
+            .trim()
+            .trim_start_matches('#')
+            .trim_start_matches(STICK_PREFIX);
+
+        if stripped == name {
+            return name.to_owned();
+        }
+
+        name = stripped;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let mut input_map = InputMap::new()
+            .with_binding("jump", KeyPad::A | KeyPad::DPAD_UP)
+            .with_binding("pause", KeyPad::START);
+        input_map.set_stick(StickSettings {
+            invert_y: true,
+            sensitivity: 1.5,
+            ..Default::default()
+        });
+
+        let mut loaded = InputMap::new().with_binding("pause", KeyPad::SELECT);
+        loaded.parse(&input_map.to_settings()).unwrap();
+        assert_eq!(loaded.keys("jump"), KeyPad::A | KeyPad::DPAD_UP);
+        assert_eq!(loaded.keys("pause"), KeyPad::START);
+        assert_eq!(loaded.stick(), input_map.stick());
+
+        // Malformed files don't change the map.
+        assert!(loaded.parse("jump = B\nrun = NOT_A_KEY").is_err());
+        assert_eq!(loaded.keys("jump"), KeyPad::A | KeyPad::DPAD_UP);
+        assert_eq!(loaded.keys("run"), KeyPad::empty());
+    }
+
+    #[test]
+    fn reserved_action_names() {
+        let input_map = InputMap::new()
+            .with_binding(" # stick.jump = up\n", KeyPad::A)
+            .with_binding("stick.invert_x", KeyPad::B);
+        let names: Vec<_> = input_map.actions().map(|(name, _)| name).collect();
+        assert_eq!(names, ["jump   up", "invert_x"]);
+
+        let mut loaded = InputMap::new();
+        loaded.parse(&input_map.to_settings()).unwrap();
+        assert_eq!(loaded, input_map);
+    }
+
+    #[test]
+    fn sensitivity_bounds() {
+        let mut loaded = InputMap::new();
+
+        loaded.parse("stick.sensitivity = -1").unwrap();
+        assert_eq!(loaded.stick().sensitivity, MIN_SENSITIVITY);
+
+        loaded.parse("stick.sensitivity = 100").unwrap();
+        assert_eq!(loaded.stick().sensitivity, MAX_SENSITIVITY);
+    }
+}
@@ -0,0 +1,325 @@
+//! Credential storage.
+//!
+//! This module provides a small store for secrets such as passwords or authentication tokens, with an API similar to the one of the
+//! [`keyring`](https://docs.rs/keyring) crate. Instead of being written in plain text on the SD card, where any other application
+//! can read them, secrets are encrypted via the [`Ps`] service and bound to the console they were saved on.
+#![doc(alias = "credentials")]
+#![doc(alias = "password")]
+
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::ops::FromResidual;
+use std::path::{Path, PathBuf};
+
+use crate::device::encrypted::EncryptedDevice;
+use crate::services::ps::{AESKeyType, Ps};
+
+/// Error enum for errors within the [`Keyring`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum Error {
+    /// No secret is stored for the entry.
+    NoEntry,
+    /// The keyring file is corrupted, or was saved on another console.
+    BadStoreFormat,
+    /// The keyring file couldn't be read or written.
+    Io(io::Error),
+    /// ctru-rs error
+    Lib(crate::Error),
+}
+
+/// Encrypted store of credentials, saved in a single file.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::keyring::Keyring;
+///
+/// let keyring = Keyring::open("sdmc:/3ds/my-app/keyring.bin")?;
+/// let entry = keyring.entry("example.com", "user");
+///
+/// entry.set_password("hunter2")?;
+/// assert_eq!(entry.get_password()?, "hunter2");
+///
+/// entry.delete_password()?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct Keyring {
+    path: PathBuf,
+    device: EncryptedDevice,
+    ps: Ps,
+}
+
+/// Single credential of a [`Keyring`], identified by a service and a user name.
+///
+/// This struct can be retrieved via [`Keyring::entry()`].
+pub struct Entry<'keyring> {
+    keyring: &'keyring Keyring,
+    service: String,
+    user: String,
+}
+
+// Credentials stored in the keyring file, as (service, user, secret).
+type Credentials = Vec<(String, String, Vec<u8>)>;
+
+impl Keyring {
+    /// Opens the keyring stored at the specified path.
+    ///
+    /// The file is created the first time a secret is saved.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the [`Ps`] service couldn't be initialized.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        Self::with_key_type(path, AESKeyType::Keyslot0D)
+    }
+
+    /// Opens the keyring stored at the specified path, encrypting it with the key of the specified keyslot.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the [`Ps`] service couldn't be initialized.
+    pub fn with_key_type(path: impl Into<PathBuf>, key_type: AESKeyType) -> Result<Self, Error> {
+        let path = path.into();
+        let root = path.parent().map(PathBuf::from).unwrap_or_default();
+
+        Ok(Self {
+            path,
+            device: EncryptedDevice::new(root, key_type)?,
+            ps: Ps::new()?,
+        })
+    }
+
+    /// Returns the entry for the specified service and user name.
+    pub fn entry(&self, service: &str, user: &str) -> Entry<'_> {
+        Entry {
+            keyring: self,
+            service: service.to_owned(),
+            user: user.to_owned(),
+        }
+    }
+
+    fn load(&self) -> Result<Credentials, Error> {
+        // If a save was interrupted after the keyring was moved to its backup, the backup holds the latest credentials.
+        let container = match fs::read(&self.path).or_else(|e| match e.kind() {
+            io::ErrorKind::NotFound => fs::read(self.backup_path()),
+            _ => Err(e),
+        }) {
+            Ok(container) => container,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        let data = self.device.unseal(&container).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => Error::BadStoreFormat,
+            _ => Error::Io(e),
+        })?;
+
+        // The console ID is authenticated along with the credentials, so keyrings copied from another console
+        // are rejected, even if the key is shared between consoles.
+        let device_id = data.get(..4).ok_or(Error::BadStoreFormat)?;
+        if device_id != self.ps.device_id()?.to_le_bytes() {
+            return Err(Error::BadStoreFormat);
+        }
+
+        decode(&data[4..]).ok_or(Error::BadStoreFormat)
+    }
+
+    fn save(&self, credentials: &Credentials) -> Result<(), Error> {
+        let mut data = self.ps.device_id()?.to_le_bytes().to_vec();
+        encode(credentials, &mut data);
+
+        let container = self.device.seal(&data).map_err(Error::Io)?;
+        let temp_path = self.path.with_extension("tmp");
+        let backup_path = self.backup_path();
+
+        // Replace the keyring as a whole, so that an interrupted write can't lose the stored credentials.
+        fs::write(&temp_path, container).map_err(Error::Io)?;
+
+        // Renaming fails if the destination already exists, so the previous keyring is moved to its backup first,
+        // which is only removed once the new keyring is in place.
+        if self.path.exists() {
+            remove_if_exists(&backup_path)?;
+            fs::rename(&self.path, &backup_path).map_err(Error::Io)?;
+        }
+
+        fs::rename(temp_path, &self.path).map_err(Error::Io)?;
+        remove_if_exists(&backup_path)
+    }
+
+    fn backup_path(&self) -> PathBuf {
+        self.path.with_extension("bak")
+    }
+}
+
+impl Entry<'_> {
+    /// Saves the password of this entry, replacing any previous one.
+    pub fn set_password(&self, password: &str) -> Result<(), Error> {
+        self.set_secret(password.as_bytes())
+    }
+
+    /// Returns the password of this entry.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::NoEntry`] if no password is stored for this entry,
+    /// or [`Error::BadStoreFormat`] if the stored secret isn't valid UTF-8.
+    pub fn get_password(&self) -> Result<String, Error> {
+        String::from_utf8(self.get_secret()?).map_err(|_| Error::BadStoreFormat)
+    }
+
+    /// Saves binary data (e.g. an authentication token) for this entry, replacing any previous secret.
+    pub fn set_secret(&self, secret: &[u8]) -> Result<(), Error> {
+        let mut credentials = self.keyring.load()?;
+
+        match credentials.iter_mut().find(|c| self.matches(c)) {
+            Some((_, _, stored)) => *stored = secret.to_vec(),
+            None => credentials.push((self.service.clone(), self.user.clone(), secret.to_vec())),
+        }
+
+        self.keyring.save(&credentials)
+    }
+
+    /// Returns the binary data stored for this entry.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::NoEntry`] if no secret is stored for this entry.
+    pub fn get_secret(&self) -> Result<Vec<u8>, Error> {
+        self.keyring
+            .load()?
+            .into_iter()
+            .find(|c| self.matches(c))
+            .map(|(_, _, secret)| secret)
+            .ok_or(Error::NoEntry)
+    }
+
+    /// Deletes the secret stored for this entry.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::NoEntry`] if no secret is stored for this entry.
+    pub fn delete_password(&self) -> Result<(), Error> {
+        let mut credentials = self.keyring.load()?;
+        let len = credentials.len();
+
+        credentials.retain(|c| !self.matches(c));
+        if credentials.len() == len {
+            return Err(Error::NoEntry);
+        }
+
+        self.keyring.save(&credentials)
+    }
+
+    fn matches(&self, (service, user, _): &(String, String, Vec<u8>)) -> bool {
+        *service == self.service && *user == self.user
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), Error> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(Error::Io(e)),
+        _ => Ok(()),
+    }
+}
+
+fn encode(credentials: &Credentials, out: &mut Vec<u8>) {
+    out.extend((credentials.len() as u32).to_le_bytes());
+
+    for (service, user, secret) in credentials {
+        for field in [service.as_bytes(), user.as_bytes(), &secret[..]] {
+            out.extend((field.len() as u32).to_le_bytes());
+            out.extend(field);
+        }
+    }
+}
+
+fn decode(mut data: &[u8]) -> Option<Credentials> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        let field = data.get(..len)?;
+        *data = &data[len..];
+        Some(field)
+    }
+
+    fn take_field(data: &mut &[u8]) -> Option<Vec<u8>> {
+        let len = u32::from_le_bytes(take(data, 4)?.try_into().unwrap());
+        take(data, len as usize).map(<[u8]>::to_vec)
+    }
+
+    let count = u32::from_le_bytes(take(&mut data, 4)?.try_into().unwrap());
+
+    (0..count)
+        .map(|_| {
+            let service = String::from_utf8(take_field(&mut data)?).ok()?;
+            let user = String::from_utf8(take_field(&mut data)?).ok()?;
+            Some((service, user, take_field(&mut data)?))
+        })
+        .collect()
+}
+
+impl From<crate::Error> for Error {
+    fn from(value: crate::Error) -> Self {
+        Error::Lib(value)
+    }
+}
+
+impl<T> FromResidual<crate::Error> for Result<T, Error> {
+    fn from_residual(residual: crate::Error) -> Self {
+        Err(residual.into())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoEntry => write!(f, "no secret is stored for this entry"),
+            Self::BadStoreFormat => {
+                write!(f, "keyring is corrupted or was saved on another console")
+            }
+            Self::Io(e) => write!(f, "keyring I/O error: {e}"),
+            Self::Lib(e) => write!(f, "ctru-rs error: {e}"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_round_trip() {
+        let credentials = vec![
+            ("example.com".into(), "user".into(), b"hunter2".to_vec()),
+            ("api".into(), String::new(), vec![0, 1, 2]),
+        ];
+
+        let mut data = Vec::new();
+        encode(&credentials, &mut data);
+
+        assert_eq!(decode(&data), Some(credentials));
+        assert_eq!(decode(&data[..data.len() - 1]), None);
+    }
+
+    #[test]
+    fn save_twice() {
+        let path = "sdmc:/ctru-rs-keyring-test.bin";
+        let keyring = Keyring::open(path).unwrap();
+        let entry = keyring.entry("example.com", "user");
+
+        entry.set_password("hunter2").unwrap();
+        entry.set_password("correct horse").unwrap();
+        assert_eq!(entry.get_password().unwrap(), "correct horse");
+        assert!(!keyring.backup_path().exists());
+
+        fs::remove_file(path).unwrap();
+    }
+}
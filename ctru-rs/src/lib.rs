@@ -59,14 +59,119 @@ macro_rules! from_impl {
     };
 }
 
+// Defines an enum mirroring a C enum, with the conversions from and into its raw representation.
+//
+// When the list of the values of the C enum is given (generated by `ctru-sys`), the build fails
+// if one of them isn't handled by a variant, so that the enum stays in sync with libctru.
+macro_rules! ffi_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident: $repr:ident $(from $values:path)? {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:expr),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr($repr)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant = $value),+
+        }
+
+        from_impl!($name, $repr);
+
+        impl TryFrom<$repr> for $name {
+            type Error = ();
+
+            fn try_from(value: $repr) -> ::core::result::Result<Self, Self::Error> {
+                $(
+                    if value == $name::$variant as $repr {
+                        return Ok($name::$variant);
+                    }
+                )+
+
+                Err(())
+            }
+        }
+
+        ffi_enum!(@check $name $repr [$($values)?] [$($variant)+]);
+    };
+    // Checks an enum defined without depending on `ctru-sys` (e.g. in `ctru-traits`) against the values of libctru.
+    (
+        extern $name:ident: $repr:ident $(from $values:path)? {
+            $($variant:ident = $value:expr),+ $(,)?
+        }
+    ) => {
+        const _: () = {
+            $(
+                assert!(
+                    $name::$variant as $repr == $value,
+                    concat!(stringify!($name), "::", stringify!($variant), " isn't in sync with libctru"),
+                );
+            )+
+        };
+
+        ffi_enum!(@check $name $repr [$($values)?] [$($variant)+]);
+    };
+    (@check $name:ident $repr:ident [] [$($variant:ident)+]) => {};
+    (@check $name:ident $repr:ident [$values:path] [$($variant:ident)+]) => {
+        const _: () = {
+            let values = $values;
+            let mut i = 0;
+
+            while i < values.len() {
+                let value = values[i] as $repr;
+                assert!(
+                    $(value == $name::$variant as $repr)||+,
+                    concat!("a value of ", stringify!($values), " isn't handled by ", stringify!($name)),
+                );
+                i += 1;
+            }
+        };
+    };
+}
+
+pub mod alarm;
+#[cfg(feature = "applets")]
 pub mod applets;
+#[doc(hidden)]
+pub mod base64;
+pub mod codec;
 pub mod console;
+pub mod crash;
+pub mod device;
 pub mod error;
+pub mod events;
+#[cfg(any(feature = "network", all(feature = "romfs", romfs_exists)))]
+mod hash;
+pub mod headless;
+#[cfg(feature = "network")]
+pub mod http_cache;
+pub mod input;
+pub mod keyring;
 pub mod linear;
+#[cfg(feature = "logger")]
+pub mod logger;
 pub mod mii;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod os;
+pub mod overlay;
+pub mod patch;
+pub mod plugin;
 pub mod prelude;
+pub mod resources;
+#[cfg(feature = "scripting")]
+pub mod scripting;
 mod sealed;
 pub mod services;
+pub mod smdh;
+pub mod splash;
+pub mod stdio;
+#[cfg(feature = "symbols")]
+pub mod symbols;
+#[cfg(feature = "tz")]
+pub mod tz;
+#[cfg(feature = "audio")]
+pub mod voice;
+pub mod vram;
 
 pub use crate::error::{Error, Result};
@@ -0,0 +1,190 @@
+//! Logging backend.
+//!
+//! [`Logger`] implements the [`log::Log`] trait, so that the messages of the [`log`] macros (`info!`, `warn!`, etc.)
+//! are written to one or more [`Sink`]s: the consoles on either screen, the standard output (which can be redirected
+//! to `3dslink` or GDB) or a file. Every sink has its own level filter, so that e.g. only warnings are shown on screen
+//! while everything is written to a file on the SD card.
+#![doc(alias = "log")]
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+pub use log::LevelFilter;
+use log::{Level, Log, Metadata, Record, SetLoggerError};
+
+/// Destination of the log messages.
+pub enum Sink {
+    /// The [`Console`](crate::console::Console) on the top screen, if any.
+    TopConsole,
+    /// The [`Console`](crate::console::Console) on the bottom screen, if any.
+    BottomConsole,
+    /// The standard output, which can be redirected to `3dslink` or GDB.
+    Stdout,
+    /// Any writer, such as a file (see [`Sink::file()`]).
+    Writer(Box<dyn Write + Send>),
+}
+
+/// Logger writing to multiple [`Sink`]s, each with its own level filter.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::console::Console;
+/// use ctru::logger::{LevelFilter, Logger, Sink};
+/// use ctru::services::gfx::Gfx;
+///
+/// let gfx = Gfx::new()?;
+/// let _console = Console::new(gfx.bottom_screen.borrow_mut());
+///
+/// Logger::new()
+///     .with_sink(Sink::BottomConsole, LevelFilter::Warn)
+///     .with_sink(Sink::file("sdmc:/app.log")?, LevelFilter::Trace)
+///     .init()?;
+///
+/// log::info!("Only written to the file");
+/// log::error!("Shown on the bottom screen too");
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct Logger {
+    sinks: Mutex<Vec<(Sink, LevelFilter)>>,
+    start: Instant,
+}
+
+impl Sink {
+    /// Opens a file in append mode (creating it if needed) to write the messages to.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be opened.
+    pub fn file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::options().append(true).create(true).open(path)?;
+
+        Ok(Self::Writer(Box::new(file)))
+    }
+
+    fn write(&mut self, line: &str, level: Level) -> io::Result<()> {
+        match self {
+            Self::TopConsole => write_to_console(ctru_sys::GFX_TOP, line, level),
+            Self::BottomConsole => write_to_console(ctru_sys::GFX_BOTTOM, line, level),
+            Self::Stdout => {
+                let mut stdout = io::stdout().lock();
+                writeln!(stdout, "{line}")?;
+                stdout.flush()
+            }
+            Self::Writer(writer) => {
+                writeln!(writer, "{line}")?;
+                writer.flush()
+            }
+        }
+    }
+}
+
+impl Logger {
+    /// Creates a logger without any sink.
+    pub fn new() -> Self {
+        Self {
+            sinks: Mutex::new(Vec::new()),
+            start: Instant::now(),
+        }
+    }
+
+    /// Adds a sink, which receives the messages up to `level`.
+    pub fn with_sink(self, sink: Sink, level: LevelFilter) -> Self {
+        self.sinks().push((sink, level));
+        self
+    }
+
+    /// Sets this logger as the global logger used by the [`log`] macros.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a global logger was already set.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let max_level = self
+            .sinks()
+            .iter()
+            .map(|&(_, level)| level)
+            .max()
+            .unwrap_or(LevelFilter::Off);
+
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(max_level);
+
+        Ok(())
+    }
+
+    fn sinks(&self) -> MutexGuard<'_, Vec<(Sink, LevelFilter)>> {
+        // A panicking sink must not disable logging for the rest of the application.
+        self.sinks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.sinks()
+            .iter()
+            .any(|&(_, level)| metadata.level() <= level)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let mut sinks = self.sinks();
+
+        let elapsed = self.start.elapsed();
+        let line = format!(
+            "[{:>4}.{:03} {:<5} {}] {}",
+            elapsed.as_secs(),
+            elapsed.subsec_millis(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        for (sink, level) in sinks.iter_mut() {
+            if record.level() <= *level {
+                // There's nowhere to report a logging failure.
+                let _ = sink.write(&line, record.level());
+            }
+        }
+    }
+
+    fn flush(&self) {
+        for (sink, _) in self.sinks().iter_mut() {
+            if let Sink::Writer(writer) = sink {
+                let _ = writer.flush();
+            }
+        }
+    }
+}
+
+fn write_to_console(screen: ctru_sys::gfxScreen_t, line: &str, level: Level) -> io::Result<()> {
+    let color = match level {
+        Level::Error => "\x1b[31m",
+        Level::Warn => "\x1b[33m",
+        Level::Info => "",
+        Level::Debug | Level::Trace => "\x1b[2m",
+    };
+
+    let mut result = Ok(());
+
+    crate::console::with_screen_console(screen, || {
+        let mut stdout = io::stdout().lock();
+        result = writeln!(stdout, "{color}{line}\x1b[0m").and_then(|()| stdout.flush());
+    });
+
+    result
+}
@@ -245,7 +245,8 @@ pub struct MoleDetails {
 ///
 /// Some values are not ordered *like* the Mii Editor UI. The mapped values can be seen [here](https://www.3dbrew.org/wiki/Mii#Mapped_Editor_.3C-.3E_Hex_values).
 ///
-/// This struct can be retrieved by [`MiiSelector::launch()`](crate::applets::mii_selector::MiiSelector::launch).
+/// This struct can be retrieved by [`MiiSelector::launch()`](crate::applets::mii_selector::MiiSelector::launch),
+/// or decoded from raw data via [`Mii::from_bytes()`].
 #[derive(Clone, Debug)]
 pub struct Mii {
     /// Mii options.
@@ -295,9 +296,24 @@ pub struct Mii {
     pub author_name: String,
 }
 
-impl From<ctru_sys::MiiData> for Mii {
-    fn from(mii_data: ctru_sys::MiiData) -> Self {
-        let raw_mii_data = mii_data._bindgen_opaque_blob;
+impl Mii {
+    /// Size of the raw Mii data (in bytes).
+    pub const SIZE: usize = 0x5C;
+
+    /// Decodes a Mii from its raw representation, as stored by the system (e.g. in `CFL_DB.dat`) or in QR codes after decryption.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// use ctru::mii::Mii;
+    ///
+    /// let raw = [0; Mii::SIZE];
+    /// let mii = Mii::from_bytes(&raw);
+    ///
+    /// assert!(mii.name.is_empty());
+    /// ```
+    pub fn from_bytes(raw_mii_data: &[u8; Self::SIZE]) -> Self {
         // Source for the representation and what each thing means: https://www.3dbrew.org/wiki/Mii
         let raw_options = vec_bit(raw_mii_data[0x1]);
         let raw_position = vec_bit(raw_mii_data[0x2]);
@@ -320,39 +336,39 @@ impl From<ctru_sys::MiiData> for Mii {
             raw_mii_data[0x14],
             raw_mii_data[0x15],
         ];
-        let raw_details: [bool; 16] = get_and_concat_vec_bit(&raw_mii_data, &[0x18, 0x19])
+        let raw_details: [bool; 16] = get_and_concat_vec_bit(raw_mii_data, &[0x18, 0x19])
             .try_into()
             .unwrap();
-        let raw_utf16_name = &raw_mii_data[0x1A..0x2D];
+        let raw_utf16_name = &raw_mii_data[0x1A..0x2E];
         let height = raw_mii_data[0x2E];
         let width = raw_mii_data[0x2F];
         let raw_face_style = vec_bit(raw_mii_data[0x30]);
         let raw_face_details = vec_bit(raw_mii_data[0x31]);
         let raw_hair_details = vec_bit(raw_mii_data[0x33]);
         let raw_eye_details: [bool; 32] =
-            get_and_concat_vec_bit(&raw_mii_data, &[0x34, 0x35, 0x36, 0x37])
+            get_and_concat_vec_bit(raw_mii_data, &[0x34, 0x35, 0x36, 0x37])
                 .try_into()
                 .unwrap();
         let raw_eyebrow_details: [bool; 32] =
-            get_and_concat_vec_bit(&raw_mii_data, &[0x38, 0x39, 0x3A, 0x3B])
+            get_and_concat_vec_bit(raw_mii_data, &[0x38, 0x39, 0x3A, 0x3B])
                 .try_into()
                 .unwrap();
-        let raw_nose_details: [bool; 16] = get_and_concat_vec_bit(&raw_mii_data, &[0x3C, 0x3D])
+        let raw_nose_details: [bool; 16] = get_and_concat_vec_bit(raw_mii_data, &[0x3C, 0x3D])
             .try_into()
             .unwrap();
-        let raw_mouth_details: [bool; 16] = get_and_concat_vec_bit(&raw_mii_data, &[0x3E, 0x3F])
+        let raw_mouth_details: [bool; 16] = get_and_concat_vec_bit(raw_mii_data, &[0x3E, 0x3F])
             .try_into()
             .unwrap();
-        let raw_mustache_details: [bool; 16] = get_and_concat_vec_bit(&raw_mii_data, &[0x40, 0x41])
+        let raw_mustache_details: [bool; 16] = get_and_concat_vec_bit(raw_mii_data, &[0x40, 0x41])
             .try_into()
             .unwrap();
-        let raw_beard_details: [bool; 16] = get_and_concat_vec_bit(&raw_mii_data, &[0x42, 0x42])
+        let raw_beard_details: [bool; 16] = get_and_concat_vec_bit(raw_mii_data, &[0x42, 0x43])
             .try_into()
             .unwrap();
-        let raw_glass_details: [bool; 16] = get_and_concat_vec_bit(&raw_mii_data, &[0x44, 0x45])
+        let raw_glass_details: [bool; 16] = get_and_concat_vec_bit(raw_mii_data, &[0x44, 0x45])
             .try_into()
             .unwrap();
-        let raw_mole_details: [bool; 16] = get_and_concat_vec_bit(&raw_mii_data, &[0x46, 0x47])
+        let raw_mole_details: [bool; 16] = get_and_concat_vec_bit(raw_mii_data, &[0x46, 0x47])
             .try_into()
             .unwrap();
         let raw_utf16_author = &raw_mii_data[0x48..0x5C];
@@ -512,6 +528,12 @@ impl From<ctru_sys::MiiData> for Mii {
     }
 }
 
+impl From<ctru_sys::MiiData> for Mii {
+    fn from(mii_data: ctru_sys::MiiData) -> Self {
+        Self::from_bytes(&mii_data._bindgen_opaque_blob)
+    }
+}
+
 // Methods to handle "_bits_", ``bitvec`` cannot compile to 32-bit targets, so I had to create a few
 // helper methods
 
@@ -526,7 +548,9 @@ fn vec_bit(data: u8) -> [bool; 8] {
 
 /// Transforms a [bool; 8] into an u8
 fn vec_bit_to_u8(data: [bool; 8]) -> u8 {
+    // Bits are stored from the least significant one.
     data.into_iter()
+        .rev()
         .fold(0, |result, bit| (result << 1) ^ u8::from(bit))
 }
 
@@ -539,16 +563,51 @@ fn partial_u8_bits_to_u8(data: &[bool]) -> u8 {
 }
 
 /// UTF-16 Strings are give in pairs of bytes (u8), this converts them into an _actual_ string
+///
+/// Strings are terminated by the first NUL character, if shorter than the buffer.
 fn utf16_byte_pairs_to_string(data: &[u8]) -> String {
     let raw_utf16_composed = data
         .chunks_exact(2)
         .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+        .take_while(|&c| c != 0)
         .collect::<Vec<u16>>();
 
-    String::from_utf16_lossy(raw_utf16_composed.as_slice()).replace('\0', "")
+    String::from_utf16_lossy(raw_utf16_composed.as_slice())
 }
 
 /// Gets the values from the slice and concatenates them
 fn get_and_concat_vec_bit(data: &[u8], get_values: &[usize]) -> Vec<bool> {
     get_values.iter().flat_map(|v| vec_bit(data[*v])).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_round_trip() {
+        assert_eq!(vec_bit_to_u8(vec_bit(0b1010_0110)), 0b1010_0110);
+        assert_eq!(partial_u8_bits_to_u8(&vec_bit(0b0110_1101)[2..=5]), 0b1011);
+    }
+
+    #[test]
+    fn decode_names() {
+        let mut raw = [0; Mii::SIZE];
+
+        for (i, c) in "Rusty".encode_utf16().enumerate() {
+            raw[0x1A + i * 2..0x1C + i * 2].copy_from_slice(&c.to_le_bytes());
+        }
+        // Name using the whole buffer, without a terminator.
+        for (i, c) in "ABCDEFGHIJ".encode_utf16().enumerate() {
+            raw[0x48 + i * 2..0x4A + i * 2].copy_from_slice(&c.to_le_bytes());
+        }
+        raw[0x18] = 0b0000_0011;
+
+        let mii = Mii::from_bytes(&raw);
+
+        assert_eq!(mii.name, "Rusty");
+        assert_eq!(mii.author_name, "ABCDEFGHIJ");
+        assert_eq!(mii.details.sex, Sex::Female);
+        assert_eq!(mii.details.birthday_month, 1);
+    }
+}
@@ -0,0 +1,28 @@
+//! Mock implementations of the service traits.
+//!
+//! This module re-exports the mocks of [`ctru_traits::mock`], so that logic which is generic over traits such as
+//! [`InputSource`](crate::services::hid::InputSource), [`SystemInfo`](crate::services::cfgu::SystemInfo)
+//! or [`Fs`](crate::services::fs::Fs) can be tested with predefined inputs.
+//!
+//! This module is only compiled if the `mock` feature is enabled. Since `ctru-rs` can only be built for the console,
+//! tests meant to run on the host should depend on `ctru-traits` (with its `mock` feature) instead.
+//!
+//! # Example
+//!
+//! ```
+//! # let _runner = test_runner::GdbRunner::default();
+//! use ctru::mock::MockHid;
+//! use ctru::services::hid::{InputSource, KeyPad};
+//!
+//! fn wants_to_jump(input: &impl InputSource) -> bool {
+//!     input.keys_down().contains(KeyPad::A)
+//! }
+//!
+//! let mut input = MockHid::default();
+//! assert!(!wants_to_jump(&input));
+//!
+//! input.keys_down = KeyPad::A;
+//! assert!(wants_to_jump(&input));
+//! ```
+
+pub use ctru_traits::mock::{MockCfgu, MockFs, MockHid};
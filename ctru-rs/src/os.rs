@@ -115,7 +115,7 @@ impl MemRegion {
 /// let strength = ctru::os::WifiStrength::current();
 /// assert!((strength as u8) < 4);
 /// ```
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[non_exhaustive]
 #[repr(u8)]
 pub enum WifiStrength {
@@ -0,0 +1,195 @@
+//! Overlays drawn on top of the main application.
+//!
+//! An [`Overlay`] is a small "mini app" (e.g. a volume indicator or an in-game quick menu) which is
+//! updated and drawn every frame after the main application, and which can temporarily steal the input focus from it.
+//! Overlays are stacked inside an [`OverlayStack`], which takes care of their lifecycle and of routing the input to the topmost focused overlay.
+//!
+//! # Example
+//!
+//! ```
+//! # let _runner = test_runner::GdbRunner::default();
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #
+//! use ctru::overlay::{Overlay, OverlayStack, Status};
+//! use ctru::prelude::*;
+//!
+//! struct QuickMenu;
+//!
+//! impl Overlay for QuickMenu {
+//!     fn update(&mut self, hid: Option<&Hid>) -> Status {
+//!         match hid {
+//!             Some(hid) if hid.keys_down().contains(KeyPad::B) => Status::Closed,
+//!             _ => Status::Focused,
+//!         }
+//!     }
+//!
+//!     fn draw(&mut self, _gfx: &Gfx) {
+//!         // Draw the menu on top of the application's frame.
+//!     }
+//! }
+//!
+//! let apt = Apt::new()?;
+//! let mut hid = Hid::new()?;
+//! let gfx = Gfx::new()?;
+//! let mut overlays = OverlayStack::new();
+//!
+//! while apt.main_loop() {
+//!     hid.scan_input();
+//!
+//!     if !overlays.update(&hid) && hid.keys_down().contains(KeyPad::SELECT) {
+//!         overlays.push(QuickMenu);
+//!     }
+//!
+//!     // Draw the application...
+//!
+//!     overlays.draw(&gfx);
+//! #   break;
+//! }
+//! #
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::services::gfx::{Capture, Gfx};
+use crate::services::hid::Hid;
+
+/// State of an [`Overlay`] after being updated.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The overlay is visible, but doesn't capture the input.
+    Passive,
+    /// The overlay is visible and captures the input, hiding it from the overlays below it and from the main application.
+    Focused,
+    /// The overlay has finished its job and should be removed.
+    Closed,
+}
+
+/// Trait implemented by "mini apps" which can be composited over the main application.
+///
+/// The lifecycle of an overlay is handled by the [`OverlayStack`] it's pushed in:
+///
+/// 1. [`Overlay::on_open()`] is called once, when the overlay is pushed in the stack.
+/// 2. [`Overlay::update()`] and [`Overlay::draw()`] are called every frame.
+/// 3. [`Overlay::on_close()`] is called once, when the overlay returns [`Status::Closed`] or is removed from the stack.
+pub trait Overlay {
+    /// Called once when the overlay is pushed in an [`OverlayStack`].
+    fn on_open(&mut self) {}
+
+    /// Updates the overlay's state.
+    ///
+    /// `hid` is [`None`] if an overlay above this one is currently capturing the input.
+    fn update(&mut self, hid: Option<&Hid>) -> Status;
+
+    /// Draws the overlay. The main application's frame has already been drawn at this point.
+    fn draw(&mut self, gfx: &Gfx);
+
+    /// Called once when the overlay is removed from its [`OverlayStack`].
+    fn on_close(&mut self) {}
+}
+
+/// Stack of [`Overlay`]s composited over the main application.
+///
+/// Overlays pushed later are considered to be "on top" of the previous ones:
+/// they are drawn last and get the first chance to capture the input.
+#[derive(Default)]
+pub struct OverlayStack {
+    overlays: Vec<Box<dyn Overlay>>,
+    focused: bool,
+}
+
+impl OverlayStack {
+    /// Creates an empty overlay stack.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pushes a new overlay on top of the stack, calling its [`Overlay::on_open()`] hook.
+    pub fn push(&mut self, overlay: impl Overlay + 'static) {
+        let mut overlay = Box::new(overlay);
+        overlay.on_open();
+
+        self.overlays.push(overlay);
+    }
+
+    /// Removes all overlays from the stack, calling their [`Overlay::on_close()`] hook.
+    pub fn clear(&mut self) {
+        while let Some(mut overlay) = self.overlays.pop() {
+            overlay.on_close();
+        }
+
+        self.focused = false;
+    }
+
+    /// Returns the amount of overlays in the stack.
+    pub fn len(&self) -> usize {
+        self.overlays.len()
+    }
+
+    /// Returns `true` if there are no overlays in the stack.
+    pub fn is_empty(&self) -> bool {
+        self.overlays.is_empty()
+    }
+
+    /// Returns `true` if an overlay captured the input during the last call to [`OverlayStack::update()`].
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Updates all overlays, from the topmost to the bottommost one.
+    ///
+    /// Once an overlay returns [`Status::Focused`], the overlays below it receive no input.
+    /// Overlays returning [`Status::Closed`] are removed from the stack.
+    ///
+    /// Returns `true` if the input was captured by an overlay, in which case the main application should ignore it for this frame.
+    pub fn update(&mut self, hid: &Hid) -> bool {
+        let mut input = Some(hid);
+
+        for index in (0..self.overlays.len()).rev() {
+            match self.overlays[index].update(input) {
+                Status::Passive => {}
+                Status::Focused => input = None,
+                Status::Closed => {
+                    let mut overlay = self.overlays.remove(index);
+                    overlay.on_close();
+                }
+            }
+        }
+
+        self.focused = input.is_none();
+        self.focused
+    }
+
+    /// Draws all overlays, from the bottommost to the topmost one.
+    ///
+    /// This should be called every frame after the main application has drawn its content, but before swapping the framebuffers.
+    pub fn draw(&mut self, gfx: &Gfx) {
+        for overlay in &mut self.overlays {
+            overlay.draw(gfx);
+        }
+    }
+
+    /// Draws all overlays like [`OverlayStack::draw()`], capturing the screens for a screenshot.
+    ///
+    /// If `with_overlays` is `false`, the screens are captured before drawing the overlays, so that they don't show up in the screenshot.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any of the screens is already borrowed.
+    pub fn draw_and_capture(&mut self, gfx: &Gfx, with_overlays: bool) -> Capture {
+        if with_overlays {
+            self.draw(gfx);
+            Capture::new(gfx)
+        } else {
+            let capture = Capture::new(gfx);
+            self.draw(gfx);
+            capture
+        }
+    }
+}
+
+impl Drop for OverlayStack {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
@@ -0,0 +1,386 @@
+//! Memory patching of the running process.
+//!
+//! A [`Patch`] replaces a few bytes of the process's memory (code or data) at a fixed address, like the "cheat codes" of
+//! the Action Replay family of devices. Before being applied, the patch checks that the memory still contains the expected
+//! original bytes, which protects against patching a different version of the program.
+//!
+//! Patches are grouped in a [`PatchSet`], which can enable and disable them at any time and restores the original memory when dropped.
+//! Read-only memory (such as the code segment) is made writable while patching, and the caches are flushed afterwards
+//! so that patched code is actually executed.
+//!
+//! # Notes
+//!
+//! Changing the protection of memory pages requires access to `svcControlProcessMemory`, which is available with the
+//! custom firmware commonly used to run homebrew. Patching memory which is already writable works everywhere.
+#![doc(alias = "cheat")]
+#![doc(alias = "action replay")]
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::FromResidual;
+
+use crate::error::ResultCode;
+
+/// Error returned when applying or removing a [`Patch`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum Error {
+    /// The memory didn't contain the expected bytes.
+    Mismatch {
+        /// Address of the patch.
+        address: usize,
+    },
+    /// The patch spans multiple memory blocks, or its address isn't mapped.
+    InvalidRange {
+        /// Address of the patch.
+        address: usize,
+    },
+    /// ctru-rs error
+    Lib(crate::Error),
+}
+
+/// Replacement of a range of bytes in the memory of the process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Patch {
+    address: usize,
+    original: Vec<u8>,
+    replacement: Vec<u8>,
+}
+
+/// Group of [`Patch`]es, which can be enabled and disabled individually.
+///
+/// Enabled patches are disabled when the set is dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::patch::{Patch, PatchSet};
+///
+/// let mut patches = PatchSet::new();
+///
+/// // Replace a `subs r0, r0, #1` instruction with a `nop`.
+/// let infinite_lives = patches.push(Patch::new(
+///     0x0010_2A40,
+///     [0x01, 0x00, 0x50, 0xE2],
+///     [0x00, 0xF0, 0x20, 0xE3],
+/// ));
+///
+/// unsafe { patches.enable(infinite_lives)? };
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct PatchSet {
+    patches: Vec<(Patch, bool)>,
+}
+
+impl Patch {
+    /// Creates a new patch, replacing the `original` bytes at `address` with `replacement`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `original` and `replacement` have different lengths.
+    pub fn new(
+        address: usize,
+        original: impl Into<Vec<u8>>,
+        replacement: impl Into<Vec<u8>>,
+    ) -> Self {
+        let original = original.into();
+        let replacement = replacement.into();
+
+        assert_eq!(
+            original.len(),
+            replacement.len(),
+            "a patch must replace as many bytes as it removes"
+        );
+
+        Self {
+            address,
+            original,
+            replacement,
+        }
+    }
+
+    /// Returns the address of the patch.
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the bytes replaced by the patch.
+    pub fn original(&self) -> &[u8] {
+        &self.original
+    }
+
+    /// Returns the bytes written by the patch.
+    pub fn replacement(&self) -> &[u8] {
+        &self.replacement
+    }
+
+    /// Returns `true` if the memory currently contains the replacement bytes.
+    ///
+    /// # Safety
+    ///
+    /// The range of the patch must be mapped and readable.
+    pub unsafe fn is_applied(&self) -> bool {
+        unsafe { self.matches(&self.replacement) }
+    }
+
+    unsafe fn matches(&self, expected: &[u8]) -> bool {
+        let current =
+            unsafe { std::slice::from_raw_parts(self.address as *const u8, expected.len()) };
+        current == expected
+    }
+
+    // Replaces `from` with `to`, checking that the memory contains `from` first.
+    unsafe fn swap(&self, from: &[u8], to: &[u8]) -> Result<(), Error> {
+        let info = query_memory(self.address)
+            .filter(|info| self.address + from.len() <= (info.base_addr + info.size) as usize)
+            .ok_or(Error::InvalidRange {
+                address: self.address,
+            })?;
+
+        if !unsafe { self.matches(from) } {
+            return Err(Error::Mismatch {
+                address: self.address,
+            });
+        }
+
+        let perm = info.perm as u32;
+        let writable = perm & ctru_sys::MEMPERM_WRITE as u32 != 0;
+
+        // Only the pages containing the patch are reprotected.
+        let start = self.address & !0xFFF;
+        let len = (self.address + to.len()).next_multiple_of(0x1000) - start;
+
+        if !writable {
+            protect(start, len, perm | ctru_sys::MEMPERM_WRITE as u32)?;
+        }
+
+        unsafe { std::ptr::copy_nonoverlapping(to.as_ptr(), self.address as *mut u8, to.len()) };
+
+        let flushed = flush_data_cache(self.address, to.len());
+
+        if perm & ctru_sys::MEMPERM_EXECUTE as u32 != 0 {
+            unsafe { ctru_sys::svcInvalidateEntireInstructionCache() };
+        }
+
+        // The original permissions are restored even if the flush failed, so that code is never left writable.
+        if !writable {
+            protect(start, len, perm)?;
+        }
+
+        flushed.map_err(Error::from)
+    }
+}
+
+impl PatchSet {
+    /// Creates an empty patch set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a disabled patch to the set, returning its index.
+    pub fn push(&mut self, patch: Patch) -> usize {
+        self.patches.push((patch, false));
+        self.patches.len() - 1
+    }
+
+    /// Returns the patch at `index`.
+    pub fn get(&self, index: usize) -> Option<&Patch> {
+        self.patches.get(index).map(|(patch, _)| patch)
+    }
+
+    /// Returns the amount of patches in the set.
+    pub fn len(&self) -> usize {
+        self.patches.len()
+    }
+
+    /// Returns `true` if there are no patches in the set.
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+
+    /// Returns `true` if the patch at `index` is enabled.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `index` is out of bounds.
+    pub fn is_enabled(&self, index: usize) -> bool {
+        self.patches[index].1
+    }
+
+    /// Applies the patch at `index`. Does nothing if the patch is already enabled.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the memory doesn't contain the original bytes of the patch,
+    /// or if the memory couldn't be made writable.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `index` is out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// The patch must leave the process in a valid state: no other code may be running or referencing the patched memory
+    /// (e.g. via Rust references) while it's being modified, and the patched code or data must be valid.
+    pub unsafe fn enable(&mut self, index: usize) -> Result<(), Error> {
+        let (patch, enabled) = &mut self.patches[index];
+
+        if !*enabled {
+            unsafe { patch.swap(&patch.original, &patch.replacement)? };
+            *enabled = true;
+        }
+
+        Ok(())
+    }
+
+    /// Restores the original bytes of the patch at `index`. Does nothing if the patch is already disabled.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the memory doesn't contain the replacement bytes of the patch (i.e. it was modified
+    /// by something else after being patched), or if the memory couldn't be made writable.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `index` is out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// See [`PatchSet::enable()`].
+    pub unsafe fn disable(&mut self, index: usize) -> Result<(), Error> {
+        let (patch, enabled) = &mut self.patches[index];
+
+        if *enabled {
+            unsafe { patch.swap(&patch.replacement, &patch.original)? };
+            *enabled = false;
+        }
+
+        Ok(())
+    }
+
+    /// Enables all patches in the set.
+    ///
+    /// If any patch fails to apply, the patches enabled by this call are rolled back, leaving the set as it was.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the error of the first patch which failed to apply.
+    ///
+    /// # Safety
+    ///
+    /// See [`PatchSet::enable()`].
+    pub unsafe fn enable_all(&mut self) -> Result<(), Error> {
+        let disabled: Vec<usize> = (0..self.len()).filter(|&i| !self.is_enabled(i)).collect();
+
+        for (applied, &index) in disabled.iter().enumerate() {
+            if let Err(e) = unsafe { self.enable(index) } {
+                for &index in disabled[..applied].iter().rev() {
+                    let _ = unsafe { self.disable(index) };
+                }
+
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disables all patches in the set, in reverse order.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the error of the first patch which failed to be removed. Other patches are removed anyway.
+    ///
+    /// # Safety
+    ///
+    /// See [`PatchSet::enable()`].
+    pub unsafe fn disable_all(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+
+        for index in (0..self.len()).rev() {
+            if let Err(e) = unsafe { self.disable(index) } {
+                result = result.and(Err(e));
+            }
+        }
+
+        result
+    }
+}
+
+impl Drop for PatchSet {
+    fn drop(&mut self) {
+        // SAFETY: the patches were applied under the same conditions.
+        let _ = unsafe { self.disable_all() };
+    }
+}
+
+fn query_memory(address: usize) -> Option<ctru_sys::MemInfo> {
+    let mut info = ctru_sys::MemInfo::default();
+    let mut page = ctru_sys::PageInfo::default();
+
+    let res = unsafe { ctru_sys::svcQueryMemory(&mut info, &mut page, address as u32) };
+    (res >= 0 && info.perm != 0).then_some(info)
+}
+
+#[doc(alias = "svcControlProcessMemory")]
+pub(crate) fn protect(start: usize, len: usize, perm: u32) -> crate::Result<()> {
+    ResultCode(unsafe {
+        ctru_sys::svcControlProcessMemory(
+            ctru_sys::CUR_PROCESS_HANDLE,
+            start as u32,
+            start as u32,
+            len as u32,
+            ctru_sys::MEMOP_PROT as u32,
+            perm,
+        )
+    })?;
+
+    Ok(())
+}
+
+#[doc(alias = "svcFlushProcessDataCache")]
+fn flush_data_cache(address: usize, len: usize) -> crate::Result<()> {
+    ResultCode(unsafe {
+        ctru_sys::svcFlushProcessDataCache(ctru_sys::CUR_PROCESS_HANDLE, address as u32, len as u32)
+    })?;
+
+    Ok(())
+}
+
+impl From<crate::Error> for Error {
+    fn from(value: crate::Error) -> Self {
+        Error::Lib(value)
+    }
+}
+
+impl<T> FromResidual<crate::Error> for Result<T, Error> {
+    fn from_residual(residual: crate::Error) -> Self {
+        Err(residual.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mismatch { address } => {
+                write!(f, "unexpected memory contents at {address:#010x}")
+            }
+            Self::InvalidRange { address } => {
+                write!(
+                    f,
+                    "patch at {address:#010x} spans unmapped or separate memory blocks"
+                )
+            }
+            Self::Lib(e) => write!(f, "ctru-rs error: {e}"),
+        }
+    }
+}
+
+impl StdError for Error {}
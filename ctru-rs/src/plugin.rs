@@ -0,0 +1,431 @@
+//! Plugin loader.
+//!
+//! Plugins are blobs of position-independent code loaded at runtime (e.g. from the RomFS or the SD card),
+//! which allows mods and extensions to be distributed without relinking the whole application.
+//!
+//! A plugin is made of:
+//!
+//! - its code and data, followed by an amount of zero-initialized memory;
+//! - relocations, which are 32-bit words in the code containing an offset from the start of the plugin,
+//!   to which the address the plugin is loaded at is added;
+//! - imports, which are 32-bit words in the code set to the address of a symbol registered by the application in an [`ApiTable`];
+//! - exports, which are the symbols (functions or data) made available by the plugin to the application.
+//!
+//! The binary format of plugins is described by [`PluginImage`], and is encoded via the [`codec`](crate::codec) module.
+//! Converting the output of a linker (e.g. an ELF file built with `-fPIC`) into a [`PluginImage`] is left to external tools.
+//!
+//! # Notes
+//!
+//! Making memory executable requires access to `svcControlProcessMemory`, which is available with the
+//! custom firmware commonly used to run homebrew.
+#![doc(alias = "mod")]
+#![doc(alias = "dlopen")]
+
+use std::alloc::{self, Layout};
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::ffi::c_void;
+use std::fmt;
+use std::ops::FromResidual;
+use std::path::Path;
+use std::ptr::NonNull;
+
+use crate::codec::{self, Decode, Decoder, Encode, Encoder};
+use crate::error::ResultCode;
+use crate::patch;
+
+const PAGE_SIZE: usize = 0x1000;
+
+/// Error returned when loading a [`Plugin`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum Error {
+    /// The plugin file couldn't be read.
+    Io(std::io::Error),
+    /// The plugin isn't a valid [`PluginImage`].
+    Format(codec::Error),
+    /// The plugin imports a symbol which isn't in the [`ApiTable`].
+    UnresolvedImport(String),
+    /// A relocation or a symbol points outside of the plugin.
+    InvalidOffset(u32),
+    /// There wasn't enough memory to load the plugin.
+    OutOfMemory,
+    /// ctru-rs error
+    Lib(crate::Error),
+}
+
+/// Symbol of a [`PluginImage`], at an offset from the start of the plugin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// Name of the symbol.
+    pub name: String,
+    /// Offset of the symbol. For ARM Thumb functions, the lowest bit is set.
+    pub offset: u32,
+}
+
+/// Binary format of a plugin.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PluginImage {
+    /// Code and initialized data.
+    pub code: Vec<u8>,
+    /// Size of the zero-initialized memory following the code.
+    pub bss_size: u32,
+    /// Offsets of the words to relocate.
+    pub relocations: Vec<u32>,
+    /// Words to set to the address of a symbol of the [`ApiTable`]. The offset is the one of the word.
+    pub imports: Vec<Symbol>,
+    /// Symbols made available to the application.
+    pub exports: Vec<Symbol>,
+}
+
+/// Symbols made available by the application to its plugins.
+#[derive(Clone, Debug, Default)]
+pub struct ApiTable {
+    symbols: HashMap<String, usize>,
+}
+
+/// Plugin loaded in executable memory.
+///
+/// The plugin is unloaded when dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::plugin::{ApiTable, Plugin};
+///
+/// extern "C" fn log(message: *const std::ffi::c_char) {
+///     let message = unsafe { std::ffi::CStr::from_ptr(message) };
+///     println!("{}", message.to_string_lossy());
+/// }
+///
+/// let mut api = ApiTable::new();
+/// api.register("log", log as *const ());
+///
+/// let plugin = Plugin::open("sdmc:/3ds/my-game/plugins/hello.plg", &api)?;
+///
+/// if let Some(init) = plugin.symbol("plugin_init") {
+///     let init: extern "C" fn() = unsafe { std::mem::transmute(init) };
+///     init();
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct Plugin {
+    memory: NonNull<u8>,
+    layout: Layout,
+    exports: HashMap<String, usize>,
+}
+
+impl PluginImage {
+    const MAGIC: &'static [u8; 4] = b"CTRP";
+    const VERSION: u16 = 1;
+
+    /// Encodes the plugin, ready to be written to a file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        self.encode(&mut encoder);
+        encoder.into_bytes()
+    }
+
+    /// Decodes a plugin encoded via [`PluginImage::to_bytes()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `bytes` doesn't contain a valid plugin.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, codec::Error> {
+        Self::decode(&mut Decoder::new(bytes))
+    }
+
+    // Total amount of memory used by the plugin once loaded, or `None` if it doesn't fit in the address space.
+    fn memory_size(&self) -> Option<usize> {
+        self.code.len().checked_add(self.bss_size as usize)
+    }
+
+    // Copies the plugin to `memory` (which is assumed to be loaded at `base`), resolving its relocations and imports.
+    fn link(&self, memory: &mut [u8], base: u32, api: &ApiTable) -> Result<(), Error> {
+        memory[..self.code.len()].copy_from_slice(&self.code);
+        memory[self.code.len()..].fill(0);
+
+        let mut write_word = |offset: u32, f: &dyn Fn(u32) -> u32| {
+            let word = memory
+                .get_mut(offset as usize..)
+                .and_then(|rest| rest.get_mut(..4))
+                .ok_or(Error::InvalidOffset(offset))?;
+
+            let value = f(u32::from_le_bytes(word.try_into().unwrap()));
+            word.copy_from_slice(&value.to_le_bytes());
+
+            Ok::<_, Error>(())
+        };
+
+        for &offset in &self.relocations {
+            write_word(offset, &|value| value.wrapping_add(base))?;
+        }
+
+        for import in &self.imports {
+            let address = *api
+                .symbols
+                .get(&import.name)
+                .ok_or_else(|| Error::UnresolvedImport(import.name.clone()))?;
+
+            write_word(import.offset, &|_| address as u32)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Encode for Symbol {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.name.encode(encoder);
+        self.offset.encode(encoder);
+    }
+}
+
+impl Decode for Symbol {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, codec::Error> {
+        Ok(Self {
+            name: String::decode(decoder)?,
+            offset: u32::decode(decoder)?,
+        })
+    }
+}
+
+impl Encode for PluginImage {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_raw(Self::MAGIC);
+        Self::VERSION.encode(encoder);
+        self.code.encode(encoder);
+        self.bss_size.encode(encoder);
+        self.relocations.encode(encoder);
+        self.imports.encode(encoder);
+        self.exports.encode(encoder);
+    }
+}
+
+impl Decode for PluginImage {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, codec::Error> {
+        if decoder.read_raw(4)? != Self::MAGIC {
+            return Err(codec::Error::WrongMagic);
+        }
+
+        let version = u16::decode(decoder)?;
+        if version != Self::VERSION {
+            return Err(codec::Error::IncompatibleVersion {
+                local: Self::VERSION..=Self::VERSION,
+                remote: version..=version,
+            });
+        }
+
+        Ok(Self {
+            code: Vec::decode(decoder)?,
+            bss_size: u32::decode(decoder)?,
+            relocations: Vec::decode(decoder)?,
+            imports: Vec::decode(decoder)?,
+            exports: Vec::decode(decoder)?,
+        })
+    }
+}
+
+impl ApiTable {
+    /// Creates an empty API table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes a symbol (usually an `extern "C"` function or a `static`) available to plugins under `name`.
+    pub fn register(&mut self, name: impl Into<String>, address: *const ()) {
+        self.symbols.insert(name.into(), address as usize);
+    }
+}
+
+impl Plugin {
+    /// Loads a plugin from a file, resolving its imports via `api`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be read, or if the plugin couldn't be loaded (see [`Plugin::load()`]).
+    pub fn open(path: impl AsRef<Path>, api: &ApiTable) -> Result<Self, Error> {
+        let bytes = std::fs::read(path).map_err(Error::Io)?;
+        let image = PluginImage::from_bytes(&bytes).map_err(Error::Format)?;
+
+        Self::load(&image, api)
+    }
+
+    /// Loads a plugin in executable memory, resolving its imports via `api`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if an import isn't in `api`, if a relocation is invalid,
+    /// or if executable memory couldn't be allocated.
+    #[doc(alias = "svcControlProcessMemory")]
+    pub fn load(image: &PluginImage, api: &ApiTable) -> Result<Self, Error> {
+        // Images are untrusted input, so their size may not even fit in the address space.
+        let memory_size = image.memory_size().ok_or(Error::OutOfMemory)?;
+        let size = memory_size
+            .max(1)
+            .checked_next_multiple_of(PAGE_SIZE)
+            .ok_or(Error::OutOfMemory)?;
+        let layout = Layout::from_size_align(size, PAGE_SIZE).map_err(|_| Error::OutOfMemory)?;
+
+        // SAFETY: the layout has a non-zero size. The memory is zeroed, so it can be viewed as a byte slice.
+        let memory =
+            NonNull::new(unsafe { alloc::alloc_zeroed(layout) }).ok_or(Error::OutOfMemory)?;
+
+        // Until the memory is made executable, dropping the plugin only frees it.
+        let mut plugin = Self {
+            memory,
+            layout,
+            exports: HashMap::new(),
+        };
+
+        let base = memory.as_ptr() as usize;
+        let slice = unsafe { std::slice::from_raw_parts_mut(memory.as_ptr(), memory_size) };
+        image.link(slice, base as u32, api)?;
+
+        for export in &image.exports {
+            if (export.offset as usize & !1) >= memory_size {
+                return Err(Error::InvalidOffset(export.offset));
+            }
+
+            plugin
+                .exports
+                .insert(export.name.clone(), base + export.offset as usize);
+        }
+
+        ResultCode(unsafe {
+            ctru_sys::svcFlushProcessDataCache(
+                ctru_sys::CUR_PROCESS_HANDLE,
+                base as u32,
+                size as u32,
+            )
+        })?;
+
+        patch::protect(
+            base,
+            size,
+            (ctru_sys::MEMPERM_READ | ctru_sys::MEMPERM_EXECUTE) as u32,
+        )?;
+
+        unsafe { ctru_sys::svcInvalidateEntireInstructionCache() };
+
+        Ok(plugin)
+    }
+
+    /// Returns the address of a symbol exported by the plugin.
+    ///
+    /// Function pointers can be obtained by transmuting the address to the right `extern "C"` function type.
+    pub fn symbol(&self, name: &str) -> Option<NonNull<c_void>> {
+        self.exports
+            .get(name)
+            .and_then(|&address| NonNull::new(address as *mut c_void))
+    }
+
+    /// Returns the names of the symbols exported by the plugin.
+    pub fn exports(&self) -> impl Iterator<Item = &str> {
+        self.exports.keys().map(String::as_str)
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        let base = self.memory.as_ptr() as usize;
+
+        // The memory was never made executable if the plugin failed to load, in which case this is a no-op.
+        let _ = patch::protect(
+            base,
+            self.layout.size(),
+            (ctru_sys::MEMPERM_READ | ctru_sys::MEMPERM_WRITE) as u32,
+        );
+
+        unsafe { alloc::dealloc(self.memory.as_ptr(), self.layout) };
+    }
+}
+
+impl From<crate::Error> for Error {
+    fn from(value: crate::Error) -> Self {
+        Error::Lib(value)
+    }
+}
+
+impl<T> FromResidual<crate::Error> for Result<T, Error> {
+    fn from_residual(residual: crate::Error) -> Self {
+        Err(residual.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read the plugin: {e}"),
+            Self::Format(e) => write!(f, "invalid plugin: {e}"),
+            Self::UnresolvedImport(name) => write!(f, "unresolved import `{name}`"),
+            Self::InvalidOffset(offset) => write!(f, "offset {offset:#x} is outside of the plugin"),
+            Self::OutOfMemory => write!(f, "not enough memory to load the plugin"),
+            Self::Lib(e) => write!(f, "ctru-rs error: {e}"),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Format(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_image() {
+        let image = PluginImage {
+            code: vec![0x10, 0, 0, 0, 0, 0, 0, 0],
+            bss_size: 4,
+            relocations: vec![0],
+            imports: vec![Symbol {
+                name: String::from("api_fn"),
+                offset: 4,
+            }],
+            exports: Vec::new(),
+        };
+
+        let image = PluginImage::from_bytes(&image.to_bytes()).unwrap();
+
+        let mut api = ApiTable::new();
+        api.register("api_fn", 0x0012_3456 as *const ());
+
+        let mut memory = vec![0xFF; image.memory_size().unwrap()];
+        image.link(&mut memory, 0x0800_0000, &api).unwrap();
+
+        assert_eq!(memory, [0x10, 0, 0, 0x08, 0x56, 0x34, 0x12, 0, 0, 0, 0, 0]);
+
+        assert!(matches!(
+            image.link(&mut memory, 0, &ApiTable::new()),
+            Err(Error::UnresolvedImport(_))
+        ));
+    }
+
+    #[test]
+    fn load_oversized_image() {
+        let image = PluginImage {
+            code: vec![0; 8],
+            bss_size: u32::MAX,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            Plugin::load(&image, &ApiTable::new()),
+            Err(Error::OutOfMemory)
+        ));
+    }
+}
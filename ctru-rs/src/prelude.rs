@@ -7,5 +7,7 @@ pub use crate::services::{
     apt::Apt,
     gfx::Gfx,
     hid::{Hid, KeyPad},
-    soc::Soc,
 };
+
+#[cfg(feature = "network")]
+pub use crate::services::soc::Soc;
@@ -0,0 +1,328 @@
+//! Suspend-safe resource management.
+//!
+//! When the user opens the HOME Menu, closes the console's lid or exits the application from the HOME Menu,
+//! the system takes over the hardware while the application is paused inside [`Apt::main_loop()`](crate::services::apt::Apt::main_loop).
+//! Resources using the hardware directly (e.g. GPU command buffers, camera captures or custom audio streaming) must be quiesced
+//! before this happens, and restarted when the application regains control.
+//!
+//! This module provides a single registry for the handlers of such resources. Handlers are called automatically by the APT service
+//! during [`Apt::main_loop()`](crate::services::apt::Apt::main_loop), in a well-defined order: handlers are notified of suspensions
+//! in the reverse order of their registration (so that resources are stopped before the ones they depend on),
+//! and of resumptions in the order of their registration.
+//!
+//! Work which must be done before the application is closed (e.g. flushing files or committing save data) can be registered
+//! via [`on_exit()`]. It runs whether the application returns from its main loop by itself or is closed from the HOME Menu.
+//!
+//! # Notes
+//!
+//! The [`ndsp`](crate::services::ndsp) service already pauses its audio output by itself, and doesn't need a handler.
+#![doc(alias = "aptHook")]
+#![doc(alias = "suspend")]
+
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+
+// Each handler is locked separately, so that the registry isn't locked while it runs.
+type Handler = Arc<Mutex<dyn FnMut(Transition) + Send>>;
+
+static HANDLERS: Mutex<Vec<(u64, Handler)>> = Mutex::new(Vec::new());
+static NEXT_ID: Mutex<u64> = Mutex::new(0);
+static HOOK: Once = Once::new();
+static EXIT_BUDGET: Mutex<Duration> = Mutex::new(DEFAULT_EXIT_BUDGET);
+// Set when the application starts exiting, to `None` if the exit budget is too long to have a deadline.
+static EXIT_DEADLINE: Mutex<Option<Option<Instant>>> = Mutex::new(None);
+
+/// Default time budget of the cleanups registered via [`on_exit()`].
+pub const DEFAULT_EXIT_BUDGET: Duration = Duration::from_secs(1);
+
+// The cookie is part of a linked list owned by libctru, so it must never move.
+static mut HOOK_COOKIE: ctru_sys::aptHookCookie = ctru_sys::aptHookCookie {
+    next: ptr::null_mut(),
+    callback: None,
+    param: ptr::null_mut(),
+};
+
+/// Transitions of the application state notified to the registered handlers.
+#[doc(alias = "APT_HookType")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Transition {
+    /// The application is about to be suspended (e.g. the HOME Menu was opened).
+    Suspend = ctru_sys::APTHOOK_ONSUSPEND,
+    /// The application has regained control after being suspended.
+    Restore = ctru_sys::APTHOOK_ONRESTORE,
+    /// The console is about to enter sleep mode.
+    Sleep = ctru_sys::APTHOOK_ONSLEEP,
+    /// The console has woken up from sleep mode.
+    WakeUp = ctru_sys::APTHOOK_ONWAKEUP,
+    /// The application is about to be closed.
+    Exit = ctru_sys::APTHOOK_ONEXIT,
+}
+
+/// Registration of a handler in the resource registry.
+///
+/// The handler is unregistered when this value is dropped.
+#[must_use = "the handler is unregistered when the registration is dropped"]
+#[derive(Debug)]
+pub struct Registration {
+    id: u64,
+}
+
+impl Transition {
+    /// Returns `true` if resources must be stopped during this transition, and `false` if they can be restarted.
+    pub fn is_quiescing(self) -> bool {
+        matches!(self, Self::Suspend | Self::Sleep | Self::Exit)
+    }
+}
+
+/// Registers a handler to be called on every application state transition.
+///
+/// # Notes
+///
+/// Handlers can register other handlers and drop [`Registration`]s (including their own). Handlers registered while a transition
+/// is being notified are only called from the next transition, and handlers unregistered in the meantime aren't called anymore.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::Arc;
+///
+/// use ctru::resources::{self, Transition};
+///
+/// let paused = Arc::new(AtomicBool::new(false));
+///
+/// let _registration = resources::register({
+///     let paused = paused.clone();
+///     move |transition: Transition| paused.store(transition.is_quiescing(), Ordering::SeqCst)
+/// });
+/// ```
+#[doc(alias = "aptHook")]
+pub fn register(handler: impl FnMut(Transition) + Send + 'static) -> Registration {
+    HOOK.call_once(|| unsafe {
+        ctru_sys::aptHook(
+            ptr::addr_of_mut!(HOOK_COOKIE),
+            Some(hook_callback),
+            ptr::null_mut(),
+        );
+    });
+
+    let id = {
+        let mut next_id = NEXT_ID.lock().unwrap();
+        *next_id += 1;
+        *next_id
+    };
+
+    HANDLERS
+        .lock()
+        .unwrap()
+        .push((id, Arc::new(Mutex::new(handler))));
+
+    Registration { id }
+}
+
+/// Registers a cleanup to be run once, when the application is about to be closed.
+///
+/// Cleanups run during [`Transition::Exit`], in the reverse order of their registration, both when the application exits
+/// by itself and when the user closes it from the HOME Menu (in which case [`Apt::main_loop()`](crate::services::apt::Apt::main_loop)
+/// returns `false`, and the cleanups run once the [`Apt`](crate::services::apt::Apt) handle is dropped).
+///
+/// All cleanups share a time budget (see [`set_exit_budget()`]): once it's spent, the remaining cleanups are skipped.
+/// The budget doesn't interrupt a cleanup which is already running, so a cleanup which blocks still stalls the closing
+/// of the application. A cleanup which panics doesn't prevent the others from running.
+///
+/// # Notes
+///
+/// The same restrictions of [`register()`] apply to the cleanups.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// use std::fs::File;
+/// use std::sync::{Arc, Mutex};
+///
+/// use ctru::resources;
+///
+/// let log = Arc::new(Mutex::new(Vec::<u8>::new()));
+///
+/// let _cleanup = resources::on_exit({
+///     let log = log.clone();
+///     move || {
+///         let _ = std::fs::write("sdmc:/log.txt", &*log.lock().unwrap());
+///     }
+/// });
+/// ```
+pub fn on_exit(cleanup: impl FnOnce() + Send + 'static) -> Registration {
+    let mut cleanup = Some(cleanup);
+
+    register(move |transition| {
+        if transition != Transition::Exit || !exit_time_left() {
+            return;
+        }
+
+        if let Some(cleanup) = cleanup.take() {
+            let _ = panic::catch_unwind(AssertUnwindSafe(cleanup));
+        }
+    })
+}
+
+/// Sets the time budget shared by all the cleanups registered via [`on_exit()`].
+///
+/// The default budget is [`DEFAULT_EXIT_BUDGET`].
+pub fn set_exit_budget(budget: Duration) {
+    *EXIT_BUDGET.lock().unwrap_or_else(|e| e.into_inner()) = budget;
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        HANDLERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+unsafe extern "C" fn hook_callback(hook: ctru_sys::APT_HookType, _param: *mut libc::c_void) {
+    let transition = match hook {
+        ctru_sys::APTHOOK_ONSUSPEND => Transition::Suspend,
+        ctru_sys::APTHOOK_ONRESTORE => Transition::Restore,
+        ctru_sys::APTHOOK_ONSLEEP => Transition::Sleep,
+        ctru_sys::APTHOOK_ONWAKEUP => Transition::WakeUp,
+        ctru_sys::APTHOOK_ONEXIT => Transition::Exit,
+        _ => return,
+    };
+
+    // Unwinding through the C callback is not allowed.
+    let _ = panic::catch_unwind(|| notify(transition));
+}
+
+fn notify(transition: Transition) {
+    if transition == Transition::Exit {
+        let budget = *EXIT_BUDGET.lock().unwrap_or_else(|e| e.into_inner());
+        *EXIT_DEADLINE.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(Instant::now().checked_add(budget));
+    }
+
+    // The handlers are called without holding the registry lock, since they may register or unregister handlers.
+    let mut handlers = HANDLERS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if transition.is_quiescing() {
+        handlers.reverse();
+    }
+
+    for (id, handler) in handlers {
+        let registered = HANDLERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|(registered, _)| *registered == id);
+
+        if registered {
+            (handler.lock().unwrap_or_else(|e| e.into_inner()))(transition);
+        }
+    }
+}
+
+fn exit_time_left() -> bool {
+    EXIT_DEADLINE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some_and(|deadline| match deadline {
+            Some(deadline) => Instant::now() < deadline,
+            None => true,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The registry is shared by all the tests, which must not notify transitions concurrently.
+    static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn notification_order() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+
+        let registrations: Vec<_> = (0..3)
+            .map(|i| {
+                let calls = calls.clone();
+                register(move |transition| calls.lock().unwrap().push((i, transition)))
+            })
+            .collect();
+
+        notify(Transition::Suspend);
+        notify(Transition::Restore);
+        drop(registrations);
+        notify(Transition::Sleep);
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                (2, Transition::Suspend),
+                (1, Transition::Suspend),
+                (0, Transition::Suspend),
+                (0, Transition::Restore),
+                (1, Transition::Restore),
+                (2, Transition::Restore),
+            ]
+        );
+    }
+
+    #[test]
+    fn reentrant_handlers() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let inner = Arc::new(Mutex::new(None));
+
+        let _outer = register({
+            let inner = inner.clone();
+            move |transition| {
+                let mut inner = inner.lock().unwrap();
+                if transition == Transition::Suspend {
+                    *inner = Some(register(|_| {}));
+                } else {
+                    inner.take();
+                }
+            }
+        });
+
+        notify(Transition::Suspend);
+        assert!(inner.lock().unwrap().is_some());
+        notify(Transition::Restore);
+        assert!(inner.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn exit_cleanups() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let cleanup = |i| {
+            let calls = calls.clone();
+            on_exit(move || calls.lock().unwrap().push(i))
+        };
+
+        let _first = cleanup(0);
+        let _panicking = on_exit(|| panic!("cleanup failed"));
+        let _second = cleanup(1);
+
+        notify(Transition::Suspend);
+        notify(Transition::Exit);
+        notify(Transition::Exit);
+        assert_eq!(*calls.lock().unwrap(), [1, 0]);
+
+        set_exit_budget(Duration::ZERO);
+        let _skipped = cleanup(2);
+        notify(Transition::Exit);
+        assert_eq!(*calls.lock().unwrap(), [1, 0]);
+
+        set_exit_budget(Duration::MAX);
+        let _unlimited = cleanup(3);
+        notify(Transition::Exit);
+        set_exit_budget(DEFAULT_EXIT_BUDGET);
+        assert_eq!(*calls.lock().unwrap(), [1, 0, 3, 2]);
+    }
+}
@@ -0,0 +1,396 @@
+//! Lua scripting bridge.
+//!
+//! A [`ScriptHost`] embeds a Lua 5.4 interpreter (via [`mlua`]) with bindings to the core functionality of the console,
+//! so that prototypes and user-made content (e.g. levels or mods) can be written as scripts running on top of a Rust application.
+//!
+//! The following global tables are available to scripts:
+//!
+//! - `gfx`: `gfx.clear(color)`, `gfx.rect(x, y, width, height, color)` and `gfx.pixel(x, y, color)`,
+//!   drawing on the [primary screen](crate::services::gfx::Gfx::primary_screen). Colors are `0xRRGGBB` integers.
+//! - `input`: `input.held(key)` and `input.down(key)`, where `key` is the name of a [`KeyPad`] flag (e.g. `"A"` or `"DPAD_UP"`),
+//!   and `input.touch()`, returning the touch position as two values.
+//! - `audio`: `audio.play(name)`, which queues a sound to be played by the host application (see [`ScriptHost::take_sounds()`]).
+//! - `fs`: `fs.read(path)`, `fs.write(path, data)` and `fs.exists(path)`, with paths relative to the script root directory.
+//!
+//! Scripts only get the standard libraries which can't reach outside of the script root directory: the `io`, `os` and `package`
+//! libraries are not loaded, and neither are the `dofile()` and `loadfile()` functions.
+//!
+//! Every frame, [`ScriptHost::frame()`] calls the global `update()` function of the script, if defined.
+//! More bindings can be added via [`ScriptHost::lua()`].
+#![doc(alias = "lua")]
+#![doc(alias = "script")]
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+pub use mlua;
+use mlua::{Function, Lua, LuaOptions, StdLib, Value};
+
+use crate::services::gfx::pixel::{Bgr8, Pixel, Rgb565, Rgb5A1, Rgba4, Rgba8};
+use crate::services::gfx::{Gfx, Screen, ScreenMut};
+use crate::services::gspgpu::FramebufferFormat;
+use crate::services::hid::{Hid, KeyPad};
+
+/// Lua interpreter with bindings to the core services.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::prelude::*;
+/// use ctru::scripting::ScriptHost;
+/// use ctru::services::gfx::{Flush, Swap};
+///
+/// let apt = Apt::new()?;
+/// let mut hid = Hid::new()?;
+/// let gfx = Gfx::new()?;
+///
+/// let _romfs = ctru::services::romfs::RomFS::new()?;
+///
+/// let mut host = ScriptHost::new("romfs:/scripts")?;
+/// host.exec_file("main.lua")?;
+///
+/// while apt.main_loop() {
+///     hid.scan_input();
+///
+///     host.frame(&hid, &gfx)?;
+///
+///     let mut screen = gfx.primary_screen();
+///     screen.flush_buffers();
+///     screen.swap_buffers();
+///     drop(screen);
+///
+///     gfx.wait_for_vblank();
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct ScriptHost {
+    lua: Lua,
+    state: Rc<RefCell<State>>,
+}
+
+struct State {
+    root: PathBuf,
+    keys_held: KeyPad,
+    keys_down: KeyPad,
+    touch: (u16, u16),
+    commands: Vec<DrawCommand>,
+    sounds: Vec<String>,
+}
+
+#[derive(Clone, Copy)]
+enum DrawCommand {
+    Clear(Rgba8),
+    Rect {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        color: Rgba8,
+    },
+    Pixel {
+        x: usize,
+        y: usize,
+        color: Rgba8,
+    },
+}
+
+impl ScriptHost {
+    /// Creates a new interpreter, with the file system bindings restricted to `root`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the bindings couldn't be registered.
+    pub fn new(root: impl Into<PathBuf>) -> mlua::Result<Self> {
+        // Libraries and functions accessing the file system directly would bypass the script root directory.
+        let lua = Lua::new_with(
+            StdLib::ALL_SAFE ^ (StdLib::IO | StdLib::OS | StdLib::PACKAGE),
+            LuaOptions::default(),
+        )?;
+        for function in ["dofile", "loadfile"] {
+            lua.globals().set(function, Value::Nil)?;
+        }
+
+        let host = Self {
+            lua,
+            state: Rc::new(RefCell::new(State {
+                root: root.into(),
+                keys_held: KeyPad::empty(),
+                keys_down: KeyPad::empty(),
+                touch: (0, 0),
+                commands: Vec::new(),
+                sounds: Vec::new(),
+            })),
+        };
+
+        host.register_gfx()?;
+        host.register_input()?;
+        host.register_audio()?;
+        host.register_fs()?;
+
+        Ok(host)
+    }
+
+    /// Returns the underlying interpreter, to register additional bindings.
+    pub fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
+    /// Runs a chunk of Lua code.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the code couldn't be parsed or raised an error.
+    pub fn exec(&self, source: &str, name: &str) -> mlua::Result<()> {
+        self.lua.load(source).set_name(name).exec()
+    }
+
+    /// Runs a script file, with its path relative to the script root directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be read, or if the script failed (see [`ScriptHost::exec()`]).
+    pub fn exec_file(&self, path: &str) -> mlua::Result<()> {
+        let full_path = self.state.borrow().resolve(path)?;
+        let source = fs::read_to_string(full_path).map_err(mlua::Error::external)?;
+
+        self.exec(&source, path)
+    }
+
+    /// Updates the input state, calls the global `update()` function of the script (if any),
+    /// and draws the frame requested by the script on the [primary screen](Gfx::primary_screen).
+    ///
+    /// This should be called once per frame, after [`Hid::scan_input()`] and before flushing and swapping the framebuffers.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `update()` raised an error.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the primary screen is already borrowed.
+    pub fn frame(&mut self, hid: &Hid, gfx: &Gfx) -> mlua::Result<()> {
+        {
+            let mut state = self.state.borrow_mut();
+            state.keys_held = hid.keys_held();
+            state.keys_down = hid.keys_down();
+            state.touch = hid.touch_position();
+        }
+
+        if let Some(update) = self.lua.globals().get::<_, Option<Function>>("update")? {
+            update.call::<_, ()>(())?;
+        }
+
+        let commands = std::mem::take(&mut self.state.borrow_mut().commands);
+        if !commands.is_empty() {
+            draw(&mut gfx.primary_screen(), &commands);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the sounds queued by the script via `audio.play(name)` since the last call.
+    ///
+    /// Playing them (e.g. via the `ndsp` service) is up to the host application,
+    /// which is in charge of loading and mixing its audio assets.
+    pub fn take_sounds(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.state.borrow_mut().sounds)
+    }
+
+    fn register_gfx(&self) -> mlua::Result<()> {
+        let gfx = self.lua.create_table()?;
+
+        let state = self.state.clone();
+        gfx.set(
+            "clear",
+            self.lua.create_function(move |_, color: u32| {
+                let mut state = state.borrow_mut();
+                // Clearing makes the previous commands useless.
+                state.commands.clear();
+                state.commands.push(DrawCommand::Clear(rgb(color)));
+                Ok(())
+            })?,
+        )?;
+
+        let state = self.state.clone();
+        gfx.set(
+            "rect",
+            self.lua.create_function(
+                move |_, (x, y, width, height, color): (usize, usize, usize, usize, u32)| {
+                    state.borrow_mut().commands.push(DrawCommand::Rect {
+                        x,
+                        y,
+                        width,
+                        height,
+                        color: rgb(color),
+                    });
+                    Ok(())
+                },
+            )?,
+        )?;
+
+        let state = self.state.clone();
+        gfx.set(
+            "pixel",
+            self.lua
+                .create_function(move |_, (x, y, color): (usize, usize, u32)| {
+                    state.borrow_mut().commands.push(DrawCommand::Pixel {
+                        x,
+                        y,
+                        color: rgb(color),
+                    });
+                    Ok(())
+                })?,
+        )?;
+
+        self.lua.globals().set("gfx", gfx)
+    }
+
+    fn register_input(&self) -> mlua::Result<()> {
+        let input = self.lua.create_table()?;
+
+        let state = self.state.clone();
+        input.set(
+            "held",
+            self.lua.create_function(move |_, key: String| {
+                Ok(state.borrow().keys_held.contains(key_from_name(&key)?))
+            })?,
+        )?;
+
+        let state = self.state.clone();
+        input.set(
+            "down",
+            self.lua.create_function(move |_, key: String| {
+                Ok(state.borrow().keys_down.contains(key_from_name(&key)?))
+            })?,
+        )?;
+
+        let state = self.state.clone();
+        input.set(
+            "touch",
+            self.lua
+                .create_function(move |_, ()| Ok(state.borrow().touch))?,
+        )?;
+
+        self.lua.globals().set("input", input)
+    }
+
+    fn register_audio(&self) -> mlua::Result<()> {
+        let audio = self.lua.create_table()?;
+
+        let state = self.state.clone();
+        audio.set(
+            "play",
+            self.lua.create_function(move |_, name: String| {
+                state.borrow_mut().sounds.push(name);
+                Ok(())
+            })?,
+        )?;
+
+        self.lua.globals().set("audio", audio)
+    }
+
+    fn register_fs(&self) -> mlua::Result<()> {
+        let table = self.lua.create_table()?;
+
+        let state = self.state.clone();
+        table.set(
+            "read",
+            self.lua.create_function(move |lua, path: String| {
+                let data =
+                    fs::read(state.borrow().resolve(&path)?).map_err(mlua::Error::external)?;
+                lua.create_string(data)
+            })?,
+        )?;
+
+        let state = self.state.clone();
+        table.set(
+            "write",
+            self.lua
+                .create_function(move |_, (path, data): (String, mlua::String)| {
+                    fs::write(state.borrow().resolve(&path)?, data.as_bytes())
+                        .map_err(mlua::Error::external)
+                })?,
+        )?;
+
+        let state = self.state.clone();
+        table.set(
+            "exists",
+            self.lua.create_function(move |_, path: String| {
+                Ok(state.borrow().resolve(&path)?.exists())
+            })?,
+        )?;
+
+        self.lua.globals().set("fs", table)
+    }
+}
+
+impl State {
+    // Scripts can only access files inside the root directory.
+    fn resolve(&self, path: &str) -> mlua::Result<PathBuf> {
+        let relative = Path::new(path);
+
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(mlua::Error::RuntimeError(format!(
+                "path `{path}` is outside of the script directory"
+            )));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+fn rgb(color: u32) -> Rgba8 {
+    let [_, r, g, b] = color.to_be_bytes();
+    Rgba8::opaque(r, g, b)
+}
+
+fn key_from_name(name: &str) -> mlua::Result<KeyPad> {
+    KeyPad::from_name(name)
+        .ok_or_else(|| mlua::Error::RuntimeError(format!("unknown key `{name}`")))
+}
+
+fn draw(screen: &mut ScreenMut<'_>, commands: &[DrawCommand]) {
+    match screen.framebuffer_format() {
+        FramebufferFormat::Rgba8 => draw_with::<Rgba8>(screen, commands),
+        FramebufferFormat::Bgr8 => draw_with::<Bgr8>(screen, commands),
+        FramebufferFormat::Rgb565 => draw_with::<Rgb565>(screen, commands),
+        FramebufferFormat::Rgb5A1 => draw_with::<Rgb5A1>(screen, commands),
+        FramebufferFormat::Rgba4 => draw_with::<Rgba4>(screen, commands),
+    }
+}
+
+fn draw_with<P: Pixel>(screen: &mut ScreenMut<'_>, commands: &[DrawCommand]) {
+    let Some(mut view) = screen.framebuffer_view::<P>() else {
+        return;
+    };
+
+    for &command in commands {
+        match command {
+            DrawCommand::Clear(color) => view.fill(color.into()),
+            DrawCommand::Rect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => view.fill_rect(x, y, width, height, color.into()),
+            DrawCommand::Pixel { x, y, color } => {
+                // Out of bounds pixels are ignored, like the parts of rectangles outside of the screen.
+                if x < view.width() && y < view.height() {
+                    view.set_pixel(x, y, color.into());
+                }
+            }
+        }
+    }
+}
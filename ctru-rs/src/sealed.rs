@@ -3,7 +3,10 @@
 //! done in this crate.
 
 use crate::console::Console;
-use crate::services::gfx::{BottomScreen, TopScreen, TopScreen3D, TopScreenLeft, TopScreenRight};
+use crate::services::gfx::pixel::{Bgr8, Rgb565, Rgb5A1, Rgba4, Rgba8};
+use crate::services::gfx::{
+    BottomScreen, ScreenMut, TopScreen, TopScreen3D, TopScreenLeft, TopScreenRight,
+};
 
 pub trait Sealed {}
 
@@ -12,4 +15,11 @@ impl Sealed for TopScreen3D<'_> {}
 impl Sealed for TopScreenLeft {}
 impl Sealed for TopScreenRight {}
 impl Sealed for BottomScreen {}
+impl Sealed for ScreenMut<'_> {}
 impl Sealed for Console<'_> {}
+
+impl Sealed for Rgba8 {}
+impl Sealed for Bgr8 {}
+impl Sealed for Rgb565 {}
+impl Sealed for Rgb5A1 {}
+impl Sealed for Rgba4 {}
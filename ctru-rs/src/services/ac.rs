@@ -0,0 +1,255 @@
+//! Automatic Connection service.
+//!
+//! The AC service manages the Wi-Fi connection of the console, using the connection settings saved in the System Settings.
+//! It can be used to check whether the console is connected to the internet (and to which access point) before opening sockets
+//! with the [`Soc`](crate::services::soc::Soc) service.
+//!
+//! See also <https://www.3dbrew.org/wiki/NWM_Services#AC_Services>
+#![doc(alias = "wifi")]
+#![doc(alias = "network")]
+
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, ResultCode};
+
+// Maximum length of an SSID (in bytes), as defined by IEEE 802.11.
+const SSID_MAX_LEN: usize = 32;
+
+// Value of `ACU_GetStatus` once the console is connected to the internet.
+const STATUS_INTERNET: u32 = 3;
+
+// The service doesn't signal status changes, so the status is polled with this interval while waiting.
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Status of the Wi-Fi connection of the console.
+#[doc(alias = "ACU_GetWifiStatus")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum WifiStatus {
+    /// The console isn't connected to an access point.
+    Disconnected = 0,
+    /// The console is connected to the internet via the Wi-Fi module of the Old 3DS.
+    Old3ds = 1,
+    /// The console is connected to the internet via the Wi-Fi module of the New 3DS.
+    New3ds = 2,
+}
+
+ffi_enum! {
+    /// Security mode of the access point the console is connected to.
+    #[doc(alias = "acSecurityMode")]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum SecurityMode: u8 from ctru_sys::acSecurityMode_VALUES {
+        /// No security (open network).
+        Open = ctru_sys::AC_OPEN,
+        /// WEP with a 40-bit key.
+        Wep40Bit = ctru_sys::AC_WEP_40BIT,
+        /// WEP with a 104-bit key.
+        Wep104Bit = ctru_sys::AC_WEP_104BIT,
+        /// WEP with a 128-bit key.
+        Wep128Bit = ctru_sys::AC_WEP_128BIT,
+        /// WPA with TKIP.
+        WpaTkip = ctru_sys::AC_WPA_TKIP,
+        /// WPA2 with TKIP.
+        Wpa2Tkip = ctru_sys::AC_WPA2_TKIP,
+        /// WPA with AES.
+        WpaAes = ctru_sys::AC_WPA_AES,
+        /// WPA2 with AES.
+        Wpa2Aes = ctru_sys::AC_WPA2_AES,
+    }
+}
+
+/// Handle to the AC service.
+pub struct Ac(());
+
+impl Ac {
+    /// Initialize a new service handle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::ac::Ac;
+    ///
+    /// let ac = Ac::new()?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "acInit")]
+    pub fn new() -> crate::Result<Self> {
+        ResultCode(unsafe { ctru_sys::acInit() })?;
+
+        Ok(Ac(()))
+    }
+
+    /// Returns the status of the Wi-Fi connection.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::ac::{Ac, WifiStatus};
+    ///
+    /// let ac = Ac::new()?;
+    ///
+    /// if ac.wifi_status()? == WifiStatus::Disconnected {
+    ///     println!("Connect to the internet to play online!");
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "ACU_GetWifiStatus")]
+    pub fn wifi_status(&self) -> crate::Result<WifiStatus> {
+        let mut status = 0;
+        ResultCode(unsafe { ctru_sys::ACU_GetWifiStatus(&mut status) })?;
+
+        match status {
+            0 => Ok(WifiStatus::Disconnected),
+            1 => Ok(WifiStatus::Old3ds),
+            2 => Ok(WifiStatus::New3ds),
+            _ => Err(Error::Other(format!("unknown Wi-Fi status: {status}"))),
+        }
+    }
+
+    /// Returns the SSID (name) of the access point the console is connected to.
+    ///
+    /// SSIDs are arbitrary bytes, which are usually (but not necessarily) UTF-8 text: invalid sequences are replaced.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the console isn't connected to an access point.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::ac::{Ac, WifiStatus};
+    ///
+    /// let ac = Ac::new()?;
+    ///
+    /// if ac.wifi_status()? != WifiStatus::Disconnected {
+    ///     println!("Connected to {}", ac.ssid()?);
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "ACU_GetSSID", alias = "ACU_GetSSIDLength")]
+    pub fn ssid(&self) -> crate::Result<String> {
+        let mut len = 0;
+        ResultCode(unsafe { ctru_sys::ACU_GetSSIDLength(&mut len) })?;
+
+        // Some versions of the service write a nul terminator after the SSID.
+        let mut buf = [0u8; SSID_MAX_LEN + 1];
+        ResultCode(unsafe { ctru_sys::ACU_GetSSID(buf.as_mut_ptr().cast()) })?;
+
+        let len = (len as usize).min(SSID_MAX_LEN);
+
+        Ok(String::from_utf8_lossy(&buf[..len]).into_owned())
+    }
+
+    /// Returns the security mode of the access point the console is connected to.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the console isn't connected to an access point.
+    #[doc(alias = "ACU_GetSecurityMode")]
+    pub fn security_mode(&self) -> crate::Result<SecurityMode> {
+        let mut mode = 0;
+        ResultCode(unsafe { ctru_sys::ACU_GetSecurityMode(&mut mode) })?;
+
+        SecurityMode::try_from(mode)
+            .map_err(|()| Error::Other(format!("unknown security mode: {mode}")))
+    }
+
+    /// Waits until the console is connected to the internet, or until `timeout` elapses.
+    ///
+    /// Unlike `acWaitInternetConnection`, which blocks forever when Wi-Fi is disabled, this returns after `timeout` at most,
+    /// so applications can keep drawing a "connecting..." screen (and let the user cancel it) by calling it with a short timeout every frame.
+    /// Timeouts too long to be represented (e.g. [`Duration::MAX`]) wait without a deadline.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the connection status couldn't be read, or if the timeout was reached.
+    /// You can use [`Error::is_timeout()`] to check for the latter.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use ctru::prelude::*;
+    /// use ctru::services::ac::Ac;
+    ///
+    /// let apt = Apt::new()?;
+    /// let mut hid = Hid::new()?;
+    /// let ac = Ac::new()?;
+    ///
+    /// println!("Connecting... (press B to cancel)");
+    ///
+    /// while apt.main_loop() {
+    ///     hid.scan_input();
+    ///     if hid.keys_down().contains(KeyPad::B) {
+    ///         return Ok(());
+    ///     }
+    ///
+    ///     match ac.wait_for_internet(Duration::from_millis(16)) {
+    ///         Ok(()) => break,
+    ///         Err(e) if e.is_timeout() => continue,
+    ///         Err(e) => return Err(e.into()),
+    ///     }
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "acWaitInternetConnection", alias = "ACU_GetStatus")]
+    pub fn wait_for_internet(&self, timeout: Duration) -> crate::Result<()> {
+        // `None` if the timeout is too long to be represented, in which case there's no deadline.
+        let deadline = Instant::now().checked_add(timeout);
+
+        loop {
+            let mut status = 0;
+            ResultCode(unsafe { ctru_sys::ACU_GetStatus(&mut status) })?;
+
+            if status == STATUS_INTERNET {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                // Same code as the timeouts of the kernel, but from the AC module.
+                return Err(Error::Os(ctru_sys::MAKERESULT(
+                    ctru_sys::RL_INFO as _,
+                    ctru_sys::RS_STATUSCHANGED as _,
+                    ctru_sys::RM_AC as _,
+                    ctru_sys::RD_TIMEOUT as _,
+                )));
+            }
+
+            let remaining = deadline.map_or(Duration::MAX, |deadline| deadline - now);
+            std::thread::sleep(STATUS_POLL_INTERVAL.min(remaining));
+        }
+    }
+}
+
+impl Drop for Ac {
+    #[doc(alias = "acExit")]
+    fn drop(&mut self) {
+        unsafe { ctru_sys::acExit() };
+    }
+}
@@ -1,17 +1,37 @@
 //! Application Manager service.
 //!
 //! As the name implies, the AM service manages installed applications. It can:
-//! - Read the installed applications on the console and their information (depending on the install location).
-//! - Install compatible applications to the console.
-//!
-//! TODO: [`ctru-rs`](crate) doesn't support installing or uninstalling titles yet.
+//! - Read the installed applications on the console and their information (depending on the install location), see [`Am::titles()`].
+//! - Find the updates and downloadable content (DLC) installed for an application, and mount their RomFS.
+//! - Install compatible applications (CIA files) to the console, and delete installed applications.
+//! - List and delete the tickets (the licenses of the titles), and clean up unfinished installations.
 #![doc(alias = "app")]
 #![doc(alias = "manager")]
 
-use crate::error::ResultCode;
+use crate::error::{Error, ResultCode};
 use crate::services::fs::MediaType;
+use std::ffi::CString;
+use std::io::{self, Write};
 use std::marker::PhantomData;
 
+// High halves of the IDs of the titles related to an application, which share the low half of its ID.
+const UPDATE_ID_HIGH: u64 = 0x0004_000E;
+const DLC_ID_HIGH: u64 = 0x0004_008C;
+
+// Number of titles whose information is read at once by `Titles`.
+const TITLE_INFO_BATCH: usize = 32;
+
+/// Returns the ID of the update title of an application.
+pub fn update_id(title_id: u64) -> u64 {
+    (UPDATE_ID_HIGH << 32) | (title_id & 0xFFFF_FFFF)
+}
+
+/// Returns the ID of the downloadable content (DLC) title of an application.
+#[doc(alias = "add-on content")]
+pub fn dlc_id(title_id: u64) -> u64 {
+    (DLC_ID_HIGH << 32) | (title_id & 0xFFFF_FFFF)
+}
+
 /// General information about a specific title entry.
 #[doc(alias = "AM_TitleEntry")]
 pub struct Title<'a> {
@@ -58,6 +78,181 @@ impl<'a> Title<'a> {
     }
 }
 
+/// Information about an installed title, as returned by [`Am::titles()`].
+#[doc(alias = "AM_TitleEntry")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TitleInfo {
+    /// ID of the title.
+    pub title_id: u64,
+    /// Install location of the title.
+    pub media_type: MediaType,
+    /// Size of the title (in bytes).
+    pub size: u64,
+    /// Installed version of the title.
+    pub version: u16,
+}
+
+/// Iterator over the titles installed in a specific install location.
+///
+/// The information of the titles is read lazily, a few titles at a time.
+///
+/// This struct can be created via [`Am::titles()`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Titles<'a> {
+    media_type: MediaType,
+    ids: std::vec::IntoIter<u64>,
+    batch: std::vec::IntoIter<TitleInfo>,
+    _am: PhantomData<&'a Am>,
+}
+
+impl Titles<'_> {
+    fn read_batch(&mut self) -> crate::Result<()> {
+        let mut ids: Vec<u64> = self.ids.by_ref().take(TITLE_INFO_BATCH).collect();
+        let mut entries = vec![ctru_sys::AM_TitleEntry::default(); ids.len()];
+
+        ResultCode(unsafe {
+            ctru_sys::AM_GetTitleInfo(
+                self.media_type.into(),
+                ids.len() as u32,
+                ids.as_mut_ptr(),
+                entries.as_mut_ptr(),
+            )
+        })?;
+
+        let media_type = self.media_type;
+        self.batch = entries
+            .into_iter()
+            .map(|entry| TitleInfo {
+                title_id: entry.titleID,
+                media_type,
+                size: entry.size,
+                version: entry.version,
+            })
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        Ok(())
+    }
+}
+
+impl Iterator for Titles<'_> {
+    type Item = crate::Result<TitleInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.len() == 0 && self.ids.len() > 0 {
+            if let Err(e) = self.read_batch() {
+                return Some(Err(e));
+            }
+        }
+
+        self.batch.next().map(Ok)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.batch.len() + self.ids.len();
+        (remaining, Some(remaining))
+    }
+}
+
+/// Content of a downloadable content (DLC) title.
+///
+/// Every content is an individual item of the DLC, identified by its index.
+#[doc(alias = "AM_ContentInfo")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ContentInfo {
+    /// Index of the content in the title.
+    pub index: u16,
+    /// ID of the content.
+    pub id: u32,
+    /// Size of the content (in bytes).
+    pub size: u64,
+    /// Whether the content is downloaded on the console.
+    pub downloaded: bool,
+    /// Whether the user owns the content (i.e. purchased it).
+    pub owned: bool,
+}
+
+/// RomFS of an installed title, mounted as a virtual drive.
+///
+/// The RomFS is unmounted when this value is dropped.
+///
+/// This struct can be created via [`Am::mount_romfs()`].
+pub struct TitleRomFS {
+    mount_name: CString,
+}
+
+/// Installation of a CIA file, started via [`Am::install_cia()`].
+///
+/// The contents of the CIA file are written to the installer in order, via its [`Write`] implementation.
+/// The installation is only completed once [`CiaInstaller::finish()`] is called, and is cancelled if the installer is dropped before that.
+#[doc(alias = "AM_StartCiaInstall")]
+#[must_use = "the installation is cancelled if the installer is dropped without being finished"]
+pub struct CiaInstaller<'a> {
+    handle: ctru_sys::Handle,
+    position: u64,
+    _am: PhantomData<&'a Am>,
+}
+
+impl CiaInstaller<'_> {
+    /// Completes the installation, once the whole CIA file was written.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the written data isn't a complete and valid CIA file, or if it couldn't be installed.
+    #[doc(alias = "AM_FinishCiaInstall")]
+    pub fn finish(self) -> crate::Result<()> {
+        // The handle is moved to the service, which closes it.
+        let handle = self.handle;
+        std::mem::forget(self);
+
+        ResultCode(unsafe { ctru_sys::AM_FinishCiaInstall(handle) })?;
+
+        Ok(())
+    }
+
+    /// Returns the amount of bytes written so far.
+    pub fn written(&self) -> u64 {
+        self.position
+    }
+}
+
+impl Write for CiaInstaller<'_> {
+    #[doc(alias = "FSFILE_Write")]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+
+        let result = unsafe {
+            ctru_sys::FSFILE_Write(
+                self.handle,
+                &mut written,
+                self.position,
+                buf.as_ptr().cast(),
+                buf.len() as u32,
+                0,
+            )
+        };
+
+        if ctru_sys::R_FAILED(result) {
+            return Err(io::Error::other(Error::from(result)));
+        }
+
+        self.position += u64::from(written);
+
+        Ok(written as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for CiaInstaller<'_> {
+    #[doc(alias = "AM_CancelCIAInstall")]
+    fn drop(&mut self) {
+        let _ = unsafe { ctru_sys::AM_CancelCIAInstall(self.handle) };
+    }
+}
+
 /// Handle to the Application Manager service.
 pub struct Am(());
 
@@ -178,6 +373,450 @@ impl Am {
             })
             .collect())
     }
+
+    /// Returns an iterator over the titles installed in a specific install location.
+    ///
+    /// Unlike [`Am::title_list()`], the information of the titles is only read when iterating,
+    /// so this scales to consoles with many installed titles.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the list of installed titles couldn't be read.
+    /// Each item is an error if the information of its title couldn't be read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::am::Am;
+    /// use ctru::services::fs::MediaType;
+    ///
+    /// let app_manager = Am::new()?;
+    ///
+    /// for title in app_manager.titles(MediaType::Sd)? {
+    ///     let title = title?;
+    ///     println!("{:016X} v{}: {} bytes", title.title_id, title.version, title.size);
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "AM_GetTitleList", alias = "AM_GetTitleInfo")]
+    pub fn titles(&self, media_type: MediaType) -> crate::Result<Titles<'_>> {
+        Ok(Titles {
+            media_type,
+            ids: self.title_ids(media_type)?.into_iter(),
+            batch: Vec::new().into_iter(),
+            _am: PhantomData,
+        })
+    }
+
+    /// Returns the installed update of an application, if any.
+    ///
+    /// Updates are always installed on the SD card.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the installed titles couldn't be read.
+    pub fn update(&self, title_id: u64) -> crate::Result<Option<Title>> {
+        self.find_title(update_id(title_id), MediaType::Sd)
+    }
+
+    /// Returns the installed downloadable content (DLC) title of an application, if any.
+    ///
+    /// DLC titles are always installed on the SD card.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the installed titles couldn't be read.
+    pub fn dlc(&self, title_id: u64) -> crate::Result<Option<Title>> {
+        self.find_title(dlc_id(title_id), MediaType::Sd)
+    }
+
+    /// Returns the contents of the downloadable content (DLC) title of an application.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the DLC title isn't installed, or if its contents couldn't be read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::am::Am;
+    /// use ctru::services::fs::MediaType;
+    ///
+    /// let app_manager = Am::new()?;
+    ///
+    /// for title in app_manager.title_list(MediaType::Sd)? {
+    ///     if app_manager.dlc(title.id())?.is_some() {
+    ///         let contents = app_manager.dlc_contents(title.id())?;
+    ///         let owned = contents.iter().filter(|content| content.owned).count();
+    ///
+    ///         println!("{}: {owned} DLC items owned", title.product_code());
+    ///     }
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(
+        alias = "AMAPP_GetDLCContentInfoCount",
+        alias = "AMAPP_ListDLCContentInfos"
+    )]
+    pub fn dlc_contents(&self, title_id: u64) -> crate::Result<Vec<ContentInfo>> {
+        let id = dlc_id(title_id);
+        let media_type = MediaType::Sd.into();
+
+        let mut count = 0;
+        ResultCode(unsafe { ctru_sys::AMAPP_GetDLCContentInfoCount(&mut count, media_type, id) })?;
+
+        let mut infos: Vec<ctru_sys::AM_ContentInfo> = Vec::with_capacity(count as usize);
+        let mut read = 0;
+
+        unsafe {
+            ResultCode(ctru_sys::AMAPP_ListDLCContentInfos(
+                &mut read,
+                media_type,
+                id,
+                count,
+                0,
+                infos.as_mut_ptr(),
+            ))?;
+
+            infos.set_len(read.min(count) as usize);
+        }
+
+        Ok(infos
+            .into_iter()
+            .map(|info| ContentInfo {
+                index: info.index,
+                id: info.contentId,
+                size: info.size,
+                downloaded: info.flags & ctru_sys::AM_CONTENT_DOWNLOADED as u8 != 0,
+                owned: info.flags & ctru_sys::AM_CONTENT_OWNED as u8 != 0,
+            })
+            .collect())
+    }
+
+    /// Mounts the RomFS of an installed title as a virtual drive, accessible via the `<mount_name>:/` prefix.
+    ///
+    /// This can be used to read the files of an application, or of its update (see [`update_id()`]).
+    /// The RomFS of the contents of a DLC title can't be mounted this way.
+    ///
+    /// # Notes
+    ///
+    /// Accessing the RomFS of other titles requires elevated permissions, which are available to applications launched via the Homebrew Launcher.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the title isn't installed, doesn't have a RomFS, or if the application doesn't have the permissions to access it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::am::{self, Am};
+    /// use ctru::services::fs::MediaType;
+    ///
+    /// let app_manager = Am::new()?;
+    /// let title_id = 0x0004000000055D00;
+    ///
+    /// if app_manager.update(title_id)?.is_some() {
+    ///     let _romfs = app_manager.mount_romfs(am::update_id(title_id), MediaType::Sd, "update")?;
+    ///     let files = std::fs::read_dir("update:/")?;
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "romfsMountFromTitle")]
+    pub fn mount_romfs(
+        &self,
+        title_id: u64,
+        media_type: MediaType,
+        mount_name: &str,
+    ) -> crate::Result<TitleRomFS> {
+        let mount_name = CString::new(mount_name)
+            .map_err(|_| Error::Other("mount name contains a nul byte".into()))?;
+
+        ResultCode(unsafe {
+            ctru_sys::romfsMountFromTitle(title_id, media_type.into(), mount_name.as_ptr())
+        })?;
+
+        Ok(TitleRomFS { mount_name })
+    }
+
+    /// Starts the installation of a CIA file to a specific install location.
+    ///
+    /// # Notes
+    ///
+    /// Installing titles requires elevated permissions, which are available to applications launched via the Homebrew Launcher.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the installation couldn't be started (e.g. another installation is in progress).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use std::fs::File;
+    ///
+    /// use ctru::services::am::Am;
+    /// use ctru::services::fs::MediaType;
+    ///
+    /// let app_manager = Am::new()?;
+    ///
+    /// let mut cia = File::open("sdmc:/cias/game.cia")?;
+    /// let mut installer = app_manager.install_cia(MediaType::Sd)?;
+    ///
+    /// std::io::copy(&mut cia, &mut installer)?;
+    /// installer.finish()?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "AM_StartCiaInstall")]
+    pub fn install_cia(&self, media_type: MediaType) -> crate::Result<CiaInstaller<'_>> {
+        let mut handle = 0;
+        ResultCode(unsafe { ctru_sys::AM_StartCiaInstall(media_type.into(), &mut handle) })?;
+
+        Ok(CiaInstaller {
+            handle,
+            position: 0,
+            _am: PhantomData,
+        })
+    }
+
+    /// Deletes an installed application, from a specific install location.
+    ///
+    /// Its update and DLC titles (see [`update_id()`] and [`dlc_id()`]) are separate titles, which aren't deleted.
+    ///
+    /// # Notes
+    ///
+    /// Deleting titles requires elevated permissions, which are available to applications launched via the Homebrew Launcher.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the title isn't installed, or if it couldn't be deleted.
+    #[doc(alias = "AM_DeleteAppTitle")]
+    pub fn delete_title(&self, media_type: MediaType, title_id: u64) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::AM_DeleteAppTitle(media_type.into(), title_id) })?;
+
+        Ok(())
+    }
+
+    /// Returns the IDs of the titles the console has a ticket (i.e. a license) for.
+    ///
+    /// Tickets are independent from the install locations, and remain after their title is deleted.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the tickets couldn't be read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::am::Am;
+    /// use ctru::services::fs::MediaType;
+    ///
+    /// let app_manager = Am::new()?;
+    /// let installed = app_manager.title_list(MediaType::Sd)?;
+    ///
+    /// for ticket in app_manager.tickets()? {
+    ///     if !installed.iter().any(|title| title.id() == ticket) {
+    ///         println!("ticket without title: {ticket:016X}");
+    ///     }
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "AM_GetTicketCount", alias = "AM_GetTicketList")]
+    pub fn tickets(&self) -> crate::Result<Vec<u64>> {
+        let mut count = 0;
+        ResultCode(unsafe { ctru_sys::AM_GetTicketCount(&mut count) })?;
+
+        let mut ids = vec![0; count as usize];
+        let mut read = 0;
+
+        ResultCode(unsafe { ctru_sys::AM_GetTicketList(&mut read, count, 0, ids.as_mut_ptr()) })?;
+
+        ids.truncate(read.min(count) as usize);
+
+        Ok(ids)
+    }
+
+    /// Deletes the ticket of a title.
+    ///
+    /// # Notes
+    ///
+    /// Deleting tickets requires elevated permissions, which are available to applications launched via the Homebrew Launcher.
+    /// The title can't be launched anymore without its ticket, even if it's still installed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no ticket for the title, or if it couldn't be deleted.
+    #[doc(alias = "AM_DeleteTicket")]
+    pub fn delete_ticket(&self, title_id: u64) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::AM_DeleteTicket(title_id) })?;
+
+        Ok(())
+    }
+
+    /// Returns the IDs of the titles whose installation wasn't completed in a specific install location,
+    /// either because it's still in progress or because it was interrupted before being finalized.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pending titles couldn't be read.
+    #[doc(alias = "AM_GetPendingTitleCount", alias = "AM_GetPendingTitleList")]
+    pub fn pending_titles(&self, media_type: MediaType) -> crate::Result<Vec<u64>> {
+        let status_mask = (ctru_sys::AM_STATUS_MASK_INSTALLING
+            | ctru_sys::AM_STATUS_MASK_AWAITING_FINALIZATION) as u32;
+
+        let mut count = 0;
+        ResultCode(unsafe {
+            ctru_sys::AM_GetPendingTitleCount(&mut count, media_type.into(), status_mask)
+        })?;
+
+        let mut ids = vec![0; count as usize];
+        let mut read = 0;
+
+        ResultCode(unsafe {
+            ctru_sys::AM_GetPendingTitleList(
+                &mut read,
+                count,
+                media_type.into(),
+                status_mask,
+                ids.as_mut_ptr(),
+            )
+        })?;
+
+        ids.truncate(read.min(count) as usize);
+
+        Ok(ids)
+    }
+
+    /// Deletes the data of an uncompleted installation from a specific install location.
+    ///
+    /// # Notes
+    ///
+    /// Deleting titles requires elevated permissions, which are available to applications launched via the Homebrew Launcher.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the title isn't pending in this location, or if it couldn't be deleted.
+    #[doc(alias = "AM_DeletePendingTitle")]
+    pub fn delete_pending_title(&self, media_type: MediaType, title_id: u64) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::AM_DeletePendingTitle(media_type.into(), title_id) })?;
+
+        Ok(())
+    }
+
+    /// Deletes the data of all the uncompleted installations from a specific install location.
+    ///
+    /// # Notes
+    ///
+    /// Deleting titles requires elevated permissions, which are available to applications launched via the Homebrew Launcher.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pending titles couldn't be deleted.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::am::Am;
+    /// use ctru::services::fs::MediaType;
+    ///
+    /// let app_manager = Am::new()?;
+    ///
+    /// // Clean up after installations interrupted by a crash or a power loss.
+    /// if !app_manager.pending_titles(MediaType::Sd)?.is_empty() {
+    ///     app_manager.delete_all_pending_titles(MediaType::Sd)?;
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "AM_DeleteAllPendingTitles")]
+    pub fn delete_all_pending_titles(&self, media_type: MediaType) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::AM_DeleteAllPendingTitles(media_type.into()) })?;
+
+        Ok(())
+    }
+
+    fn find_title(&self, id: u64, media_type: MediaType) -> crate::Result<Option<Title>> {
+        let mut entry = std::mem::MaybeUninit::<ctru_sys::AM_TitleEntry>::uninit();
+        let mut ids = [id];
+
+        // Getting the information of a missing title fails, but so do other errors: check the list first.
+        if !self.title_ids(media_type)?.contains(&id) {
+            return Ok(None);
+        }
+
+        let entry = unsafe {
+            ResultCode(ctru_sys::AM_GetTitleInfo(
+                media_type.into(),
+                1,
+                ids.as_mut_ptr(),
+                entry.as_mut_ptr(),
+            ))?;
+
+            entry.assume_init()
+        };
+
+        Ok(Some(Title {
+            id: entry.titleID,
+            mediatype: media_type,
+            size: entry.size,
+            version: entry.version,
+            _am: PhantomData,
+        }))
+    }
+
+    fn title_ids(&self, media_type: MediaType) -> crate::Result<Vec<u64>> {
+        let count = self.title_count(media_type)?;
+        let mut ids = vec![0; count as usize];
+        let mut read = 0;
+
+        ResultCode(unsafe {
+            ctru_sys::AM_GetTitleList(&mut read, media_type.into(), count, ids.as_mut_ptr())
+        })?;
+
+        ids.truncate(read.min(count) as usize);
+
+        Ok(ids)
+    }
+}
+
+impl Drop for TitleRomFS {
+    #[doc(alias = "romfsUnmount")]
+    fn drop(&mut self) {
+        let _ = unsafe { ctru_sys::romfsUnmount(self.mount_name.as_ptr()) };
+    }
 }
 
 impl Drop for Am {
@@ -5,12 +5,206 @@
 //!
 //! It also handles running applets, small programs made available by the OS to streamline specific functionality.
 //! Those are implemented in the [`applets`](crate::applets) module.
+//!
+//! Resources which must be stopped while the application is suspended can register their handlers in the [`resources`](crate::resources) module.
+//! For simpler applications, [`EventLoop`] wraps the main loop and calls closures when the state of the application changes.
+
+use crate::error::{Error, ResultCode};
+use crate::resources::{self, Registration, Transition};
+use crate::services::cfgu::Region;
+use crate::services::fs::MediaType;
+use crate::services::gspgpu::FramebufferFormat;
+
+/// Maximum size of the parameter passed to another application by [`Apt::jump_to_title()`].
+pub const MAX_JUMP_PARAMETER_SIZE: usize = 0x300;
 
-use crate::error::ResultCode;
+/// Size of the HMAC passed to another application by [`Apt::jump_to_title()`].
+pub const JUMP_HMAC_SIZE: usize = 0x20;
+
+// Size of the parameter expected by the Internet Browser, which contains the URL to open.
+const BROWSER_PARAMETER_SIZE: usize = 0x400;
 
 /// Handle to the Applet service.
 pub struct Apt(());
 
+/// Parameter passed to the application by the title which launched it.
+///
+/// This struct can be retrieved via [`Apt::launch_parameter()`].
+#[doc(alias = "deliver arg")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LaunchParameter {
+    data: Vec<u8>,
+    hmac: [u8; JUMP_HMAC_SIZE],
+    sender: u64,
+}
+
+impl LaunchParameter {
+    /// Returns the raw parameter.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the HMAC passed along with the parameter.
+    pub fn hmac(&self) -> &[u8; JUMP_HMAC_SIZE] {
+        &self.hmac
+    }
+
+    /// Returns the ID of the title which launched the application.
+    pub fn sender(&self) -> u64 {
+        self.sender
+    }
+
+    /// Parses the parameter as a list of arguments, as encoded by [`encode_args()`].
+    ///
+    /// Invalid UTF-8 sequences are replaced with [`U+FFFD REPLACEMENT CHARACTER`](char::REPLACEMENT_CHARACTER).
+    pub fn args(&self) -> Vec<String> {
+        let data = match self.data.iter().rposition(|&b| b != 0) {
+            Some(end) => &self.data[..=end],
+            None => return Vec::new(),
+        };
+
+        data.split(|&b| b == 0)
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect()
+    }
+}
+
+/// Encodes a list of arguments as a parameter for [`Apt::jump_to_title()`], which can be parsed back by [`LaunchParameter::args()`].
+///
+/// Every argument is terminated by a nul byte.
+pub fn encode_args<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Vec<u8> {
+    let mut parameter = Vec::new();
+
+    for arg in args {
+        parameter.extend_from_slice(arg.as_ref().as_bytes());
+        parameter.push(0);
+    }
+
+    parameter
+}
+
+/// Layout of the screen capture shown by the HOME Menu and the applets while the application is suspended.
+///
+/// When the application is suspended, the content of the screens is copied to a capture buffer, which is used for the thumbnail
+/// of the application in the HOME Menu and for the fade transitions. This struct describes the layout of that buffer.
+///
+/// This struct can be created via [`CaptureInfo::current()`], and sent via [`Apt::set_capture_info()`].
+#[doc(alias = "aptCaptureBufInfo")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CaptureInfo {
+    /// Total size of the capture buffer (in bytes).
+    pub size: u32,
+    /// Whether the top screen is captured in stereoscopic 3D (with both left and right images).
+    pub is_3d: bool,
+    /// Layout of the top screen capture.
+    pub top: ScreenCapture,
+    /// Layout of the bottom screen capture.
+    pub bottom: ScreenCapture,
+}
+
+/// Layout of the capture of a single screen, part of a [`CaptureInfo`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScreenCapture {
+    /// Offset of the left image in the capture buffer (in bytes).
+    pub left_offset: u32,
+    /// Offset of the right image in the capture buffer (in bytes). Only used by the top screen in 3D.
+    pub right_offset: u32,
+    /// Format of the captured pixels.
+    pub format: FramebufferFormat,
+}
+
+impl CaptureInfo {
+    /// Returns the layout matching the current configuration of the screens (framebuffer formats and 3D mode).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the configuration of the screens couldn't be read.
+    #[doc(
+        alias = "aptInitCaptureInfo",
+        alias = "GSPGPU_ImportDisplayCaptureInfo"
+    )]
+    pub fn current() -> crate::Result<Self> {
+        let mut gsp_info = std::mem::MaybeUninit::<ctru_sys::GSPGPU_CaptureInfo>::zeroed();
+        ResultCode(unsafe { ctru_sys::GSPGPU_ImportDisplayCaptureInfo(gsp_info.as_mut_ptr()) })?;
+        let gsp_info = unsafe { gsp_info.assume_init() };
+
+        let [top, bottom] = gsp_info.screencapture;
+
+        Ok(Self::with_formats(
+            FramebufferFormat::from(top.format & 0x7),
+            FramebufferFormat::from(bottom.format & 0x7),
+            top.format & 0x20 != 0,
+        ))
+    }
+
+    /// Returns the layout for the given framebuffer formats and 3D mode, as computed by `libctru`.
+    pub fn with_formats(top: FramebufferFormat, bottom: FramebufferFormat, is_3d: bool) -> Self {
+        // Captures are stored with columns padded to 256 pixels: 320x256 pixels for the bottom screen and 400x256 for the top screen.
+        const BOTTOM_PIXELS: u32 = 320 * 256;
+        const TOP_PIXELS: u32 = 400 * 256;
+
+        let top_size = TOP_PIXELS * top.pixel_depth_bytes() as u32;
+        let bottom_size = BOTTOM_PIXELS * bottom.pixel_depth_bytes() as u32;
+
+        let left_offset = bottom_size;
+        let right_offset = if is_3d {
+            left_offset + top_size
+        } else {
+            left_offset
+        };
+
+        Self {
+            size: right_offset + top_size,
+            is_3d,
+            top: ScreenCapture {
+                left_offset,
+                right_offset,
+                format: top,
+            },
+            bottom: ScreenCapture {
+                left_offset: 0,
+                right_offset: 0,
+                format: bottom,
+            },
+        }
+    }
+
+    fn to_raw(self) -> ctru_sys::aptCaptureBufInfo {
+        let mut raw: ctru_sys::aptCaptureBufInfo = unsafe { std::mem::zeroed() };
+
+        raw.size = self.size;
+        raw.is3D = self.is_3d.into();
+        raw.top.leftOffset = self.top.left_offset;
+        raw.top.rightOffset = self.top.right_offset;
+        raw.top.format = self.top.format.into();
+        raw.bottom.leftOffset = self.bottom.left_offset;
+        raw.bottom.rightOffset = self.bottom.right_offset;
+        raw.bottom.format = self.bottom.format.into();
+
+        raw
+    }
+}
+
+/// System applets which can be launched over the application.
+#[doc(alias = "NS_APPID")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+#[non_exhaustive]
+pub enum SystemApplet {
+    /// Internet Browser.
+    Browser = ctru_sys::APPID_WEB,
+    /// Friend List.
+    FriendList = ctru_sys::APPID_FRIENDS_LIST,
+    /// Game Notes.
+    GameNotes = ctru_sys::APPID_GAME_NOTES,
+    /// Notifications.
+    Notifications = ctru_sys::APPID_NOTIFICATIONS,
+    /// Miiverse.
+    Miiverse = ctru_sys::APPID_MIIVERSE,
+    /// amiibo Settings.
+    AmiiboSettings = ctru_sys::APPID_AMIIBO_SETTINGS,
+}
+
 impl Apt {
     /// Initialize a new service handle.
     ///
@@ -119,11 +313,412 @@ impl Apt {
         unsafe { ctru_sys::aptIsHomeAllowed() }
     }
 
+    /// Returns the title ID of the running application.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the ID couldn't be retrieved.
+    #[doc(alias = "APT_GetProgramID", alias = "title_id")]
+    pub fn program_id(&self) -> crate::Result<u64> {
+        let mut id = 0;
+        ResultCode(unsafe { ctru_sys::APT_GetProgramID(&mut id) })?;
+
+        Ok(id)
+    }
+
     /// Immediately jumps to the home menu.
     #[doc(alias = "aptJumpToHomeMenu")]
     pub fn jump_to_home_menu(&mut self) {
         unsafe { ctru_sys::aptJumpToHomeMenu() }
     }
+
+    /// Returns the parameter passed by the title which launched the application via [`Apt::jump_to_title()`], if any.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the parameter couldn't be read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::apt::Apt;
+    ///
+    /// let apt = Apt::new()?;
+    ///
+    /// if let Some(parameter) = apt.launch_parameter()? {
+    ///     // e.g. a level editor passing the level to test.
+    ///     if let Some(level) = parameter.args().first() {
+    ///         println!("Loading {level}");
+    ///     }
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "APT_ReceiveDeliverArg")]
+    pub fn launch_parameter(&self) -> crate::Result<Option<LaunchParameter>> {
+        let mut data = vec![0; MAX_JUMP_PARAMETER_SIZE];
+        let mut hmac = [0; JUMP_HMAC_SIZE];
+        let mut sender = 0;
+        let mut received = false;
+
+        ResultCode(unsafe {
+            ctru_sys::APT_ReceiveDeliverArg(
+                data.as_mut_ptr().cast(),
+                data.len(),
+                hmac.as_mut_ptr().cast(),
+                &mut sender,
+                &mut received,
+            )
+        })?;
+
+        if !received {
+            return Ok(None);
+        }
+
+        // The size of the parameter isn't reported, so the trailing padding is removed.
+        let len = data.iter().rposition(|&b| b != 0).map_or(0, |end| end + 1);
+        data.truncate(len);
+
+        Ok(Some(LaunchParameter { data, hmac, sender }))
+    }
+
+    /// Closes the application and launches another title, passing it a parameter and an HMAC.
+    ///
+    /// The parameter and the HMAC can be read by the launched application via [`Apt::launch_parameter()`] (e.g. to open a specific file,
+    /// or to authenticate the caller). Titles which don't expect them ignore them. Lists of arguments can be encoded via [`encode_args()`].
+    ///
+    /// The jump happens when the application exits: [`Apt::main_loop()`] returns `false` right after this call,
+    /// and the application should clean up and return from `main` as usual.
+    ///
+    /// To launch a title once the application exits by itself, use the [`Chainloader`] instead.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `parameter` is longer than [`MAX_JUMP_PARAMETER_SIZE`], or if the jump couldn't be prepared
+    /// (e.g. if the title isn't installed).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::apt::Apt;
+    /// use ctru::services::fs::MediaType;
+    ///
+    /// let mut apt = Apt::new()?;
+    ///
+    /// apt.jump_to_title(0x0004000000030800, MediaType::Sd, b"open:sdmc:/save.bin", None)?;
+    ///
+    /// while apt.main_loop() {}
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(
+        alias = "APT_PrepareToDoApplicationJump",
+        alias = "APT_DoApplicationJump"
+    )]
+    pub fn jump_to_title(
+        &mut self,
+        title_id: u64,
+        media_type: MediaType,
+        parameter: &[u8],
+        hmac: Option<&[u8; JUMP_HMAC_SIZE]>,
+    ) -> crate::Result<()> {
+        if parameter.len() > MAX_JUMP_PARAMETER_SIZE {
+            return Err(Error::Other(format!(
+                "the jump parameter is {} bytes long, but at most {MAX_JUMP_PARAMETER_SIZE} bytes can be passed",
+                parameter.len()
+            )));
+        }
+
+        let hmac = hmac.unwrap_or(&[0; JUMP_HMAC_SIZE]);
+
+        unsafe {
+            // A flag of 0 launches the title passed as argument, instead of the current one.
+            ResultCode(ctru_sys::APT_PrepareToDoApplicationJump(
+                0,
+                title_id,
+                media_type as u8,
+            ))?;
+            ResultCode(ctru_sys::APT_DoApplicationJump(
+                parameter.as_ptr().cast(),
+                parameter.len(),
+                hmac.as_ptr().cast(),
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Launches a system applet over the application, passing it a parameter.
+    ///
+    /// The application is suspended until the user closes the applet, as when the HOME Menu is opened.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the applet couldn't be launched.
+    #[doc(
+        alias = "aptLaunchSystemApplet",
+        alias = "APT_PrepareToStartSystemApplet"
+    )]
+    pub fn launch_system_applet(
+        &mut self,
+        applet: SystemApplet,
+        parameter: &[u8],
+    ) -> crate::Result<()> {
+        // The applet doesn't write to the buffer, despite the signature of the function.
+        let mut parameter = parameter.to_vec();
+
+        ResultCode(unsafe {
+            ctru_sys::aptLaunchSystemApplet(
+                applet as u32,
+                parameter.as_mut_ptr().cast(),
+                parameter.len(),
+                0,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Opens a web page in the Internet Browser, over the application.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the URL is too long (the browser accepts up to 1023 bytes), if it contains a nul byte,
+    /// or if the browser couldn't be launched.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::apt::Apt;
+    ///
+    /// let mut apt = Apt::new()?;
+    ///
+    /// apt.open_browser("https://www.3dbrew.org")?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_browser(&mut self, url: &str) -> crate::Result<()> {
+        // The URL is passed as a nul-terminated string in a fixed-size buffer.
+        if url.len() >= BROWSER_PARAMETER_SIZE || url.contains('\0') {
+            return Err(Error::Other(format!(
+                "invalid URL for the browser: {url:?}"
+            )));
+        }
+
+        let mut parameter = [0; BROWSER_PARAMETER_SIZE];
+        parameter[..url.len()].copy_from_slice(url.as_bytes());
+
+        self.launch_system_applet(SystemApplet::Browser, &parameter)
+    }
+
+    /// Closes the application and opens the Nintendo eShop, either on its main page or on the page of a title.
+    ///
+    /// As with [`Apt::jump_to_title()`], [`Apt::main_loop()`] returns `false` right after this call,
+    /// and the application should clean up and return from `main` as usual.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the Nintendo eShop isn't available in `region`, or if it couldn't be launched.
+    pub fn open_eshop(&mut self, region: Region, title_id: Option<u64>) -> crate::Result<()> {
+        let eshop_id = match region {
+            Region::Japan => 0x0004_0010_0002_0900,
+            Region::USA => 0x0004_0010_0002_1900,
+            Region::Europe | Region::Australia => 0x0004_0010_0002_2900,
+            Region::Korea => 0x0004_0010_0002_7900,
+            Region::Taiwan => 0x0004_0010_0002_8900,
+            Region::China => {
+                return Err(Error::Other(
+                    "the Nintendo eShop isn't available in China".into(),
+                ))
+            }
+        };
+
+        // The page of a title is selected with a `tid=` query.
+        let parameter = title_id
+            .map(|id| format!("tid={id:016X}\0").into_bytes())
+            .unwrap_or_default();
+
+        self.jump_to_title(eshop_id, MediaType::Nand, &parameter, None)
+    }
+
+    /// Closes the application and opens the System Settings.
+    ///
+    /// As with [`Apt::jump_to_title()`], [`Apt::main_loop()`] returns `false` right after this call,
+    /// and the application should clean up and return from `main` as usual.
+    ///
+    /// # Notes
+    ///
+    /// The System Settings always open on their main page, since the parameters selecting the other pages aren't documented.
+    /// To lead the user to the Internet Settings after a connection error, an error [`PopUp`](crate::applets::error::PopUp)
+    /// with [`PopUp::set_settings_jump()`](crate::applets::error::PopUp::set_settings_jump) can be shown instead.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the System Settings couldn't be launched.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::apt::Apt;
+    /// use ctru::services::cfgu::Cfgu;
+    ///
+    /// let mut apt = Apt::new()?;
+    /// let cfgu = Cfgu::new()?;
+    ///
+    /// apt.open_system_settings(cfgu.region()?)?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_system_settings(&mut self, region: Region) -> crate::Result<()> {
+        let settings_id = match region {
+            Region::Japan => 0x0004_0010_0002_0000,
+            Region::USA => 0x0004_0010_0002_1000,
+            Region::Europe | Region::Australia => 0x0004_0010_0002_2000,
+            Region::China => 0x0004_0010_0002_6000,
+            Region::Korea => 0x0004_0010_0002_7000,
+            Region::Taiwan => 0x0004_0010_0002_8000,
+        };
+
+        self.jump_to_title(settings_id, MediaType::Nand, &[], None)
+    }
+
+    /// Sends the layout of the screen capture used while the application is suspended.
+    ///
+    /// `libctru` sends the layout matching the current configuration of the screens when the HOME Menu is opened.
+    /// This function can be used to send it in advance (e.g. before launching an applet manually, or after changing the framebuffer formats),
+    /// so that the thumbnail and the transitions show the content of the screens instead of a black frame.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the layout couldn't be sent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::apt::{Apt, CaptureInfo};
+    /// use ctru::services::gfx::Gfx;
+    ///
+    /// let mut apt = Apt::new()?;
+    /// let gfx = Gfx::new()?;
+    ///
+    /// apt.set_capture_info(&CaptureInfo::current()?)?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "APT_SendCaptureBufferInfo", alias = "aptSetCaptureInfo")]
+    pub fn set_capture_info(&mut self, info: &CaptureInfo) -> crate::Result<()> {
+        let raw = info.to_raw();
+        ResultCode(unsafe { ctru_sys::APT_SendCaptureBufferInfo(&raw) })?;
+
+        Ok(())
+    }
+
+    /// Disables the HOME button until the returned guard is dropped, which restores the previous setting.
+    ///
+    /// This is useful to protect operations which must not be interrupted, such as saving.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::apt::Apt;
+    ///
+    /// let apt = Apt::new()?;
+    ///
+    /// {
+    ///     let _home = apt.block_home_button();
+    ///     let _sleep = apt.block_sleep();
+    ///
+    ///     // Save the game here.
+    /// }
+    ///
+    /// assert!(apt.is_home_allowed());
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "aptSetHomeAllowed")]
+    pub fn block_home_button(&self) -> HomeButtonBlock<'_> {
+        let previous = self.is_home_allowed();
+        unsafe { ctru_sys::aptSetHomeAllowed(false) };
+
+        HomeButtonBlock {
+            _apt: self,
+            previous,
+        }
+    }
+
+    /// Prevents the console from entering sleep mode until the returned guard is dropped, which restores the previous setting.
+    ///
+    /// See [`Apt::block_home_button()`] for an example.
+    #[doc(alias = "aptSetSleepAllowed")]
+    pub fn block_sleep(&self) -> SleepBlock<'_> {
+        let previous = self.is_sleep_allowed();
+        unsafe { ctru_sys::aptSetSleepAllowed(false) };
+
+        SleepBlock {
+            _apt: self,
+            previous,
+        }
+    }
+}
+
+/// Guard disabling the HOME button while it's alive.
+///
+/// This struct can be created via [`Apt::block_home_button()`].
+#[must_use = "the HOME button is enabled again when the guard is dropped"]
+pub struct HomeButtonBlock<'apt> {
+    _apt: &'apt Apt,
+    previous: bool,
+}
+
+/// Guard preventing the console from entering sleep mode while it's alive.
+///
+/// This struct can be created via [`Apt::block_sleep()`].
+#[must_use = "sleep mode is allowed again when the guard is dropped"]
+pub struct SleepBlock<'apt> {
+    _apt: &'apt Apt,
+    previous: bool,
+}
+
+impl Drop for HomeButtonBlock<'_> {
+    fn drop(&mut self) {
+        unsafe { ctru_sys::aptSetHomeAllowed(self.previous) };
+    }
+}
+
+impl Drop for SleepBlock<'_> {
+    fn drop(&mut self) {
+        unsafe { ctru_sys::aptSetSleepAllowed(self.previous) };
+    }
 }
 
 impl Drop for Apt {
@@ -133,6 +728,168 @@ impl Drop for Apt {
     }
 }
 
+/// Main loop of the application, calling closures when its state changes.
+///
+/// The closures are set up with a builder pattern, and called from [`EventLoop::main_loop()`]:
+///
+/// - [`on_suspend()`](EventLoop::on_suspend) and [`on_restore()`](EventLoop::on_restore) when the application loses and regains control
+///   (e.g. when the HOME Menu is opened and closed).
+/// - [`on_sleep()`](EventLoop::on_sleep) and [`on_wakeup()`](EventLoop::on_wakeup) when the console enters and leaves sleep mode.
+/// - [`on_exit()`](EventLoop::on_exit) when the application is closed from the HOME Menu.
+/// - [`on_home_pressed()`](EventLoop::on_home_pressed) when the HOME button is pressed, before the HOME Menu is opened.
+///
+/// The closures are unregistered, and the previous settings of the [`Apt`] restored, when the event loop is dropped.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::apt::{Apt, EventLoop};
+///
+/// let mut apt = Apt::new()?;
+///
+/// let mut event_loop = EventLoop::new(&mut apt)
+///     .on_sleep(|| println!("Good night!"))
+///     .on_wakeup(|| println!("Good morning!"))
+///     .on_home_pressed(|| {
+///         // Save the game before going to the HOME Menu.
+///         true
+///     });
+///
+/// while event_loop.main_loop() {
+///     // Main program logic should be written here.
+/// #   break;
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "aptHook", alias = "aptMainLoop")]
+pub struct EventLoop<'apt> {
+    apt: &'apt mut Apt,
+    registrations: Vec<Registration>,
+    on_home_pressed: Option<Box<dyn FnMut() -> bool + 'apt>>,
+    home_allowed: bool,
+    sleep_allowed: bool,
+}
+
+impl<'apt> EventLoop<'apt> {
+    /// Creates an event loop without any closure.
+    pub fn new(apt: &'apt mut Apt) -> Self {
+        let home_allowed = apt.is_home_allowed();
+        let sleep_allowed = apt.is_sleep_allowed();
+
+        Self {
+            apt,
+            registrations: Vec::new(),
+            on_home_pressed: None,
+            home_allowed,
+            sleep_allowed,
+        }
+    }
+
+    /// Calls `handler` when the application is about to be suspended (e.g. when the HOME Menu is opened).
+    pub fn on_suspend(self, handler: impl FnMut() + Send + 'static) -> Self {
+        self.on_transition(Transition::Suspend, handler)
+    }
+
+    /// Calls `handler` when the application regains control after being suspended.
+    pub fn on_restore(self, handler: impl FnMut() + Send + 'static) -> Self {
+        self.on_transition(Transition::Restore, handler)
+    }
+
+    /// Calls `handler` when the console is about to enter sleep mode.
+    pub fn on_sleep(self, handler: impl FnMut() + Send + 'static) -> Self {
+        self.on_transition(Transition::Sleep, handler)
+    }
+
+    /// Calls `handler` when the console wakes up from sleep mode.
+    pub fn on_wakeup(self, handler: impl FnMut() + Send + 'static) -> Self {
+        self.on_transition(Transition::WakeUp, handler)
+    }
+
+    /// Calls `handler` when the application is about to be closed from the HOME Menu.
+    ///
+    /// [`EventLoop::main_loop()`] returns `false` right after.
+    pub fn on_exit(self, handler: impl FnMut() + Send + 'static) -> Self {
+        self.on_transition(Transition::Exit, handler)
+    }
+
+    /// Calls `handler` when the HOME button is pressed.
+    ///
+    /// The HOME Menu is opened only if `handler` returns `true`, which gives the application the opportunity to finish
+    /// what it's doing first (e.g. saving), or to refuse altogether.
+    ///
+    /// # Notes
+    ///
+    /// This disables the HOME button (see [`Apt::set_home_allowed()`]) until the event loop is dropped.
+    #[doc(alias = "aptCheckHomePressRejected")]
+    pub fn on_home_pressed(mut self, handler: impl FnMut() -> bool + 'apt) -> Self {
+        self.apt.set_home_allowed(false);
+        self.on_home_pressed = Some(Box::new(handler));
+        self
+    }
+
+    /// Sets if the console is allowed to enter sleep mode while the event loop is active.
+    ///
+    /// See [`Apt::set_sleep_allowed()`] for more information.
+    pub fn sleep_allowed(self, allowed: bool) -> Self {
+        self.apt.set_sleep_allowed(allowed);
+        self
+    }
+
+    /// Returns `true` if the application is running in the foreground as normal, calling the closures of the events
+    /// which happened since the last call.
+    ///
+    /// See [`Apt::main_loop()`] for more information.
+    #[doc(alias = "aptMainLoop")]
+    pub fn main_loop(&mut self) -> bool {
+        if !self.apt.main_loop() {
+            return false;
+        }
+
+        if let Some(handler) = &mut self.on_home_pressed {
+            if unsafe { ctru_sys::aptCheckHomePressRejected() } && handler() {
+                self.apt.jump_to_home_menu();
+
+                // Jumping to the HOME Menu can close the application.
+                return self.apt.main_loop();
+            }
+        }
+
+        true
+    }
+
+    /// Returns the underlying [`Apt`] service.
+    pub fn apt(&mut self) -> &mut Apt {
+        self.apt
+    }
+
+    fn on_transition(
+        mut self,
+        expected: Transition,
+        mut handler: impl FnMut() + Send + 'static,
+    ) -> Self {
+        self.registrations
+            .push(resources::register(move |transition| {
+                if transition == expected {
+                    handler();
+                }
+            }));
+        self
+    }
+}
+
+impl Drop for EventLoop<'_> {
+    fn drop(&mut self) {
+        self.apt.set_home_allowed(self.home_allowed);
+        self.apt.set_sleep_allowed(self.sleep_allowed);
+    }
+}
+
 /// Can launch other applications when the current one exits.
 pub struct Chainloader<'a> {
     _apt: &'a Apt,
@@ -179,3 +936,19 @@ impl<'a> Chainloader<'a> {
         unsafe { ctru_sys::aptSetChainloaderToSelf() }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launch_args() {
+        let parameter = LaunchParameter {
+            data: encode_args(["level.bin", "", "--debug"]),
+            hmac: [0; JUMP_HMAC_SIZE],
+            sender: 0,
+        };
+
+        assert_eq!(parameter.args(), ["level.bin", "", "--debug"]);
+    }
+}
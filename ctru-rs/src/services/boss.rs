@@ -0,0 +1,253 @@
+//! Background Online Storage Service.
+//!
+//! The BOSS service handles SpotPass, the system's background downloading feature.
+//! Data delivered in the background is stored in the application's ExtData as "NsData" containers,
+//! which can be read from the application at any time via [`Boss::ns_data()`].
+#![doc(alias = "spotpass")]
+#![doc(alias = "nsdata")]
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Mutex;
+
+use crate::error::ResultCode;
+use crate::services::ServiceReference;
+
+static BOSS_ACTIVE: Mutex<()> = Mutex::new(());
+
+/// Handle to the BOSS service.
+pub struct Boss {
+    _service_handler: ServiceReference,
+}
+
+/// Information stored in the header of an NsData container.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NsDataHeader {
+    /// Title ID of the program the data was delivered for.
+    pub program_id: u64,
+    /// Application-defined data type.
+    pub data_type: u32,
+    /// Size of the payload (in bytes).
+    pub payload_size: u32,
+    /// ID of the container.
+    pub ns_data_id: u32,
+    /// Application-defined version of the data.
+    pub version: u32,
+}
+
+/// Streaming reader over the payload of an NsData container.
+///
+/// Returned by [`Boss::ns_data()`]. It implements [`Read`] and [`Seek`], so it can be consumed with normal Rust I/O code.
+pub struct NsData<'boss> {
+    header: NsDataHeader,
+    position: u64,
+    _boss: &'boss Boss,
+}
+
+// Header info "types" accepted by `bossGetNsDataHeaderInfo`, with their respective output sizes.
+// See <https://www.3dbrew.org/wiki/BOSSU:GetNsDataHeaderInfo>
+const HEADER_INFO_PROGRAM_ID: (u8, usize) = (0, 8);
+const HEADER_INFO_DATA_TYPE: (u8, usize) = (2, 4);
+const HEADER_INFO_PAYLOAD_SIZE: (u8, usize) = (3, 4);
+const HEADER_INFO_NS_DATA_ID: (u8, usize) = (4, 4);
+const HEADER_INFO_VERSION: (u8, usize) = (5, 4);
+
+impl Boss {
+    /// Initialize a new service handle for the current application.
+    ///
+    /// `enable_tasks` determines whether background tasks should be registered and run for this application.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::boss::Boss;
+    ///
+    /// let boss = Boss::new(false)?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "bossInit")]
+    pub fn new(enable_tasks: bool) -> crate::Result<Self> {
+        let _service_handler = ServiceReference::new(
+            &BOSS_ACTIVE,
+            || {
+                // A program ID of 0 makes libctru use the ID of the running application.
+                ResultCode(unsafe { ctru_sys::bossInit(0, enable_tasks) })?;
+
+                Ok(())
+            },
+            || unsafe {
+                ctru_sys::bossExit();
+            },
+        )?;
+
+        Ok(Self { _service_handler })
+    }
+
+    /// Returns the header of the NsData container with the specified ID.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::boss::Boss;
+    /// let boss = Boss::new(false)?;
+    ///
+    /// let header = boss.ns_data_header(0x1)?;
+    ///
+    /// println!("SpotPass payload of {} bytes", header.payload_size);
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "bossGetNsDataHeaderInfo")]
+    pub fn ns_data_header(&self, ns_data_id: u32) -> crate::Result<NsDataHeader> {
+        let mut program_id = [0; 8];
+        self.header_info(ns_data_id, HEADER_INFO_PROGRAM_ID, &mut program_id)?;
+
+        Ok(NsDataHeader {
+            program_id: u64::from_ne_bytes(program_id),
+            data_type: self.header_info_u32(ns_data_id, HEADER_INFO_DATA_TYPE)?,
+            payload_size: self.header_info_u32(ns_data_id, HEADER_INFO_PAYLOAD_SIZE)?,
+            ns_data_id: self.header_info_u32(ns_data_id, HEADER_INFO_NS_DATA_ID)?,
+            version: self.header_info_u32(ns_data_id, HEADER_INFO_VERSION)?,
+        })
+    }
+
+    /// Open the NsData container with the specified ID for reading.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use std::io::Read;
+    /// use ctru::services::boss::Boss;
+    /// let boss = Boss::new(false)?;
+    ///
+    /// let mut news = String::new();
+    /// boss.ns_data(0x1)?.read_to_string(&mut news)?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ns_data(&self, ns_data_id: u32) -> crate::Result<NsData<'_>> {
+        Ok(NsData {
+            header: self.ns_data_header(ns_data_id)?,
+            position: 0,
+            _boss: self,
+        })
+    }
+
+    /// Delete the NsData container with the specified ID.
+    #[doc(alias = "bossDeleteNsData")]
+    pub fn delete_ns_data(&mut self, ns_data_id: u32) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::bossDeleteNsData(ns_data_id) })?;
+        Ok(())
+    }
+
+    fn header_info(&self, ns_data_id: u32, info: (u8, usize), out: &mut [u8]) -> crate::Result<()> {
+        debug_assert_eq!(info.1, out.len());
+
+        ResultCode(unsafe {
+            ctru_sys::bossGetNsDataHeaderInfo(
+                ns_data_id,
+                info.0,
+                out.as_mut_ptr().cast(),
+                out.len() as u32,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    fn header_info_u32(&self, ns_data_id: u32, info: (u8, usize)) -> crate::Result<u32> {
+        let mut value = [0; 4];
+        self.header_info(ns_data_id, info, &mut value)?;
+
+        Ok(u32::from_ne_bytes(value))
+    }
+}
+
+impl NsData<'_> {
+    /// Returns the header of this NsData container.
+    pub fn header(&self) -> &NsDataHeader {
+        &self.header
+    }
+
+    /// Returns the size of the payload (in bytes).
+    pub fn len(&self) -> u64 {
+        self.header.payload_size.into()
+    }
+
+    /// Returns `true` if the payload is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[doc(alias = "bossReadNsData")]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> crate::Result<u32> {
+        let mut transfer_total = 0;
+        let mut unk_out = 0;
+
+        ResultCode(unsafe {
+            ctru_sys::bossReadNsData(
+                self.header.ns_data_id,
+                offset,
+                buf.as_mut_ptr().cast(),
+                buf.len() as u32,
+                &mut transfer_total,
+                &mut unk_out,
+            )
+        })?;
+
+        Ok(transfer_total)
+    }
+}
+
+impl Read for NsData<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len().saturating_sub(self.position);
+        let size = buf.len().min(remaining as usize);
+
+        if size == 0 {
+            return Ok(0);
+        }
+
+        let read = self
+            .read_at(self.position, &mut buf[..size])
+            .map_err(io::Error::other)?;
+
+        self.position += u64::from(read);
+
+        Ok(read as usize)
+    }
+}
+
+impl Seek for NsData<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        match new_position {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
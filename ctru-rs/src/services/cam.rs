@@ -244,6 +244,77 @@ pub enum Trimming {
     Off,
 }
 
+/// Set of image controls to be applied to a camera at once.
+///
+/// Settings left unspecified are not changed when applied via [`Camera::apply_image_settings()`].
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::cam::{Cam, Camera, Contrast, FlipMode, ImageSettings, WhiteBalance};
+/// let mut cam = Cam::new()?;
+///
+/// let settings = ImageSettings::new()
+///     .exposure(2)
+///     .white_balance(WhiteBalance::Temp5200K)
+///     .contrast(Contrast::High)
+///     .flip(FlipMode::Horizontal);
+///
+/// cam.inner_cam.apply_image_settings(&settings)?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImageSettings {
+    exposure: Option<i8>,
+    white_balance: Option<WhiteBalance>,
+    contrast: Option<Contrast>,
+    flip: Option<FlipMode>,
+    trimming: Option<Trimming>,
+}
+
+impl ImageSettings {
+    /// Creates a new set of image controls, with no setting specified.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the exposure level. See [`Camera::set_exposure()`].
+    pub fn exposure(mut self, exposure: i8) -> Self {
+        self.exposure = Some(exposure);
+        self
+    }
+
+    /// Set the white balance. See [`Camera::set_white_balance()`].
+    pub fn white_balance(mut self, white_balance: WhiteBalance) -> Self {
+        self.white_balance = Some(white_balance);
+        self
+    }
+
+    /// Set the contrast. See [`Camera::set_contrast()`].
+    pub fn contrast(mut self, contrast: Contrast) -> Self {
+        self.contrast = Some(contrast);
+        self
+    }
+
+    /// Set the flip mode of the image. See [`Camera::flip_image()`].
+    pub fn flip(mut self, flip: FlipMode) -> Self {
+        self.flip = Some(flip);
+        self
+    }
+
+    /// Set the trimming configuration. See [`Camera::set_trimming()`].
+    pub fn trimming(mut self, trimming: Trimming) -> Self {
+        self.trimming = Some(trimming);
+        self
+    }
+}
+
 /// Data used by the camera to calibrate image quality for a single camera.
 // TODO: Implement Image quality calibration.
 #[doc(alias = "CAMU_ImageQualityCalibrationData")]
@@ -976,6 +1047,31 @@ pub trait Camera: private::ConfigurableCamera {
         }
     }
 
+    /// Apply a set of [`ImageSettings`] to the camera.
+    ///
+    /// # Errors
+    ///
+    /// This function stops and returns the error of the first setting that fails to be applied.
+    fn apply_image_settings(&mut self, settings: &ImageSettings) -> crate::Result<()> {
+        if let Some(exposure) = settings.exposure {
+            self.set_exposure(exposure)?;
+        }
+        if let Some(white_balance) = settings.white_balance {
+            self.set_white_balance(white_balance)?;
+        }
+        if let Some(contrast) = settings.contrast {
+            self.set_contrast(contrast)?;
+        }
+        if let Some(flip) = settings.flip {
+            self.flip_image(flip)?;
+        }
+        if let Some(trimming) = settings.trimming {
+            self.set_trimming(trimming)?;
+        }
+
+        Ok(())
+    }
+
     /// Request the camera to take a picture and write it in a buffer.
     ///
     /// # Errors
@@ -1017,6 +1113,7 @@ pub trait Camera: private::ConfigurableCamera {
     /// # Ok(())
     /// # }
     /// ```
+    #[doc(alias = "capture_frame", alias = "CAMU_StartCapture")]
     fn take_picture(&mut self, buffer: &mut [u8], timeout: Duration) -> crate::Result<()> {
         // Check whether the provided buffer is big enough to store the image.
         let max_size = self.final_byte_length();
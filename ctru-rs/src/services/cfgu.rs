@@ -5,77 +5,155 @@
 
 use crate::error::ResultCode;
 
-/// Console region.
-#[doc(alias = "CFG_Region")]
+pub use ctru_traits::cfgu::{Language, Region, SystemInfo, SystemModel};
+
+// The enums are defined without depending on `ctru-sys`, to be usable on the host.
+ffi_enum! {
+    extern Region: u8 from ctru_sys::CFG_Region_VALUES {
+        Japan = ctru_sys::CFG_REGION_JPN,
+        USA = ctru_sys::CFG_REGION_USA,
+        Europe = ctru_sys::CFG_REGION_EUR,
+        Australia = ctru_sys::CFG_REGION_AUS,
+        China = ctru_sys::CFG_REGION_CHN,
+        Korea = ctru_sys::CFG_REGION_KOR,
+        Taiwan = ctru_sys::CFG_REGION_TWN,
+    }
+}
+
+ffi_enum! {
+    extern Language: i8 from ctru_sys::CFG_Language_VALUES {
+        Japanese = ctru_sys::CFG_LANGUAGE_JP as i8,
+        English = ctru_sys::CFG_LANGUAGE_EN as i8,
+        French = ctru_sys::CFG_LANGUAGE_FR as i8,
+        German = ctru_sys::CFG_LANGUAGE_DE as i8,
+        Italian = ctru_sys::CFG_LANGUAGE_IT as i8,
+        Spanish = ctru_sys::CFG_LANGUAGE_ES as i8,
+        Korean = ctru_sys::CFG_LANGUAGE_KO as i8,
+        Dutch = ctru_sys::CFG_LANGUAGE_NL as i8,
+        Portuguese = ctru_sys::CFG_LANGUAGE_PT as i8,
+        Russian = ctru_sys::CFG_LANGUAGE_RU as i8,
+        SimplifiedChinese = ctru_sys::CFG_LANGUAGE_ZH as i8,
+        TraditionalChinese = ctru_sys::CFG_LANGUAGE_TW as i8,
+    }
+}
+
+ffi_enum! {
+    extern SystemModel: u8 from ctru_sys::CFG_SystemModel_VALUES {
+        Old3DS = ctru_sys::CFG_MODEL_3DS,
+        Old3DSXL = ctru_sys::CFG_MODEL_3DSXL,
+        New3DS = ctru_sys::CFG_MODEL_N3DS,
+        Old2DS = ctru_sys::CFG_MODEL_2DS,
+        New3DSXL = ctru_sys::CFG_MODEL_N3DSXL,
+        New2DSXL = ctru_sys::CFG_MODEL_N2DSXL,
+    }
+}
+
+/// Birthday of the console's owner, as set in the System Settings.
+///
+/// # Notes
+///
+/// The system doesn't store the year of birth, so it can't be used to compute the user's age.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-#[repr(u8)]
-pub enum Region {
-    /// Japan.
-    Japan = ctru_sys::CFG_REGION_JPN,
-    /// USA.
-    USA = ctru_sys::CFG_REGION_USA,
-    /// Europe.
-    Europe = ctru_sys::CFG_REGION_EUR,
-    /// Australia.
-    Australia = ctru_sys::CFG_REGION_AUS,
-    /// China.
-    China = ctru_sys::CFG_REGION_CHN,
-    /// Korea.
-    Korea = ctru_sys::CFG_REGION_KOR,
-    /// Taiwan.
-    Taiwan = ctru_sys::CFG_REGION_TWN,
+pub struct Birthday {
+    /// Month of the year (1-12).
+    pub month: u8,
+    /// Day of the month (1-31).
+    pub day: u8,
 }
 
-/// Language set for the console's OS.
-#[doc(alias = "CFG_Language")]
+/// Parental Controls settings of the console.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-#[repr(i8)]
-pub enum Language {
-    /// Japanese.
-    Japanese = ctru_sys::CFG_LANGUAGE_JP as i8,
-    /// English.
-    English = ctru_sys::CFG_LANGUAGE_EN as i8,
-    /// French.
-    French = ctru_sys::CFG_LANGUAGE_FR as i8,
-    /// German.
-    German = ctru_sys::CFG_LANGUAGE_DE as i8,
-    /// Italian.
-    Italian = ctru_sys::CFG_LANGUAGE_IT as i8,
-    /// Spanish.
-    Spanish = ctru_sys::CFG_LANGUAGE_ES as i8,
-    /// Korean.
-    Korean = ctru_sys::CFG_LANGUAGE_KO as i8,
-    /// Dutch.
-    Dutch = ctru_sys::CFG_LANGUAGE_NL as i8,
-    /// Portuguese.
-    Portuguese = ctru_sys::CFG_LANGUAGE_PT as i8,
-    /// Russian.
-    Russian = ctru_sys::CFG_LANGUAGE_RU as i8,
-    /// Simplified Chinese.
-    SimplifiedChinese = ctru_sys::CFG_LANGUAGE_ZH as i8,
-    /// Traditional Chinese.
-    TraditionalChinese = ctru_sys::CFG_LANGUAGE_TW as i8,
+pub struct ParentalControls {
+    /// Maximum content rating (expressed as a minimum age) allowed by the parental controls,
+    /// or [`None`] if software isn't restricted by its rating.
+    pub max_rating_age: Option<u8>,
 }
 
-/// Specific model of the console.
-#[doc(alias = "CFG_SystemModel")]
+/// Information about the console's owner relevant to content gating.
+///
+/// This struct can be retrieved via [`Cfgu::user_profile()`] and checked against a [`ContentPolicy`].
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-#[repr(u8)]
-pub enum SystemModel {
-    /// Old Nintendo 3DS.
-    Old3DS = ctru_sys::CFG_MODEL_3DS,
-    /// Old Nintendo 3DS XL.
-    Old3DSXL = ctru_sys::CFG_MODEL_3DSXL,
-    /// New Nintendo 3DS.
-    New3DS = ctru_sys::CFG_MODEL_N3DS,
-    /// Old Nintendo 2DS.
-    Old2DS = ctru_sys::CFG_MODEL_2DS,
-    /// New Nintendo 3DS XL.
-    New3DSXL = ctru_sys::CFG_MODEL_N3DSXL,
-    /// New Nintendo 2DS XL.
-    New2DSXL = ctru_sys::CFG_MODEL_N2DSXL,
+pub struct UserProfile {
+    /// Console region.
+    pub region: Region,
+    /// Birthday of the console's owner.
+    pub birthday: Birthday,
+    /// Parental Controls settings.
+    pub parental_controls: ParentalControls,
+}
+
+/// Policy deciding whether some content can be shown to the console's owner.
+///
+/// This trait is implemented for all closures taking a [`UserProfile`], so custom policies can be easily written inline.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::cfgu::{Cfgu, ContentPolicy, MinimumAge, Region, RegionLock, UserProfile};
+/// let cfgu = Cfgu::new()?;
+/// let profile = cfgu.user_profile()?;
+///
+/// let teen = MinimumAge(13);
+/// let western = RegionLock(&[Region::USA, Region::Europe, Region::Australia]);
+/// let not_on_birthday = |profile: &UserProfile| profile.birthday.month != 4 || profile.birthday.day != 1;
+///
+/// if teen.allows(&profile) && western.allows(&profile) && not_on_birthday.allows(&profile) {
+///     println!("Welcome!");
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub trait ContentPolicy {
+    /// Returns `true` if the content can be shown to the user described by `profile`.
+    fn allows(&self, profile: &UserProfile) -> bool;
 }
 
+/// [`ContentPolicy`] allowing content rated for the specified minimum age.
+///
+/// Content is allowed if the Parental Controls don't restrict software by rating, or if their maximum rating is at least the specified age.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MinimumAge(pub u8);
+
+/// [`ContentPolicy`] allowing content only on consoles from the specified regions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RegionLock<'a>(pub &'a [Region]);
+
+impl<F: Fn(&UserProfile) -> bool> ContentPolicy for F {
+    fn allows(&self, profile: &UserProfile) -> bool {
+        self(profile)
+    }
+}
+
+impl ContentPolicy for MinimumAge {
+    fn allows(&self, profile: &UserProfile) -> bool {
+        match profile.parental_controls.max_rating_age {
+            Some(max_age) => max_age >= self.0,
+            None => true,
+        }
+    }
+}
+
+impl ContentPolicy for RegionLock<'_> {
+    fn allows(&self, profile: &UserProfile) -> bool {
+        self.0.contains(&profile.region)
+    }
+}
+
+// Config blocks read via `CFGU_GetConfigInfoBlk2`.
+// See <https://www.3dbrew.org/wiki/Config_Savegame>
+const BIRTHDAY_BLOCK_ID: u32 = 0x000A_0001;
+const PARENTAL_CONTROLS_BLOCK_ID: u32 = 0x000C_0000;
+const PARENTAL_CONTROLS_BLOCK_SIZE: usize = 0xC0;
+// Bit of the restrictions bitmask enabling the rating-based restrictions.
+const PARENTAL_RESTRICT_BY_RATING: u32 = 1 << 0;
+// Offset of the maximum allowed rating age in the Parental Controls block.
+const PARENTAL_RATING_AGE_OFFSET: usize = 0x0A;
+
 /// Handle to the System Configuration service.
 pub struct Cfgu(());
 
@@ -232,73 +310,76 @@ impl Cfgu {
         ResultCode(unsafe { ctru_sys::CFGU_GetModelNintendo2DS(&mut is_2ds_family) })?;
         Ok(is_2ds_family == 0)
     }
-}
 
-impl Drop for Cfgu {
-    #[doc(alias = "cfguExit")]
-    fn drop(&mut self) {
-        unsafe {
-            ctru_sys::cfguExit();
-        }
+    /// Returns the birthday of the console's owner.
+    #[doc(alias = "CFGU_GetConfigInfoBlk2")]
+    pub fn birthday(&self) -> crate::Result<Birthday> {
+        let mut birthday = [0; 2];
+        self.config_block(BIRTHDAY_BLOCK_ID, &mut birthday)?;
+
+        Ok(Birthday {
+            month: birthday[0],
+            day: birthday[1],
+        })
     }
-}
 
-from_impl!(Region, u8);
-from_impl!(Language, i8);
-from_impl!(SystemModel, u8);
-
-impl TryFrom<u8> for Region {
-    type Error = ();
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            ctru_sys::CFG_REGION_JPN => Ok(Region::Japan),
-            ctru_sys::CFG_REGION_USA => Ok(Region::USA),
-            ctru_sys::CFG_REGION_EUR => Ok(Region::Europe),
-            ctru_sys::CFG_REGION_AUS => Ok(Region::Australia),
-            ctru_sys::CFG_REGION_CHN => Ok(Region::China),
-            ctru_sys::CFG_REGION_KOR => Ok(Region::Korea),
-            ctru_sys::CFG_REGION_TWN => Ok(Region::Taiwan),
-            _ => Err(()),
-        }
+    /// Returns the Parental Controls settings of the console.
+    #[doc(alias = "CFGU_GetConfigInfoBlk2")]
+    pub fn parental_controls(&self) -> crate::Result<ParentalControls> {
+        let mut block = [0; PARENTAL_CONTROLS_BLOCK_SIZE];
+        self.config_block(PARENTAL_CONTROLS_BLOCK_ID, &mut block)?;
+
+        let restrictions = u32::from_le_bytes(block[0..4].try_into().unwrap());
+        let max_rating_age = (restrictions & PARENTAL_RESTRICT_BY_RATING != 0)
+            .then_some(block[PARENTAL_RATING_AGE_OFFSET]);
+
+        Ok(ParentalControls { max_rating_age })
+    }
+
+    /// Returns the information needed to check a [`ContentPolicy`], combining the console's region, the owner's birthday
+    /// and the Parental Controls settings.
+    pub fn user_profile(&self) -> crate::Result<UserProfile> {
+        Ok(UserProfile {
+            region: self.region()?,
+            birthday: self.birthday()?,
+            parental_controls: self.parental_controls()?,
+        })
+    }
+
+    fn config_block(&self, block_id: u32, out: &mut [u8]) -> crate::Result<()> {
+        ResultCode(unsafe {
+            ctru_sys::CFGU_GetConfigInfoBlk2(out.len() as u32, block_id, out.as_mut_ptr().cast())
+        })?;
+
+        Ok(())
     }
 }
 
-impl TryFrom<i8> for Language {
-    type Error = ();
-
-    fn try_from(value: i8) -> Result<Self, Self::Error> {
-        let value = value as u8;
-        match value {
-            ctru_sys::CFG_LANGUAGE_JP => Ok(Language::Japanese),
-            ctru_sys::CFG_LANGUAGE_EN => Ok(Language::English),
-            ctru_sys::CFG_LANGUAGE_FR => Ok(Language::French),
-            ctru_sys::CFG_LANGUAGE_DE => Ok(Language::German),
-            ctru_sys::CFG_LANGUAGE_IT => Ok(Language::Italian),
-            ctru_sys::CFG_LANGUAGE_ES => Ok(Language::Spanish),
-            ctru_sys::CFG_LANGUAGE_ZH => Ok(Language::SimplifiedChinese),
-            ctru_sys::CFG_LANGUAGE_KO => Ok(Language::Korean),
-            ctru_sys::CFG_LANGUAGE_NL => Ok(Language::Dutch),
-            ctru_sys::CFG_LANGUAGE_PT => Ok(Language::Portuguese),
-            ctru_sys::CFG_LANGUAGE_RU => Ok(Language::Russian),
-            ctru_sys::CFG_LANGUAGE_TW => Ok(Language::TraditionalChinese),
-            _ => Err(()),
-        }
+impl SystemInfo for Cfgu {
+    type Error = crate::Error;
+
+    fn region(&self) -> crate::Result<Region> {
+        Cfgu::region(self)
+    }
+
+    fn model(&self) -> crate::Result<SystemModel> {
+        Cfgu::model(self)
+    }
+
+    fn language(&self) -> crate::Result<Language> {
+        Cfgu::language(self)
+    }
+
+    fn is_2ds_family(&self) -> crate::Result<bool> {
+        Cfgu::is_2ds_family(self)
     }
 }
 
-impl TryFrom<u8> for SystemModel {
-    type Error = ();
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            ctru_sys::CFG_MODEL_3DS => Ok(SystemModel::Old3DS),
-            ctru_sys::CFG_MODEL_3DSXL => Ok(SystemModel::Old3DSXL),
-            ctru_sys::CFG_MODEL_N3DS => Ok(SystemModel::New3DS),
-            ctru_sys::CFG_MODEL_2DS => Ok(SystemModel::Old2DS),
-            ctru_sys::CFG_MODEL_N3DSXL => Ok(SystemModel::New3DSXL),
-            ctru_sys::CFG_MODEL_N2DSXL => Ok(SystemModel::New2DSXL),
-            _ => Err(()),
+impl Drop for Cfgu {
+    #[doc(alias = "cfguExit")]
+    fn drop(&mut self) {
+        unsafe {
+            ctru_sys::cfguExit();
         }
     }
 }
@@ -0,0 +1,543 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::{ArchiveID, Attribute, Fs, FsPath, Open};
+use crate::error::ResultCode;
+
+/// Archive opened via the FS service, containing files and directories.
+///
+/// Archives give access to the storages which aren't mounted as standard library paths (e.g. system save data or extra data),
+/// without going through the raw [`ctru_sys`] functions. The archive is closed when dropped.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::io::{Read, Write};
+///
+/// use ctru::services::fs::{Archive, ArchiveID, File, FsPath};
+///
+/// let sdmc = Archive::open(ArchiveID::Sdmc, &FsPath::empty())?;
+/// let path = FsPath::try_from("/ctru-rs-archive-example.txt")?;
+///
+/// File::create(&sdmc, &path)?.write_all(b"Hello!")?;
+///
+/// let mut contents = String::new();
+/// File::open(&sdmc, &path)?.read_to_string(&mut contents)?;
+/// assert_eq!(contents, "Hello!");
+///
+/// sdmc.remove_file(&path)?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "FS_Archive")]
+#[derive(Debug)]
+pub struct Archive {
+    handle: ctru_sys::FS_Archive,
+    id: ArchiveID,
+}
+
+/// File inside of an [`Archive`], closed when dropped.
+///
+/// The file keeps track of its own cursor, used by the [`Read`], [`Write`] and [`Seek`] implementations.
+#[derive(Debug)]
+pub struct File<'a> {
+    handle: ctru_sys::Handle,
+    position: u64,
+    _archive: &'a Archive,
+}
+
+/// Options to open a [`File`], like [`std::fs::OpenOptions`].
+#[derive(Clone, Debug, Default)]
+pub struct OpenOptions {
+    flags: Open,
+    truncate: bool,
+}
+
+/// Iterator over the entries of a directory inside of an [`Archive`].
+///
+/// This struct can be created via [`Archive::read_dir()`].
+#[derive(Debug)]
+pub struct Dir<'a> {
+    handle: ctru_sys::Handle,
+    _archive: &'a Archive,
+}
+
+/// Entry of a directory.
+#[doc(alias = "FS_DirectoryEntry")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    name: String,
+    attributes: Attribute,
+    size: u64,
+}
+
+impl Archive {
+    /// Opens an archive.
+    ///
+    /// Most archives are opened with an [empty path](FsPath::empty), while others require a binary path whose meaning depends on the archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive doesn't exist, or if the application isn't allowed to access it.
+    #[doc(alias = "FSUSER_OpenArchive")]
+    pub fn open(id: ArchiveID, path: &FsPath) -> crate::Result<Self> {
+        let mut handle = 0;
+
+        ResultCode(unsafe { ctru_sys::FSUSER_OpenArchive(&mut handle, id.into(), path.as_raw()) })?;
+
+        Ok(Self { handle, id })
+    }
+
+    /// Returns the ID of the archive.
+    pub fn id(&self) -> ArchiveID {
+        self.id
+    }
+
+    /// Opens a directory to list its entries.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory doesn't exist.
+    #[doc(alias = "FSUSER_OpenDirectory")]
+    pub fn read_dir(&self, path: &FsPath) -> crate::Result<Dir<'_>> {
+        let mut handle = 0;
+
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_OpenDirectory(&mut handle, self.handle, path.as_raw())
+        })?;
+
+        Ok(Dir {
+            handle,
+            _archive: self,
+        })
+    }
+
+    /// Creates an empty directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory already exists, or if its parent doesn't.
+    #[doc(alias = "FSUSER_CreateDirectory")]
+    pub fn create_dir(&self, path: &FsPath) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::FSUSER_CreateDirectory(self.handle, path.as_raw(), 0) })?;
+
+        Ok(())
+    }
+
+    /// Creates a file of `size` bytes, filled with zeros.
+    ///
+    /// Some archives (e.g. extra data) can't grow files after they are created, so their size must be known beforehand.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file already exists, or if there isn't enough space left in the archive.
+    #[doc(alias = "FSUSER_CreateFile")]
+    pub fn create_file(&self, path: &FsPath, size: u64) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::FSUSER_CreateFile(self.handle, path.as_raw(), 0, size) })?;
+
+        Ok(())
+    }
+
+    /// Deletes a file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file doesn't exist, or if it's open.
+    #[doc(alias = "FSUSER_DeleteFile")]
+    pub fn remove_file(&self, path: &FsPath) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::FSUSER_DeleteFile(self.handle, path.as_raw()) })?;
+
+        Ok(())
+    }
+
+    /// Deletes an empty directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory doesn't exist or isn't empty.
+    #[doc(alias = "FSUSER_DeleteDirectory")]
+    pub fn remove_dir(&self, path: &FsPath) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::FSUSER_DeleteDirectory(self.handle, path.as_raw()) })?;
+
+        Ok(())
+    }
+
+    /// Deletes a directory and all of its contents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory doesn't exist.
+    #[doc(alias = "FSUSER_DeleteDirectoryRecursively")]
+    pub fn remove_dir_all(&self, path: &FsPath) -> crate::Result<()> {
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_DeleteDirectoryRecursively(self.handle, path.as_raw())
+        })?;
+
+        Ok(())
+    }
+
+    /// Renames a file inside of the archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file doesn't exist, or if the destination already exists.
+    #[doc(alias = "FSUSER_RenameFile")]
+    pub fn rename_file(&self, from: &FsPath, to: &FsPath) -> crate::Result<()> {
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_RenameFile(self.handle, from.as_raw(), self.handle, to.as_raw())
+        })?;
+
+        Ok(())
+    }
+
+    /// Renames a directory inside of the archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory doesn't exist, or if the destination already exists.
+    #[doc(alias = "FSUSER_RenameDirectory")]
+    pub fn rename_dir(&self, from: &FsPath, to: &FsPath) -> crate::Result<()> {
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_RenameDirectory(self.handle, from.as_raw(), self.handle, to.as_raw())
+        })?;
+
+        Ok(())
+    }
+
+    /// Returns the raw handle of the archive, to be used with the [`ctru_sys`] functions not wrapped by this crate.
+    pub fn as_raw(&self) -> ctru_sys::FS_Archive {
+        self.handle
+    }
+}
+
+// Paths are relative to the root of the archive (e.g. `/save.bin`).
+impl Fs for Archive {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        File::open(self, &fs_path(path)?)
+            .map_err(io::Error::other)?
+            .read_to_end(&mut contents)?;
+
+        Ok(contents)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        File::create(self, &fs_path(path)?)
+            .map_err(io::Error::other)?
+            .write_all(contents)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        Archive::remove_file(self, &fs_path(path)?).map_err(io::Error::other)
+    }
+}
+
+impl Drop for Archive {
+    #[doc(alias = "FSUSER_CloseArchive")]
+    fn drop(&mut self) {
+        let _ = unsafe { ctru_sys::FSUSER_CloseArchive(self.handle) };
+    }
+}
+
+impl<'a> File<'a> {
+    /// Opens a file in read-only mode, like [`std::fs::File::open()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file doesn't exist.
+    pub fn open(archive: &'a Archive, path: &FsPath) -> crate::Result<Self> {
+        OpenOptions::new().read(true).open(archive, path)
+    }
+
+    /// Opens a file in write-only mode, creating or truncating it, like [`std::fs::File::create()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be created.
+    pub fn create(archive: &'a Archive, path: &FsPath) -> crate::Result<Self> {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(archive, path)
+    }
+
+    /// Returns the size of the file (in bytes).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the size couldn't be read.
+    #[doc(alias = "FSFILE_GetSize")]
+    pub fn len(&self) -> crate::Result<u64> {
+        let mut size = 0;
+        ResultCode(unsafe { ctru_sys::FSFILE_GetSize(self.handle, &mut size) })?;
+
+        Ok(size)
+    }
+
+    /// Returns `true` if the file is empty.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the size couldn't be read.
+    pub fn is_empty(&self) -> crate::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Truncates or extends the file to `size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive doesn't allow changing the size of its files (e.g. extra data).
+    #[doc(alias = "FSFILE_SetSize")]
+    pub fn set_len(&mut self, size: u64) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::FSFILE_SetSize(self.handle, size) })?;
+
+        Ok(())
+    }
+
+    /// Flushes the data written to the file to the storage.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the data couldn't be written.
+    #[doc(alias = "FSFILE_Flush")]
+    pub fn sync_all(&self) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::FSFILE_Flush(self.handle) })?;
+
+        Ok(())
+    }
+
+    /// Returns the raw handle of the file, to be used with the [`ctru_sys`] functions not wrapped by this crate.
+    pub fn as_raw(&self) -> ctru_sys::Handle {
+        self.handle
+    }
+}
+
+impl Read for File<'_> {
+    #[doc(alias = "FSFILE_Read")]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+
+        check(unsafe {
+            ctru_sys::FSFILE_Read(
+                self.handle,
+                &mut read,
+                self.position,
+                buf.as_mut_ptr().cast(),
+                buf.len() as u32,
+            )
+        })
+        .map_err(io::Error::other)?;
+
+        self.position += u64::from(read);
+
+        Ok(read as usize)
+    }
+}
+
+impl Write for File<'_> {
+    #[doc(alias = "FSFILE_Write")]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+
+        check(unsafe {
+            ctru_sys::FSFILE_Write(
+                self.handle,
+                &mut written,
+                self.position,
+                buf.as_ptr().cast(),
+                buf.len() as u32,
+                0,
+            )
+        })
+        .map_err(io::Error::other)?;
+
+        self.position += u64::from(written);
+
+        Ok(written as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.sync_all().map_err(io::Error::other)
+    }
+}
+
+impl Seek for File<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::Current(offset) => (self.position, offset),
+            SeekFrom::End(offset) => (self.len().map_err(io::Error::other)?, offset),
+        };
+
+        self.position = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        Ok(self.position)
+    }
+}
+
+impl Drop for File<'_> {
+    #[doc(alias = "FSFILE_Close")]
+    fn drop(&mut self) {
+        let _ = unsafe { ctru_sys::FSFILE_Close(self.handle) };
+    }
+}
+
+impl OpenOptions {
+    /// Creates options with all flags disabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the option for read access.
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.flags.set(Open::FS_OPEN_READ, read);
+        self
+    }
+
+    /// Sets the option for write access.
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.flags.set(Open::FS_OPEN_WRITE, write);
+        self
+    }
+
+    /// Sets the option to create the file if it doesn't exist.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.flags.set(Open::FS_OPEN_CREATE, create);
+        self
+    }
+
+    /// Sets the option to truncate the file to 0 bytes when it's opened.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Opens a file inside of `archive` with these options.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be opened.
+    #[doc(alias = "FSUSER_OpenFile")]
+    pub fn open<'a>(&self, archive: &'a Archive, path: &FsPath) -> crate::Result<File<'a>> {
+        let mut handle = 0;
+
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_OpenFile(
+                &mut handle,
+                archive.handle,
+                path.as_raw(),
+                self.flags.bits().into(),
+                0,
+            )
+        })?;
+
+        let mut file = File {
+            handle,
+            position: 0,
+            _archive: archive,
+        };
+
+        if self.truncate {
+            file.set_len(0)?;
+        }
+
+        Ok(file)
+    }
+}
+
+impl Iterator for Dir<'_> {
+    type Item = crate::Result<DirEntry>;
+
+    #[doc(alias = "FSDIR_Read")]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut entry: ctru_sys::FS_DirectoryEntry = unsafe { std::mem::zeroed() };
+        let mut read = 0;
+
+        if let Err(e) =
+            check(unsafe { ctru_sys::FSDIR_Read(self.handle, &mut read, 1, &mut entry) })
+        {
+            return Some(Err(e));
+        }
+
+        if read == 0 {
+            return None;
+        }
+
+        let len = entry
+            .name
+            .iter()
+            .position(|&unit| unit == 0)
+            .unwrap_or(entry.name.len());
+
+        Some(Ok(DirEntry {
+            name: String::from_utf16_lossy(&entry.name[..len]),
+            attributes: Attribute::from_bits_truncate(entry.attributes),
+            size: entry.fileSize,
+        }))
+    }
+}
+
+impl Drop for Dir<'_> {
+    #[doc(alias = "FSDIR_Close")]
+    fn drop(&mut self) {
+        let _ = unsafe { ctru_sys::FSDIR_Close(self.handle) };
+    }
+}
+
+impl DirEntry {
+    /// Returns the name of the entry (without the path of its directory).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true` if the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.attributes.contains(Attribute::FS_ATTRIBUTE_DIRECTORY)
+    }
+
+    /// Returns `true` if the entry is hidden.
+    pub fn is_hidden(&self) -> bool {
+        self.attributes.contains(Attribute::FS_ATTRIBUTE_HIDDEN)
+    }
+
+    /// Returns `true` if the entry is read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.attributes.contains(Attribute::FS_ATTRIBUTE_READ_ONLY)
+    }
+
+    /// Returns the size of the file (in bytes), or 0 for directories.
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns `true` if the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+// Converts the result of a call to the FS service, to handle it in functions which don't return a `crate::Result`.
+fn check(result: ctru_sys::Result) -> crate::Result<()> {
+    ResultCode(result)?;
+
+    Ok(())
+}
+
+fn fs_path(path: &Path) -> io::Result<FsPath> {
+    FsPath::try_from(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
@@ -0,0 +1,177 @@
+//! Extra data archives.
+//!
+//! Extra data (ExtData) is storage kept outside of the save data of a title, usually on the SD card.
+//! Titles use it for downloadable data or large caches, and SpotPass (BOSS) tasks store the data they download in it.
+//! Some system features (e.g. the Mii Maker or the StreetPass data) use shared extra data archives stored on the NAND instead.
+//!
+//! [`ExtData`] identifies an archive, and can open it as an [`Archive`], create it or delete it.
+#![doc(alias = "extdata")]
+#![doc(alias = "SpotPass")]
+
+use super::{Archive, ArchiveID, FsPath, MediaType};
+use crate::error::ResultCode;
+
+// High word of the IDs of the shared extra data archives.
+const SHARED_HIGH_ID: u32 = 0x0004_8000;
+
+/// Extra data archive, identified by its media type and ID.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::fs::extdata::ExtData;
+/// use ctru::services::fs::{FsPath, MediaType};
+///
+/// let extdata = ExtData::title(MediaType::Sd, 0x0000_055D);
+///
+/// for entry in extdata.open()?.read_dir(&FsPath::try_from("/")?)? {
+///     println!("{}", entry?.name());
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "FS_ExtSaveDataInfo")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExtData {
+    media_type: MediaType,
+    id: u64,
+}
+
+impl ExtData {
+    /// Returns the extra data archive of a title.
+    ///
+    /// The ID of the archive is usually the unique ID of the title (bits 8 to 27 of the title ID), but titles may use any other.
+    pub fn title(media_type: MediaType, id: u64) -> Self {
+        Self { media_type, id }
+    }
+
+    /// Returns a shared extra data archive, stored on the NAND.
+    ///
+    /// `id` is the low word of the ID of the archive (e.g. `0xF000000B` for the StreetPass and Play Coin data).
+    pub fn shared(id: u32) -> Self {
+        Self {
+            media_type: MediaType::Nand,
+            id: (u64::from(SHARED_HIGH_ID) << 32) | u64::from(id),
+        }
+    }
+
+    /// Returns the media type the archive is stored on.
+    pub fn media_type(&self) -> MediaType {
+        self.media_type
+    }
+
+    /// Returns the full ID of the archive.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns `true` if the archive is a shared extra data archive.
+    pub fn is_shared(&self) -> bool {
+        (self.id >> 32) as u32 == SHARED_HIGH_ID
+    }
+
+    /// Returns the path used to open the archive.
+    pub fn path(&self) -> FsPath {
+        FsPath::from_words(&[
+            self.media_type as u32,
+            self.id as u32,
+            (self.id >> 32) as u32,
+        ])
+    }
+
+    /// Opens the archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive doesn't exist, or if the application isn't allowed to access it
+    /// (titles can only open the archives listed in their exheader, unless they run with elevated permissions).
+    pub fn open(&self) -> crate::Result<Archive> {
+        let archive = if self.is_shared() {
+            ArchiveID::SharedExtdata
+        } else {
+            ArchiveID::Extdata
+        };
+
+        Archive::open(archive, &self.path())
+    }
+
+    /// Opens the archive containing the data downloaded by the SpotPass tasks of the title.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive doesn't exist, or if the application isn't allowed to access it.
+    pub fn open_boss(&self) -> crate::Result<Archive> {
+        Archive::open(ArchiveID::BossExtdata, &self.path())
+    }
+
+    /// Creates the archive.
+    ///
+    /// `max_directories` and `max_files` limit the amount of entries in the archive, since they can't grow after the creation.
+    /// `smdh` is the icon and title information shown in the data management of the System Settings (usually the SMDH of the title).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive already exists, or if it couldn't be created.
+    #[doc(alias = "FSUSER_CreateExtSaveData")]
+    pub fn create(&self, max_directories: u32, max_files: u32, smdh: &[u8]) -> crate::Result<()> {
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_CreateExtSaveData(
+                self.as_raw(),
+                max_directories,
+                max_files,
+                u64::MAX,
+                smdh.len() as u32,
+                smdh.as_ptr().cast_mut(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Deletes the archive and all of its contents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive doesn't exist, or if it's open.
+    #[doc(alias = "FSUSER_DeleteExtSaveData")]
+    pub fn delete(&self) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::FSUSER_DeleteExtSaveData(self.as_raw()) })?;
+
+        Ok(())
+    }
+
+    fn as_raw(&self) -> ctru_sys::FS_ExtSaveDataInfo {
+        let mut info: ctru_sys::FS_ExtSaveDataInfo = unsafe { std::mem::zeroed() };
+        info.set_mediaType(self.media_type.into());
+        info.saveId = self.id;
+
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_paths() {
+        let shared = ExtData::shared(0xF000_000B);
+        assert!(shared.is_shared());
+        assert_eq!(
+            shared.path(),
+            FsPath::from_words(&[MediaType::Nand as u32, 0xF000_000B, 0x0004_8000])
+        );
+
+        let title = ExtData::title(MediaType::Sd, 0x055D);
+        assert!(!title.is_shared());
+        assert_eq!(
+            title.path(),
+            FsPath::from_words(&[MediaType::Sd as u32, 0x055D, 0])
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use std::time::{Duration, Instant};
+
+use super::MediaType;
+use crate::error::{Error, ResultCode};
+
+// Interval between the checks of the card slot in `GameCardMonitor::wait()`.
+const CARD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Capacity and free space of a storage medium.
+///
+/// This struct can be retrieved via [`media_info()`].
+#[doc(alias = "FS_ArchiveResource")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MediaInfo {
+    /// Size of a sector (in bytes).
+    pub sector_size: u32,
+    /// Size of a cluster, the allocation unit of files (in bytes).
+    pub cluster_size: u32,
+    /// Total number of clusters.
+    pub total_clusters: u32,
+    /// Number of free clusters.
+    pub free_clusters: u32,
+}
+
+/// Change in the state of the game card slot, reported by [`GameCardMonitor`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameCardEvent {
+    /// A game card was inserted.
+    Inserted,
+    /// The game card was removed.
+    Removed,
+}
+
+/// Detects insertions and removals of game cards.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::time::Duration;
+///
+/// use ctru::services::fs::{GameCardEvent, GameCardMonitor};
+///
+/// let mut monitor = GameCardMonitor::new()?;
+///
+/// if !GameCardMonitor::is_inserted()? {
+///     println!("Insert a game card...");
+///
+///     while monitor.wait(Duration::from_secs(1))? != Some(GameCardEvent::Inserted) {}
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct GameCardMonitor {
+    inserted: bool,
+}
+
+/// Returns the capacity and free space of the SD card or of the internal memory.
+///
+/// # Errors
+///
+/// This function will return an error for [`MediaType::GameCard`], which isn't a writable storage, or if the information couldn't be read
+/// (e.g. no SD card is inserted).
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::fs::{self, MediaType};
+///
+/// let sd = fs::media_info(MediaType::Sd)?;
+/// println!("{} MiB free", sd.free_bytes() / 1024 / 1024);
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "FSUSER_GetArchiveResource")]
+pub fn media_info(media_type: MediaType) -> crate::Result<MediaInfo> {
+    let system_media_type = match media_type {
+        MediaType::Nand => ctru_sys::SYSTEM_MEDIATYPE_CTR_NAND,
+        MediaType::Sd => ctru_sys::SYSTEM_MEDIATYPE_SD,
+        MediaType::GameCard => {
+            return Err(Error::Other(
+                "the free space of game cards can't be queried".into(),
+            ))
+        }
+    };
+
+    let mut resource = ctru_sys::FS_ArchiveResource::default();
+    ResultCode(unsafe { ctru_sys::FSUSER_GetArchiveResource(&mut resource, system_media_type) })?;
+
+    Ok(MediaInfo {
+        sector_size: resource.sectorSize,
+        cluster_size: resource.clusterSize,
+        total_clusters: resource.totalClusters,
+        free_clusters: resource.freeClusters,
+    })
+}
+
+impl MediaInfo {
+    /// Returns the total capacity of the medium (in bytes).
+    pub fn total_bytes(&self) -> u64 {
+        u64::from(self.total_clusters) * u64::from(self.cluster_size)
+    }
+
+    /// Returns the free space of the medium (in bytes).
+    pub fn free_bytes(&self) -> u64 {
+        u64::from(self.free_clusters) * u64::from(self.cluster_size)
+    }
+}
+
+impl GameCardMonitor {
+    /// Creates a new monitor, reading the current state of the card slot.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state of the card slot couldn't be read.
+    pub fn new() -> crate::Result<Self> {
+        Ok(Self {
+            inserted: Self::is_inserted()?,
+        })
+    }
+
+    /// Returns `true` if a game card is inserted.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state of the card slot couldn't be read.
+    #[doc(alias = "FSUSER_CardSlotIsInserted")]
+    pub fn is_inserted() -> crate::Result<bool> {
+        let mut inserted = false;
+        ResultCode(unsafe { ctru_sys::FSUSER_CardSlotIsInserted(&mut inserted) })?;
+
+        Ok(inserted)
+    }
+
+    /// Checks if a game card was inserted or removed since the last call.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state of the card slot couldn't be read.
+    pub fn poll(&mut self) -> crate::Result<Option<GameCardEvent>> {
+        let inserted = Self::is_inserted()?;
+
+        if inserted == self.inserted {
+            return Ok(None);
+        }
+
+        self.inserted = inserted;
+
+        Ok(Some(if inserted {
+            GameCardEvent::Inserted
+        } else {
+            GameCardEvent::Removed
+        }))
+    }
+
+    /// Blocks until a game card is inserted or removed, or until `timeout` expires (in which case [`None`] is returned).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state of the card slot couldn't be read.
+    pub fn wait(&mut self, timeout: Duration) -> crate::Result<Option<GameCardEvent>> {
+        // `None` if the timeout is too long to be represented, in which case there's no deadline.
+        let deadline = Instant::now().checked_add(timeout);
+
+        loop {
+            if let Some(event) = self.poll()? {
+                return Ok(Some(event));
+            }
+
+            let now = Instant::now();
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                return Ok(None);
+            }
+
+            let remaining = deadline.map_or(Duration::MAX, |deadline| deadline - now);
+            std::thread::sleep(CARD_POLL_INTERVAL.min(remaining));
+        }
+    }
+}
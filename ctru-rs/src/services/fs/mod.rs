@@ -0,0 +1,393 @@
+//! FileSystem service.
+//!
+//! This module contains datatypes to easily operate with unsafe [`ctru_sys`] code regarding the file-system functionality.
+//!
+//! Paths used by the FS service are represented by [`FsPath`], which takes care of the UTF-16 encoding required by the system,
+//! and by [`QualifiedPath`] when the archive containing the file is needed too.
+//! Archives which aren't mounted as standard library paths (e.g. system save data or extra data) can be accessed through [`Archive`],
+//! with the [`File`] and [`Dir`] types to read and write their contents.
+//! Logic which only reads and writes whole files can be made generic over [`Fs`], which is implemented by [`Archive`] and [`StdFs`].
+//! Save data and extra data archives are handled by the [`savedata`] and [`extdata`] modules.
+//! The free space of the storages is returned by [`media_info()`], and game card insertions are detected by [`GameCardMonitor`].
+//! Removal of the SD card while the application is running is handled by the [`sdmc`] module.
+#![doc(alias = "filesystem")]
+
+mod archive;
+pub mod extdata;
+mod media;
+pub mod savedata;
+pub mod sdmc;
+pub mod secure_value;
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use bitflags::bitflags;
+
+pub use archive::{Archive, Dir, DirEntry, File, OpenOptions};
+pub use ctru_traits::fs::{Fs, StdFs};
+pub use media::{media_info, GameCardEvent, GameCardMonitor, MediaInfo};
+
+bitflags! {
+    #[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+    struct Open: u8 {
+        const FS_OPEN_READ   = ctru_sys::FS_OPEN_READ;
+        const FS_OPEN_WRITE  = ctru_sys::FS_OPEN_WRITE;
+        const FS_OPEN_CREATE = ctru_sys::FS_OPEN_CREATE;
+    }
+
+    #[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+    struct Write: u16 {
+        const FS_WRITE_FLUSH       = ctru_sys::FS_WRITE_FLUSH;
+        const FS_WRITE_UPDATE_TIME = ctru_sys::FS_WRITE_UPDATE_TIME;
+    }
+
+    #[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+    struct Attribute: u32 {
+        const FS_ATTRIBUTE_DIRECTORY = ctru_sys::FS_ATTRIBUTE_DIRECTORY;
+        const FS_ATTRIBUTE_HIDDEN    = ctru_sys::FS_ATTRIBUTE_HIDDEN;
+        const FS_ATTRIBUTE_ARCHIVE   = ctru_sys::FS_ATTRIBUTE_ARCHIVE;
+        const FS_ATTRIBUTE_READ_ONLY = ctru_sys::FS_ATTRIBUTE_READ_ONLY;
+    }
+}
+
+/// Media type used for storage.
+#[doc(alias = "FS_MediaType")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MediaType {
+    /// Internal NAND memory.
+    Nand = ctru_sys::MEDIATYPE_NAND,
+    /// External SD card.
+    Sd = ctru_sys::MEDIATYPE_SD,
+    /// Game Cartridge.
+    GameCard = ctru_sys::MEDIATYPE_GAME_CARD,
+}
+
+/// Kind of file path.
+#[doc(alias = "FS_PathType")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PathType {
+    /// Invalid path.
+    Invalid = ctru_sys::PATH_INVALID,
+    /// Empty path.
+    Empty = ctru_sys::PATH_EMPTY,
+    /// Binary path.
+    ///
+    /// Its meaning differs depending on the Archive it is used on.
+    Binary = ctru_sys::PATH_BINARY,
+    /// ASCII path.
+    ASCII = ctru_sys::PATH_ASCII,
+    /// UTF-16 path.
+    UTF16 = ctru_sys::PATH_UTF16,
+}
+
+/// Error returned when converting a path for the FS service.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The path contains a NUL character, which would truncate it.
+    NulInPath,
+    /// The path isn't valid UTF-8.
+    InvalidUtf8,
+    /// The path isn't prefixed by the name of a known archive (e.g. `sdmc:`).
+    UnknownArchive(String),
+}
+
+/// Path to a file, directory or archive, in the format expected by the FS service.
+///
+/// Text paths are encoded in UTF-16 (with a NUL terminator), as required by most archives.
+/// Some archives (e.g. extra data or system save data) are instead opened with binary paths, whose meaning depends on the archive.
+///
+/// # Example
+///
+/// ```
+/// use ctru::services::fs::{FsPath, PathType};
+///
+/// let path = FsPath::try_from("/3ds/app/config.ini")?;
+/// assert_eq!(path.path_type(), PathType::UTF16);
+///
+/// let root = FsPath::empty();
+/// assert_eq!(root.path_type(), PathType::Empty);
+/// # Ok::<(), ctru::services::fs::Error>(())
+/// ```
+#[doc(alias = "FS_Path")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FsPath {
+    kind: PathType,
+    data: Vec<u8>,
+}
+
+/// Path qualified by the archive which contains it.
+///
+/// Paths in the `sdmc:/path/to/file` format (as used by the standard library) can be parsed via [`str::parse()`].
+/// Paths without a prefix are relative to the SD card, like with the standard library.
+///
+/// # Example
+///
+/// ```
+/// use ctru::services::fs::{ArchiveID, FsPath, QualifiedPath};
+///
+/// let path: QualifiedPath = "sdmc:/3ds/app/config.ini".parse()?;
+///
+/// assert_eq!(path.archive, ArchiveID::Sdmc);
+/// assert_eq!(path.path, FsPath::try_from("/3ds/app/config.ini")?);
+/// # Ok::<(), ctru::services::fs::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QualifiedPath {
+    /// Archive containing the file.
+    pub archive: ArchiveID,
+    /// Path used to open the archive (empty for most archives).
+    pub archive_path: FsPath,
+    /// Path of the file inside of the archive.
+    pub path: FsPath,
+}
+
+/// Index of the various usable data archives.
+#[doc(alias = "FS_ArchiveID")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ArchiveID {
+    /// Read-Only Memory File System.
+    RomFS = ctru_sys::ARCHIVE_ROMFS,
+    /// Game save data.
+    Savedata = ctru_sys::ARCHIVE_SAVEDATA,
+    /// Game ext data.
+    Extdata = ctru_sys::ARCHIVE_EXTDATA,
+    /// Shared ext data.
+    SharedExtdata = ctru_sys::ARCHIVE_SHARED_EXTDATA,
+    /// System save data.
+    SystemSavedata = ctru_sys::ARCHIVE_SYSTEM_SAVEDATA,
+    /// SD card.
+    Sdmc = ctru_sys::ARCHIVE_SDMC,
+    /// SD card (write-only).
+    SdmcWriteOnly = ctru_sys::ARCHIVE_SDMC_WRITE_ONLY,
+    /// BOSS ext data.
+    BossExtdata = ctru_sys::ARCHIVE_BOSS_EXTDATA,
+    /// Card SPI File System.
+    CardSpiFS = ctru_sys::ARCHIVE_CARD_SPIFS,
+    /// Game ext data and BOSS data.
+    ExtDataAndBossExtdata = ctru_sys::ARCHIVE_EXTDATA_AND_BOSS_EXTDATA,
+    /// System save data.
+    SystemSaveData2 = ctru_sys::ARCHIVE_SYSTEM_SAVEDATA2,
+    /// Internal NAND (read-write).
+    NandRW = ctru_sys::ARCHIVE_NAND_RW,
+    /// Internal NAND (read-only).
+    NandRO = ctru_sys::ARCHIVE_NAND_RO,
+    /// Internal NAND (read-only write access).
+    NandROWriteAccess = ctru_sys::ARCHIVE_NAND_RO_WRITE_ACCESS,
+    /// User save data and ExeFS/RomFS.
+    SaveDataAndContent = ctru_sys::ARCHIVE_SAVEDATA_AND_CONTENT,
+    /// User save data and ExeFS/RomFS (only ExeFS for fs:LDR).
+    SaveDataAndContent2 = ctru_sys::ARCHIVE_SAVEDATA_AND_CONTENT2,
+    /// NAND CTR File System.
+    NandCtrFS = ctru_sys::ARCHIVE_NAND_CTR_FS,
+    /// TWL photo.
+    TwlPhoto = ctru_sys::ARCHIVE_TWL_PHOTO,
+    /// NAND TWL File System.
+    NandTwlFS = ctru_sys::ARCHIVE_NAND_TWL_FS,
+    /// Game card save data.
+    GameCardSavedata = ctru_sys::ARCHIVE_GAMECARD_SAVEDATA,
+    /// User save data.
+    UserSavedata = ctru_sys::ARCHIVE_USER_SAVEDATA,
+    /// Demo save data.
+    DemoSavedata = ctru_sys::ARCHIVE_DEMO_SAVEDATA,
+}
+
+impl FsPath {
+    /// Returns an empty path, used to open most archives or to refer to their root.
+    pub fn empty() -> Self {
+        Self {
+            kind: PathType::Empty,
+            data: vec![0],
+        }
+    }
+
+    /// Creates a binary path out of raw bytes.
+    pub fn binary(data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            kind: PathType::Binary,
+            data: data.into(),
+        }
+    }
+
+    /// Creates a binary path out of 32-bit words, as used by most archives opened with binary paths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ctru::services::fs::{FsPath, MediaType};
+    ///
+    /// // Extra data archives are identified by their media type and ID.
+    /// let extdata = FsPath::from_words(&[MediaType::Sd as u32, 0x0000_1234, 0]);
+    /// assert_eq!(extdata.as_bytes().len(), 12);
+    /// ```
+    pub fn from_words(words: &[u32]) -> Self {
+        Self::binary(
+            words
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Returns the kind of the path.
+    pub fn path_type(&self) -> PathType {
+        self.kind
+    }
+
+    /// Returns the raw data of the path, as sent to the FS service.
+    ///
+    /// Text paths are returned as UTF-16 little-endian bytes, including the NUL terminator.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the path as a string, if it's a text path.
+    pub fn to_string_lossy(&self) -> Option<String> {
+        match self.kind {
+            PathType::UTF16 => {
+                let units: Vec<u16> = self
+                    .data
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .take_while(|&unit| unit != 0)
+                    .collect();
+
+                Some(String::from_utf16_lossy(&units))
+            }
+            PathType::ASCII => Some(
+                String::from_utf8_lossy(self.data.strip_suffix(&[0]).unwrap_or(&self.data))
+                    .into_owned(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns the path in the format used by [`ctru_sys`].
+    ///
+    /// The returned value points to the data of the path, so it must not outlive it.
+    pub fn as_raw(&self) -> ctru_sys::FS_Path {
+        ctru_sys::FS_Path {
+            type_: self.kind.into(),
+            size: self.data.len() as u32,
+            data: self.data.as_ptr().cast(),
+        }
+    }
+}
+
+impl TryFrom<&str> for FsPath {
+    type Error = Error;
+
+    fn try_from(path: &str) -> Result<Self, Self::Error> {
+        if path.contains('\0') {
+            return Err(Error::NulInPath);
+        }
+
+        let data = path
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+
+        Ok(Self {
+            kind: PathType::UTF16,
+            data,
+        })
+    }
+}
+
+impl TryFrom<&Path> for FsPath {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        path.to_str().ok_or(Error::InvalidUtf8)?.try_into()
+    }
+}
+
+impl QualifiedPath {
+    /// Creates a path to a file inside of an archive opened with `archive_path`.
+    pub fn new(archive: ArchiveID, archive_path: FsPath, path: FsPath) -> Self {
+        Self {
+            archive,
+            archive_path,
+            path,
+        }
+    }
+}
+
+impl FromStr for QualifiedPath {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let path = match path.split_once(":/") {
+            Some(("sdmc", path)) => path,
+            Some((archive, _)) => return Err(Error::UnknownArchive(archive.to_owned())),
+            None => path.trim_start_matches('/'),
+        };
+
+        Ok(Self::new(
+            ArchiveID::Sdmc,
+            FsPath::empty(),
+            FsPath::try_from(format!("/{path}").as_str())?,
+        ))
+    }
+}
+
+impl TryFrom<&Path> for QualifiedPath {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        path.to_str().ok_or(Error::InvalidUtf8)?.parse()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NulInPath => write!(f, "path contains a NUL character"),
+            Self::InvalidUtf8 => write!(f, "path isn't valid UTF-8"),
+            Self::UnknownArchive(name) => write!(f, "unknown archive `{name}:`"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+from_impl!(MediaType, ctru_sys::FS_MediaType);
+from_impl!(PathType, ctru_sys::FS_PathType);
+from_impl!(ArchiveID, ctru_sys::FS_ArchiveID);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf16_paths() {
+        let path = FsPath::try_from("/é").unwrap();
+
+        assert_eq!(path.as_bytes(), [b'/', 0, 0xE9, 0, 0, 0]);
+        assert_eq!(path.to_string_lossy().as_deref(), Some("/é"));
+        assert_eq!(FsPath::try_from("a\0b"), Err(Error::NulInPath));
+    }
+
+    #[test]
+    fn qualified_paths() {
+        let expected = FsPath::try_from("/data/save.bin").unwrap();
+
+        for path in ["sdmc:/data/save.bin", "/data/save.bin", "data/save.bin"] {
+            let path: QualifiedPath = path.parse().unwrap();
+            assert_eq!(path.archive, ArchiveID::Sdmc);
+            assert_eq!(path.path, expected);
+        }
+
+        assert_eq!(
+            "romfs:/file".parse::<QualifiedPath>(),
+            Err(Error::UnknownArchive("romfs".into()))
+        );
+    }
+}
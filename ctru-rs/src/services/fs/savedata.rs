@@ -0,0 +1,182 @@
+//! Save data archives.
+//!
+//! The save data of a title is stored in a dedicated archive, separated from the files of the SD card.
+//! Writes to the archive are journaled: they only become permanent once [`SaveData::commit()`] is called,
+//! so that a crash (or a power loss) while saving leaves the previous save untouched. Uncommitted changes are lost when the archive is closed.
+//!
+//! A title's save data must be formatted (once, usually the first time the title runs) before it can be opened.
+#![doc(alias = "save")]
+
+use std::ops::Deref;
+
+use super::{Archive, ArchiveID, FsPath, MediaType};
+use crate::error::ResultCode;
+
+/// Save data archive, opened until dropped.
+///
+/// The archive can be used like any other [`Archive`] to read and write the files inside of it.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::io::Write;
+///
+/// use ctru::services::fs::savedata::{SaveData, SaveDataFormat};
+/// use ctru::services::fs::{File, FsPath};
+///
+/// let save = match SaveData::open() {
+///     Ok(save) => save,
+///     // The save data is formatted the first time the application runs.
+///     Err(_) => {
+///         SaveDataFormat::default().format_own()?;
+///         SaveData::open()?
+///     }
+/// };
+///
+/// File::create(&save, &FsPath::try_from("/progress.bin")?)?.write_all(&[1, 2, 3])?;
+/// save.commit()?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SaveData {
+    archive: Archive,
+}
+
+/// Layout of a save data archive, used when formatting it.
+///
+/// The layout can't be changed after the archive is formatted, without losing its contents.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SaveDataFormat {
+    /// Size of the archive, in blocks of 512 bytes.
+    pub size_in_blocks: u32,
+    /// Maximum number of directories.
+    pub max_directories: u32,
+    /// Maximum number of files.
+    pub max_files: u32,
+    /// Whether every block is stored twice, so that a save interrupted by a power loss can be recovered.
+    ///
+    /// This halves the usable space of the archive.
+    pub duplicate_data: bool,
+}
+
+impl SaveData {
+    /// Opens the save data of the running title.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the save data isn't formatted, or if the title doesn't have save data
+    /// (e.g. homebrew applications launched from the Homebrew Launcher, which don't have a title of their own).
+    #[doc(alias = "ARCHIVE_SAVEDATA")]
+    pub fn open() -> crate::Result<Self> {
+        Ok(Self {
+            archive: Archive::open(ArchiveID::Savedata, &FsPath::empty())?,
+        })
+    }
+
+    /// Opens the save data of another title.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the title has no save data, or if the application isn't allowed to access it.
+    #[doc(alias = "ARCHIVE_USER_SAVEDATA")]
+    pub fn open_title(media_type: MediaType, title_id: u64) -> crate::Result<Self> {
+        Ok(Self {
+            archive: Archive::open(ArchiveID::UserSavedata, &title_path(media_type, title_id))?,
+        })
+    }
+
+    /// Makes the changes written to the archive permanent.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the changes couldn't be committed, in which case the previous save is kept.
+    #[doc(
+        alias = "FSUSER_ControlArchive",
+        alias = "ARCHIVE_ACTION_COMMIT_SAVE_DATA"
+    )]
+    pub fn commit(&self) -> crate::Result<()> {
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_ControlArchive(
+                self.archive.as_raw(),
+                ctru_sys::ARCHIVE_ACTION_COMMIT_SAVE_DATA,
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+                0,
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Deref for SaveData {
+    type Target = Archive;
+
+    fn deref(&self) -> &Archive {
+        &self.archive
+    }
+}
+
+impl SaveDataFormat {
+    /// Formats the save data of the running title, erasing its contents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the save data is open, or if the title doesn't have save data.
+    #[doc(alias = "FSUSER_FormatSaveData")]
+    pub fn format_own(&self) -> crate::Result<()> {
+        self.format(ArchiveID::Savedata, &FsPath::empty())
+    }
+
+    /// Formats the save data of another title, erasing its contents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the save data is open, or if the application isn't allowed to access it.
+    #[doc(alias = "FSUSER_FormatSaveData")]
+    pub fn format_title(&self, media_type: MediaType, title_id: u64) -> crate::Result<()> {
+        self.format(ArchiveID::UserSavedata, &title_path(media_type, title_id))
+    }
+
+    fn format(&self, archive: ArchiveID, path: &FsPath) -> crate::Result<()> {
+        // The hash tables of the entries are sized like the maximum number of entries, as done by the official titles.
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_FormatSaveData(
+                archive.into(),
+                path.as_raw(),
+                self.size_in_blocks,
+                self.max_directories,
+                self.max_files,
+                self.max_directories,
+                self.max_files,
+                self.duplicate_data,
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Default for SaveDataFormat {
+    /// Returns a small layout (512 KiB, 10 directories and 10 files), with duplicated data.
+    fn default() -> Self {
+        Self {
+            size_in_blocks: 0x400,
+            max_directories: 10,
+            max_files: 10,
+            duplicate_data: true,
+        }
+    }
+}
+
+// Path used to open the save data of a title.
+fn title_path(media_type: MediaType, title_id: u64) -> FsPath {
+    FsPath::from_words(&[media_type as u32, title_id as u32, (title_id >> 32) as u32])
+}
@@ -0,0 +1,331 @@
+//! SD card insertion and removal.
+//!
+//! The SD card can be removed while the application is running. When that happens, files which were open on it become unusable,
+//! and using them anyway leads to unpredictable errors. This module tracks the state of the card:
+//!
+//! - [`SdCardMonitor`] detects removals and insertions, and re-mounts the card once it's back.
+//! - [`SdFile`] wraps a [`File`] stored on the SD card, which fails with a [`CardRemoved`] error once the card it was opened on is removed,
+//!   even if a card is inserted again (the file may have been changed in the meantime).
+//!
+//! Files opened via [`File`] directly aren't tracked.
+#![doc(alias = "sd card")]
+#![doc(alias = "eject")]
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::error::ResultCode;
+
+// Incremented every time the card is removed, to invalidate the files opened before.
+static CARD_GENERATION: AtomicU32 = AtomicU32::new(0);
+static CARD_MOUNTED: AtomicBool = AtomicBool::new(true);
+
+/// Change in the state of the SD card, reported by [`SdCardMonitor::poll()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SdCardEvent {
+    /// The card was removed. All [`SdFile`]s opened until now can't be used anymore.
+    Removed,
+    /// A card was inserted. It must be re-mounted via [`SdCardMonitor::remount()`] before being used.
+    Inserted,
+}
+
+/// Error returned by [`SdFile`] operations after the SD card was removed.
+///
+/// It's wrapped in an [`io::Error`] of kind [`io::ErrorKind::NotConnected`], and can be retrieved via [`io::Error::get_ref()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CardRemoved;
+
+/// Detects insertions and removals of the SD card.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::io::Write;
+///
+/// use ctru::prelude::*;
+/// use ctru::services::fs::sdmc::{SdCardEvent, SdCardMonitor, SdFile};
+///
+/// let apt = Apt::new()?;
+/// let mut monitor = SdCardMonitor::new()?;
+/// let mut log = SdFile::create("sdmc:/app.log")?;
+///
+/// while apt.main_loop() {
+///     match monitor.poll()? {
+///         Some(SdCardEvent::Removed) => println!("The SD card was removed!"),
+///         Some(SdCardEvent::Inserted) => {
+///             monitor.remount()?;
+///             log = SdFile::options().append(true).create(true).open("sdmc:/app.log")?;
+///         }
+///         None => {}
+///     }
+///
+///     // Fails with a `CardRemoved` error while the card isn't there.
+///     let _ = writeln!(log, "Still running");
+/// #   break;
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct SdCardMonitor {
+    inserted: bool,
+}
+
+/// File stored on the SD card, which stops working once the card is removed.
+///
+/// See the [module documentation](self) for more information.
+#[derive(Debug)]
+pub struct SdFile {
+    file: File,
+    generation: u32,
+}
+
+/// Options to open an [`SdFile`], like [`OpenOptions`].
+///
+/// This struct can be created via [`SdFile::options()`].
+#[derive(Clone, Debug)]
+pub struct SdOpenOptions(OpenOptions);
+
+impl SdCardMonitor {
+    /// Creates a new monitor, reading the current state of the card.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state of the card couldn't be read.
+    pub fn new() -> crate::Result<Self> {
+        Ok(Self {
+            inserted: Self::is_inserted()?,
+        })
+    }
+
+    /// Returns `true` if an SD card is inserted.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state of the card couldn't be read.
+    #[doc(alias = "FSUSER_IsSdmcDetected")]
+    pub fn is_inserted() -> crate::Result<bool> {
+        let mut detected = false;
+        ResultCode(unsafe { ctru_sys::FSUSER_IsSdmcDetected(&mut detected) })?;
+
+        Ok(detected)
+    }
+
+    /// Returns `true` if the card is mounted, i.e. it can be accessed via the `sdmc:` prefix.
+    pub fn is_mounted(&self) -> bool {
+        CARD_MOUNTED.load(Ordering::Acquire)
+    }
+
+    /// Checks if the card was removed or inserted since the last call.
+    ///
+    /// This should be called regularly (e.g. once per frame) for [`SdFile`]s to notice the removal of the card.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state of the card couldn't be read.
+    pub fn poll(&mut self) -> crate::Result<Option<SdCardEvent>> {
+        let inserted = Self::is_inserted()?;
+
+        if inserted == self.inserted {
+            return Ok(None);
+        }
+
+        self.inserted = inserted;
+
+        if inserted {
+            Ok(Some(SdCardEvent::Inserted))
+        } else {
+            card_removed();
+            Ok(Some(SdCardEvent::Removed))
+        }
+    }
+
+    /// Mounts the card again after it was inserted, so that it can be accessed via the `sdmc:` prefix.
+    ///
+    /// Files must be opened again after re-mounting.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no card is inserted, or if it couldn't be mounted.
+    #[doc(alias = "sdmcInit", alias = "sdmcExit")]
+    pub fn remount(&mut self) -> crate::Result<()> {
+        // The card may have been swapped without a `poll()` noticing it, so files are always invalidated.
+        card_removed();
+
+        // The old mount may already be unusable, so failing to close it isn't an issue.
+        let _ = unsafe { ctru_sys::sdmcExit() };
+        ResultCode(unsafe { ctru_sys::sdmcInit() })?;
+
+        self.inserted = true;
+        CARD_MOUNTED.store(true, Ordering::Release);
+
+        Ok(())
+    }
+}
+
+impl SdFile {
+    /// Opens a file in read-only mode, like [`File::open()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the card isn't mounted, or if the file couldn't be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::options().read(true).open(path)
+    }
+
+    /// Opens a file in write-only mode, creating or truncating it, like [`File::create()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the card isn't mounted, or if the file couldn't be created.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+    }
+
+    /// Returns options to open a file with more control, like [`File::options()`].
+    pub fn options() -> SdOpenOptions {
+        SdOpenOptions(OpenOptions::new())
+    }
+
+    /// Returns `true` if the card the file was opened on was removed.
+    pub fn is_poisoned(&self) -> bool {
+        self.check().is_err()
+    }
+
+    /// Returns the underlying file, if the card wasn't removed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`CardRemoved`] error if the card was removed.
+    pub fn file(&self) -> io::Result<&File> {
+        self.check()?;
+        Ok(&self.file)
+    }
+
+    /// Flushes the data written to the file to the card, like [`File::sync_all()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the card was removed, or if the data couldn't be written.
+    pub fn sync_all(&self) -> io::Result<()> {
+        self.file()?.sync_all()
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if CARD_MOUNTED.load(Ordering::Acquire)
+            && CARD_GENERATION.load(Ordering::Acquire) == self.generation
+        {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::NotConnected, CardRemoved))
+        }
+    }
+}
+
+impl SdOpenOptions {
+    /// Sets the option for read access.
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.0.read(read);
+        self
+    }
+
+    /// Sets the option for write access.
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.0.write(write);
+        self
+    }
+
+    /// Sets the option for append mode.
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.0.append(append);
+        self
+    }
+
+    /// Sets the option for truncating the file.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.0.truncate(truncate);
+        self
+    }
+
+    /// Sets the option to create the file if it doesn't exist.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.0.create(create);
+        self
+    }
+
+    /// Sets the option to create a new file, failing if it already exists.
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.0.create_new(create_new);
+        self
+    }
+
+    /// Opens the file at `path` with these options.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the card isn't mounted, or if the file couldn't be opened.
+    pub fn open(&self, path: impl AsRef<Path>) -> io::Result<SdFile> {
+        // Read the generation first, so that a removal happening while the file is opened invalidates it.
+        let generation = CARD_GENERATION.load(Ordering::Acquire);
+
+        if !CARD_MOUNTED.load(Ordering::Acquire) {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, CardRemoved));
+        }
+
+        Ok(SdFile {
+            file: self.0.open(path)?,
+            generation,
+        })
+    }
+}
+
+impl Read for SdFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check()?;
+        self.file.read(buf)
+    }
+}
+
+impl Write for SdFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check()?;
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.check()?;
+        self.file.flush()
+    }
+}
+
+impl Seek for SdFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.check()?;
+        self.file.seek(pos)
+    }
+}
+
+fn card_removed() {
+    CARD_MOUNTED.store(false, Ordering::Release);
+    CARD_GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+impl fmt::Display for CardRemoved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the SD card was removed")
+    }
+}
+
+impl StdError for CardRemoved {}
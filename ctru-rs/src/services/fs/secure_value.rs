@@ -0,0 +1,112 @@
+//! Save data secure values.
+//!
+//! Titles installed on the SD card can store a secure value in the system, alongside a copy in their save data.
+//! When the two don't match (e.g. after an older save was restored), the title considers its save data as rolled back,
+//! and may refuse to use it. Tools which back up and restore save data must keep both values in sync:
+//!
+//! 1. When exporting a save, read the current value via [`SecureValue::get()`] and store it along with the backup.
+//! 2. After importing it, write the stored value back via [`SecureValue::set()`], so that it matches the imported save again.
+//!
+//! [`SecureValue::restore()`] implements the second step, and leaves the system untouched when the backup didn't record any value.
+#![doc(alias = "anti-rollback")]
+
+use crate::error::ResultCode;
+
+/// Secure value of a title.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::fs::secure_value::SecureValue;
+///
+/// let secure_value = SecureValue::new(0x0004000000055D00);
+///
+/// // When exporting the save data.
+/// let exported = secure_value.get()?;
+///
+/// // After importing it back.
+/// secure_value.restore(exported)?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "FS_SecureValueSlot")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SecureValue {
+    unique_id: u32,
+    variation: u8,
+}
+
+impl SecureValue {
+    /// Returns the secure value of the title with ID `title_id`.
+    pub fn new(title_id: u64) -> Self {
+        Self {
+            unique_id: ((title_id >> 8) & 0xFF_FFFF) as u32,
+            variation: title_id as u8,
+        }
+    }
+
+    /// Returns the value stored in the system, if the title has one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the value couldn't be read.
+    #[doc(alias = "FSUSER_GetSaveDataSecureValue")]
+    pub fn get(&self) -> crate::Result<Option<u64>> {
+        let mut exists = false;
+        let mut value = 0;
+
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_GetSaveDataSecureValue(
+                &mut exists,
+                &mut value,
+                ctru_sys::SECUREVALUE_SLOT_SD,
+                self.unique_id,
+                self.variation,
+            )
+        })?;
+
+        Ok(exists.then_some(value))
+    }
+
+    /// Stores a value in the system.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the value couldn't be written.
+    #[doc(alias = "FSUSER_SetSaveDataSecureValue")]
+    pub fn set(&self, value: u64) -> crate::Result<()> {
+        ResultCode(unsafe {
+            ctru_sys::FSUSER_SetSaveDataSecureValue(
+                value,
+                ctru_sys::SECUREVALUE_SLOT_SD,
+                self.unique_id,
+                self.variation,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Brings the value stored in the system back in sync with imported save data,
+    /// given the value which was read when the save data was exported.
+    ///
+    /// Returns `true` if the value was changed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the value couldn't be read or written.
+    pub fn restore(&self, exported: Option<u64>) -> crate::Result<bool> {
+        match exported {
+            Some(value) if self.get()? != Some(value) => {
+                self.set(value)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
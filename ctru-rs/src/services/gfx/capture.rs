@@ -0,0 +1,290 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::pixel::{Bgr8, Pixel, Rgb565, Rgb5A1, Rgba4, Rgba8};
+use super::{Gfx, Screen};
+use crate::services::gspgpu::FramebufferFormat;
+
+/// Copy of the contents of a [`Screen`], in RGBA format.
+///
+/// This struct can be retrieved via [`Screen::capture()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Screenshot {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+/// Copy of both screens of the application, which can be shared as a screenshot.
+///
+/// This struct can be retrieved via [`Capture::new()`] or, to choose whether overlays are included,
+/// via [`OverlayStack::draw_and_capture()`](crate::overlay::OverlayStack::draw_and_capture).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capture {
+    top: Screenshot,
+    bottom: Screenshot,
+}
+
+/// Information about the application, saved along with a [`Capture`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureMetadata {
+    /// Name of the application.
+    pub title: String,
+    /// Title ID of the application (see [`Apt::program_id()`](crate::services::apt::Apt::program_id)).
+    pub title_id: u64,
+}
+
+impl Screenshot {
+    pub(super) fn capture<S: Screen>(screen: &mut S) -> Self {
+        match screen.framebuffer_format() {
+            FramebufferFormat::Rgba8 => Self::capture_with::<Rgba8, S>(screen),
+            FramebufferFormat::Bgr8 => Self::capture_with::<Bgr8, S>(screen),
+            FramebufferFormat::Rgb565 => Self::capture_with::<Rgb565, S>(screen),
+            FramebufferFormat::Rgb5A1 => Self::capture_with::<Rgb5A1, S>(screen),
+            FramebufferFormat::Rgba4 => Self::capture_with::<Rgba4, S>(screen),
+        }
+    }
+
+    fn capture_with<P: Pixel + Into<Rgba8>, S: Screen>(screen: &mut S) -> Self {
+        let view = screen
+            .framebuffer_view::<P>()
+            .expect("the pixel type should match the framebuffer format");
+
+        let (width, height) = (view.width(), view.height());
+        let mut data = Vec::with_capacity(width * height * 4);
+
+        for y in 0..height {
+            for x in 0..width {
+                let Rgba8 { r, g, b, a } = view.pixel(x, y).unwrap().into();
+                data.extend_from_slice(&[r, g, b, a]);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Returns the width of the screenshot (in pixels).
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the screenshot (in pixels).
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the pixels of the screenshot, as RGBA bytes in row-major order starting from the top left corner.
+    pub fn as_rgba(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consumes the screenshot, returning its pixels as RGBA bytes in row-major order starting from the top left corner.
+    pub fn into_rgba(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Writes the screenshot as a BMP image (with 24 bits per pixel, since the screens can't show transparency).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if writing to `out` failed.
+    pub fn write_bmp(&self, mut out: impl Write) -> io::Result<()> {
+        const HEADERS_LEN: u32 = 14 + 40;
+
+        // Every row is padded to a multiple of 4 bytes.
+        let row_len = (self.width * 3).next_multiple_of(4);
+        let image_len = (row_len * self.height) as u32;
+
+        // File header
+        out.write_all(b"BM")?;
+        out.write_all(&(HEADERS_LEN + image_len).to_le_bytes())?;
+        out.write_all(&[0; 4])?;
+        out.write_all(&HEADERS_LEN.to_le_bytes())?;
+
+        // Info header
+        out.write_all(&40u32.to_le_bytes())?;
+        out.write_all(&(self.width as i32).to_le_bytes())?;
+        out.write_all(&(self.height as i32).to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?; // Planes
+        out.write_all(&24u16.to_le_bytes())?; // Bits per pixel
+        out.write_all(&0u32.to_le_bytes())?; // No compression
+        out.write_all(&image_len.to_le_bytes())?;
+        out.write_all(&2835i32.to_le_bytes())?; // 72 DPI
+        out.write_all(&2835i32.to_le_bytes())?;
+        out.write_all(&0u32.to_le_bytes())?; // Palette size
+        out.write_all(&0u32.to_le_bytes())?; // Important colors
+
+        // Rows are stored from the bottom to the top, with pixels in BGR order.
+        let mut row = Vec::with_capacity(row_len);
+        for pixels in self.data.chunks_exact(self.width * 4).rev() {
+            row.clear();
+            for pixel in pixels.chunks_exact(4) {
+                row.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+            }
+            row.resize(row_len, 0);
+
+            out.write_all(&row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Saves the screenshot as a BMP image at the specified path (e.g. on the SD card).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::{Gfx, Screen};
+    ///
+    /// let gfx = Gfx::new()?;
+    /// let screenshot = gfx.top_screen.borrow_mut().capture();
+    ///
+    /// screenshot.save_bmp("sdmc:/screenshot.bmp")?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_bmp(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_bmp(&mut out)?;
+        out.flush()
+    }
+}
+
+impl Capture {
+    /// Captures the current content of both screens.
+    ///
+    /// As with [`Screen::capture()`], the content is read from the framebuffers being drawn to,
+    /// so this should be called after drawing a frame but before swapping the buffers.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any of the screens is already borrowed.
+    pub fn new(gfx: &Gfx) -> Self {
+        Self {
+            top: gfx.top_screen.borrow_mut().capture(),
+            bottom: gfx.bottom_screen.borrow_mut().capture(),
+        }
+    }
+
+    /// Returns the capture of the top screen.
+    pub fn top(&self) -> &Screenshot {
+        &self.top
+    }
+
+    /// Returns the capture of the bottom screen.
+    pub fn bottom(&self) -> &Screenshot {
+        &self.bottom
+    }
+
+    /// Saves the capture inside `dir`, creating the directory if needed.
+    ///
+    /// Every capture is saved as 3 files sharing the same name, made of the title ID and the current time:
+    /// a BMP image per screen (with the `_top.bmp` and `_bottom.bmp` suffixes), and a `.txt` file with the `metadata`.
+    ///
+    /// Returns the path of the files, without the suffixes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the files couldn't be written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::prelude::*;
+    /// use ctru::services::gfx::{Capture, CaptureMetadata};
+    ///
+    /// let apt = Apt::new()?;
+    /// let gfx = Gfx::new()?;
+    ///
+    /// let metadata = CaptureMetadata {
+    ///     title: String::from("My Game"),
+    ///     title_id: apt.program_id()?,
+    /// };
+    ///
+    /// Capture::new(&gfx).save("sdmc:/screenshots", &metadata)?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save(&self, dir: impl AsRef<Path>, metadata: &CaptureMetadata) -> io::Result<PathBuf> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let stem = dir.join(format!(
+            "{:016X}_{}_{:03}",
+            metadata.title_id,
+            time.as_secs(),
+            time.subsec_millis()
+        ));
+
+        self.top.save_bmp(with_suffix(&stem, "_top.bmp"))?;
+        self.bottom.save_bmp(with_suffix(&stem, "_bottom.bmp"))?;
+
+        fs::write(
+            with_suffix(&stem, ".txt"),
+            format!(
+                "title={}\ntitle_id={:016X}\ntime={}\n",
+                metadata.title,
+                metadata.title_id,
+                time.as_secs()
+            ),
+        )?;
+
+        Ok(stem)
+    }
+}
+
+fn with_suffix(stem: &Path, suffix: &str) -> PathBuf {
+    let mut path = stem.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bmp_layout() {
+        let screenshot = Screenshot {
+            width: 2,
+            height: 2,
+            data: vec![
+                1, 2, 3, 0xFF, 4, 5, 6, 0xFF, // Top row
+                7, 8, 9, 0xFF, 10, 11, 12, 0xFF, // Bottom row
+            ],
+        };
+
+        let mut bmp = Vec::new();
+        screenshot.write_bmp(&mut bmp).unwrap();
+
+        assert_eq!(bmp.len(), 54 + 2 * 8);
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(
+            &bmp[54..],
+            [9, 8, 7, 12, 11, 10, 0, 0, 3, 2, 1, 6, 5, 4, 0, 0]
+        );
+    }
+}
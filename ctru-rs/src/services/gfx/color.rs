@@ -0,0 +1,265 @@
+//! Color adjustment of the screens.
+//!
+//! The display controller of the 3DS passes every pixel shown on the screens through a color lookup table (LUT),
+//! which maps each 8-bit channel value to another. Changing the table adjusts the colors of the whole screen at no cost,
+//! which allows implementing gamma correction or a night mode (reducing the amount of blue light) without touching the rendering code.
+//!
+//! [`ColorLut`] builds such a table, and applies it either to the hardware table of a screen via [`ColorLut::apply_to_screen()`],
+//! or in software to the pixels of a [`FramebufferView`] via [`ColorLut::apply_to_view()`] (e.g. for screenshots, or when the hardware
+//! table is already used by a system feature like the screen filters of Luma3DS).
+//!
+//! The backlight brightness of the screens is controlled by the [`gsplcd`](crate::services::gsplcd) service instead.
+#![doc(alias = "gamma")]
+#![doc(alias = "night mode")]
+#![doc(alias = "blue light filter")]
+
+use super::pixel::{Pixel, Rgba8};
+use super::{FramebufferView, Gfx};
+use crate::error::ResultCode;
+use crate::services::gsplcd::LcdScreen;
+
+// Registers of the color LUTs of the display controller, relative to the GPU register base accessible via GSP (0x1EB00000).
+// Writing the index register selects the first entry to write, and each write to the element register advances it by one.
+const TOP_LUT_INDEX: u32 = 0x400484;
+const BOTTOM_LUT_INDEX: u32 = 0x400584;
+
+// Maximum amount of bytes written by a single `GSPGPU_WriteHWRegRepeat` call.
+const MAX_REGISTER_WRITE: usize = 0x80;
+
+/// Color temperature of the screens when no adjustment is made (in Kelvin).
+pub const NEUTRAL_TEMPERATURE: u32 = 6500;
+
+/// Color lookup table, mapping each value of the red, green and blue channels to another.
+///
+/// The adjustments compose in the order they are made.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::gfx::color::ColorLut;
+/// use ctru::services::gfx::Gfx;
+/// use ctru::services::gsplcd::LcdScreen;
+///
+/// let gfx = Gfx::new()?;
+///
+/// // Warm colors for night reading.
+/// let night_mode = ColorLut::identity().with_temperature(3400).with_gamma(1.2);
+/// night_mode.apply_to_screen(&gfx, LcdScreen::Both)?;
+///
+/// // The table outlives the application, so it must be reset before exiting.
+/// ColorLut::identity().apply_to_screen(&gfx, LcdScreen::Both)?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "LUT")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorLut {
+    red: [u8; 256],
+    green: [u8; 256],
+    blue: [u8; 256],
+}
+
+impl ColorLut {
+    /// Returns the table which doesn't change any color.
+    pub fn identity() -> Self {
+        Self::from_fn(|value| (value, value, value))
+    }
+
+    /// Builds a table by calling `f` with every channel value, which returns the (red, green, blue) values to map it to.
+    pub fn from_fn(mut f: impl FnMut(u8) -> (u8, u8, u8)) -> Self {
+        let mut lut = Self {
+            red: [0; 256],
+            green: [0; 256],
+            blue: [0; 256],
+        };
+
+        for value in 0..=255 {
+            let (r, g, b) = f(value);
+            lut.red[usize::from(value)] = r;
+            lut.green[usize::from(value)] = g;
+            lut.blue[usize::from(value)] = b;
+        }
+
+        lut
+    }
+
+    /// Applies a gamma curve (`output = input ^ gamma`, with channels normalized between 0 and 1).
+    ///
+    /// Values greater than 1 darken the mid-tones, while values lower than 1 brighten them.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `gamma` isn't a positive number.
+    pub fn with_gamma(self, gamma: f32) -> Self {
+        assert!(gamma > 0.0, "the gamma must be positive");
+
+        self.map(|_, value| (value / 255.0).powf(gamma) * 255.0)
+    }
+
+    /// Shifts the white point to the color of a black body at `kelvin` degrees.
+    ///
+    /// [`NEUTRAL_TEMPERATURE`] doesn't change the colors. Lower temperatures give warmer (more red) colors,
+    /// and are commonly used to reduce eye strain at night. Higher temperatures give colder (more blue) colors.
+    /// The temperature is clamped between 1000 and 40000 Kelvin.
+    pub fn with_temperature(self, kelvin: u32) -> Self {
+        let white = white_point(kelvin);
+        let neutral = white_point(NEUTRAL_TEMPERATURE);
+
+        self.map(|channel, value| value * white[channel] / neutral[channel])
+    }
+
+    /// Scales the intensity of all colors by `factor`, clamped between 0 and 1.
+    ///
+    /// Unlike [`GspLcd::set_brightness()`](crate::services::gsplcd::GspLcd::set_brightness), this doesn't change the intensity of the backlight.
+    pub fn with_brightness(self, factor: f32) -> Self {
+        let factor = factor.clamp(0.0, 1.0);
+
+        self.map(|_, value| value * factor)
+    }
+
+    /// Returns the (red, green, blue) values `value` is mapped to.
+    pub fn get(&self, value: u8) -> (u8, u8, u8) {
+        let index = usize::from(value);
+
+        (self.red[index], self.green[index], self.blue[index])
+    }
+
+    /// Maps a color through the table, keeping its alpha channel.
+    pub fn map_color(&self, color: Rgba8) -> Rgba8 {
+        Rgba8::new(
+            self.red[usize::from(color.r)],
+            self.green[usize::from(color.g)],
+            self.blue[usize::from(color.b)],
+            color.a,
+        )
+    }
+
+    /// Sets the table as the hardware color LUT of a screen.
+    ///
+    /// # Notes
+    ///
+    /// The table isn't reset by the system when the application exits, so [`ColorLut::identity()`] should be applied before exiting.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the registers of the display controller couldn't be written.
+    #[doc(alias = "GSPGPU_WriteHWRegs", alias = "GSPGPU_WriteHWRegRepeat")]
+    pub fn apply_to_screen(&self, _gfx: &Gfx, screen: LcdScreen) -> crate::Result<()> {
+        let registers: &[u32] = match screen {
+            LcdScreen::Top => &[TOP_LUT_INDEX],
+            LcdScreen::Bottom => &[BOTTOM_LUT_INDEX],
+            LcdScreen::Both => &[TOP_LUT_INDEX, BOTTOM_LUT_INDEX],
+        };
+
+        // The entries have the layout 0x00BBGGRR.
+        let entries: Vec<u32> = (0..256)
+            .map(|i| {
+                u32::from(self.red[i])
+                    | (u32::from(self.green[i]) << 8)
+                    | (u32::from(self.blue[i]) << 16)
+            })
+            .collect();
+
+        for &index_register in registers {
+            ResultCode(unsafe { ctru_sys::GSPGPU_WriteHWRegs(index_register, &0, 4) })?;
+
+            for chunk in entries.chunks(MAX_REGISTER_WRITE / 4) {
+                ResultCode(unsafe {
+                    ctru_sys::GSPGPU_WriteHWRegRepeat(
+                        index_register + 4,
+                        chunk.as_ptr(),
+                        (chunk.len() * 4) as u8,
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Maps all the pixels of a view through the table, in software.
+    pub fn apply_to_view<P: Pixel + Into<Rgba8>>(&self, view: &mut FramebufferView<'_, P>) {
+        for mut row in view.rows_mut() {
+            for x in 0..row.len() {
+                if let Some(pixel) = row.get(x) {
+                    row.set(x, self.map_color(pixel.into()).into());
+                }
+            }
+        }
+    }
+
+    // Transforms every entry of the table, with the channel index (0 for red, 1 for green, 2 for blue) and the current value.
+    fn map(mut self, f: impl Fn(usize, f32) -> f32) -> Self {
+        for (channel, table) in [&mut self.red, &mut self.green, &mut self.blue]
+            .into_iter()
+            .enumerate()
+        {
+            for entry in table.iter_mut() {
+                *entry = f(channel, f32::from(*entry)).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+
+        self
+    }
+}
+
+impl Default for ColorLut {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+// Approximation of the color of a black body, normalized between 0 and 1 (Tanner Helland's fit of the CIE 1964 data).
+fn white_point(kelvin: u32) -> [f32; 3] {
+    let t = kelvin.clamp(1000, 40000) as f32 / 100.0;
+
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+
+    let green = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+
+    [red, green, blue].map(|c| c.clamp(0.0, 255.0) / 255.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjustments() {
+        assert_eq!(
+            ColorLut::identity().with_temperature(NEUTRAL_TEMPERATURE),
+            ColorLut::identity()
+        );
+        assert_eq!(ColorLut::identity().with_gamma(1.0), ColorLut::identity());
+
+        let warm = ColorLut::identity().with_temperature(3000);
+        let (r, g, b) = warm.get(255);
+        assert!(r == 255 && g < 255 && b < g);
+
+        let dark = ColorLut::identity().with_gamma(2.0).with_brightness(0.5);
+        assert_eq!(dark.get(0), (0, 0, 0));
+        assert_eq!(dark.get(255), (128, 128, 128));
+        assert_eq!(dark.get(128).0, 32);
+    }
+}
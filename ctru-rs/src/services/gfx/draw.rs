@@ -0,0 +1,266 @@
+//! Software 2D drawing.
+//!
+//! Simple drawing primitives operating on a [`FramebufferView`], for applications which don't need the GPU:
+//! outlined rectangles, lines, alpha-blended blits of RGBA images and text.
+//! Filled rectangles are drawn via [`FramebufferView::fill_rect()`].
+//!
+//! All primitives are clipped to the bounds of the view, so they can be partially (or fully) outside of it.
+//!
+//! # Example
+//!
+//! ```
+//! # let _runner = test_runner::GdbRunner::default();
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #
+//! use ctru::services::gfx::pixel::Bgr8;
+//! use ctru::services::gfx::{draw, Gfx, Screen};
+//!
+//! let gfx = Gfx::new()?;
+//! let mut top_screen = gfx.top_screen.borrow_mut();
+//! let mut view = top_screen.framebuffer_view::<Bgr8>().unwrap();
+//!
+//! let white = Bgr8::new(0xFF, 0xFF, 0xFF);
+//!
+//! view.fill(Bgr8::new(0, 0, 0));
+//! draw::rect_outline(&mut view, 10, 10, 100, 50, white);
+//! draw::line(&mut view, (10, 10), (109, 59), white);
+//! draw::text(&mut view, 16, 70, "Hello, World!", white);
+//! #
+//! # Ok(())
+//! # }
+//! ```
+#![doc(alias = "graphics")]
+#![doc(alias = "2d")]
+
+use super::pixel::{Pixel, Rgba8};
+use super::FramebufferView;
+
+/// Size (in pixels) of the characters drawn by [`text()`].
+pub const GLYPH_SIZE: usize = 8;
+
+/// Draws the outline of a rectangle, one pixel wide.
+///
+/// The rectangle starts at the top left corner (`x`, `y`).
+pub fn rect_outline<P: Pixel>(
+    view: &mut FramebufferView<'_, P>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    color: P,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    view.fill_rect(x, y, width, 1, color);
+    view.fill_rect(x, y + height - 1, width, 1, color);
+    view.fill_rect(x, y, 1, height, color);
+    view.fill_rect(x + width - 1, y, 1, height, color);
+}
+
+/// Draws a line between two points (both included), one pixel wide.
+///
+/// The points may be outside of the view (even with negative coordinates).
+pub fn line<P: Pixel>(
+    view: &mut FramebufferView<'_, P>,
+    from: (isize, isize),
+    to: (isize, isize),
+    color: P,
+) {
+    // Bresenham's line algorithm.
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    loop {
+        set_clipped(view, x, y, color);
+
+        if (x, y) == to {
+            break;
+        }
+
+        let double_error = 2 * error;
+        if double_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if double_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Draws an RGBA image (4 bytes per pixel, in row-major order) with its top left corner at (`x`, `y`),
+/// blending it with the content of the view according to its alpha channel.
+///
+/// # Panics
+///
+/// This function will panic if `width` is zero or if the length of `data` isn't a multiple of `width * 4`.
+pub fn blit_rgba<P: Pixel + Into<Rgba8>>(
+    view: &mut FramebufferView<'_, P>,
+    x: isize,
+    y: isize,
+    width: usize,
+    data: &[u8],
+) {
+    assert!(
+        width != 0 && data.len() % (width * 4) == 0,
+        "the image data doesn't match its width"
+    );
+
+    for (row_y, row) in data.chunks_exact(width * 4).enumerate() {
+        for (row_x, pixel) in row.chunks_exact(4).enumerate() {
+            let source = Rgba8::new(pixel[0], pixel[1], pixel[2], pixel[3]);
+            blend_clipped(view, x + row_x as isize, y + row_y as isize, source);
+        }
+    }
+}
+
+/// Draws text with its top left corner at (`x`, `y`), using the built-in 8x8 font of the [`Console`](crate::console::Console).
+///
+/// `'\n'` starts a new line. Characters missing from the font are drawn as `'?'`.
+/// For localized text, use the [system font](super::font::SystemFont) instead.
+///
+/// Returns the size (width, height) of the text, in pixels.
+pub fn text<P: Pixel>(
+    view: &mut FramebufferView<'_, P>,
+    x: isize,
+    y: isize,
+    text: &str,
+    color: P,
+) -> (usize, usize) {
+    let font = unsafe { (*ctru_sys::consoleGetDefault()).font };
+    let first = font.asciiOffset as u32;
+    let count = font.numChars as u32;
+
+    let (mut column, mut line) = (0, 0);
+    let mut width = 0;
+
+    for c in text.chars() {
+        if c == '\n' {
+            column = 0;
+            line += 1;
+            continue;
+        }
+
+        let index = match c as u32 {
+            code if (first..first + count).contains(&code) => code - first,
+            _ => '?' as u32 - first,
+        };
+
+        let glyph = unsafe {
+            std::slice::from_raw_parts(font.gfx.add(index as usize * GLYPH_SIZE), GLYPH_SIZE)
+        };
+
+        let left = x + (column * GLYPH_SIZE) as isize;
+        let top = y + (line * GLYPH_SIZE) as isize;
+
+        for (glyph_y, bits) in glyph.iter().enumerate() {
+            for glyph_x in 0..GLYPH_SIZE {
+                // The leftmost pixel is the most significant bit.
+                if bits & (0x80 >> glyph_x) != 0 {
+                    set_clipped(view, left + glyph_x as isize, top + glyph_y as isize, color);
+                }
+            }
+        }
+
+        column += 1;
+        width = width.max(column);
+    }
+
+    if text.is_empty() {
+        return (0, 0);
+    }
+
+    (width * GLYPH_SIZE, (line + 1) * GLYPH_SIZE)
+}
+
+// Blends a color over a pixel of the view, ignoring pixels out of bounds.
+pub(super) fn blend_clipped<P: Pixel + Into<Rgba8>>(
+    view: &mut FramebufferView<'_, P>,
+    x: isize,
+    y: isize,
+    color: Rgba8,
+) {
+    if color.a == 0 || x < 0 || y < 0 {
+        return;
+    }
+
+    if let Some(destination) = view.pixel(x as usize, y as usize) {
+        view.set_pixel(
+            x as usize,
+            y as usize,
+            blend(color, destination.into()).into(),
+        );
+    }
+}
+
+fn set_clipped<P: Pixel>(view: &mut FramebufferView<'_, P>, x: isize, y: isize, color: P) {
+    if x >= 0 && y >= 0 && (x as usize) < view.width() && (y as usize) < view.height() {
+        view.set_pixel(x as usize, y as usize, color);
+    }
+}
+
+// "Source over" alpha blending of a color over an opaque background.
+fn blend(source: Rgba8, destination: Rgba8) -> Rgba8 {
+    let alpha = u16::from(source.a);
+    let mix =
+        |s: u8, d: u8| ((u16::from(s) * alpha + u16::from(d) * (255 - alpha) + 127) / 255) as u8;
+
+    Rgba8::new(
+        mix(source.r, destination.r),
+        mix(source.g, destination.g),
+        mix(source.b, destination.b),
+        0xFF,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alpha_blending() {
+        let background = Rgba8::opaque(0, 0, 0xFF);
+
+        assert_eq!(
+            blend(Rgba8::new(0xFF, 0, 0, 0xFF), background),
+            Rgba8::opaque(0xFF, 0, 0)
+        );
+        assert_eq!(
+            blend(Rgba8::new(0xFF, 0, 0, 0x80), background),
+            Rgba8::opaque(0x80, 0, 0x7F)
+        );
+    }
+
+    #[test]
+    fn bench_fast_paths() {
+        use crate::services::gfx::pixel::Bgr8;
+        use crate::services::gfx::{Gfx, Screen};
+        use test_runner::bench::bench;
+
+        let gfx = Gfx::new().unwrap();
+        let mut top_screen = gfx.top_screen.borrow_mut();
+        let mut view = top_screen.framebuffer_view::<Bgr8>().unwrap();
+
+        let image = vec![0x80; 64 * 64 * 4];
+        let color = Bgr8::new(0x20, 0x40, 0x60);
+
+        bench("gfx::fill", || view.fill(color));
+        bench("gfx::fill_rect_64", || {
+            view.fill_rect(10, 10, 64, 64, color)
+        });
+        bench("gfx::blit_rgba_64", || {
+            blit_rgba(&mut view, 10, 10, 64, &image)
+        });
+        bench("gfx::text", || {
+            text(&mut view, 0, 0, "Hello, World!", color)
+        });
+    }
+}
@@ -0,0 +1,364 @@
+//! System font.
+//!
+//! The system font is the font used by the HOME Menu and most applications. It covers thousands of characters,
+//! so it can be used to render localized text (including Japanese) without bundling a font with the application.
+//!
+//! [`SystemFont`] rasterizes UTF-8 strings directly into a [`FramebufferView`], without using the GPU.
+//! Chinese, Korean and Taiwanese consoles use different fonts, which can be loaded on any console with [`SystemFont::for_region()`].
+#![doc(alias = "text")]
+#![doc(alias = "bcfnt")]
+
+use std::ffi::CStr;
+use std::ptr::NonNull;
+
+use super::draw;
+use super::pixel::{Pixel, Rgba8};
+use super::texture::tiled_index;
+use super::FramebufferView;
+use crate::error::{Error, ResultCode};
+use crate::services::cfgu::Region;
+
+/// Font shared by the system, or loaded from the font of another region.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::gfx::font::SystemFont;
+/// use ctru::services::gfx::pixel::{Bgr8, Rgba8};
+/// use ctru::services::gfx::{Gfx, Screen};
+///
+/// let gfx = Gfx::new()?;
+/// let font = SystemFont::new()?;
+///
+/// let mut top_screen = gfx.top_screen.borrow_mut();
+/// let mut view = top_screen.framebuffer_view::<Bgr8>().unwrap();
+///
+/// let text = "こんにちは、世界！";
+/// let (width, _) = font.measure(text, 1.0);
+///
+/// // Center the text horizontally.
+/// let x = (view.width() as isize - width as isize) / 2;
+/// font.draw(&mut view, x, 100, text, 1.0, Rgba8::opaque(0xFF, 0xFF, 0xFF));
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct SystemFont {
+    font: NonNull<ctru_sys::CFNT_s>,
+    // Fonts loaded from the system archives own their data, while the shared font is mapped by the system.
+    _data: Option<Box<[u32]>>,
+}
+
+impl SystemFont {
+    /// Returns the font shared by the system, which depends on the region of the console.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the shared font couldn't be mapped in the memory of the application.
+    #[doc(alias = "fontEnsureMapped", alias = "fontGetSystemFont")]
+    pub fn new() -> crate::Result<Self> {
+        ResultCode(unsafe { ctru_sys::fontEnsureMapped() })?;
+
+        let font = NonNull::new(unsafe { ctru_sys::fontGetSystemFont() })
+            .ok_or_else(|| Error::Other("the system font isn't available".into()))?;
+
+        Ok(Self { font, _data: None })
+    }
+
+    /// Loads the system font used by consoles of another region.
+    ///
+    /// Japanese, American, European and Australian consoles share the same font, while Chinese, Korean and Taiwanese consoles
+    /// have their own fonts, covering different characters. All fonts are installed on every console.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the font couldn't be read from the system archives.
+    #[doc(alias = "fontFixPointers")]
+    pub fn for_region(region: Region) -> crate::Result<Self> {
+        let (title_id, file) = match region {
+            Region::China => (0x0004_009B_0001_4102, "cbf_zh-Hans-CN.bcfnt.lz"),
+            Region::Korea => (0x0004_009B_0001_4202, "cbf_ko-Hang-KR.bcfnt.lz"),
+            Region::Taiwan => (0x0004_009B_0001_4302, "cbf_zh-Hant-TW.bcfnt.lz"),
+            _ => (0x0004_009B_0001_4002, "cbf_std.bcfnt.lz"),
+        };
+
+        let mount_name = CStr::from_bytes_with_nul(b"font\0").unwrap();
+
+        ResultCode(unsafe {
+            ctru_sys::romfsMountFromTitle(title_id, ctru_sys::MEDIATYPE_NAND, mount_name.as_ptr())
+        })?;
+
+        let compressed = std::fs::read(format!("font:/{file}"));
+
+        unsafe { ctru_sys::romfsUnmount(mount_name.as_ptr()) };
+
+        let compressed =
+            compressed.map_err(|e| Error::Other(format!("couldn't read the system font: {e}")))?;
+        let data = decompress_lz11(&compressed)
+            .ok_or_else(|| Error::Other("the system font is corrupted".into()))?;
+
+        // The font contains 32-bit fields, so its data must be aligned.
+        let mut buffer = vec![0u32; data.len().div_ceil(4)].into_boxed_slice();
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), buffer.as_mut_ptr().cast(), data.len())
+        };
+
+        let font = NonNull::new(buffer.as_mut_ptr().cast::<ctru_sys::CFNT_s>()).unwrap();
+
+        // The font stores offsets, which must be turned into pointers before use.
+        unsafe { ctru_sys::fontFixPointers(font.as_ptr()) };
+
+        Ok(Self {
+            font,
+            _data: Some(buffer),
+        })
+    }
+
+    /// Returns the distance between two lines of text, in pixels.
+    pub fn line_height(&self) -> u8 {
+        self.info().lineFeed
+    }
+
+    /// Returns `true` if the font contains a glyph for the character.
+    ///
+    /// Missing characters are drawn with the replacement glyph of the font (usually `'?'`), for which this function returns `false`.
+    #[doc(alias = "fontGlyphIndexFromCodePoint")]
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyph_index(c) != i32::from(self.info().alterCharIndex)
+    }
+
+    /// Returns the size (width, height) in pixels of the text drawn with [`SystemFont::draw()`] at the same scale.
+    pub fn measure(&self, text: &str, scale: f32) -> (usize, usize) {
+        self.layout(text, scale, |_, _, _| {})
+    }
+
+    /// Draws text with its top left corner at (`x`, `y`), blended over the contents of the view.
+    ///
+    /// `'\n'` starts a new line. The text is scaled by `scale`, with `1.0` being the native size of the font.
+    ///
+    /// Returns the size (width, height) of the text, in pixels.
+    #[doc(alias = "fontCalcGlyphPos")]
+    pub fn draw<P: Pixel + Into<Rgba8>>(
+        &self,
+        view: &mut FramebufferView<'_, P>,
+        x: isize,
+        y: isize,
+        text: &str,
+        scale: f32,
+        color: Rgba8,
+    ) -> (usize, usize) {
+        self.layout(text, scale, |index, glyph_x, glyph_y| {
+            self.rasterize(
+                view,
+                index,
+                x + glyph_x as isize,
+                y + glyph_y as isize,
+                scale,
+                color,
+            )
+        })
+    }
+
+    // Calls `place` with the glyph index and position of every character, returning the size of the text.
+    fn layout(
+        &self,
+        text: &str,
+        scale: f32,
+        mut place: impl FnMut(i32, f32, f32),
+    ) -> (usize, usize) {
+        if text.is_empty() {
+            return (0, 0);
+        }
+
+        let line_height = f32::from(self.line_height()) * scale;
+        let (mut pen_x, mut pen_y) = (0.0, 0.0);
+        let mut width: f32 = 0.0;
+
+        for c in text.chars() {
+            if c == '\n' {
+                pen_x = 0.0;
+                pen_y += line_height;
+                continue;
+            }
+
+            let index = self.glyph_index(c);
+            let widths = unsafe { &*ctru_sys::fontGetCharWidthInfo(self.font.as_ptr(), index) };
+
+            place(index, pen_x + f32::from(widths.left) * scale, pen_y);
+
+            pen_x += f32::from(widths.charWidth) * scale;
+            width = width.max(pen_x);
+        }
+
+        (width.ceil() as usize, (pen_y + line_height).ceil() as usize)
+    }
+
+    fn rasterize<P: Pixel + Into<Rgba8>>(
+        &self,
+        view: &mut FramebufferView<'_, P>,
+        index: i32,
+        x: isize,
+        y: isize,
+        scale: f32,
+        color: Rgba8,
+    ) {
+        let sheets = unsafe { &*ctru_sys::fontGetGlyphInfo(self.font.as_ptr()) };
+        let widths = unsafe { &*ctru_sys::fontGetCharWidthInfo(self.font.as_ptr(), index) };
+
+        let per_sheet = i32::from(sheets.nRows) * i32::from(sheets.nLines);
+        let (sheet_index, index_in_sheet) = (index / per_sheet, index % per_sheet);
+        let sheet =
+            unsafe { ctru_sys::fontGetGlyphSheetTex(self.font.as_ptr(), sheet_index).cast::<u8>() };
+
+        // Glyphs are laid out in a grid of cells, separated by one pixel.
+        let cell_x = (index_in_sheet % i32::from(sheets.nRows)) as usize
+            * (usize::from(sheets.cellWidth) + 1)
+            + 1;
+        let cell_y = (index_in_sheet / i32::from(sheets.nRows)) as usize
+            * (usize::from(sheets.cellHeight) + 1)
+            + 1;
+
+        let width = (f32::from(widths.glyphWidth) * scale).ceil() as usize;
+        let height = (f32::from(sheets.cellHeight) * scale).ceil() as usize;
+
+        for dy in 0..height {
+            for dx in 0..width {
+                let source_x = cell_x + (dx as f32 / scale) as usize;
+                let source_y = cell_y + (dy as f32 / scale) as usize;
+
+                let coverage = u16::from(unsafe { texel(sheets, sheet, source_x, source_y) });
+                let alpha = (u16::from(color.a) * coverage / 255) as u8;
+
+                draw::blend_clipped(
+                    view,
+                    x + dx as isize,
+                    y + dy as isize,
+                    Rgba8 { a: alpha, ..color },
+                );
+            }
+        }
+    }
+
+    fn glyph_index(&self, c: char) -> i32 {
+        unsafe { ctru_sys::fontGlyphIndexFromCodePoint(self.font.as_ptr(), c.into()) }
+    }
+
+    fn info(&self) -> &ctru_sys::FINF_s {
+        unsafe { &*ctru_sys::fontGetInfo(self.font.as_ptr()) }
+    }
+}
+
+// Reads the alpha value of a pixel of a glyph sheet.
+//
+// Sheets are stored like GPU textures: upside down, in tiles of 8x8 pixels whose pixels are in Morton order.
+unsafe fn texel(sheets: &ctru_sys::TGLP_s, sheet: *const u8, x: usize, y: usize) -> u8 {
+    let width = usize::from(sheets.sheetWidth);
+    let height = usize::from(sheets.sheetHeight);
+
+    if x >= width || y >= height {
+        return 0;
+    }
+
+    let index = tiled_index(x, y, width, height);
+
+    match u32::from(sheets.sheetFmt) {
+        format if format == ctru_sys::GPU_A4 as u32 => {
+            let byte = unsafe { *sheet.add(index / 2) };
+            let value = if index % 2 == 0 {
+                byte & 0xF
+            } else {
+                byte >> 4
+            };
+            value * 0x11
+        }
+        format if format == ctru_sys::GPU_A8 as u32 => unsafe { *sheet.add(index) },
+        // The system fonts only use alpha formats.
+        _ => 0,
+    }
+}
+
+// Decompresses data in the LZ11 format used by the system archives.
+fn decompress_lz11(data: &[u8]) -> Option<Vec<u8>> {
+    let (&[magic, a, b, c], mut input) = data.split_first_chunk::<4>()?;
+    if magic != 0x11 {
+        return None;
+    }
+
+    let mut size = u32::from_le_bytes([a, b, c, 0]) as usize;
+    if size == 0 {
+        let (extended, rest) = input.split_first_chunk::<4>()?;
+        size = u32::from_le_bytes(*extended) as usize;
+        input = rest;
+    }
+
+    let mut output = Vec::with_capacity(size);
+    let mut bytes = input.iter().copied();
+
+    while output.len() < size {
+        let flags = bytes.next()?;
+
+        for bit in (0..8).rev() {
+            if output.len() >= size {
+                break;
+            }
+
+            if flags & (1 << bit) == 0 {
+                output.push(bytes.next()?);
+                continue;
+            }
+
+            let first = usize::from(bytes.next()?);
+            let second = usize::from(bytes.next()?);
+
+            let (length, distance) = match first >> 4 {
+                0 => {
+                    let third = usize::from(bytes.next()?);
+                    (
+                        ((first & 0xF) << 4 | second >> 4) + 0x11,
+                        ((second & 0xF) << 8 | third) + 1,
+                    )
+                }
+                1 => {
+                    let third = usize::from(bytes.next()?);
+                    let fourth = usize::from(bytes.next()?);
+                    (
+                        ((first & 0xF) << 12 | second << 4 | third >> 4) + 0x111,
+                        ((third & 0xF) << 8 | fourth) + 1,
+                    )
+                }
+                high => (high + 1, ((first & 0xF) << 8 | second) + 1),
+            };
+
+            let start = output.len().checked_sub(distance)?;
+            for i in 0..length.min(size - output.len()) {
+                output.push(output[start + i]);
+            }
+        }
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lz11() {
+        assert_eq!(
+            decompress_lz11(&[0x11, 3, 0, 0, 0x00, b'a', b'b', b'c']).as_deref(),
+            Some(&b"abc"[..])
+        );
+        // Two literals, then a copy of 6 bytes from 2 bytes behind.
+        assert_eq!(
+            decompress_lz11(&[0x11, 8, 0, 0, 0x20, b'a', b'b', 0x50, 0x01]).as_deref(),
+            Some(&b"abababab"[..])
+        );
+        assert_eq!(decompress_lz11(&[0x10, 3, 0, 0, 0x00, b'a']), None);
+        assert_eq!(decompress_lz11(&[0x11, 3, 0, 0, 0x00, b'a']), None);
+    }
+}
@@ -4,15 +4,29 @@
 //! The screens are subordinate to the GFX service handle and can be used by only one borrower at a time.
 #![doc(alias = "graphics")]
 
-use std::cell::{Ref, RefCell, RefMut};
+use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
 use crate::error::Result;
 use crate::sealed::Sealed;
+use crate::services::cfgu::{SystemInfo, SystemModel};
 use crate::services::gspgpu::{self, FramebufferFormat};
 use crate::services::ServiceReference;
 
+mod capture;
+pub mod color;
+pub mod draw;
+pub mod font;
+pub mod pixel;
+pub mod texture;
+mod view;
+
+pub use capture::{Capture, CaptureMetadata, Screenshot};
+use pixel::Pixel;
+pub use view::{FramebufferView, Rotation, RowMut, RowsMut};
+
 /// Trait to handle common functionality for all screens.
 ///
 /// This trait is implemented by the screen structs for working with frame buffers and
@@ -51,6 +65,83 @@ pub trait Screen: Sealed {
         }
     }
 
+    /// Returns a typed [`FramebufferView`] for the screen, handling the rotated memory layout of the framebuffer.
+    ///
+    /// Returns [`None`] if `P` doesn't match the current [`Screen::framebuffer_format()`].
+    ///
+    /// # Panics
+    ///
+    /// If the [`Gfx`] service was initialised via [`Gfx::with_formats_vram()`] this function will crash the program with an ARM exception.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::pixel::Bgr8;
+    /// use ctru::services::gfx::{Gfx, Screen};
+    ///
+    /// let gfx = Gfx::new()?;
+    /// let mut top_screen = gfx.top_screen.borrow_mut();
+    ///
+    /// // The default format of the screens is BGR8.
+    /// let mut view = top_screen.framebuffer_view::<Bgr8>().unwrap();
+    ///
+    /// view.fill(Bgr8::new(0, 0, 0));
+    /// view.set_pixel(200, 120, Bgr8::new(0xFF, 0xFF, 0xFF));
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn framebuffer_view<P: Pixel>(&mut self) -> Option<FramebufferView<'_, P>>
+    where
+        Self: Sized,
+    {
+        if self.framebuffer_format() != P::FORMAT {
+            return None;
+        }
+
+        Some(FramebufferView::new(self.raw_framebuffer()))
+    }
+
+    /// Copies the contents of the screen's framebuffer, converting them to RGBA and to the orientation of the screen.
+    ///
+    /// # Notes
+    ///
+    /// The framebuffer copied is the one returned by [`Screen::raw_framebuffer()`]: if double buffering is enabled,
+    /// this function should be called after drawing a frame and before [swapping the buffers](Swap::swap_buffers).
+    ///
+    /// # Panics
+    ///
+    /// If the [`Gfx`] service was initialised via [`Gfx::with_formats_vram()`] this function will crash the program with an ARM exception.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::{Gfx, Screen};
+    ///
+    /// let gfx = Gfx::new()?;
+    /// let screenshot = gfx.bottom_screen.borrow_mut().capture();
+    ///
+    /// assert_eq!((screenshot.width(), screenshot.height()), (320, 240));
+    /// assert_eq!(screenshot.as_rgba().len(), 320 * 240 * 4);
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn capture(&mut self) -> Screenshot
+    where
+        Self: Sized,
+    {
+        Screenshot::capture(self)
+    }
+
     /// Gets the framebuffer format.
     #[doc(alias = "gfxGetScreenFormat")]
     fn framebuffer_format(&self) -> FramebufferFormat {
@@ -65,6 +156,74 @@ pub trait Screen: Sealed {
     fn set_framebuffer_format(&mut self, fmt: FramebufferFormat) {
         unsafe { ctru_sys::gfxSetScreenFormat(self.as_raw(), fmt.into()) }
     }
+
+    /// Change the framebuffer format, checking first that the framebuffers can be resized to fit it.
+    ///
+    /// Formats with more bytes per pixel need larger framebuffers, which [`Screen::set_framebuffer_format()`] reallocates
+    /// in the memory sector chosen when initializing [`Gfx`] (see [`FramebufferLocation`]). If that allocation fails, the process is terminated.
+    /// This function instead leaves the format unchanged and returns an error if there isn't enough free memory for the new framebuffers.
+    ///
+    /// As with [`Screen::set_framebuffer_format()`], [`Swap::swap_buffers`] must be called after this method for the configuration
+    /// change to take effect.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there isn't enough memory for the framebuffers in the new format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::{Gfx, Screen, Swap};
+    /// use ctru::services::gspgpu::FramebufferFormat;
+    ///
+    /// let gfx = Gfx::new()?;
+    /// let mut top_screen = gfx.top_screen.borrow_mut();
+    ///
+    /// // Switch to a format with transparency for a while.
+    /// top_screen.try_set_framebuffer_format(FramebufferFormat::Rgba8)?;
+    /// top_screen.swap_buffers();
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "gfxSetScreenFormat")]
+    fn try_set_framebuffer_format(&mut self, fmt: FramebufferFormat) -> Result<()> {
+        let required = framebuffers_size(self.as_raw(), fmt);
+
+        if required > framebuffers_size(self.as_raw(), self.framebuffer_format()) {
+            let free = if FRAMEBUFFERS_IN_VRAM.load(Ordering::Relaxed) {
+                unsafe { ctru_sys::vramSpaceFree() }
+            } else {
+                unsafe { ctru_sys::linearSpaceFree() }
+            } as usize;
+
+            if required > free {
+                return Err(crate::Error::Other(format!(
+                    "not enough memory for {fmt:?} framebuffers ({required} bytes needed, {free} available)"
+                )));
+            }
+        }
+
+        self.set_framebuffer_format(fmt);
+
+        Ok(())
+    }
+}
+
+// Size of the memory allocated by libctru for the (double-buffered) framebuffers of a screen.
+fn framebuffers_size(screen: ctru_sys::gfxScreen_t, fmt: FramebufferFormat) -> usize {
+    // The top screen always has room for both sides (or for wide mode).
+    let pixels = if screen == ctru_sys::GFX_TOP {
+        240 * 800
+    } else {
+        240 * 320
+    };
+
+    2 * pixels * fmt.pixel_depth_bytes()
 }
 
 /// The top LCD screen.
@@ -134,6 +293,22 @@ impl Swap for TopScreen {
     }
 }
 
+impl Swap for ScreenMut<'_> {
+    fn swap_buffers(&mut self) {
+        match self {
+            Self::Top(screen) => screen.swap_buffers(),
+            Self::Bottom(screen) => screen.swap_buffers(),
+        }
+    }
+
+    fn set_double_buffering(&mut self, enabled: bool) {
+        match self {
+            Self::Top(screen) => screen.set_double_buffering(enabled),
+            Self::Bottom(screen) => screen.set_double_buffering(enabled),
+        }
+    }
+}
+
 impl Swap for BottomScreen {
     fn swap_buffers(&mut self) {
         unsafe {
@@ -198,6 +373,16 @@ pub struct TopScreenRight;
 #[non_exhaustive]
 pub struct BottomScreen;
 
+/// Mutable borrow of either screen, as returned by [`Gfx::primary_screen()`] and [`Gfx::secondary_screen()`].
+///
+/// Both variants implement [`Screen`] and [`Swap`], so drawing code doesn't need to know which physical screen it's drawing to.
+pub enum ScreenMut<'gfx> {
+    /// The top screen.
+    Top(RefMut<'gfx, TopScreen>),
+    /// The bottom screen.
+    Bottom(RefMut<'gfx, BottomScreen>),
+}
+
 /// Representation of a framebuffer for one [`Side`] of the top screen, or the entire bottom screen.
 ///
 /// The inner pointer is only valid for one frame if double
@@ -227,6 +412,18 @@ pub enum Side {
     Right = ctru_sys::GFX_RIGHT,
 }
 
+/// Memory sector where the framebuffers of the screens are allocated.
+///
+/// See [`Gfx::with_framebuffer_location()`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FramebufferLocation {
+    /// The LINEAR memory, which is accessible by both the CPU and the GPU.
+    #[default]
+    Linear,
+    /// The VRAM, which should only be accessed by the GPU.
+    Vram,
+}
+
 /// Handle to the GFX service.
 ///
 /// This service is a wrapper around the lower-level [GSPGPU](crate::services::gspgpu) service that
@@ -236,11 +433,15 @@ pub struct Gfx {
     pub top_screen: RefCell<TopScreen>,
     /// Bottom screen representation.
     pub bottom_screen: RefCell<BottomScreen>,
+    screens_swapped: Cell<bool>,
     _service_handler: ServiceReference,
 }
 
 pub(crate) static GFX_ACTIVE: Mutex<()> = Mutex::new(());
 
+// Whether the framebuffers were allocated in VRAM, which is only known when initializing the service.
+static FRAMEBUFFERS_IN_VRAM: AtomicBool = AtomicBool::new(false);
+
 impl Gfx {
     /// Initialize a new default service handle.
     ///
@@ -350,6 +551,40 @@ impl Gfx {
         Self::with_configuration(top_fb_fmt, bottom_fb_fmt, true)
     }
 
+    /// Initialize a new service handle with the default framebuffer formats, allocating the framebuffers in the chosen memory sector.
+    ///
+    /// Allocating the framebuffers in VRAM leaves more LINEAR memory available for other uses (such as audio buffers).
+    /// Additional VRAM can be allocated with the [`VramAllocator`](crate::vram::VramAllocator).
+    ///
+    /// # Safety
+    ///
+    /// When using [`FramebufferLocation::Vram`], all functionality that relies on CPU manipulation of the framebuffers will
+    /// be completely unavailable, as with [`Gfx::with_formats_vram()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::{FramebufferLocation, Gfx};
+    ///
+    /// // Only the GPU will draw to the screens.
+    /// let gfx = unsafe { Gfx::with_framebuffer_location(FramebufferLocation::Vram)? };
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "gfxInit")]
+    pub unsafe fn with_framebuffer_location(location: FramebufferLocation) -> Result<Self> {
+        Self::with_configuration(
+            FramebufferFormat::Bgr8,
+            FramebufferFormat::Bgr8,
+            location == FramebufferLocation::Vram,
+        )
+    }
+
     // Internal function to handle the initialization of `Gfx`.
     fn with_configuration(
         top_fb_fmt: FramebufferFormat,
@@ -360,6 +595,7 @@ impl Gfx {
             &GFX_ACTIVE,
             || unsafe {
                 ctru_sys::gfxInit(top_fb_fmt.into(), bottom_fb_fmt.into(), vram_buffer);
+                FRAMEBUFFERS_IN_VRAM.store(vram_buffer, Ordering::Relaxed);
 
                 Ok(())
             },
@@ -369,6 +605,7 @@ impl Gfx {
         Ok(Self {
             top_screen: RefCell::new(TopScreen::new()),
             bottom_screen: RefCell::new(BottomScreen),
+            screens_swapped: Cell::new(false),
             _service_handler: handler,
         })
     }
@@ -404,6 +641,71 @@ impl Gfx {
     pub fn wait_for_vblank(&self) {
         gspgpu::wait_for_event(gspgpu::Event::VBlank0, true);
     }
+
+    /// Sets whether the roles of the screens are swapped.
+    ///
+    /// By default, the primary screen is the top screen and the secondary screen is the bottom screen.
+    /// Code drawing via [`Gfx::primary_screen()`] and [`Gfx::secondary_screen()`] can be moved to the other screen at runtime,
+    /// e.g. to show the main content on the touch screen when the console is held sideways.
+    ///
+    /// # Notes
+    ///
+    /// This setting only affects the screens returned by this service: the displays themselves are left untouched,
+    /// and the touch position is still read from the bottom screen.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::{Gfx, Screen};
+    ///
+    /// let gfx = Gfx::new()?;
+    /// gfx.set_screens_swapped(true);
+    ///
+    /// assert_eq!(gfx.primary_screen().as_raw(), ctru_sys::GFX_BOTTOM);
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_screens_swapped(&self, swapped: bool) {
+        self.screens_swapped.set(swapped);
+    }
+
+    /// Returns whether the roles of the screens are swapped.
+    ///
+    /// See [`Gfx::set_screens_swapped()`].
+    pub fn screens_swapped(&self) -> bool {
+        self.screens_swapped.get()
+    }
+
+    /// Mutably borrows the primary screen, which is the top screen unless the [screens are swapped](Gfx::set_screens_swapped).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the screen is already borrowed.
+    pub fn primary_screen(&self) -> ScreenMut<'_> {
+        if self.screens_swapped() {
+            ScreenMut::Bottom(self.bottom_screen.borrow_mut())
+        } else {
+            ScreenMut::Top(self.top_screen.borrow_mut())
+        }
+    }
+
+    /// Mutably borrows the secondary screen, which is the bottom screen unless the [screens are swapped](Gfx::set_screens_swapped).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the screen is already borrowed.
+    pub fn secondary_screen(&self) -> ScreenMut<'_> {
+        if self.screens_swapped() {
+            ScreenMut::Top(self.top_screen.borrow_mut())
+        } else {
+            ScreenMut::Bottom(self.bottom_screen.borrow_mut())
+        }
+    }
 }
 
 impl TopScreen3D<'_> {
@@ -418,6 +720,88 @@ impl TopScreen3D<'_> {
             (&mut screen.left, &mut screen.right)
         })
     }
+
+    /// Borrows the framebuffers of both sides of the screen as typed views, and passes them to `draw` as `(left, right)`.
+    ///
+    /// Returns [`None`] (without calling `draw`) if `P` doesn't match the current [`Screen::framebuffer_format()`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::pixel::Bgr8;
+    /// use ctru::services::gfx::{Gfx, Parallax, TopScreen3D};
+    ///
+    /// let gfx = Gfx::new()?;
+    /// let top_screen = TopScreen3D::from(&gfx.top_screen);
+    ///
+    /// // A square floating in front of the screen.
+    /// let (left_offset, right_offset) = Parallax::from_slider(20.0).offsets(-0.5);
+    ///
+    /// top_screen.draw_views::<Bgr8, _>(|mut left, mut right| {
+    ///     let white = Bgr8::new(0xFF, 0xFF, 0xFF);
+    ///
+    ///     left.fill_rect((180.0 + left_offset) as usize, 100, 40, 40, white);
+    ///     right.fill_rect((180.0 + right_offset) as usize, 100, 40, 40, white);
+    /// });
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_views<P: Pixel, R>(
+        &self,
+        draw: impl FnOnce(FramebufferView<'_, P>, FramebufferView<'_, P>) -> R,
+    ) -> Option<R> {
+        let (mut left_screen, mut right_screen) = self.split_mut();
+
+        let left = left_screen.framebuffer_view::<P>()?;
+        let right = right_screen.framebuffer_view::<P>()?;
+
+        Some(draw(left, right))
+    }
+}
+
+/// Horizontal offsets between the images of the two eyes, which make objects appear in front of or behind the screen in 3D mode.
+///
+/// The offsets are scaled by the position of the 3D slider, so that the depth effect is disabled when the slider is down.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Parallax {
+    slider: f32,
+    max_disparity: f32,
+}
+
+impl Parallax {
+    /// Creates a new parallax computation for the specified 3D slider state (between 0.0 and 1.0).
+    ///
+    /// `max_disparity` is the distance (in pixels) between the images of the two eyes for an object at the maximum depth,
+    /// with the slider all the way up. Values around 20 pixels are comfortable for most users.
+    pub fn new(slider: f32, max_disparity: f32) -> Self {
+        Self {
+            slider: slider.clamp(0.0, 1.0),
+            max_disparity,
+        }
+    }
+
+    /// Creates a new parallax computation for the current state of the 3D slider.
+    ///
+    /// See [`Parallax::new()`].
+    #[doc(alias = "osGet3DSliderState")]
+    pub fn from_slider(max_disparity: f32) -> Self {
+        Self::new(crate::os::current_3d_slider_state(), max_disparity)
+    }
+
+    /// Returns the horizontal offsets (in pixels) of an object for the `(left, right)` eyes.
+    ///
+    /// `depth` ranges from -1.0 (as far in front of the screen as possible) to 1.0 (as far behind the screen as possible),
+    /// with 0.0 being on the screen's plane.
+    pub fn offsets(&self, depth: f32) -> (f32, f32) {
+        let disparity = self.slider * self.max_disparity * depth.clamp(-1.0, 1.0);
+
+        (-disparity / 2.0, disparity / 2.0)
+    }
 }
 
 /// Convert the [`TopScreen`] into a [`TopScreen3D`] and activate stereoscopic 3D.
@@ -495,6 +879,72 @@ impl TopScreen {
     pub fn is_wide(&self) -> bool {
         unsafe { ctru_sys::gfxIsWide() }
     }
+
+    /// Returns whether the console supports wide mode, which is the case for every model except the Old 2DS.
+    pub fn is_wide_mode_supported<S: SystemInfo>(system: &S) -> crate::Result<bool>
+    where
+        crate::Error: From<S::Error>,
+    {
+        Ok(system.model()? != SystemModel::Old2DS)
+    }
+
+    /// Enables or disables wide mode on the top screen, if the console supports it.
+    ///
+    /// Returns whether wide mode is enabled after the call: enabling wide mode on an Old 2DS
+    /// leaves the screen in the regular 400x240 mode, so applications can keep rendering at that resolution.
+    ///
+    /// # Notes
+    ///
+    /// [`Swap::swap_buffers`] must be called after this method for the configuration
+    /// to take effect.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::cfgu::Cfgu;
+    /// use ctru::services::gfx::{Gfx, Swap};
+    ///
+    /// let gfx = Gfx::new()?;
+    /// let cfgu = Cfgu::new()?;
+    /// let mut top_screen = gfx.top_screen.borrow_mut();
+    ///
+    /// let wide = top_screen.try_set_wide_mode(true, &cfgu)?;
+    /// top_screen.swap_buffers();
+    ///
+    /// assert_eq!(top_screen.framebuffer_width(), if wide { 800 } else { 400 });
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "gfxSetWide")]
+    pub fn try_set_wide_mode<S: SystemInfo>(
+        &mut self,
+        enable: bool,
+        system: &S,
+    ) -> crate::Result<bool>
+    where
+        crate::Error: From<S::Error>,
+    {
+        let enable = enable && Self::is_wide_mode_supported(system)?;
+        self.set_wide_mode(enable);
+
+        Ok(enable)
+    }
+
+    /// Returns the horizontal resolution (in pixels) of the top screen's framebuffer: 800 in wide mode, 400 otherwise.
+    ///
+    /// This is the width of the [`FramebufferView`] of the top screen.
+    pub fn framebuffer_width(&self) -> usize {
+        if self.is_wide() {
+            800
+        } else {
+            400
+        }
+    }
 }
 
 // When 3D mode is disabled, only the left side is used, so this Screen impl
@@ -539,6 +989,22 @@ impl Screen for BottomScreen {
     }
 }
 
+impl Screen for ScreenMut<'_> {
+    fn as_raw(&self) -> ctru_sys::gfxScreen_t {
+        match self {
+            Self::Top(screen) => screen.as_raw(),
+            Self::Bottom(screen) => screen.as_raw(),
+        }
+    }
+
+    fn side(&self) -> Side {
+        match self {
+            Self::Top(screen) => screen.side(),
+            Self::Bottom(screen) => screen.side(),
+        }
+    }
+}
+
 from_impl!(Side, ctru_sys::gfx3dSide_t);
 
 #[cfg(test)]
@@ -554,4 +1020,15 @@ mod tests {
 
         assert!(matches!(Gfx::new(), Err(Error::ServiceAlreadyActive)));
     }
+
+    #[test]
+    fn parallax_offsets() {
+        let parallax = Parallax::new(0.5, 20.0);
+
+        assert_eq!(parallax.offsets(0.0), (0.0, 0.0));
+        // Objects in front of the screen are shifted right for the left eye.
+        assert_eq!(parallax.offsets(-1.0), (5.0, -5.0));
+        assert_eq!(parallax.offsets(2.0), (-5.0, 5.0));
+        assert_eq!(Parallax::new(0.0, 20.0).offsets(1.0), (0.0, 0.0));
+    }
 }
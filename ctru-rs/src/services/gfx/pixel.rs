@@ -0,0 +1,250 @@
+//! Framebuffer pixel formats.
+//!
+//! This module contains one type for each of the [`FramebufferFormat`]s supported by the screens,
+//! all implementing the [`Pixel`] trait to convert from and to the byte layout used in the framebuffer memory.
+
+use crate::sealed::Sealed;
+use crate::services::gspgpu::FramebufferFormat;
+
+/// Pixel stored in a framebuffer with a specific [`FramebufferFormat`].
+///
+/// Every pixel format can be created from an [`Rgba8`] color, so that graphics code can be made generic over this trait.
+pub trait Pixel: Copy + From<Rgba8> + Sealed {
+    /// Framebuffer format using this pixel type.
+    const FORMAT: FramebufferFormat;
+
+    /// Size of a single pixel (in bytes).
+    const SIZE: usize;
+
+    /// Reads a pixel from its framebuffer representation.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `bytes` is shorter than [`Pixel::SIZE`].
+    fn read(bytes: &[u8]) -> Self;
+
+    /// Writes the pixel in its framebuffer representation.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `bytes` is shorter than [`Pixel::SIZE`].
+    fn write(self, bytes: &mut [u8]);
+}
+
+/// 32-bit color with 8 bits per channel.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgba8 {
+    /// Red channel.
+    pub r: u8,
+    /// Green channel.
+    pub g: u8,
+    /// Blue channel.
+    pub b: u8,
+    /// Alpha channel.
+    pub a: u8,
+}
+
+/// 24-bit color with 8 bits per channel and no alpha.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bgr8 {
+    /// Red channel.
+    pub r: u8,
+    /// Green channel.
+    pub g: u8,
+    /// Blue channel.
+    pub b: u8,
+}
+
+/// 16-bit color with 5 bits of red, 6 bits of green and 5 bits of blue.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb565(pub u16);
+
+/// 16-bit color with 5 bits per color channel and 1 bit of alpha.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb5A1(pub u16);
+
+/// 16-bit color with 4 bits per channel.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgba4(pub u16);
+
+impl Rgba8 {
+    /// Creates a new color from its channels.
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Creates a new fully opaque color.
+    pub const fn opaque(r: u8, g: u8, b: u8) -> Self {
+        Self::new(r, g, b, 0xFF)
+    }
+}
+
+impl Bgr8 {
+    /// Creates a new color from its channels.
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl Rgb565 {
+    /// Creates a new color from 8-bit channels, discarding their lowest bits.
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self((r as u16 >> 3) << 11 | (g as u16 >> 2) << 5 | b as u16 >> 3)
+    }
+}
+
+impl Rgb5A1 {
+    /// Creates a new color from 8-bit channels, discarding their lowest bits.
+    ///
+    /// The pixel is opaque if `a` is at least `0x80`.
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self((r as u16 >> 3) << 11 | (g as u16 >> 3) << 6 | (b as u16 >> 3) << 1 | a as u16 >> 7)
+    }
+}
+
+impl Rgba4 {
+    /// Creates a new color from 8-bit channels, discarding their lowest bits.
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self((r as u16 >> 4) << 12 | (g as u16 >> 4) << 8 | (b as u16 >> 4) << 4 | a as u16 >> 4)
+    }
+}
+
+// Expands a channel with `bits` significant bits to the full 8-bit range.
+const fn expand(value: u16, bits: u32) -> u8 {
+    let value = (value & ((1 << bits) - 1)) as u8;
+    (value << (8 - bits)) | (value >> (2 * bits).saturating_sub(8))
+}
+
+impl Pixel for Rgba8 {
+    const FORMAT: FramebufferFormat = FramebufferFormat::Rgba8;
+    const SIZE: usize = 4;
+
+    fn read(bytes: &[u8]) -> Self {
+        // The framebuffer stores the channels in reverse order (little-endian RGBA).
+        Self::new(bytes[3], bytes[2], bytes[1], bytes[0])
+    }
+
+    fn write(self, bytes: &mut [u8]) {
+        bytes[..4].copy_from_slice(&[self.a, self.b, self.g, self.r]);
+    }
+}
+
+impl Pixel for Bgr8 {
+    const FORMAT: FramebufferFormat = FramebufferFormat::Bgr8;
+    const SIZE: usize = 3;
+
+    fn read(bytes: &[u8]) -> Self {
+        Self::new(bytes[2], bytes[1], bytes[0])
+    }
+
+    fn write(self, bytes: &mut [u8]) {
+        bytes[..3].copy_from_slice(&[self.b, self.g, self.r]);
+    }
+}
+
+macro_rules! impl_16bit_pixel {
+    ($pixel:ident) => {
+        impl Pixel for $pixel {
+            const FORMAT: FramebufferFormat = FramebufferFormat::$pixel;
+            const SIZE: usize = 2;
+
+            fn read(bytes: &[u8]) -> Self {
+                Self(u16::from_le_bytes([bytes[0], bytes[1]]))
+            }
+
+            fn write(self, bytes: &mut [u8]) {
+                bytes[..2].copy_from_slice(&self.0.to_le_bytes());
+            }
+        }
+    };
+}
+
+impl_16bit_pixel!(Rgb565);
+impl_16bit_pixel!(Rgb5A1);
+impl_16bit_pixel!(Rgba4);
+
+impl From<Rgba8> for Bgr8 {
+    fn from(value: Rgba8) -> Self {
+        Self::new(value.r, value.g, value.b)
+    }
+}
+
+impl From<Rgba8> for Rgb565 {
+    fn from(value: Rgba8) -> Self {
+        Self::new(value.r, value.g, value.b)
+    }
+}
+
+impl From<Rgba8> for Rgb5A1 {
+    fn from(value: Rgba8) -> Self {
+        Self::new(value.r, value.g, value.b, value.a)
+    }
+}
+
+impl From<Rgba8> for Rgba4 {
+    fn from(value: Rgba8) -> Self {
+        Self::new(value.r, value.g, value.b, value.a)
+    }
+}
+
+impl From<Bgr8> for Rgba8 {
+    fn from(value: Bgr8) -> Self {
+        Self::opaque(value.r, value.g, value.b)
+    }
+}
+
+impl From<Rgb565> for Rgba8 {
+    fn from(value: Rgb565) -> Self {
+        Self::opaque(
+            expand(value.0 >> 11, 5),
+            expand(value.0 >> 5, 6),
+            expand(value.0, 5),
+        )
+    }
+}
+
+impl From<Rgb5A1> for Rgba8 {
+    fn from(value: Rgb5A1) -> Self {
+        Self::new(
+            expand(value.0 >> 11, 5),
+            expand(value.0 >> 6, 5),
+            expand(value.0 >> 1, 5),
+            if value.0 & 1 == 1 { 0xFF } else { 0 },
+        )
+    }
+}
+
+impl From<Rgba4> for Rgba8 {
+    fn from(value: Rgba4) -> Self {
+        Self::new(
+            expand(value.0 >> 12, 4),
+            expand(value.0 >> 8, 4),
+            expand(value.0 >> 4, 4),
+            expand(value.0, 4),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba8_layout() {
+        let mut bytes = [0; 4];
+        Rgba8::new(1, 2, 3, 4).write(&mut bytes);
+
+        assert_eq!(bytes, [4, 3, 2, 1]);
+        assert_eq!(Rgba8::read(&bytes), Rgba8::new(1, 2, 3, 4));
+    }
+
+    #[test]
+    fn rgb565_round_trip() {
+        let white = Rgb565::from(Rgba8::opaque(0xFF, 0xFF, 0xFF));
+        assert_eq!(white, Rgb565(0xFFFF));
+        assert_eq!(Rgba8::from(white), Rgba8::opaque(0xFF, 0xFF, 0xFF));
+
+        let red = Rgb565::from(Rgba8::opaque(0xFF, 0, 0));
+        assert_eq!(Rgba8::from(red), Rgba8::opaque(0xFF, 0, 0));
+    }
+}
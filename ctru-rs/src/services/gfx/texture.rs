@@ -0,0 +1,395 @@
+//! GPU texture staging and background asset loading.
+//!
+//! The GPU of the 3DS reads textures from LINEAR memory, in a tiled layout: the image is stored upside down,
+//! in tiles of 8x8 pixels whose pixels are in Morton (Z) order. Both dimensions must be powers of two between 8 and 1024.
+//! [`Texture`] converts a decoded [`Image`] to this layout, in any of the [`Pixel`] formats (which share their byte layout with the matching texture formats).
+//!
+//! Decoding and converting large images takes a while, so [`Loader`] runs both on worker threads,
+//! reporting the results through a [`PendingTexture`] or a callback while the main thread keeps rendering.
+//!
+//! # Notes
+//!
+//! This module doesn't include any image decoder. The decoding function is provided by the application,
+//! usually wrapping a crate like [`png`](https://crates.io/crates/png) or [`zune-jpeg`](https://crates.io/crates/zune-jpeg).
+#![doc(alias = "tiling")]
+#![doc(alias = "swizzle")]
+
+use std::marker::PhantomData;
+use std::os::horizon::thread::BuilderExt;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use super::pixel::{Pixel, Rgba8};
+use crate::error::Error;
+use crate::linear::LinearAllocator;
+
+/// Minimum size of a texture side (in pixels).
+pub const MIN_TEXTURE_SIZE: usize = 8;
+
+/// Maximum size of a texture side (in pixels).
+pub const MAX_TEXTURE_SIZE: usize = 1024;
+
+/// Decoded image, with its pixels in row-major order starting from the top-left corner.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgba8>,
+}
+
+/// Image converted to the tiled layout of the GPU, stored in LINEAR memory.
+///
+/// The texture is padded to the next valid size, with transparent pixels on the right and bottom sides.
+/// Use [`Texture::image_size()`] to compute the texture coordinates of the image.
+pub struct Texture<P: Pixel> {
+    width: usize,
+    height: usize,
+    image_width: usize,
+    image_height: usize,
+    data: Box<[u8], LinearAllocator>,
+    _format: PhantomData<P>,
+}
+
+/// Function decoding the bytes of an asset file into an [`Image`].
+pub type Decoder = dyn Fn(&[u8]) -> Result<Image, String> + Send + Sync;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Pool of worker threads decoding assets into [`Texture`]s.
+///
+/// Dropping the loader waits for the queued assets to be loaded.
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// use ctru::services::gfx::pixel::{Rgb565, Rgba8};
+/// use ctru::services::gfx::texture::{Image, Loader};
+///
+/// // A real application would call an image decoding crate here.
+/// let loader = Loader::new(|bytes: &[u8]| {
+///     let pixels = bytes.iter().map(|&v| Rgba8::opaque(v, v, v)).collect();
+///     Image::new(4, 4, pixels).ok_or_else(|| String::from("invalid size"))
+/// });
+///
+/// let pending = loader.load::<Rgb565>(vec![0x80; 16]);
+///
+/// // Render other frames in the meantime...
+///
+/// let texture = pending.wait().unwrap();
+/// assert_eq!(texture.size(), (8, 8));
+/// assert_eq!(texture.image_size(), (4, 4));
+/// ```
+pub struct Loader {
+    decoder: Arc<Decoder>,
+    jobs: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// Texture being loaded by a [`Loader`].
+#[must_use]
+pub struct PendingTexture<P: Pixel> {
+    receiver: Receiver<crate::Result<Texture<P>>>,
+}
+
+impl Image {
+    /// Creates an image from its pixels, in row-major order starting from the top-left corner.
+    ///
+    /// Returns [`None`] if the number of pixels doesn't match the size, or if the image is too big to be a texture.
+    pub fn new(width: usize, height: usize, pixels: Vec<Rgba8>) -> Option<Self> {
+        if width == 0
+            || height == 0
+            || width > MAX_TEXTURE_SIZE
+            || height > MAX_TEXTURE_SIZE
+            || pixels.len() != width * height
+        {
+            return None;
+        }
+
+        Some(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Creates an image from RGBA bytes (4 bytes per pixel, in the R, G, B, A order), as returned by most decoders.
+    ///
+    /// Returns [`None`] if the amount of bytes doesn't match the size, or if the image is too big to be a texture.
+    pub fn from_rgba_bytes(width: usize, height: usize, bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != width * height * 4 {
+            return None;
+        }
+
+        let pixels = bytes
+            .chunks_exact(4)
+            .map(|p| Rgba8::new(p[0], p[1], p[2], p[3]))
+            .collect();
+
+        Self::new(width, height, pixels)
+    }
+
+    /// Returns the (width, height) of the image.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the pixels of the image.
+    pub fn pixels(&self) -> &[Rgba8] {
+        &self.pixels
+    }
+
+    /// Returns the pixel at the given position, if it's inside the image.
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgba8> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+}
+
+impl<P: Pixel> Texture<P> {
+    /// Converts an image to a texture.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there isn't enough LINEAR memory left for the texture.
+    pub fn from_image(image: &Image) -> crate::Result<Self> {
+        let width = texture_side(image.width);
+        let height = texture_side(image.height);
+        let len = width * height * P::SIZE;
+
+        let mut data = Vec::new_in(LinearAllocator);
+        data.try_reserve_exact(len).map_err(|_| {
+            Error::Other(format!(
+                "not enough LINEAR memory for a {width}x{height} texture"
+            ))
+        })?;
+        data.resize(len, 0);
+
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let offset = tiled_index(x, y, width, height) * P::SIZE;
+                P::from(image.pixels[y * image.width + x]).write(&mut data[offset..]);
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            image_width: image.width,
+            image_height: image.height,
+            data: data.into_boxed_slice(),
+            _format: PhantomData,
+        })
+    }
+
+    /// Returns the (width, height) of the texture, including the padding.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the (width, height) of the image stored in the texture.
+    pub fn image_size(&self) -> (usize, usize) {
+        (self.image_width, self.image_height)
+    }
+
+    /// Returns the tiled data of the texture, ready to be used by the GPU.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the pixel at the given position (starting from the top-left corner), if it's inside the texture.
+    pub fn get(&self, x: usize, y: usize) -> Option<P> {
+        if x < self.width && y < self.height {
+            Some(P::read(
+                &self.data[tiled_index(x, y, self.width, self.height) * P::SIZE..],
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl<P: Pixel> std::fmt::Debug for Texture<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Texture")
+            .field("format", &P::FORMAT)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("image_width", &self.image_width)
+            .field("image_height", &self.image_height)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Loader {
+    /// Creates a loader with a single worker thread, running on the same core as the calling thread.
+    pub fn new(decoder: impl Fn(&[u8]) -> Result<Image, String> + Send + Sync + 'static) -> Self {
+        Self::with_workers(decoder, 1, -2)
+    }
+
+    /// Creates a loader with `workers` threads, running on the core `processor_id`.
+    ///
+    /// Use `-2` for the core of the calling thread, or `1` to decode on the syscore
+    /// (which requires a time limit set via [`Apt::set_app_cpu_time_limit()`](crate::services::apt::Apt::set_app_cpu_time_limit)).
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `workers` is 0, or if the threads couldn't be spawned.
+    pub fn with_workers(
+        decoder: impl Fn(&[u8]) -> Result<Image, String> + Send + Sync + 'static,
+        workers: usize,
+        processor_id: i32,
+    ) -> Self {
+        assert!(workers > 0, "at least one worker is needed");
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..workers)
+            .map(|i| {
+                let receiver = receiver.clone();
+
+                std::thread::Builder::new()
+                    .name(format!("texture loader #{i}"))
+                    .processor_id(processor_id)
+                    .spawn(move || loop {
+                        // The lock is released before running the job, so that other workers can pick the next one.
+                        let job = receiver.lock().unwrap().recv();
+
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("failed to spawn a texture loader thread")
+            })
+            .collect();
+
+        Self {
+            decoder: Arc::new(decoder),
+            jobs: Some(sender),
+            workers,
+        }
+    }
+
+    /// Queues the decoding of an asset, returning a handle to wait for the texture.
+    pub fn load<P: Pixel + Send + 'static>(&self, bytes: Vec<u8>) -> PendingTexture<P> {
+        let (sender, receiver) = mpsc::channel();
+
+        self.load_with(bytes, move |result| {
+            // The handle may have been dropped, in which case nobody is waiting for the texture.
+            let _ = sender.send(result);
+        });
+
+        PendingTexture { receiver }
+    }
+
+    /// Queues the decoding of an asset, calling `callback` with the texture on the worker thread.
+    pub fn load_with<P: Pixel + Send + 'static>(
+        &self,
+        bytes: Vec<u8>,
+        callback: impl FnOnce(crate::Result<Texture<P>>) + Send + 'static,
+    ) {
+        let decoder = self.decoder.clone();
+
+        let job = Box::new(move || {
+            let result = decoder(&bytes)
+                .map_err(|e| Error::Other(format!("failed to decode an asset: {e}")))
+                .and_then(|image| Texture::from_image(&image));
+
+            callback(result);
+        });
+
+        // The workers only stop once the sender is dropped.
+        let _ = self.jobs.as_ref().unwrap().send(job);
+    }
+}
+
+impl Drop for Loader {
+    fn drop(&mut self) {
+        // Closing the channel stops the workers once the queue is empty.
+        self.jobs.take();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<P: Pixel> PendingTexture<P> {
+    /// Returns the texture if it's done loading, or [`None`] if it's still being loaded.
+    pub fn try_take(&self) -> Option<crate::Result<Texture<P>>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::Other(String::from(
+                "the texture loader panicked",
+            )))),
+        }
+    }
+
+    /// Blocks until the texture is done loading.
+    pub fn wait(self) -> crate::Result<Texture<P>> {
+        self.receiver
+            .recv()
+            .unwrap_or_else(|_| Err(Error::Other(String::from("the texture loader panicked"))))
+    }
+}
+
+// Smallest valid texture side containing `size` pixels.
+fn texture_side(size: usize) -> usize {
+    size.next_power_of_two().max(MIN_TEXTURE_SIZE)
+}
+
+// Index of a pixel (starting from the top-left corner) in a texture with the tiled layout of the GPU.
+pub(super) fn tiled_index(x: usize, y: usize, width: usize, height: usize) -> usize {
+    let y = height - 1 - y;
+    let tile = (y / 8) * (width / 8) + x / 8;
+
+    tile * 64 + morton(x % 8, y % 8)
+}
+
+// Interleaves the bits of the coordinates of a pixel inside of a tile.
+pub(crate) fn morton(x: usize, y: usize) -> usize {
+    (0..3).fold(0, |index, bit| {
+        index | ((x >> bit) & 1) << (2 * bit) | ((y >> bit) & 1) << (2 * bit + 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn morton_order() {
+        assert_eq!(morton(0, 0), 0);
+        assert_eq!(morton(1, 0), 1);
+        assert_eq!(morton(0, 1), 2);
+        assert_eq!(morton(7, 7), 63);
+    }
+
+    #[test]
+    fn tiled_layout() {
+        // The bottom-left pixel comes first, and the tiles are ordered left to right, bottom to top.
+        assert_eq!(tiled_index(0, 15, 16, 16), 0);
+        assert_eq!(tiled_index(1, 15, 16, 16), 1);
+        assert_eq!(tiled_index(0, 14, 16, 16), 2);
+        assert_eq!(tiled_index(7, 8, 16, 16), 63);
+        assert_eq!(tiled_index(8, 15, 16, 16), 64);
+        assert_eq!(tiled_index(0, 7, 16, 16), 128);
+
+        let mut indices: Vec<usize> = (0..16)
+            .flat_map(|y| (0..16).map(move |x| tiled_index(x, y, 16, 16)))
+            .collect();
+        indices.sort_unstable();
+        assert!(indices.into_iter().eq(0..256));
+
+        assert_eq!(texture_side(1), 8);
+        assert_eq!(texture_side(100), 128);
+        assert_eq!(texture_side(256), 256);
+    }
+}
@@ -0,0 +1,448 @@
+use std::marker::PhantomData;
+
+use super::pixel::Pixel;
+use super::RawFrameBuffer;
+
+/// Typed view over the framebuffer of a [`Screen`](super::Screen).
+///
+/// The framebuffers of the 3DS are rotated 90 degrees counter-clockwise compared to what is shown on the screens:
+/// contiguous memory goes from the bottom to the top of the screen, one column at a time.
+/// This view hides that layout, addressing pixels via screen coordinates where (0, 0) is the top left corner.
+///
+/// The view can also be [rotated](FramebufferView::rotate), for applications meant to be played with the console held sideways.
+///
+/// This struct can be retrieved via [`Screen::framebuffer_view()`](super::Screen::framebuffer_view).
+///
+/// # Lifetime
+///
+/// The view mutably borrows its screen, so it's only valid for the frame being drawn:
+/// the buffers can't be [swapped](super::Swap::swap_buffers) (which would make the view point to the buffer being shown)
+/// until the view is dropped.
+///
+/// ```compile_fail
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::gfx::pixel::Bgr8;
+/// use ctru::services::gfx::{Gfx, Screen, Swap};
+///
+/// let gfx = Gfx::new()?;
+/// let mut top_screen = gfx.top_screen.borrow_mut();
+///
+/// let mut view = top_screen.framebuffer_view::<Bgr8>().unwrap();
+/// top_screen.swap_buffers();
+///
+/// // Error: the view can't be used after the buffers are swapped.
+/// view.set_pixel(0, 0, Bgr8::new(0xFF, 0xFF, 0xFF));
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "Frame")]
+pub struct FramebufferView<'screen, P: Pixel> {
+    ptr: *mut u8,
+    layout: Layout,
+    _pixel: PhantomData<P>,
+    _screen: PhantomData<&'screen mut [u8]>,
+}
+
+// Mapping between view coordinates and pixel indices in the framebuffer memory.
+#[derive(Clone, Copy, Debug)]
+struct Layout {
+    width: usize,
+    height: usize,
+    // Index of the pixel at (0, 0).
+    origin: isize,
+    // Distance between horizontally and vertically adjacent pixels, which may be negative.
+    step_x: isize,
+    step_y: isize,
+}
+
+/// Clockwise rotation of a [`FramebufferView`].
+///
+/// The screens of the 3DS are meant to be seen in landscape orientation: rotating a view by 90 or 270 degrees
+/// allows drawing in portrait orientation, with the console held sideways like a book.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    /// No rotation.
+    #[default]
+    None,
+    /// Rotation of 90 degrees. The top of the view is on the right side of the screen.
+    Clockwise90,
+    /// Rotation of 180 degrees. The view is upside down.
+    Clockwise180,
+    /// Rotation of 270 degrees. The top of the view is on the left side of the screen.
+    Clockwise270,
+}
+
+/// Mutable view over a single row of a [`FramebufferView`].
+///
+/// Rows are not contiguous in memory, so the pixels must be accessed one by one.
+pub struct RowMut<'view, P: Pixel> {
+    ptr: *mut u8,
+    width: usize,
+    // Distance in bytes between consecutive pixels of the row.
+    stride: isize,
+    _pixel: PhantomData<P>,
+    _view: PhantomData<&'view mut [u8]>,
+}
+
+/// Iterator over the mutable rows of a [`FramebufferView`], from top to bottom.
+///
+/// This struct can be retrieved via [`FramebufferView::rows_mut()`].
+pub struct RowsMut<'view, P: Pixel> {
+    ptr: *mut u8,
+    layout: Layout,
+    next: usize,
+    _pixel: PhantomData<P>,
+    _view: PhantomData<&'view mut [u8]>,
+}
+
+impl<'screen, P: Pixel> FramebufferView<'screen, P> {
+    pub(super) fn new(framebuffer: RawFrameBuffer<'screen>) -> Self {
+        // The raw framebuffer reports its size in memory order, which is rotated compared to the screen.
+        let (width, height) = (framebuffer.height, framebuffer.width);
+
+        Self {
+            ptr: framebuffer.ptr,
+            layout: Layout {
+                width,
+                height,
+                origin: height as isize - 1,
+                step_x: height as isize,
+                step_y: -1,
+            },
+            _pixel: PhantomData,
+            _screen: PhantomData,
+        }
+    }
+
+    /// Returns the width of the view (in pixels).
+    pub fn width(&self) -> usize {
+        self.layout.width
+    }
+
+    /// Returns the height of the view (in pixels).
+    pub fn height(&self) -> usize {
+        self.layout.height
+    }
+
+    /// Rotates the view clockwise, relative to its current orientation.
+    ///
+    /// After a rotation of 90 or 270 degrees, the width and the height of the view are swapped.
+    /// Use [`Rotation::to_view()`] to convert touch positions to the rotated coordinates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::pixel::Bgr8;
+    /// use ctru::services::gfx::{Gfx, Rotation, Screen};
+    ///
+    /// let gfx = Gfx::new()?;
+    /// let mut bottom_screen = gfx.bottom_screen.borrow_mut();
+    ///
+    /// // Portrait view, for a console held like a book.
+    /// let mut view = bottom_screen
+    ///     .framebuffer_view::<Bgr8>()
+    ///     .unwrap()
+    ///     .rotate(Rotation::Clockwise90);
+    ///
+    /// assert_eq!((view.width(), view.height()), (240, 320));
+    /// view.fill_rect(0, 0, 240, 20, Bgr8::new(0xFF, 0, 0));
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rotate(mut self, rotation: Rotation) -> Self {
+        for _ in 0..rotation.quarter_turns() {
+            let l = self.layout;
+
+            // The new (x, y) is the old (width - 1 - y, x).
+            self.layout = Layout {
+                width: l.height,
+                height: l.width,
+                origin: l.origin + (l.width as isize - 1) * l.step_x,
+                step_x: l.step_y,
+                step_y: -l.step_x,
+            };
+        }
+
+        self
+    }
+
+    // Offset in bytes of the pixel at the specified view coordinates.
+    fn offset(&self, x: usize, y: usize) -> usize {
+        self.layout.index(x, y) * P::SIZE
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the framebuffer is valid for the whole lifetime of the view, which borrows the screen mutably.
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                self.ptr,
+                self.layout.width * self.layout.height * P::SIZE,
+            )
+        }
+    }
+
+    /// Returns the pixel at the specified view coordinates, or [`None`] if they are out of bounds.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<P> {
+        if x >= self.layout.width || y >= self.layout.height {
+            return None;
+        }
+
+        let offset = self.offset(x, y);
+
+        // SAFETY: the offset is in bounds, and the framebuffer is valid for the whole lifetime of the view.
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr.add(offset), P::SIZE) };
+
+        Some(P::read(bytes))
+    }
+
+    /// Sets the pixel at the specified view coordinates.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the coordinates are out of bounds.
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: P) {
+        assert!(
+            x < self.layout.width && y < self.layout.height,
+            "pixel ({x}, {y}) is out of bounds for a {}x{} view",
+            self.layout.width,
+            self.layout.height
+        );
+
+        let offset = self.offset(x, y);
+        color.write(&mut self.bytes_mut()[offset..offset + P::SIZE]);
+    }
+
+    /// Fills the whole view with a single color.
+    pub fn fill(&mut self, color: P) {
+        let mut pixel = [0; 4];
+        color.write(&mut pixel);
+
+        for chunk in self.bytes_mut().chunks_exact_mut(P::SIZE) {
+            chunk.copy_from_slice(&pixel[..P::SIZE]);
+        }
+    }
+
+    /// Fills a rectangle with a single color.
+    ///
+    /// The rectangle starts at the top left corner (`x`, `y`) and is clipped to the view bounds.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: P) {
+        let x_end = x.saturating_add(width).min(self.layout.width);
+        let y_end = y.saturating_add(height).min(self.layout.height);
+
+        if x >= x_end || y >= y_end {
+            return;
+        }
+
+        let mut pixel = [0; 4];
+        color.write(&mut pixel);
+
+        // Every physical column of the screen is contiguous in memory, which is either a column or a row of the view.
+        let lines: Vec<_> = if self.layout.step_y.abs() == 1 {
+            (x..x_end)
+                .map(|column| (self.offset(column, y), self.offset(column, y_end - 1)))
+                .collect()
+        } else {
+            (y..y_end)
+                .map(|row| (self.offset(x, row), self.offset(x_end - 1, row)))
+                .collect()
+        };
+
+        for (first, last) in lines {
+            let (start, end) = (first.min(last), first.max(last) + P::SIZE);
+
+            for chunk in self.bytes_mut()[start..end].chunks_exact_mut(P::SIZE) {
+                chunk.copy_from_slice(&pixel[..P::SIZE]);
+            }
+        }
+    }
+
+    /// Returns a mutable view over the row at the specified height, or [`None`] if it is out of bounds.
+    pub fn row_mut(&mut self, y: usize) -> Option<RowMut<'_, P>> {
+        if y >= self.layout.height {
+            return None;
+        }
+
+        Some(self.layout.row(self.ptr, y))
+    }
+
+    /// Returns an iterator over the rows of the view, from top to bottom.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gfx::pixel::Rgb565;
+    /// use ctru::services::gfx::{Gfx, Screen};
+    /// use ctru::services::gspgpu::FramebufferFormat;
+    ///
+    /// let gfx = Gfx::new()?;
+    /// let mut bottom_screen = gfx.bottom_screen.borrow_mut();
+    /// bottom_screen.set_framebuffer_format(FramebufferFormat::Rgb565);
+    ///
+    /// let mut view = bottom_screen.framebuffer_view::<Rgb565>().unwrap();
+    ///
+    /// // Draw a vertical gradient.
+    /// for (y, mut row) in view.rows_mut().enumerate() {
+    ///     row.fill(Rgb565::new(0, y as u8, 0));
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rows_mut(&mut self) -> RowsMut<'_, P> {
+        RowsMut {
+            ptr: self.ptr,
+            layout: self.layout,
+            next: 0,
+            _pixel: PhantomData,
+            _view: PhantomData,
+        }
+    }
+}
+
+impl Rotation {
+    fn quarter_turns(self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::Clockwise90 => 1,
+            Self::Clockwise180 => 2,
+            Self::Clockwise270 => 3,
+        }
+    }
+
+    /// Converts a position on a screen of the specified size (in landscape orientation) to the coordinates of a view with this rotation.
+    ///
+    /// This is mostly useful to convert the [touch position](crate::services::hid::InputSource::touch_position)
+    /// for a rotated view of the bottom screen, which is 320x240 pixels.
+    ///
+    /// Returns [`None`] if the position is outside of the screen.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ctru::services::gfx::Rotation;
+    ///
+    /// // The top right corner of the screen is the top left corner of the rotated view.
+    /// assert_eq!(Rotation::Clockwise90.to_view((319, 0), (320, 240)), Some((0, 0)));
+    /// assert_eq!(Rotation::Clockwise90.to_view((320, 0), (320, 240)), None);
+    /// ```
+    pub fn to_view(self, position: (u16, u16), screen_size: (u16, u16)) -> Option<(u16, u16)> {
+        let ((mut x, mut y), (mut width, mut height)) = (position, screen_size);
+
+        if x >= width || y >= height {
+            return None;
+        }
+
+        for _ in 0..self.quarter_turns() {
+            (x, y) = (y, width - 1 - x);
+            (width, height) = (height, width);
+        }
+
+        Some((x, y))
+    }
+}
+
+impl Layout {
+    fn index(&self, x: usize, y: usize) -> usize {
+        (self.origin + x as isize * self.step_x + y as isize * self.step_y) as usize
+    }
+
+    fn row<'view, P: Pixel>(&self, ptr: *mut u8, y: usize) -> RowMut<'view, P> {
+        RowMut {
+            // SAFETY: the offset of the first pixel of the row is in bounds.
+            ptr: unsafe { ptr.add(self.index(0, y) * P::SIZE) },
+            width: self.width,
+            stride: self.step_x * P::SIZE as isize,
+            _pixel: PhantomData,
+            _view: PhantomData,
+        }
+    }
+}
+
+impl<P: Pixel> RowMut<'_, P> {
+    /// Returns the amount of pixels in the row.
+    pub fn len(&self) -> usize {
+        self.width
+    }
+
+    /// Returns `true` if the row contains no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0
+    }
+
+    /// Returns the pixel at the specified horizontal position, or [`None`] if it is out of bounds.
+    pub fn get(&self, x: usize) -> Option<P> {
+        if x >= self.width {
+            return None;
+        }
+
+        // SAFETY: the position is in bounds, and no other view can access the pixels of this row.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(self.ptr.offset(x as isize * self.stride), P::SIZE)
+        };
+
+        Some(P::read(bytes))
+    }
+
+    /// Sets the pixel at the specified horizontal position.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the position is out of bounds.
+    pub fn set(&mut self, x: usize, color: P) {
+        assert!(
+            x < self.width,
+            "pixel {x} is out of bounds for a row of {} pixels",
+            self.width
+        );
+
+        // SAFETY: the position is in bounds, and no other view can access the pixels of this row.
+        let bytes = unsafe {
+            std::slice::from_raw_parts_mut(self.ptr.offset(x as isize * self.stride), P::SIZE)
+        };
+
+        color.write(bytes);
+    }
+
+    /// Fills the whole row with a single color.
+    pub fn fill(&mut self, color: P) {
+        for x in 0..self.width {
+            self.set(x, color);
+        }
+    }
+}
+
+impl<'view, P: Pixel> Iterator for RowsMut<'view, P> {
+    type Item = RowMut<'view, P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.layout.height {
+            return None;
+        }
+
+        let y = self.next;
+        self.next += 1;
+
+        // Every row yielded by the iterator accesses a different set of pixels, so they can be alive at the same time.
+        Some(self.layout.row(self.ptr, y))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.layout.height - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<P: Pixel> ExactSizeIterator for RowsMut<'_, P> {}
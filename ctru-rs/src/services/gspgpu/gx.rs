@@ -0,0 +1,425 @@
+//! Scheduling of GX operations.
+//!
+//! Besides 3D rendering, the GPU has two engines working asynchronously from the CPU: the memory fill engine (PSC), which clears buffers,
+//! and the transfer engine (PPF), which copies buffers and converts images between formats (e.g. to show a rendered image on a screen).
+//! Mixing both engines is error-prone: an operation started before the completion of the one writing its input
+//! (e.g. a display transfer racing the fill which clears its source) leads to corrupted images.
+//!
+//! [`Scheduler`] tracks the buffers used by every operation, and waits for the completion of the previous ones only when needed:
+//!
+//! - an operation waits for the previous one on the same engine, since each engine runs one operation at a time;
+//! - an operation waits for the one running on the other engine if either of them writes to a buffer used by the other.
+//!
+//! Independent operations (e.g. clearing the depth buffer while the previous frame is being transferred to the screen) still run in parallel.
+//! The data cache of the CPU is flushed before the GPU uses a buffer, and invalidated before the CPU accesses it again.
+//!
+//! # Notes
+//!
+//! The scheduler relies on the completion events of the engines ([`Event::Psc0`] and [`Event::PPF`]), so all the GX operations
+//! of the application should go through it while it's active.
+#![doc(alias = "GX")]
+#![doc(alias = "frame graph")]
+
+use std::marker::PhantomData;
+use std::ptr;
+
+use super::{Event, FramebufferFormat};
+use crate::error::ResultCode;
+use crate::services::gfx::Screen;
+use crate::Error;
+
+// `GX_TRANSFER_RAW_COPY(1)`, which is a function-like macro in `libctru`.
+const TRANSFER_RAW_COPY: u32 = 1 << 3;
+
+/// Identifier of a buffer registered in a [`Scheduler`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BufferId(usize);
+
+/// Size of the value written by [`Scheduler::fill()`].
+#[doc(alias = "GX_FILL_CONTROL")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FillWidth {
+    /// 16 bits per pixel (e.g. [`FramebufferFormat::Rgb565`]).
+    Bits16,
+    /// 24 bits per pixel (e.g. [`FramebufferFormat::Bgr8`]).
+    Bits24,
+    /// 32 bits per pixel (e.g. [`FramebufferFormat::Rgba8`]).
+    Bits32,
+}
+
+/// Image stored in a buffer, used by [`Scheduler::display_transfer()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    /// Buffer containing the image.
+    pub buffer: BufferId,
+    /// Width of the image (in pixels).
+    pub width: u16,
+    /// Height of the image (in pixels).
+    pub height: u16,
+    /// Format of the pixels.
+    pub format: FramebufferFormat,
+}
+
+/// Scheduler of GX operations, ordering them according to the buffers they use.
+///
+/// Buffers must be registered via [`Scheduler::buffer()`] or [`Scheduler::screen()`] before being used,
+/// and stay borrowed until the scheduler is dropped. Dropping the scheduler waits for all the operations to complete.
+///
+/// See the [module documentation](self) for more information.
+///
+/// # Example
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::linear::LinearAllocator;
+/// use ctru::services::gfx::{Gfx, Swap};
+/// use ctru::services::gspgpu::gx::{FillWidth, Image, Scheduler};
+/// use ctru::services::gspgpu::FramebufferFormat;
+///
+/// let gfx = Gfx::new()?;
+/// let mut top_screen = gfx.top_screen.borrow_mut();
+/// let mut render_target = Box::new_in([0u8; 240 * 400 * 4], LinearAllocator);
+///
+/// {
+///     let mut scheduler = Scheduler::new();
+///     let target = scheduler.buffer(&mut render_target[..])?;
+///     let screen = scheduler.screen(&mut *top_screen);
+///
+///     scheduler.fill(target, 0xFF0000FF, FillWidth::Bits32)?;
+///
+///     // Waits for the fill to complete before starting.
+///     scheduler.display_transfer(
+///         Image { buffer: target, width: 240, height: 400, format: FramebufferFormat::Rgba8 },
+///         Image { buffer: screen, width: 240, height: 400, format: FramebufferFormat::Bgr8 },
+///         0,
+///     )?;
+/// }
+///
+/// top_screen.swap_buffers();
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct Scheduler<'buf> {
+    buffers: Vec<(*mut u8, usize)>,
+    in_flight: [Option<Operation>; 2],
+    _buffers: PhantomData<&'buf mut [u8]>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Engine {
+    Fill = 0,
+    Transfer = 1,
+}
+
+// Buffers used by an operation in flight.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Operation {
+    read: Option<BufferId>,
+    write: BufferId,
+}
+
+impl<'buf> Scheduler<'buf> {
+    /// Creates a scheduler without any buffer.
+    pub fn new() -> Self {
+        Self {
+            buffers: Vec::new(),
+            in_flight: [None, None],
+            _buffers: PhantomData,
+        }
+    }
+
+    /// Registers a buffer, to be used by the operations of the scheduler.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the buffer isn't stored in LINEAR memory or VRAM, which are the only memory sectors accessible to the GPU.
+    #[doc(alias = "osConvertVirtToPhys")]
+    pub fn buffer(&mut self, data: &'buf mut [u8]) -> crate::Result<BufferId> {
+        if data.is_empty() || unsafe { ctru_sys::osConvertVirtToPhys(data.as_ptr().cast()) } == 0 {
+            return Err(Error::Other(
+                "the buffer isn't stored in LINEAR memory or VRAM".into(),
+            ));
+        }
+
+        Ok(self.register(data.as_mut_ptr(), data.len()))
+    }
+
+    /// Registers the current framebuffer of a screen, to be used by the operations of the scheduler.
+    pub fn screen(&mut self, screen: &'buf mut impl Screen) -> BufferId {
+        let format = screen.framebuffer_format();
+        let framebuffer = screen.raw_framebuffer();
+
+        self.register(
+            framebuffer.ptr,
+            framebuffer.width * framebuffer.height * format.pixel_depth_bytes(),
+        )
+    }
+
+    /// Fills a buffer with a repeated value.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation couldn't be queued.
+    #[doc(alias = "GX_MemoryFill")]
+    pub fn fill(&mut self, target: BufferId, value: u32, width: FillWidth) -> crate::Result<()> {
+        let depth = match width {
+            FillWidth::Bits16 => ctru_sys::GX_FILL_16BIT_DEPTH,
+            FillWidth::Bits24 => ctru_sys::GX_FILL_24BIT_DEPTH,
+            FillWidth::Bits32 => ctru_sys::GX_FILL_32BIT_DEPTH,
+        };
+        let control = (ctru_sys::GX_FILL_TRIGGER | depth) as u16;
+
+        let (start, len) = self.prepare(Engine::Fill, None, target);
+
+        ResultCode(unsafe {
+            ctru_sys::GX_MemoryFill(
+                start.cast(),
+                value,
+                start.add(len).cast(),
+                control,
+                ptr::null_mut(),
+                0,
+                ptr::null_mut(),
+                0,
+            )
+        })?;
+
+        self.start(Engine::Fill, None, target);
+
+        Ok(())
+    }
+
+    /// Copies the content of a buffer to another, up to the size of the smallest of them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the operation couldn't be queued.
+    #[doc(alias = "GX_TextureCopy")]
+    pub fn copy(&mut self, source: BufferId, target: BufferId) -> crate::Result<()> {
+        let size = self.buffers[source.0].1.min(self.buffers[target.0].1);
+        let (source_ptr, _) = self.buffers[source.0];
+        let (target_ptr, _) = self.prepare(Engine::Transfer, Some(source), target);
+
+        ResultCode(unsafe {
+            ctru_sys::GX_TextureCopy(
+                source_ptr.cast(),
+                0,
+                target_ptr.cast(),
+                0,
+                size as u32,
+                TRANSFER_RAW_COPY,
+            )
+        })?;
+
+        self.start(Engine::Transfer, Some(source), target);
+
+        Ok(())
+    }
+
+    /// Transfers an image to another buffer, converting its format.
+    ///
+    /// `flags` contains the additional `GX_TRANSFER_*` flags of the operation (e.g. flipping, tiling or scaling).
+    /// The format flags are set according to `source` and `target`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the images don't fit in their buffers, or if the operation couldn't be queued.
+    #[doc(alias = "GX_DisplayTransfer")]
+    pub fn display_transfer(
+        &mut self,
+        source: Image,
+        target: Image,
+        flags: u32,
+    ) -> crate::Result<()> {
+        for image in [source, target] {
+            let size = usize::from(image.width)
+                * usize::from(image.height)
+                * image.format.pixel_depth_bytes();
+
+            if size > self.buffers[image.buffer.0].1 {
+                return Err(Error::BufferTooShort {
+                    provided: self.buffers[image.buffer.0].1,
+                    wanted: size,
+                });
+            }
+        }
+
+        // Equivalent to `GX_TRANSFER_IN_FORMAT()` and `GX_TRANSFER_OUT_FORMAT()`.
+        let flags = (flags & !0x7700)
+            | (u32::from(source.format as u8) << 8)
+            | (u32::from(target.format as u8) << 12);
+
+        let (source_ptr, _) = self.buffers[source.buffer.0];
+        let (target_ptr, _) = self.prepare(Engine::Transfer, Some(source.buffer), target.buffer);
+
+        ResultCode(unsafe {
+            ctru_sys::GX_DisplayTransfer(
+                source_ptr.cast(),
+                dimensions(source),
+                target_ptr.cast(),
+                dimensions(target),
+                flags,
+            )
+        })?;
+
+        self.start(Engine::Transfer, Some(source.buffer), target.buffer);
+
+        Ok(())
+    }
+
+    /// Waits for the operations using a buffer to complete, and returns its content.
+    #[doc(alias = "GSPGPU_InvalidateDataCache")]
+    pub fn access(&mut self, buffer: BufferId) -> &mut [u8] {
+        for engine in [Engine::Fill, Engine::Transfer] {
+            if let Some(operation) = self.in_flight[engine as usize] {
+                if operation.write == buffer || operation.read == Some(buffer) {
+                    self.wait(engine);
+                }
+            }
+        }
+
+        let (ptr, len) = self.buffers[buffer.0];
+        let _ = unsafe { ctru_sys::GSPGPU_InvalidateDataCache(ptr.cast(), len as u32) };
+
+        unsafe { std::slice::from_raw_parts_mut(ptr, len) }
+    }
+
+    /// Waits for all the operations to complete.
+    pub fn finish(&mut self) {
+        for engine in [Engine::Fill, Engine::Transfer] {
+            if self.in_flight[engine as usize].is_some() {
+                self.wait(engine);
+            }
+        }
+    }
+
+    fn register(&mut self, ptr: *mut u8, len: usize) -> BufferId {
+        self.buffers.push((ptr, len));
+        BufferId(self.buffers.len() - 1)
+    }
+
+    // Waits for the conflicting operations and flushes the buffers, returning the target buffer.
+    fn prepare(
+        &mut self,
+        engine: Engine,
+        read: Option<BufferId>,
+        write: BufferId,
+    ) -> (*mut u8, usize) {
+        let waits = conflicts(&self.in_flight, engine, read, write);
+
+        for engine in [Engine::Fill, Engine::Transfer] {
+            if waits[engine as usize] {
+                self.wait(engine);
+            }
+        }
+
+        for buffer in read.into_iter().chain([write]) {
+            let (ptr, len) = self.buffers[buffer.0];
+            let _ = unsafe { ctru_sys::GSPGPU_FlushDataCache(ptr.cast(), len as u32) };
+        }
+
+        self.buffers[write.0]
+    }
+
+    fn start(&mut self, engine: Engine, read: Option<BufferId>, write: BufferId) {
+        self.in_flight[engine as usize] = Some(Operation { read, write });
+    }
+
+    fn wait(&mut self, engine: Engine) {
+        let event = match engine {
+            Engine::Fill => Event::Psc0,
+            Engine::Transfer => Event::PPF,
+        };
+
+        super::wait_for_event(event, false);
+        self.in_flight[engine as usize] = None;
+    }
+}
+
+impl Default for Scheduler<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Scheduler<'_> {
+    fn drop(&mut self) {
+        // The GPU may still be using the buffers, which are released after this.
+        self.finish();
+    }
+}
+
+// Returns the engines whose operation must complete before starting a new one.
+fn conflicts(
+    in_flight: &[Option<Operation>; 2],
+    engine: Engine,
+    read: Option<BufferId>,
+    write: BufferId,
+) -> [bool; 2] {
+    let mut waits = [false; 2];
+
+    for (index, operation) in in_flight.iter().enumerate() {
+        if let Some(operation) = operation {
+            waits[index] = index == engine as usize
+                || operation.write == write
+                || Some(operation.write) == read
+                || operation.read == Some(write);
+        }
+    }
+
+    waits
+}
+
+// Equivalent to `GX_BUFFER_DIM()`.
+fn dimensions(image: Image) -> u32 {
+    (u32::from(image.height) << 16) | u32::from(image.width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dependency_tracking() {
+        let (a, b, c) = (BufferId(0), BufferId(1), BufferId(2));
+        let fill_a = Some(Operation {
+            read: None,
+            write: a,
+        });
+        let copy_a_to_b = Some(Operation {
+            read: Some(a),
+            write: b,
+        });
+
+        // The same engine is always waited for.
+        assert_eq!(
+            conflicts(&[fill_a, None], Engine::Fill, None, c),
+            [true, false]
+        );
+        // Reading a buffer being filled.
+        assert_eq!(
+            conflicts(&[fill_a, None], Engine::Transfer, Some(a), b),
+            [true, false]
+        );
+        // Filling a buffer being read.
+        assert_eq!(
+            conflicts(&[None, copy_a_to_b], Engine::Fill, None, a),
+            [false, true]
+        );
+        // Filling a buffer being written.
+        assert_eq!(
+            conflicts(&[None, copy_a_to_b], Engine::Fill, None, b),
+            [false, true]
+        );
+        // Independent buffers run in parallel.
+        assert_eq!(
+            conflicts(&[None, copy_a_to_b], Engine::Fill, None, c),
+            [false, false]
+        );
+    }
+}
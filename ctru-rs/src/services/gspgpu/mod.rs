@@ -1,5 +1,7 @@
 //! GSPGPU service
 
+pub mod gx;
+
 /// GSPGPU events that can be awaited.
 #[doc(alias = "GSPGPU_Event")]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
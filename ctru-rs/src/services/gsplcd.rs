@@ -0,0 +1,153 @@
+//! LCD service.
+//!
+//! This service controls the backlights of the screens, which allows applications to dim or turn off a screen
+//! (e.g. while it's unused or during long idle periods) to save power.
+//!
+//! See also <https://www.3dbrew.org/wiki/GSP_Services#GSP::LCD>
+#![doc(alias = "brightness")]
+#![doc(alias = "backlight")]
+#![doc(alias = "lcd")]
+
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+
+use crate::error::ResultCode;
+use crate::services::ServiceReference;
+
+static GSPLCD_ACTIVE: Mutex<()> = Mutex::new(());
+
+/// Screens whose backlight can be controlled.
+#[doc(alias = "GSPLCD_SCREEN_BOTH")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LcdScreen {
+    /// Top screen.
+    Top = ctru_sys::GSPLCD_SCREEN_TOP,
+    /// Bottom screen.
+    Bottom = ctru_sys::GSPLCD_SCREEN_BOTTOM,
+    /// Both screens.
+    Both = ctru_sys::GSPLCD_SCREEN_BOTH,
+}
+
+/// Handle to the LCD service.
+pub struct GspLcd {
+    _service_handler: ServiceReference,
+}
+
+impl GspLcd {
+    /// Brightness levels accepted by [`GspLcd::set_brightness()`], matching the ones available in the HOME Menu.
+    pub const BRIGHTNESS_LEVELS: RangeInclusive<u8> = 1..=5;
+
+    /// Initialize a new service handle.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service was unable to be initialized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gsplcd::GspLcd;
+    ///
+    /// let lcd = GspLcd::new()?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "gspLcdInit")]
+    pub fn new() -> crate::Result<Self> {
+        let _service_handler = ServiceReference::new(
+            &GSPLCD_ACTIVE,
+            || {
+                ResultCode(unsafe { ctru_sys::gspLcdInit() })?;
+
+                Ok(())
+            },
+            || unsafe {
+                ctru_sys::gspLcdExit();
+            },
+        )?;
+
+        Ok(Self { _service_handler })
+    }
+
+    /// Sets the brightness level of a screen, clamped to [`GspLcd::BRIGHTNESS_LEVELS`].
+    ///
+    /// The level isn't persisted, and is reset by the system when the user changes the brightness from the HOME Menu.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the brightness couldn't be set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::gsplcd::{GspLcd, LcdScreen};
+    ///
+    /// let mut lcd = GspLcd::new()?;
+    ///
+    /// // Dim the bottom screen while it isn't used.
+    /// lcd.set_brightness(LcdScreen::Bottom, 1)?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "GSPLCD_SetBrightness")]
+    pub fn set_brightness(&mut self, screen: LcdScreen, level: u8) -> crate::Result<()> {
+        let level = level.clamp(
+            *Self::BRIGHTNESS_LEVELS.start(),
+            *Self::BRIGHTNESS_LEVELS.end(),
+        );
+
+        ResultCode(unsafe { ctru_sys::GSPLCD_SetBrightness(screen.into(), level.into()) })?;
+
+        Ok(())
+    }
+
+    /// Returns the raw brightness of a screen's backlight.
+    ///
+    /// # Notes
+    ///
+    /// The raw value isn't a [brightness level](GspLcd::BRIGHTNESS_LEVELS), but the intensity of the backlight as set by the system.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the brightness couldn't be read, or if `screen` is [`LcdScreen::Both`].
+    #[doc(alias = "GSPLCD_GetBrightness")]
+    pub fn brightness(&self, screen: LcdScreen) -> crate::Result<u32> {
+        let mut brightness = 0;
+        ResultCode(unsafe { ctru_sys::GSPLCD_GetBrightness(screen.into(), &mut brightness) })?;
+
+        Ok(brightness)
+    }
+
+    /// Turns the backlight of a screen on or off.
+    ///
+    /// The screen keeps working with its backlight off, but its content is barely visible.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the backlight couldn't be changed.
+    #[doc(alias = "GSPLCD_PowerOnBacklight", alias = "GSPLCD_PowerOffBacklight")]
+    pub fn set_backlight(&mut self, screen: LcdScreen, enabled: bool) -> crate::Result<()> {
+        ResultCode(unsafe {
+            if enabled {
+                ctru_sys::GSPLCD_PowerOnBacklight(screen.into())
+            } else {
+                ctru_sys::GSPLCD_PowerOffBacklight(screen.into())
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+from_impl!(LcdScreen, u32);
@@ -2,82 +2,79 @@
 //!
 //! The HID service provides read access to user input such as [button presses](Hid::keys_down), [touch screen presses](Hid::touch_position),
 //! and [circle pad information](Hid::circlepad_position). It also provides information from the [volume slider](Hid::volume_slider()),
-//! the [accelerometer](Hid::accelerometer_vector()), and the [gyroscope](Hid::gyroscope_rate()).
+//! the [accelerometer](Hid::accelerometer_vector()), and the [gyroscope](Gyroscope).
+//!
+//! The C-Stick and the ZL and ZR buttons of New 3DS consoles are only reported while the [`Irrst`](crate::services::irrst::Irrst) service is active.
 #![doc(alias = "input")]
 #![doc(alias = "controller")]
 #![doc(alias = "gamepad")]
 
 use std::sync::Mutex;
+use std::time::Duration;
 
 use crate::error::ResultCode;
+use crate::services::svc::HandleExt;
 use crate::services::ServiceReference;
 
-use bitflags::bitflags;
+pub use ctru_traits::hid::{InputSource, KeyPad};
 
 static HID_ACTIVE: Mutex<()> = Mutex::new(());
 
-bitflags! {
-    /// A set of flags corresponding to the button and directional pad inputs present on the 3DS.
-    #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
-    pub struct KeyPad: u32 {
-        /// A button.
-        const A             = ctru_sys::KEY_A;
-        /// B button.
-        const B             = ctru_sys::KEY_B;
-        /// Select button.
-        const SELECT        = ctru_sys::KEY_SELECT;
-        /// Start button.
-        const START         = ctru_sys::KEY_START;
-        /// D-Pad Right.
-        const DPAD_RIGHT        = ctru_sys::KEY_DRIGHT;
-        /// D-Pad Left.
-        const DPAD_LEFT         = ctru_sys::KEY_DLEFT;
-        /// D-Pad Up.
-        const DPAD_UP           = ctru_sys::KEY_DUP;
-        /// D-Pad Down.
-        const DPAD_DOWN         = ctru_sys::KEY_DDOWN;
-        /// R button.
-        const R             = ctru_sys::KEY_R;
-        /// L button.
-        const L             = ctru_sys::KEY_L;
-        /// X button.
-        const X             = ctru_sys::KEY_X;
-        /// Y button.
-        const Y             = ctru_sys::KEY_Y;
-        /// ZL button.
-        const ZL            = ctru_sys::KEY_ZL;
-        /// ZR button.
-        const ZR            = ctru_sys::KEY_ZR;
-        /// Touchscreen.
-        const TOUCH         = ctru_sys::KEY_TOUCH;
-        /// C-Stick Right.
-        const CSTICK_RIGHT  = ctru_sys::KEY_CSTICK_RIGHT;
-        /// C-Stick Left.
-        const CSTICK_LEFT   = ctru_sys::KEY_CSTICK_LEFT;
-        /// C-Stick Up.
-        const CSTICK_UP     = ctru_sys::KEY_CSTICK_UP;
-        /// C-Stick Down.
-        const CSTICK_DOWN   = ctru_sys::KEY_CSTICK_DOWN;
-        /// CirclePad Right.
-        const CPAD_RIGHT    = ctru_sys::KEY_CPAD_RIGHT;
-        /// CirclePad Left.
-        const CPAD_LEFT     = ctru_sys::KEY_CPAD_LEFT;
-        /// CirclePad Up.
-        const CPAD_UP       = ctru_sys::KEY_CPAD_UP;
-        /// CirclePad Down.
-        const CPAD_DOWN     = ctru_sys::KEY_CPAD_DOWN;
-
-        // Convenience catch-all for the D-Pad and the CirclePad
-
-        /// Direction Up (either D-Pad or CirclePad).
-        const UP    = KeyPad::DPAD_UP.bits()    | KeyPad::CPAD_UP.bits();
-        /// Direction Down (either D-Pad or CirclePad).
-        const DOWN  = KeyPad::DPAD_DOWN.bits()  | KeyPad::CPAD_DOWN.bits();
-        /// Direction Left (either D-Pad or CirclePad).
-        const LEFT  = KeyPad::DPAD_LEFT.bits()  | KeyPad::CPAD_LEFT.bits();
-        /// Direction Right (either D-Pad or CirclePad).
-        const RIGHT = KeyPad::DPAD_RIGHT.bits() | KeyPad::CPAD_RIGHT.bits();
+// The buttons are defined without depending on `ctru-sys`, to be usable on the host.
+const _: () = {
+    let keys = [
+        (KeyPad::A, ctru_sys::KEY_A),
+        (KeyPad::B, ctru_sys::KEY_B),
+        (KeyPad::SELECT, ctru_sys::KEY_SELECT),
+        (KeyPad::START, ctru_sys::KEY_START),
+        (KeyPad::DPAD_RIGHT, ctru_sys::KEY_DRIGHT),
+        (KeyPad::DPAD_LEFT, ctru_sys::KEY_DLEFT),
+        (KeyPad::DPAD_UP, ctru_sys::KEY_DUP),
+        (KeyPad::DPAD_DOWN, ctru_sys::KEY_DDOWN),
+        (KeyPad::R, ctru_sys::KEY_R),
+        (KeyPad::L, ctru_sys::KEY_L),
+        (KeyPad::X, ctru_sys::KEY_X),
+        (KeyPad::Y, ctru_sys::KEY_Y),
+        (KeyPad::ZL, ctru_sys::KEY_ZL),
+        (KeyPad::ZR, ctru_sys::KEY_ZR),
+        (KeyPad::TOUCH, ctru_sys::KEY_TOUCH),
+        (KeyPad::CSTICK_RIGHT, ctru_sys::KEY_CSTICK_RIGHT),
+        (KeyPad::CSTICK_LEFT, ctru_sys::KEY_CSTICK_LEFT),
+        (KeyPad::CSTICK_UP, ctru_sys::KEY_CSTICK_UP),
+        (KeyPad::CSTICK_DOWN, ctru_sys::KEY_CSTICK_DOWN),
+        (KeyPad::CPAD_RIGHT, ctru_sys::KEY_CPAD_RIGHT),
+        (KeyPad::CPAD_LEFT, ctru_sys::KEY_CPAD_LEFT),
+        (KeyPad::CPAD_UP, ctru_sys::KEY_CPAD_UP),
+        (KeyPad::CPAD_DOWN, ctru_sys::KEY_CPAD_DOWN),
+    ];
+
+    let mut i = 0;
+    while i < keys.len() {
+        assert!(
+            keys[i].0.bits() == keys[i].1,
+            "KeyPad isn't in sync with libctru"
+        );
+        i += 1;
     }
+};
+
+/// Events signaled by the HID module when the shared input state is updated.
+///
+/// Have a look at [`Hid::wait_for_event()`] for more information.
+#[doc(alias = "HID_Event")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HidEvent {
+    /// The pad, circle pad or touch screen state was updated.
+    Pad0 = ctru_sys::HIDEVENT_PAD0,
+    /// The pad, circle pad or touch screen state was updated.
+    Pad1 = ctru_sys::HIDEVENT_PAD1,
+    /// The accelerometer state was updated.
+    Accelerometer = ctru_sys::HIDEVENT_Accel,
+    /// The gyroscope state was updated.
+    Gyroscope = ctru_sys::HIDEVENT_Gyro,
+    /// The debug pad state was updated.
+    DebugPad = ctru_sys::HIDEVENT_DebugPad,
 }
 
 /// Error enum for generic errors within the [`Hid`] service.
@@ -92,7 +89,7 @@ pub enum Error {
 
 /// Representation of the acceleration vector read by the accelerometer.
 ///
-/// Have a look at [`Hid::set_accelerometer()`] for more information.
+/// Have a look at [`Hid::set_accelerometer()`] for more information, and at [`Acceleration::approx_in_g()`] to convert the raw readings.
 #[allow(missing_docs)]
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Acceleration {
@@ -103,7 +100,7 @@ pub struct Acceleration {
 
 /// Representation of the angular rate read by the gyroscope.
 ///
-/// Have a look at [`Hid::set_gyroscope()`] for more information.
+/// Have a look at [`Gyroscope`] for more information, and at [`AngularRate::in_dps()`] to convert the raw readings.
 #[allow(missing_docs)]
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct AngularRate {
@@ -112,10 +109,42 @@ pub struct AngularRate {
     yaw: i16,
 }
 
+/// Handle to the gyroscope of the console, owned by [`Hid`].
+///
+/// The gyroscope is disabled by default, and must be enabled via [`Gyroscope::set_enabled()`] before its readings can be used.
+/// Like the other inputs, its readings are updated by [`Hid::scan_input()`].
+///
+/// # Example
+///
+/// ```
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::hid::Hid;
+/// let mut hid = Hid::new()?;
+///
+/// hid.gyroscope_mut().set_enabled(true)?;
+/// hid.scan_input();
+///
+/// let (roll, pitch, yaw) = hid.gyroscope().rate_dps()?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Gyroscope {
+    enabled: bool,
+    // Raw units per degree per second, read when the gyroscope is enabled.
+    coefficient: f32,
+}
+
 /// Handle to the HID service.
 pub struct Hid {
     active_accelerometer: bool,
-    active_gyroscope: bool,
+    gyroscope: Gyroscope,
+    // Event handles, retrieved the first time they're waited on.
+    event_handles: Option<[ctru_sys::Handle; ctru_sys::HIDEVENT_MAX as usize]>,
     _service_handler: ServiceReference,
 }
 
@@ -160,7 +189,11 @@ impl Hid {
 
         Ok(Self {
             active_accelerometer: false,
-            active_gyroscope: false,
+            gyroscope: Gyroscope {
+                enabled: false,
+                coefficient: 0.0,
+            },
+            event_handles: None,
             _service_handler: handler,
         })
     }
@@ -190,6 +223,62 @@ impl Hid {
         unsafe { ctru_sys::hidScanInput() };
     }
 
+    /// Waits until the HID module signals the specified event, or until the timeout is reached.
+    ///
+    /// Waiting on [`HidEvent::Pad0`] lets the application sleep until the user's input changes, instead of
+    /// calling [`Hid::scan_input()`] on every frame. The input state still has to be scanned once the event is signaled.
+    ///
+    /// If `next_event` is `true`, an event signaled before calling this function is ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the event handles couldn't be retrieved or if the timeout was reached.
+    /// You can use [`Error::is_timeout()`](crate::Error::is_timeout) to check for the latter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use std::time::Duration;
+    ///
+    /// use ctru::services::hid::{Hid, HidEvent};
+    /// let mut hid = Hid::new()?;
+    ///
+    /// match hid.wait_for_event(HidEvent::Pad0, true, Duration::from_millis(100)) {
+    ///     Ok(()) => hid.scan_input(),
+    ///     Err(e) if e.is_timeout() => println!("No input received."),
+    ///     Err(e) => return Err(e.into()),
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "hidWaitForEvent")]
+    #[doc(alias = "HIDUSER_GetHandles")]
+    pub fn wait_for_event(
+        &mut self,
+        event: HidEvent,
+        next_event: bool,
+        timeout: Duration,
+    ) -> crate::Result<()> {
+        let handle = self.event_handles()?[event as usize];
+
+        if next_event {
+            ResultCode(unsafe { ctru_sys::svcClearEvent(handle) })?;
+        }
+
+        handle.wait_for_event(timeout)?;
+
+        if !next_event {
+            ResultCode(unsafe { ctru_sys::svcClearEvent(handle) })?;
+        }
+
+        Ok(())
+    }
+
     /// Returns a bitflag struct representing which buttons have just been pressed
     /// on the current frame (and were not pressed on the previous frame).
     ///
@@ -401,7 +490,7 @@ impl Hid {
     /// # Ok(())
     /// # }
     /// ```
-    #[doc(alias = "HIDUSER_EnableAccelerometer")]
+    #[doc(alias = "HIDUSER_EnableAccelerometer", alias = "enable_accelerometer")]
     #[doc(alias = "HIDUSER_DisableAccelerometer")]
     pub fn set_accelerometer(&mut self, enabled: bool) -> crate::Result<()> {
         if enabled {
@@ -417,6 +506,8 @@ impl Hid {
 
     /// Activate/deactivate the console's gyroscopic sensor.
     ///
+    /// See [`Gyroscope::set_enabled()`] for more information.
+    ///
     /// # Example
     ///
     /// ```
@@ -435,15 +526,17 @@ impl Hid {
     #[doc(alias = "HIDUSER_EnableGyroscope")]
     #[doc(alias = "HIDUSER_DisableGyroscope")]
     pub fn set_gyroscope(&mut self, enabled: bool) -> crate::Result<()> {
-        if enabled {
-            ResultCode(unsafe { ctru_sys::HIDUSER_EnableGyroscope() })?;
-        } else {
-            ResultCode(unsafe { ctru_sys::HIDUSER_DisableGyroscope() })?;
-        }
+        self.gyroscope.set_enabled(enabled)
+    }
 
-        self.active_gyroscope = enabled;
+    /// Returns the handle to the gyroscope.
+    pub fn gyroscope(&self) -> &Gyroscope {
+        &self.gyroscope
+    }
 
-        Ok(())
+    /// Returns the handle to the gyroscope, to enable or disable it.
+    pub fn gyroscope_mut(&mut self) -> &mut Gyroscope {
+        &mut self.gyroscope
     }
 
     /// Returns the acceleration vector (x, y, z) registered by the accelerometer.
@@ -495,6 +588,8 @@ impl Hid {
 
     /// Returns the angular rate registered by the gyroscope.
     ///
+    /// See [`Gyroscope::rate()`] for more information.
+    ///
     /// # Errors
     ///
     /// This function returns an error if the gyroscope was not previously enabled.
@@ -523,7 +618,49 @@ impl Hid {
     /// ```
     #[doc(alias = "hidGyroRead")]
     pub fn gyroscope_rate(&self) -> Result<AngularRate, Error> {
-        if !self.active_gyroscope {
+        self.gyroscope.rate()
+    }
+}
+
+impl Gyroscope {
+    /// Enables or disables the gyroscope.
+    ///
+    /// The calibration coefficient of the console (see [`Gyroscope::rate_dps()`]) is read when the gyroscope is enabled.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the gyroscope couldn't be enabled or disabled, or if the coefficient couldn't be read.
+    #[doc(alias = "HIDUSER_EnableGyroscope")]
+    #[doc(alias = "HIDUSER_DisableGyroscope")]
+    #[doc(alias = "HIDUSER_GetGyroscopeRawToDpsCoefficient")]
+    pub fn set_enabled(&mut self, enabled: bool) -> crate::Result<()> {
+        if enabled {
+            ResultCode(unsafe { ctru_sys::HIDUSER_EnableGyroscope() })?;
+            ResultCode(unsafe {
+                ctru_sys::HIDUSER_GetGyroscopeRawToDpsCoefficient(&mut self.coefficient)
+            })?;
+        } else {
+            ResultCode(unsafe { ctru_sys::HIDUSER_DisableGyroscope() })?;
+        }
+
+        self.enabled = enabled;
+
+        Ok(())
+    }
+
+    /// Returns `true` if the gyroscope is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the raw angular rate registered by the gyroscope.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the gyroscope is disabled.
+    #[doc(alias = "hidGyroRead")]
+    pub fn rate(&self) -> Result<AngularRate, Error> {
+        if !self.enabled {
             return Err(Error::UnavailableGyroscope);
         }
 
@@ -539,6 +676,115 @@ impl Hid {
             yaw: res.z,
         })
     }
+
+    /// Returns the angular rate (roll, pitch, yaw) registered by the gyroscope, in degrees per second.
+    ///
+    /// The raw readings are converted using the calibration coefficient provided by the system.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the gyroscope is disabled.
+    #[doc(alias = "hidGyroRead")]
+    pub fn rate_dps(&self) -> Result<(f32, f32, f32), Error> {
+        Ok(self.rate()?.in_dps(self.coefficient))
+    }
+
+    /// Returns the amount of raw units per degree per second of the gyroscope, as calibrated by the system.
+    ///
+    /// Returns [`None`] if the gyroscope is disabled.
+    #[doc(alias = "HIDUSER_GetGyroscopeRawToDpsCoefficient")]
+    pub fn coefficient(&self) -> Option<f32> {
+        self.enabled.then_some(self.coefficient)
+    }
+}
+
+impl Acceleration {
+    /// Nominal raw reading corresponding to the standard gravity (1 g) on a single axis.
+    ///
+    /// This is an approximation: the system doesn't provide a calibration for the accelerometer,
+    /// and the actual scale and offset of the readings vary between consoles.
+    pub const APPROX_ONE_G: i16 = 512;
+
+    /// Returns the raw reading on the x-axis.
+    pub fn x(&self) -> i16 {
+        self.x
+    }
+
+    /// Returns the raw reading on the y-axis.
+    pub fn y(&self) -> i16 {
+        self.y
+    }
+
+    /// Returns the raw reading on the z-axis.
+    pub fn z(&self) -> i16 {
+        self.z
+    }
+
+    /// Returns the acceleration vector (x, y, z) in units of standard gravity, approximated using [`Acceleration::APPROX_ONE_G`].
+    ///
+    /// The result is uncalibrated, and can be off by several percent. Applications which need accurate readings
+    /// should calibrate the raw values themselves (e.g. by sampling them while the console lies flat).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::hid::Hid;
+    /// let mut hid = Hid::new()?;
+    ///
+    /// hid.set_accelerometer(true)?;
+    /// hid.scan_input();
+    ///
+    /// let (x, y, z) = hid.accelerometer_vector()?.approx_in_g();
+    ///
+    /// // While at rest, the only acceleration is the gravity.
+    /// if (x * x + y * y + z * z).sqrt() > 1.5 {
+    ///     println!("Shake detected!");
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn approx_in_g(&self) -> (f32, f32, f32) {
+        let scale = f32::from(Self::APPROX_ONE_G);
+
+        (
+            f32::from(self.x) / scale,
+            f32::from(self.y) / scale,
+            f32::from(self.z) / scale,
+        )
+    }
+}
+
+impl AngularRate {
+    /// Returns the raw reading of the roll axis.
+    pub fn roll(&self) -> i16 {
+        self.roll
+    }
+
+    /// Returns the raw reading of the pitch axis.
+    pub fn pitch(&self) -> i16 {
+        self.pitch
+    }
+
+    /// Returns the raw reading of the yaw axis.
+    pub fn yaw(&self) -> i16 {
+        self.yaw
+    }
+
+    /// Returns the angular rate (roll, pitch, yaw) in degrees per second, given the amount of raw units per degree per second.
+    ///
+    /// The coefficient of the console is used by [`Gyroscope::rate_dps()`].
+    pub fn in_dps(&self, coefficient: f32) -> (f32, f32, f32) {
+        (
+            f32::from(self.roll) / coefficient,
+            f32::from(self.pitch) / coefficient,
+            f32::from(self.yaw) / coefficient,
+        )
+    }
 }
 
 impl From<Acceleration> for (i16, i16, i16) {
@@ -553,6 +799,38 @@ impl From<AngularRate> for (i16, i16, i16) {
     }
 }
 
+impl Hid {
+    fn event_handles(
+        &mut self,
+    ) -> crate::Result<[ctru_sys::Handle; ctru_sys::HIDEVENT_MAX as usize]> {
+        if let Some(handles) = self.event_handles {
+            return Ok(handles);
+        }
+
+        let mut shared_memory = 0;
+        let mut handles = [0; ctru_sys::HIDEVENT_MAX as usize];
+        let [pad0, pad1, accel, gyro, debug_pad] = &mut handles;
+
+        ResultCode(unsafe {
+            ctru_sys::HIDUSER_GetHandles(&mut shared_memory, pad0, pad1, accel, gyro, debug_pad)
+        })?;
+
+        // The shared memory is already mapped by libctru, only the events are needed.
+        let _ = unsafe { ctru_sys::svcCloseHandle(shared_memory) };
+
+        self.event_handles = Some(handles);
+        Ok(handles)
+    }
+}
+
+impl Drop for Hid {
+    fn drop(&mut self) {
+        for handle in self.event_handles.into_iter().flatten() {
+            let _ = unsafe { ctru_sys::svcCloseHandle(handle) };
+        }
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -563,3 +841,25 @@ impl std::fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+impl InputSource for Hid {
+    fn keys_down(&self) -> KeyPad {
+        Hid::keys_down(self)
+    }
+
+    fn keys_held(&self) -> KeyPad {
+        Hid::keys_held(self)
+    }
+
+    fn keys_up(&self) -> KeyPad {
+        Hid::keys_up(self)
+    }
+
+    fn touch_position(&self) -> (u16, u16) {
+        Hid::touch_position(self)
+    }
+
+    fn circlepad_position(&self) -> (i16, i16) {
+        Hid::circlepad_position(self)
+    }
+}
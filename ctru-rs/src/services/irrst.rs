@@ -0,0 +1,150 @@
+//! IR Right Stick service.
+//!
+//! The New 3DS family of consoles has additional inputs (the C-Stick and the ZL and ZR buttons), which are read by the system
+//! through the same interface used by the Circle Pad Pro accessory on Old 3DS consoles. This service gives access to them.
+//!
+//! While an [`Irrst`] handle is alive, the additional buttons are also reported by [`Hid`](crate::services::hid::Hid) alongside all other keys,
+//! using the [`KeyPad::ZL`], [`KeyPad::ZR`] and `KeyPad::CSTICK_*` flags.
+//!
+//! See also <https://www.3dbrew.org/wiki/IR_Services>
+#![doc(alias = "cstick")]
+#![doc(alias = "circle pad pro")]
+#![doc(alias = "zl")]
+#![doc(alias = "zr")]
+
+use std::sync::Mutex;
+
+use crate::error::ResultCode;
+use crate::services::hid::KeyPad;
+use crate::services::ServiceReference;
+
+static IRRST_ACTIVE: Mutex<()> = Mutex::new(());
+
+/// Handle to the IR Right Stick service.
+pub struct Irrst {
+    _service_handler: ServiceReference,
+}
+
+impl Irrst {
+    /// Initialize a new service handle.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service was unable to be initialized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::hid::{Hid, KeyPad};
+    /// use ctru::services::irrst::Irrst;
+    ///
+    /// let mut hid = Hid::new()?;
+    /// let _irrst = Irrst::new()?;
+    ///
+    /// hid.scan_input();
+    ///
+    /// if hid.keys_down().contains(KeyPad::ZR) {
+    ///     println!("You have pressed the ZR button!")
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "irrstInit")]
+    pub fn new() -> crate::Result<Self> {
+        let _service_handler = ServiceReference::new(
+            &IRRST_ACTIVE,
+            || {
+                ResultCode(unsafe { ctru_sys::irrstInit() })?;
+
+                Ok(())
+            },
+            || unsafe {
+                ctru_sys::irrstExit();
+            },
+        )?;
+
+        Ok(Self { _service_handler })
+    }
+
+    /// Scan the service for the additional inputs occurring on the current frame.
+    ///
+    /// # Notes
+    ///
+    /// [`Hid::scan_input()`](crate::services::hid::Hid::scan_input) already does this while the service is active,
+    /// so this is only needed when reading the inputs without the [`Hid`](crate::services::hid::Hid) service.
+    #[doc(alias = "irrstScanInput")]
+    pub fn scan_input(&mut self) {
+        unsafe { ctru_sys::irrstScanInput() };
+    }
+
+    /// Returns the additional buttons (ZL, ZR and the C-Stick directions) held down during the current frame.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::hid::KeyPad;
+    /// use ctru::services::irrst::Irrst;
+    /// let mut irrst = Irrst::new()?;
+    ///
+    /// irrst.scan_input();
+    ///
+    /// if irrst.keys_held().intersects(KeyPad::ZL | KeyPad::ZR) {
+    ///     println!("You are holding a shoulder trigger!")
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "irrstKeysHeld")]
+    pub fn keys_held(&self) -> KeyPad {
+        unsafe { KeyPad::from_bits_truncate(ctru_sys::irrstKeysHeld()) }
+    }
+
+    /// Returns the current C-Stick position in relative (x, y).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::irrst::Irrst;
+    /// let mut irrst = Irrst::new()?;
+    ///
+    /// irrst.scan_input();
+    ///
+    /// let (stick_x, stick_y) = irrst.cstick_position();
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "irrstCstickRead")]
+    #[doc(alias = "hidCstickRead")]
+    pub fn cstick_position(&self) -> (i16, i16) {
+        let mut res = ctru_sys::circlePosition { dx: 0, dy: 0 };
+
+        unsafe {
+            ctru_sys::irrstCstickRead(&mut res);
+        }
+
+        (res.dx, res.dy)
+    }
+
+    /// Waits for the service to receive new input.
+    ///
+    /// If `next_event` is `true`, any input received before calling this function is ignored.
+    #[doc(alias = "irrstWaitForEvent")]
+    pub fn wait_for_event(&self, next_event: bool) {
+        unsafe { ctru_sys::irrstWaitForEvent(next_event) };
+    }
+}
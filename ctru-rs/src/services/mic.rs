@@ -0,0 +1,245 @@
+//! Microphone service.
+//!
+//! The microphone records audio into a ring buffer shared with the system. Samples are read from it via [`Mic::read_samples()`],
+//! which returns everything recorded since the previous call.
+//!
+//! See also <https://www.3dbrew.org/wiki/MIC_Services>
+#![doc(alias = "microphone")]
+#![doc(alias = "record")]
+
+use std::cell::UnsafeCell;
+use std::ptr;
+use std::sync::Mutex;
+
+use crate::error::ResultCode;
+use crate::services::ServiceReference;
+
+static MIC_ACTIVE: Mutex<()> = Mutex::new(());
+
+// Memory shared with the MIC service. Only one `Mic` handle can exist at a time, and the buffer must outlive the service,
+// so it's allocated statically instead of per-handle.
+const BUFFER_SIZE: usize = 0x10000;
+
+#[repr(C, align(4096))]
+struct SharedBuffer(UnsafeCell<[u8; BUFFER_SIZE]>);
+
+// SAFETY: the buffer is only accessed while holding `MIC_ACTIVE`.
+unsafe impl Sync for SharedBuffer {}
+
+static MIC_BUFFER: SharedBuffer = SharedBuffer(UnsafeCell::new([0; BUFFER_SIZE]));
+
+/// Sample rate of the recording.
+#[doc(alias = "MICU_SampleRate")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SampleRate {
+    /// 32730 Hz.
+    Hz32730 = ctru_sys::MICU_SAMPLE_RATE_32730,
+    /// 16360 Hz.
+    Hz16360 = ctru_sys::MICU_SAMPLE_RATE_16360,
+    /// 10910 Hz.
+    Hz10910 = ctru_sys::MICU_SAMPLE_RATE_10910,
+    /// 8180 Hz.
+    Hz8180 = ctru_sys::MICU_SAMPLE_RATE_8180,
+}
+
+/// Handle to the Microphone service.
+pub struct Mic {
+    _service_handler: ServiceReference,
+    read_offset: usize,
+}
+
+impl Mic {
+    /// Initialize a new service handle.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service was unable to be initialized.
+    /// Since this service requires no special or elevated permissions, errors are rare in practice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::mic::Mic;
+    ///
+    /// let mic = Mic::new()?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "micInit")]
+    pub fn new() -> crate::Result<Self> {
+        let _service_handler = ServiceReference::new(
+            &MIC_ACTIVE,
+            || {
+                ResultCode(unsafe {
+                    ctru_sys::micInit(MIC_BUFFER.0.get().cast(), BUFFER_SIZE as u32)
+                })?;
+
+                Ok(())
+            },
+            || unsafe {
+                ctru_sys::micExit();
+            },
+        )?;
+
+        Ok(Self {
+            _service_handler,
+            read_offset: 0,
+        })
+    }
+
+    /// Starts recording signed 16-bit samples at the specified rate.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the recording couldn't be started (e.g. if it's already running).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::mic::{Mic, SampleRate};
+    ///
+    /// let mut mic = Mic::new()?;
+    ///
+    /// mic.start_sampling(SampleRate::Hz16360)?;
+    ///
+    /// let samples = mic.read_samples();
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "MICU_StartSampling")]
+    pub fn start_sampling(&mut self, rate: SampleRate) -> crate::Result<()> {
+        ResultCode(unsafe {
+            ctru_sys::MICU_StartSampling(
+                ctru_sys::MICU_ENCODING_PCM16_SIGNED,
+                rate as _,
+                0,
+                Self::data_size() as u32,
+                true,
+            )
+        })?;
+
+        self.read_offset = 0;
+
+        Ok(())
+    }
+
+    /// Stops recording.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the recording couldn't be stopped.
+    #[doc(alias = "MICU_StopSampling")]
+    pub fn stop_sampling(&mut self) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::MICU_StopSampling() })?;
+
+        Ok(())
+    }
+
+    /// Returns `true` if the microphone is recording.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state couldn't be read.
+    #[doc(alias = "MICU_IsSampling")]
+    pub fn is_sampling(&self) -> crate::Result<bool> {
+        let mut sampling = false;
+        ResultCode(unsafe { ctru_sys::MICU_IsSampling(&mut sampling) })?;
+
+        Ok(sampling)
+    }
+
+    /// Sets the gain of the microphone amplifier (between 0 and 119, where each step is 0.5 dB).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the gain couldn't be set.
+    #[doc(alias = "MICU_SetGain")]
+    pub fn set_gain(&mut self, gain: u8) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::MICU_SetGain(gain.min(119)) })?;
+
+        Ok(())
+    }
+
+    /// Returns the gain of the microphone amplifier.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the gain couldn't be read.
+    #[doc(alias = "MICU_GetGain")]
+    pub fn gain(&self) -> crate::Result<u8> {
+        let mut gain = 0;
+        ResultCode(unsafe { ctru_sys::MICU_GetGain(&mut gain) })?;
+
+        Ok(gain)
+    }
+
+    /// Sets whether the recording keeps going while the console's shell is closed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the setting couldn't be changed.
+    #[doc(alias = "MICU_SetAllowShellClosed")]
+    pub fn set_allow_shell_closed(&mut self, allow: bool) -> crate::Result<()> {
+        ResultCode(unsafe { ctru_sys::MICU_SetAllowShellClosed(allow) })?;
+
+        Ok(())
+    }
+
+    /// Returns the samples recorded since the previous call (or since the recording started).
+    ///
+    /// This function must be called often enough for the ring buffer (about one second of audio at the highest sample rate)
+    /// not to wrap around between calls, or the oldest samples will be lost.
+    #[doc(alias = "micGetLastSampleOffset")]
+    pub fn read_samples(&mut self) -> Vec<i16> {
+        let data_size = Self::data_size();
+        // Samples are 2 bytes long, so the offset is always even.
+        let end = (unsafe { ctru_sys::micGetLastSampleOffset() } as usize % data_size) & !1;
+
+        let len = (end + data_size - self.read_offset) % data_size;
+        let mut samples = Vec::with_capacity(len / 2);
+
+        let buffer = MIC_BUFFER.0.get().cast::<u8>();
+        while self.read_offset != end {
+            // The buffer is written by the system while recording.
+            let bytes = unsafe {
+                [
+                    ptr::read_volatile(buffer.add(self.read_offset)),
+                    ptr::read_volatile(buffer.add((self.read_offset + 1) % data_size)),
+                ]
+            };
+            samples.push(i16::from_le_bytes(bytes));
+
+            self.read_offset = (self.read_offset + 2) % data_size;
+        }
+
+        samples
+    }
+
+    // Size of the recorded data in the shared buffer (the rest is used by the service).
+    fn data_size() -> usize {
+        unsafe { ctru_sys::micGetSampleDataSize() as usize }
+    }
+}
+
+impl SampleRate {
+    /// Returns the sample rate in Hz.
+    pub fn hz(self) -> u32 {
+        match self {
+            Self::Hz32730 => 32730,
+            Self::Hz16360 => 16360,
+            Self::Hz10910 => 10910,
+            Self::Hz8180 => 8180,
+        }
+    }
+}
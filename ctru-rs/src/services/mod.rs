@@ -10,22 +10,46 @@
 //! which enables all network communications via sockets.
 //!
 //! In [`ctru-rs`](crate) some services only allow a single handle to be created at a time, to ensure a safe and controlled environment.
+//!
+//! Since some services can't be safely exited while services initialized after them are still active, handles dropped "out of order"
+//! (e.g. as fields of the same struct) don't exit their service right away. Instead, the service is exited as soon as all services initialized
+//! after it have been exited too, which guarantees services are always closed in the reverse order of their initialization.
+//! A new handle to such a service can be created in the meantime: it reuses the running instance, which is then exited when the new handle is dropped.
 
+#[cfg(feature = "network")]
+pub mod ac;
 pub mod am;
 pub mod apt;
+#[cfg(feature = "network")]
+pub mod boss;
+#[cfg(feature = "camera")]
 pub mod cam;
 pub mod cfgu;
 pub mod fs;
 pub mod gfx;
 pub mod gspgpu;
+pub mod gsplcd;
 pub mod hid;
+#[cfg(feature = "ir")]
 pub mod ir_user;
+pub mod irrst;
+#[cfg(feature = "audio")]
+pub mod mic;
+#[cfg(feature = "audio")]
 pub mod ndsp;
+pub mod news;
 pub mod ps;
+#[cfg(feature = "privileged")]
+pub mod ptm;
+#[cfg(feature = "privileged")]
+pub mod pxidev;
 mod reference;
+#[cfg(feature = "network")]
 pub mod soc;
+#[cfg(feature = "network")]
 pub mod sslc;
 pub mod svc;
+#[cfg(feature = "network")]
 pub mod uds;
 
 cfg_if::cfg_if! {
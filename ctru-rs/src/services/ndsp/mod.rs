@@ -14,7 +14,9 @@
 // this module are `no_run`, since Citra doesn't provide a stub for the DSP firmware:
 // https://github.com/citra-emu/citra/issues/6111
 
+pub mod stream;
 pub mod wave;
+pub use stream::AudioStream;
 use wave::{Status, Wave};
 
 use crate::error::ResultCode;
@@ -41,6 +43,17 @@ pub enum OutputMode {
     Surround = ctru_sys::NDSP_OUTPUT_SURROUND,
 }
 
+/// Clipping applied to the final audio output.
+#[doc(alias = "ndspClippingMode")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ClippingMode {
+    /// "Normal" clipping, which just cuts off the out-of-range samples.
+    Normal = ctru_sys::NDSP_CLIP_NORMAL,
+    /// "Soft" clipping, which smoothens the samples close to the limits of the range.
+    Soft = ctru_sys::NDSP_CLIP_SOFT,
+}
+
 /// PCM formats supported by the audio engine.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u8)]
@@ -226,6 +239,50 @@ impl Ndsp {
     pub fn set_output_mode(&mut self, mode: OutputMode) {
         unsafe { ctru_sys::ndspSetOutputMode(mode.into()) };
     }
+
+    /// Set the master volume of the audio output. Defaults to `1.0`.
+    ///
+    /// The volume of each channel is multiplied by this value, on top of its own [`AudioMix`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::ndsp::Ndsp;
+    /// let mut ndsp = Ndsp::new()?;
+    ///
+    /// // Play everything at half volume.
+    /// ndsp.set_master_volume(0.5);
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "ndspSetMasterVol")]
+    pub fn set_master_volume(&mut self, volume: f32) {
+        unsafe { ctru_sys::ndspSetMasterVol(volume) };
+    }
+
+    /// Set the clipping mode of the audio output. Defaults to [`ClippingMode::Soft`].
+    #[doc(alias = "ndspSetClippingMode")]
+    pub fn set_clipping_mode(&mut self, mode: ClippingMode) {
+        unsafe { ctru_sys::ndspSetClippingMode(mode.into()) };
+    }
+
+    /// Enable or disable the specified auxiliary output.
+    ///
+    /// Channels send audio to the auxiliary outputs via the `aux` volumes of their [`AudioMix`].
+    #[doc(alias = "ndspAuxSetEnable")]
+    pub fn set_aux_enabled(&mut self, id: AuxDevice, enable: bool) {
+        unsafe { ctru_sys::ndspAuxSetEnable(id as i32, enable) };
+    }
+
+    /// Set the volume of the specified auxiliary output. Defaults to `1.0`.
+    #[doc(alias = "ndspAuxSetVolume")]
+    pub fn set_aux_volume(&mut self, id: AuxDevice, volume: f32) {
+        unsafe { ctru_sys::ndspAuxSetVolume(id as i32, volume) };
+    }
 }
 
 impl Channel<'_> {
@@ -787,4 +844,5 @@ impl Drop for Ndsp {
 
 from_impl!(InterpolationType, ctru_sys::ndspInterpType);
 from_impl!(OutputMode, ctru_sys::ndspOutputMode);
+from_impl!(ClippingMode, ctru_sys::ndspClippingMode);
 from_impl!(AudioFormat, u16);
@@ -0,0 +1,161 @@
+//! Streaming audio playback.
+//!
+//! This module contains [`AudioStream`], which handles the rotation of multiple [`Wave`]s on a single [`Channel`],
+//! so that audio data can be generated or decoded on the fly without loading it all in memory first.
+
+use super::wave::{Status, Wave};
+use super::{AudioFormat, Channel};
+use crate::linear::LinearAllocator;
+
+/// Audio stream playing on a [`Channel`] and refilled on demand.
+///
+/// The stream owns `N` wave buffers allocated on the [LINEAR memory](crate::linear), which are queued one after the other.
+/// Every time a buffer finishes playing, [`AudioStream::update()`] invokes the user callback to refill it and queues it again.
+///
+/// The callback receives the buffer to fill and returns the amount of bytes it has written to it.
+/// Returning `0` signals the end of the stream.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::prelude::*;
+/// use ctru::services::ndsp::{AudioFormat, AudioStream, Ndsp};
+///
+/// let apt = Apt::new()?;
+/// let ndsp = Ndsp::new()?;
+///
+/// let mut channel = ndsp.channel(0)?;
+/// channel.set_format(AudioFormat::PCM16Stereo);
+/// channel.set_sample_rate(44100.);
+///
+/// // Stream silence, using 2 buffers of 4096 samples each.
+/// let mut stream = AudioStream::new(channel, AudioFormat::PCM16Stereo, 2, 4096, |buffer| {
+///     buffer.fill(0);
+///     buffer.len()
+/// });
+///
+/// while apt.main_loop() && stream.update() {
+///     // ...
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub struct AudioStream<'ndsp, F: FnMut(&mut [u8]) -> usize> {
+    // The waves must be dropped before the channel, since they clear its queue if still playing.
+    waves: Vec<Wave<Box<[u8], LinearAllocator>>>,
+    channel: Channel<'ndsp>,
+    callback: F,
+    next: usize,
+    finished: bool,
+}
+
+impl<'ndsp, F: FnMut(&mut [u8]) -> usize> AudioStream<'ndsp, F> {
+    /// Creates a new stream playing on `channel`, using `buffer_count` buffers able to hold `samples_per_buffer` samples each.
+    ///
+    /// All buffers are immediately filled via `callback` and queued on the channel, so playback starts right away.
+    ///
+    /// # Notes
+    ///
+    /// The channel's format and sample rate are not changed by this function, and should match the data produced by `callback`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `buffer_count` is `0`.
+    pub fn new(
+        channel: Channel<'ndsp>,
+        audio_format: AudioFormat,
+        buffer_count: usize,
+        samples_per_buffer: usize,
+        callback: F,
+    ) -> Self {
+        assert!(
+            buffer_count > 0,
+            "an audio stream needs at least one buffer"
+        );
+
+        let buffer_size = samples_per_buffer * audio_format.size();
+
+        let waves = (0..buffer_count)
+            .map(|_| {
+                let buffer = Box::new_zeroed_slice_in(buffer_size, LinearAllocator);
+                // SAFETY: a zeroed byte slice is a valid byte slice.
+                let buffer = unsafe { buffer.assume_init() };
+
+                Wave::new(buffer, audio_format, false)
+            })
+            .collect();
+
+        let mut stream = Self {
+            waves,
+            channel,
+            callback,
+            next: 0,
+            finished: false,
+        };
+
+        stream.update();
+
+        stream
+    }
+
+    /// Refills and queues again all buffers which finished playing.
+    ///
+    /// This function should be called frequently enough (e.g. once per frame) to not let the channel run out of queued buffers.
+    ///
+    /// Returns `false` once the callback signaled the end of the stream and all buffers finished playing.
+    pub fn update(&mut self) -> bool {
+        // Buffers are always queued in order, so only the next one in the rotation has to be checked.
+        while !self.finished {
+            let wave = &mut self.waves[self.next];
+
+            if !matches!(wave.status(), Status::Free | Status::Done) {
+                break;
+            }
+
+            let format_size = wave.format().size();
+            // Cannot fail, since the wave isn't busy.
+            let buffer = wave.get_buffer_mut().unwrap();
+            let written = (self.callback)(buffer).min(buffer.len());
+
+            if written == 0 {
+                self.finished = true;
+                break;
+            }
+
+            // The DSP reads the data directly from memory, so the new contents must be flushed from the CPU cache.
+            unsafe {
+                let _r = ctru_sys::DSP_FlushDataCache(buffer.as_ptr().cast(), written as u32);
+            }
+
+            // Cannot fail, since the wave isn't busy and the sample count fits in the buffer.
+            wave.set_sample_count(written / format_size).unwrap();
+            self.channel.queue_wave(wave).unwrap();
+
+            self.next = (self.next + 1) % self.waves.len();
+        }
+
+        !self.finished || self.channel.is_playing()
+    }
+
+    /// Returns a mutable reference to the channel the stream is playing on.
+    ///
+    /// Useful to change the channel's settings (e.g. mix, pause) during playback.
+    pub fn channel(&mut self) -> &mut Channel<'ndsp> {
+        &mut self.channel
+    }
+
+    /// Stops the playback and returns the channel used by the stream.
+    #[doc(alias = "ndspChnWaveBufClear")]
+    pub fn into_channel(mut self) -> Channel<'ndsp> {
+        self.channel.clear_queue();
+
+        let Self { waves, channel, .. } = self;
+        drop(waves);
+
+        channel
+    }
+}
@@ -0,0 +1,116 @@
+//! Notification service.
+//!
+//! The NEWS service manages the notifications shown in the Notifications applet of the HOME Menu.
+//! Notifications added by the application are shown right away, with the application's icon and a blue light on the
+//! notification LED. See the [`alarm`](crate::alarm) module to post them at a given time instead.
+//!
+//! See also <https://www.3dbrew.org/wiki/NEWS_Services>
+#![doc(alias = "notification")]
+
+use crate::error::{Error, ResultCode};
+
+/// Maximum length of the title of a notification (in UTF-16 code units).
+pub const MAX_TITLE_LEN: usize = 32;
+
+/// Maximum length of the message of a notification (in UTF-16 code units).
+pub const MAX_MESSAGE_LEN: usize = 0x1780 / 2;
+
+/// Handle to the NEWS service.
+pub struct News(());
+
+impl News {
+    /// Initialize a new service handle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::news::News;
+    ///
+    /// let news = News::new()?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "newsInit")]
+    pub fn new() -> crate::Result<Self> {
+        ResultCode(unsafe { ctru_sys::newsInit() })?;
+
+        Ok(News(()))
+    }
+
+    /// Adds a notification to the Notifications applet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the title is longer than [`MAX_TITLE_LEN`], if the message is longer than
+    /// [`MAX_MESSAGE_LEN`], or if the notification couldn't be added.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::news::News;
+    ///
+    /// let mut news = News::new()?;
+    ///
+    /// news.add_notification("Daily bonus", "Your daily bonus is ready to be collected!")?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "NEWS_AddNotification")]
+    pub fn add_notification(&mut self, title: &str, message: &str) -> crate::Result<()> {
+        let title: Vec<u16> = title.encode_utf16().collect();
+        let message: Vec<u16> = message.encode_utf16().collect();
+
+        if title.len() > MAX_TITLE_LEN {
+            return Err(Error::Other(format!(
+                "notification title is too long ({} > {MAX_TITLE_LEN})",
+                title.len()
+            )));
+        }
+        if message.len() > MAX_MESSAGE_LEN {
+            return Err(Error::Other(format!(
+                "notification message is too long ({} > {MAX_MESSAGE_LEN})",
+                message.len()
+            )));
+        }
+
+        ResultCode(unsafe {
+            ctru_sys::NEWS_AddNotification(
+                title.as_ptr(),
+                title.len() as u32,
+                message.as_ptr(),
+                message.len() as u32,
+                std::ptr::null(),
+                0,
+                false,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Returns the number of notifications stored by the system, from all applications.
+    #[doc(alias = "NEWS_GetTotalNotifications")]
+    pub fn notification_count(&self) -> crate::Result<u32> {
+        let mut count = 0;
+        ResultCode(unsafe { ctru_sys::NEWS_GetTotalNotifications(&mut count) })?;
+
+        Ok(count)
+    }
+}
+
+impl Drop for News {
+    #[doc(alias = "newsExit")]
+    fn drop(&mut self) {
+        unsafe { ctru_sys::newsExit() };
+    }
+}
@@ -7,7 +7,10 @@
 //! See also <https://www.3dbrew.org/wiki/Process_Services>
 
 use crate::error::ResultCode;
-use crate::Result;
+use crate::{Error, Result};
+
+// Length of the MAC produced by the AES-CCM operations.
+const CCM_MAC_LEN: usize = 16;
 
 /// Type of AES algorithm to use.
 #[doc(alias = "PS_AESAlgorithm")]
@@ -160,6 +163,134 @@ impl Ps {
         })?;
         Ok(())
     }
+
+    /// Encrypts or decrypts `data` in place using AES-CBC or AES-CTR with a key stored in the specified keyslot.
+    ///
+    /// `iv` is used as the initialization vector (or initial counter) and is updated with the value
+    /// to use for a following operation on the continuation of `data`.
+    ///
+    /// # Notes
+    ///
+    /// The CCM algorithms can't be used with this function, see [`Ps::encrypt_sign_aes_ccm()`] and [`Ps::decrypt_verify_aes_ccm()`] instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::ps::{AESAlgorithm, AESKeyType, Ps};
+    /// let ps = Ps::new()?;
+    ///
+    /// let mut data = *b"Super secret message";
+    /// let iv = [0; 16];
+    ///
+    /// ps.encrypt_decrypt_aes(&mut data, AESAlgorithm::CtrEnc, AESKeyType::Keyslot0D, &mut iv.clone())?;
+    /// ps.encrypt_decrypt_aes(&mut data, AESAlgorithm::CtrDec, AESKeyType::Keyslot0D, &mut iv.clone())?;
+    ///
+    /// assert_eq!(&data, b"Super secret message");
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "PS_EncryptDecryptAes")]
+    pub fn encrypt_decrypt_aes(
+        &self,
+        data: &mut [u8],
+        algorithm: AESAlgorithm,
+        key_type: AESKeyType,
+        iv: &mut [u8; 16],
+    ) -> crate::Result<()> {
+        let mut input = data.to_vec();
+
+        ResultCode(unsafe {
+            ctru_sys::PS_EncryptDecryptAes(
+                data.len() as u32,
+                input.as_mut_ptr(),
+                data.as_mut_ptr(),
+                algorithm.into(),
+                key_type.into(),
+                iv.as_mut_ptr(),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Encrypts `data` using AES-CCM with a key stored in the specified keyslot, authenticating it together with `associated_data`.
+    ///
+    /// Returns the encrypted data followed by a 16 bytes long MAC.
+    /// The `associated_data` is not included in the output, and must be provided again to [`Ps::decrypt_verify_aes_ccm()`].
+    #[doc(alias = "PS_EncryptSignDataAesCcm")]
+    pub fn encrypt_sign_aes_ccm(
+        &self,
+        associated_data: &[u8],
+        data: &[u8],
+        key_type: AESKeyType,
+        nonce: &[u8; 12],
+    ) -> crate::Result<Vec<u8>> {
+        let mut input = [associated_data, data].concat();
+        let mut output = vec![0; data.len() + CCM_MAC_LEN];
+        let mut nonce = *nonce;
+
+        ResultCode(unsafe {
+            ctru_sys::PS_EncryptSignDataAesCcm(
+                input.as_mut_ptr(),
+                input.len() as u32,
+                output.as_mut_ptr(),
+                output.len() as u32,
+                data.len() as u32,
+                associated_data.len() as u32,
+                CCM_MAC_LEN as u32,
+                AESAlgorithm::CcmEnc.into(),
+                key_type.into(),
+                nonce.as_mut_ptr(),
+            )
+        })?;
+        Ok(output)
+    }
+
+    /// Decrypts data produced by [`Ps::encrypt_sign_aes_ccm()`], verifying its MAC.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `sealed` is shorter than the MAC, or if the MAC doesn't match
+    /// (i.e. the data, the associated data, the nonce or the key are not the ones used for encryption).
+    #[doc(alias = "PS_DecryptVerifyAesCcm")]
+    pub fn decrypt_verify_aes_ccm(
+        &self,
+        associated_data: &[u8],
+        sealed: &[u8],
+        key_type: AESKeyType,
+        nonce: &[u8; 12],
+    ) -> crate::Result<Vec<u8>> {
+        let Some(data_len) = sealed.len().checked_sub(CCM_MAC_LEN) else {
+            return Err(Error::BufferTooShort {
+                provided: sealed.len(),
+                wanted: CCM_MAC_LEN,
+            });
+        };
+
+        let mut input = [associated_data, sealed].concat();
+        let mut output = vec![0; data_len];
+        let mut nonce = *nonce;
+
+        ResultCode(unsafe {
+            ctru_sys::PS_DecryptVerifyAesCcm(
+                input.as_mut_ptr(),
+                input.len() as u32,
+                output.as_mut_ptr(),
+                output.len() as u32,
+                data_len as u32,
+                associated_data.len() as u32,
+                CCM_MAC_LEN as u32,
+                AESAlgorithm::CcmDec.into(),
+                key_type.into(),
+                nonce.as_mut_ptr(),
+            )
+        })?;
+        Ok(output)
+    }
 }
 
 impl Drop for Ps {
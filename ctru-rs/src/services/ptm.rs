@@ -0,0 +1,176 @@
+//! Power and Time Management service.
+//!
+//! The PTM system service (`ptm:sysm`) keeps track, among other things, of the play history shown in the system's Activity Log:
+//! every time a title is launched or closed, the system records an event with its timestamp.
+//!
+//! # Notes
+//!
+//! This service is only accessible to applications with elevated permissions.
+//! As such, this module is only compiled if the `privileged` feature is enabled.
+#![doc(alias = "activity")]
+#![doc(alias = "playhistory")]
+
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::error::ResultCode;
+use crate::services::svc::{make_ipc_header, HandleExt};
+use crate::services::ServiceReference;
+
+static PTM_SYSM_ACTIVE: Mutex<()> = Mutex::new(());
+
+// ptm:sysm command headers
+const GET_PLAY_HISTORY_COMMAND_HEADER: u32 = make_ipc_header(0x807, 2, 2);
+const GET_PLAY_HISTORY_START_COMMAND_HEADER: u32 = make_ipc_header(0x808, 0, 0);
+const GET_PLAY_HISTORY_LENGTH_COMMAND_HEADER: u32 = make_ipc_header(0x809, 0, 0);
+
+// Size of a single play history entry, as stored by the system.
+const PLAY_EVENT_SIZE: usize = 8;
+// Maximum amount of entries requested at once.
+const PLAY_HISTORY_CHUNK_LEN: u32 = 0x100;
+// Seconds between the UNIX epoch and 2000-01-01 00:00, the epoch used by the system.
+const SYSTEM_EPOCH_OFFSET: u64 = 946_684_800;
+
+/// Handle to the PTM system service.
+pub struct Ptm {
+    _service_handler: ServiceReference,
+}
+
+/// Single entry of the console's play history.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PlayEvent {
+    /// Lower word of the Title ID of the title which caused the event.
+    pub title_id_low: u32,
+    /// Type of the event, as stored by the system (e.g. title launch or exit).
+    pub kind: u8,
+    /// Time of the event (in local time, with a precision of one minute).
+    pub time: SystemTime,
+}
+
+impl Ptm {
+    /// Initialize a new service handle.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service was unable to be initialized
+    /// (most commonly because the application lacks access to `ptm:sysm`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::ptm::Ptm;
+    ///
+    /// let ptm = Ptm::new()?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "ptmSysmInit")]
+    pub fn new() -> crate::Result<Self> {
+        let _service_handler = ServiceReference::new(
+            &PTM_SYSM_ACTIVE,
+            || {
+                ResultCode(unsafe { ctru_sys::ptmSysmInit() })?;
+
+                Ok(())
+            },
+            || unsafe {
+                ctru_sys::ptmSysmExit();
+            },
+        )?;
+
+        Ok(Self { _service_handler })
+    }
+
+    /// Returns the amount of entries stored in the play history.
+    pub fn play_history_len(&self) -> crate::Result<u32> {
+        let response = unsafe {
+            self.handle()
+                .send_service_request(vec![GET_PLAY_HISTORY_LENGTH_COMMAND_HEADER], 3)?
+        };
+
+        Ok(response[2])
+    }
+
+    /// Returns the whole play history, from the oldest to the most recent event.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::ptm::Ptm;
+    /// let ptm = Ptm::new()?;
+    ///
+    /// for event in ptm.play_history()? {
+    ///     println!("{:08X} at {:?}", event.title_id_low, event.time);
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn play_history(&self) -> crate::Result<Vec<PlayEvent>> {
+        let start = unsafe {
+            self.handle()
+                .send_service_request(vec![GET_PLAY_HISTORY_START_COMMAND_HEADER], 3)?[2]
+        };
+        let len = self.play_history_len()?;
+
+        let mut events = Vec::with_capacity(len as usize);
+        let mut buffer = vec![0u8; PLAY_HISTORY_CHUNK_LEN as usize * PLAY_EVENT_SIZE];
+
+        while (events.len() as u32) < len {
+            let count = (len - events.len() as u32).min(PLAY_HISTORY_CHUNK_LEN);
+            let buffer = &mut buffer[..count as usize * PLAY_EVENT_SIZE];
+
+            let request = vec![
+                GET_PLAY_HISTORY_COMMAND_HEADER,
+                start + events.len() as u32,
+                count,
+                // Write-only buffer descriptor.
+                ((buffer.len() as u32) << 4) | 0xC,
+                buffer.as_mut_ptr() as u32,
+            ];
+            let response = unsafe { self.handle().send_service_request(request, 3)? };
+
+            let read = (response[2] as usize).min(count as usize);
+            if read == 0 {
+                break;
+            }
+
+            events.extend(
+                buffer
+                    .chunks_exact(PLAY_EVENT_SIZE)
+                    .take(read)
+                    .map(PlayEvent::from_bytes),
+            );
+        }
+
+        Ok(events)
+    }
+
+    fn handle(&self) -> ctru_sys::Handle {
+        unsafe { *ctru_sys::ptmSysmGetSessionHandle() }
+    }
+}
+
+impl PlayEvent {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let title_id_low = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let info = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+
+        // The lower 28 bits hold the minutes since the system epoch, while the upper 4 bits hold the event type.
+        let minutes = u64::from(info & 0x0FFF_FFFF);
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(SYSTEM_EPOCH_OFFSET + minutes * 60);
+
+        Self {
+            title_id_low,
+            kind: (info >> 28) as u8,
+            time,
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! PXI Device service.
+//!
+//! The PXIDEV service gives raw access to the SPI bus of the game card slot, which is mainly used to read the save memory chips of
+//! Nintendo DS (TWL) cartridges. Together with the card detection functions offered by the FS service, it covers the functionality needed by
+//! game card dumping and save backup tools.
+//!
+//! # Notes
+//!
+//! This service is only accessible to applications with elevated permissions (e.g. when running via a CIA with the proper service access control).
+//! As such, this module is only compiled if the `privileged` feature is enabled.
+//!
+//! The save memory of Nintendo 3DS (CTR) cartridges cannot be accessed through this service; use the [`fs`](crate::services::fs) archives instead.
+#![doc(alias = "card")]
+#![doc(alias = "gamecard")]
+#![doc(alias = "cartridge")]
+
+use std::sync::Mutex;
+
+use crate::error::ResultCode;
+use crate::services::ServiceReference;
+
+static PXIDEV_ACTIVE: Mutex<()> = Mutex::new(());
+
+/// Size of the biggest single SPI transfer performed by [`Card::read_save()`].
+///
+/// Bigger transfers reduce the IPC overhead when dumping the whole save memory of a card.
+const CHUNK_SIZE: usize = 0x1000;
+
+/// Size of the address space of save memory chips using 3-byte addressing.
+const ADDRESS_SPACE: u64 = 1 << 24;
+
+/// SPI command used to read data from the save memory chip.
+const SPI_CMD_READ: u8 = 0x03;
+/// SPI command used to read the JEDEC identifier of FLASH save memory chips.
+const SPI_CMD_RDID: u8 = 0x9F;
+
+/// Kind of game card inserted in the console.
+#[doc(alias = "FS_CardType")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CardType {
+    /// Nintendo 3DS game card.
+    Ctr = ctru_sys::CARD_CTR,
+    /// Nintendo DS(i) game card.
+    Twl = ctru_sys::CARD_TWL,
+}
+
+/// Clock rate of the card SPI bus.
+#[doc(alias = "PXIDEV_BaudRate")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BaudRate {
+    /// 512 kHz.
+    Khz512 = ctru_sys::BAUDRATE_512KHZ,
+    /// 1 MHz.
+    Mhz1 = ctru_sys::BAUDRATE_1MHZ,
+    /// 2 MHz.
+    Mhz2 = ctru_sys::BAUDRATE_2MHZ,
+    /// 4 MHz.
+    Mhz4 = ctru_sys::BAUDRATE_4MHZ,
+    /// 8 MHz.
+    Mhz8 = ctru_sys::BAUDRATE_8MHZ,
+    /// 16 MHz.
+    Mhz16 = ctru_sys::BAUDRATE_16MHZ,
+}
+
+/// Handle to the PXI Device service.
+pub struct Card {
+    baud_rate: BaudRate,
+    _service_handler: ServiceReference,
+}
+
+impl Card {
+    /// Initialize a new service handle.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the service was unable to be initialized
+    /// (most commonly because the application lacks access to `pxi:dev`).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::pxidev::Card;
+    ///
+    /// let card = Card::new()?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "pxiDevInit")]
+    pub fn new() -> crate::Result<Self> {
+        let _service_handler = ServiceReference::new(
+            &PXIDEV_ACTIVE,
+            || {
+                ResultCode(unsafe { ctru_sys::pxiDevInit() })?;
+
+                Ok(())
+            },
+            || unsafe {
+                ctru_sys::pxiDevExit();
+            },
+        )?;
+
+        Ok(Self {
+            baud_rate: BaudRate::Mhz4,
+            _service_handler,
+        })
+    }
+
+    /// Returns `true` if a game card is inserted in the card slot.
+    #[doc(alias = "FSUSER_CardSlotIsInserted")]
+    pub fn is_inserted(&self) -> crate::Result<bool> {
+        let mut inserted = false;
+
+        ResultCode(unsafe { ctru_sys::FSUSER_CardSlotIsInserted(&mut inserted) })?;
+        Ok(inserted)
+    }
+
+    /// Returns the kind of the inserted game card.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::pxidev::{Card, CardType};
+    /// let card = Card::new()?;
+    ///
+    /// if card.is_inserted()? && card.card_type()? == CardType::Twl {
+    ///     println!("A Nintendo DS cartridge is inserted!");
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "FSUSER_GetCardType")]
+    pub fn card_type(&self) -> crate::Result<CardType> {
+        let mut card_type = 0;
+
+        ResultCode(unsafe { ctru_sys::FSUSER_GetCardType(&mut card_type) })?;
+
+        match card_type {
+            ctru_sys::CARD_CTR => Ok(CardType::Ctr),
+            ctru_sys::CARD_TWL => Ok(CardType::Twl),
+            _ => Err(crate::Error::Other(format!(
+                "unknown game card type {card_type}"
+            ))),
+        }
+    }
+
+    /// Returns the clock rate used for SPI transfers.
+    pub fn baud_rate(&self) -> BaudRate {
+        self.baud_rate
+    }
+
+    /// Set the clock rate used for SPI transfers. Defaults to [`BaudRate::Mhz4`].
+    ///
+    /// # Notes
+    ///
+    /// Not all save memory chips support the faster clock rates. If reads return garbage data, try lowering this value.
+    pub fn set_baud_rate(&mut self, baud_rate: BaudRate) {
+        self.baud_rate = baud_rate;
+    }
+
+    /// Returns the JEDEC identifier of the save memory chip of the inserted Nintendo DS card.
+    ///
+    /// # Notes
+    ///
+    /// Only FLASH chips answer to the identification command. EEPROM chips will usually return `0xFFFFFF` or `0`.
+    #[doc(alias = "PXIDEV_SPIMultiWriteRead")]
+    pub fn save_chip_id(&mut self) -> crate::Result<u32> {
+        let mut answer = [0u8; 3];
+
+        self.spi_write_read(&[SPI_CMD_RDID], &mut answer)?;
+
+        Ok(u32::from(answer[0]) << 16 | u32::from(answer[1]) << 8 | u32::from(answer[2]))
+    }
+
+    /// Read the save memory of the inserted Nintendo DS card starting at `offset` and write it into `buffer`.
+    ///
+    /// # Notes
+    ///
+    /// The read is split into multiple chunked SPI transfers, so `buffer` can be as big as the whole save memory.
+    /// FLASH and bigger EEPROM chips use 3-byte addressing, which is the only addressing mode supported by this function.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the read goes past the 16 MiB which can be addressed with 3 bytes,
+    /// or if the SPI transfers failed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::pxidev::Card;
+    /// let mut card = Card::new()?;
+    ///
+    /// // Dump the first 256 KiB of the save memory.
+    /// let mut save = vec![0; 0x40000];
+    /// card.read_save(0, &mut save)?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "PXIDEV_SPIMultiWriteRead")]
+    pub fn read_save(&mut self, offset: u32, buffer: &mut [u8]) -> crate::Result<()> {
+        // Addresses would silently wrap around past the end of the address space.
+        if u64::from(offset) + buffer.len() as u64 > ADDRESS_SPACE {
+            return Err(crate::Error::Other(format!(
+                "save memory read of {} bytes at {offset:#x} is out of range",
+                buffer.len()
+            )));
+        }
+
+        let mut address = offset;
+
+        for chunk in buffer.chunks_mut(CHUNK_SIZE) {
+            let command = [
+                SPI_CMD_READ,
+                (address >> 16) as u8,
+                (address >> 8) as u8,
+                address as u8,
+            ];
+
+            self.spi_write_read(&command, chunk)?;
+
+            address += chunk.len() as u32;
+        }
+
+        Ok(())
+    }
+
+    // Send a command to the card SPI bus and read its answer in a single transaction.
+    fn spi_write_read(&mut self, command: &[u8], answer: &mut [u8]) -> crate::Result<()> {
+        let transfer_option = unsafe {
+            ctru_sys::pxiDevMakeTransferOption(self.baud_rate.into(), ctru_sys::BUSMODE_1BIT)
+        };
+        let wait_operation = unsafe {
+            ctru_sys::pxiDevMakeWaitOperation(ctru_sys::WAIT_NONE, ctru_sys::DEASSERT_NONE, 0)
+        };
+
+        let mut header = ctru_sys::PXIDEV_SPIBuffer {
+            ptr: std::ptr::null_mut(),
+            size: 0,
+            transferOption: transfer_option,
+            waitOperation: wait_operation,
+        };
+        let mut write_buffer = ctru_sys::PXIDEV_SPIBuffer {
+            ptr: command.as_ptr().cast_mut().cast(),
+            size: command.len() as u32,
+            transferOption: transfer_option,
+            waitOperation: wait_operation,
+        };
+        let mut read_buffer = ctru_sys::PXIDEV_SPIBuffer {
+            ptr: answer.as_mut_ptr().cast(),
+            size: answer.len() as u32,
+            transferOption: transfer_option,
+            waitOperation: wait_operation,
+        };
+        let mut empty_write = header;
+        let mut empty_read = header;
+        let mut footer = header;
+
+        ResultCode(unsafe {
+            ctru_sys::PXIDEV_SPIMultiWriteRead(
+                &mut header,
+                &mut write_buffer,
+                &mut read_buffer,
+                &mut empty_write,
+                &mut empty_read,
+                &mut footer,
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+from_impl!(CardType, ctru_sys::FS_CardType);
+from_impl!(BaudRate, ctru_sys::PXIDEV_BaudRate);
@@ -1,9 +1,43 @@
 use crate::Error;
 use std::sync::{Mutex, MutexGuard, TryLockError};
 
+type Close = Box<dyn Fn() + Send + Sync>;
+
+// Service which was initialized via a `ServiceReference`, and hasn't been exited yet.
+struct ActiveService {
+    id: u64,
+    counter: &'static Mutex<()>,
+    // Exit function of a service whose handle was dropped while services initialized after it were still active.
+    pending_close: Option<Close>,
+}
+
+struct Registry {
+    next_id: u64,
+    services: Vec<ActiveService>,
+}
+
+// Services active in the whole application, in initialization order.
+//
+// Some `libctru` services crash if exited while a service initialized after them is still active.
+// To avoid this, the exit of services dropped out of order is postponed until all services initialized after them
+// have exited too. The handle itself is released right away, so the service can be initialized again in the meantime,
+// in which case the postponed exit is cancelled and the running instance is reused.
+//
+// The exit functions run while the registry is locked, so they must not create or drop other services.
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    next_id: 0,
+    services: Vec::new(),
+});
+
+fn registry() -> MutexGuard<'static, Registry> {
+    // Exit functions may panic, but the registry itself is always left in a consistent state.
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 pub(crate) struct ServiceReference {
+    id: u64,
+    close: Option<Close>,
     _guard: MutexGuard<'static, ()>,
-    close: Box<dyn Fn() + Send + Sync>,
 }
 
 impl ServiceReference {
@@ -12,33 +46,145 @@ impl ServiceReference {
         S: FnOnce() -> crate::Result<()>,
         E: Fn() + Send + Sync + 'static,
     {
-        let _guard = match counter.try_lock() {
-            Ok(lock) => lock,
-            Err(e) => match e {
-                TryLockError::Poisoned(guard) => {
-                    // If the MutexGuard is poisoned that means that the "other" service instance (of which the thread panicked)
-                    // was NOT properly closed. To avoid any weird behaviour, we try closing the service now, to then re-open a fresh instance.
-                    //
-                    // It's up to our `close()` implementations to avoid panicking/doing weird stuff again.
-                    close();
-
-                    guard.into_inner()
-                }
-                TryLockError::WouldBlock => return Err(Error::ServiceAlreadyActive),
-            },
+        let (_guard, poisoned) = match counter.try_lock() {
+            Ok(lock) => (lock, false),
+            Err(TryLockError::Poisoned(guard)) => (guard.into_inner(), true),
+            Err(TryLockError::WouldBlock) => return Err(Error::ServiceAlreadyActive),
         };
 
+        // The service may still be running, if its previous handle was dropped out of order.
+        // In that case the postponed exit is cancelled, and the running instance reused as is.
+        let revived = registry()
+            .services
+            .iter_mut()
+            .find(|service| {
+                std::ptr::eq(service.counter, counter) && service.pending_close.is_some()
+            })
+            .map(|service| {
+                service.pending_close = None;
+                service.id
+            });
+
+        if let Some(id) = revived {
+            return Ok(Self {
+                id,
+                close: Some(Box::new(close)),
+                _guard,
+            });
+        }
+
+        if poisoned {
+            // If the MutexGuard is poisoned that means that the "other" service instance (of which the thread panicked)
+            // was NOT properly closed. To avoid any weird behaviour, we try closing the service now, to then re-open a fresh instance.
+            //
+            // It's up to our `close()` implementations to avoid panicking/doing weird stuff again.
+            close();
+        }
+
         start()?;
 
+        let mut registry = registry();
+        let id = registry.next_id;
+        registry.next_id += 1;
+        registry.services.push(ActiveService {
+            id,
+            counter,
+            pending_close: None,
+        });
+
         Ok(Self {
+            id,
+            close: Some(Box::new(close)),
             _guard,
-            close: Box::new(close),
         })
     }
 }
 
 impl Drop for ServiceReference {
     fn drop(&mut self) {
-        (self.close)();
+        let close = self.close.take().unwrap();
+        let mut registry = registry();
+
+        let Some(position) = registry
+            .services
+            .iter()
+            .position(|service| service.id == self.id)
+        else {
+            close();
+            return;
+        };
+
+        if position != registry.services.len() - 1 {
+            // Services initialized after this one are still active: postpone the exit.
+            registry.services[position].pending_close = Some(close);
+            return;
+        }
+
+        registry.services.pop();
+        close();
+
+        // Exit the services which were waiting for this one, from the latest initialized to the earliest one.
+        while let Some(ActiveService {
+            pending_close: Some(_),
+            ..
+        }) = registry.services.last()
+        {
+            let service = registry.services.pop().unwrap();
+            if let Some(close) = service.pending_close {
+                close();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static FIRST_ACTIVE: Mutex<()> = Mutex::new(());
+    static SECOND_ACTIVE: Mutex<()> = Mutex::new(());
+    static EXITS: AtomicU32 = AtomicU32::new(0);
+    static STARTS: AtomicU32 = AtomicU32::new(0);
+
+    fn first() -> ServiceReference {
+        ServiceReference::new(
+            &FIRST_ACTIVE,
+            || {
+                STARTS.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+            || {
+                // The second service must have exited already.
+                assert_eq!(EXITS.fetch_add(1, Ordering::SeqCst), 1);
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn out_of_order_exit_is_postponed() {
+        let first_handle = first();
+        let second = ServiceReference::new(
+            &SECOND_ACTIVE,
+            || Ok(()),
+            || {
+                assert_eq!(EXITS.fetch_add(1, Ordering::SeqCst), 0);
+            },
+        )
+        .unwrap();
+
+        // The handle is released right away, but the exit is postponed.
+        drop(first_handle);
+        assert_eq!(EXITS.load(Ordering::SeqCst), 0);
+        assert!(FIRST_ACTIVE.try_lock().is_ok());
+
+        // Initializing the service again reuses the running instance.
+        let first_handle = first();
+        assert_eq!(STARTS.load(Ordering::SeqCst), 1);
+        drop(first_handle);
+
+        drop(second);
+        assert_eq!(EXITS.load(Ordering::SeqCst), 2);
     }
 }
@@ -0,0 +1,730 @@
+//! Asset manifest with integrity checks.
+//!
+//! Installs copied by hand to the SD card can easily end up truncated or incomplete, which usually results in confusing failures
+//! far away from the actual cause (e.g. a texture decoder panicking on a half-written file). An [`AssetManifest`] lists the files
+//! the application expects to find in the RomFS, optionally with their size and SHA-256 hash, so that broken installs can be detected
+//! at startup and reported with a meaningful message.
+//!
+//! # Manifest format
+//!
+//! The manifest is a JSON document mapping asset names to their description:
+//!
+//! ```json
+//! {
+//!     "assets": {
+//!         "logo": {
+//!             "path": "gfx/logo.bin",
+//!             "size": 4096,
+//!             "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+//!             "critical": true
+//!         },
+//!         "music": { "path": "audio/theme.pcm" }
+//!     }
+//! }
+//! ```
+//!
+//! Paths are relative to the RomFS root. Only `path` is required: `size` and `sha256` are checked when present,
+//! and `critical` assets (`false` by default) are the ones checked by [`AssetManifest::verify()`].
+//!
+//! The manifest can either be stored in the RomFS itself and read at runtime via [`AssetManifest::load()`],
+//! or embedded in the executable at build time with the [`asset_manifest!`](crate::asset_manifest) macro.
+#![doc(alias = "integrity")]
+#![doc(alias = "checksum")]
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+
+use super::RomFS;
+use crate::hash::sha256;
+
+/// Default location of the manifest loaded by [`AssetManifest::load()`].
+pub const DEFAULT_PATH: &str = "romfs:/manifest.json";
+
+/// Embeds an asset manifest in the executable at build time.
+///
+/// The path is relative to the directory containing the package's `Cargo.toml` manifest.
+/// The macro evaluates to a `Result<AssetManifest, ManifestError>`, so that the manifest itself can't be affected by a corrupted install.
+///
+/// # Example
+///
+/// ```ignore
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::romfs::RomFS;
+///
+/// let _romfs = RomFS::new()?;
+/// let manifest = ctru::asset_manifest!("romfs/manifest.json")?;
+///
+/// manifest.verify()?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! asset_manifest {
+    ($path:literal) => {
+        $crate::services::romfs::manifest::AssetManifest::parse(::core::include_str!(
+            ::core::concat!(::core::env!("CARGO_MANIFEST_DIR"), "/", $path)
+        ))
+    };
+}
+
+/// Error enum for errors related to the [`AssetManifest`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum ManifestError {
+    /// The manifest isn't valid JSON, or doesn't follow the expected format.
+    Parse {
+        /// Line of the manifest where the error was found, or `0` if the error concerns the manifest structure as a whole.
+        line: usize,
+        /// Description of the error.
+        message: String,
+    },
+    /// No asset with the requested name is listed in the manifest.
+    UnknownAsset(String),
+    /// An asset listed in the manifest couldn't be found.
+    Missing {
+        /// Name of the asset.
+        name: String,
+        /// Full path of the asset.
+        path: String,
+    },
+    /// An asset doesn't have the expected size, which usually means it was only partially copied.
+    SizeMismatch {
+        /// Name of the asset.
+        name: String,
+        /// Size listed in the manifest.
+        expected: u64,
+        /// Actual size of the file.
+        actual: u64,
+    },
+    /// An asset doesn't have the expected SHA-256 hash.
+    HashMismatch {
+        /// Name of the asset.
+        name: String,
+        /// Hash listed in the manifest, as lowercase hexadecimal.
+        expected: String,
+        /// Actual hash of the file, as lowercase hexadecimal.
+        actual: String,
+    },
+    /// A file couldn't be read.
+    Io {
+        /// Full path of the file.
+        path: String,
+        /// The underlying error.
+        error: io::Error,
+    },
+}
+
+/// Description of a file listed in an [`AssetManifest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Asset {
+    path: String,
+    size: Option<u64>,
+    sha256: Option<[u8; 32]>,
+    critical: bool,
+}
+
+/// List of the assets bundled in the RomFS.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::romfs::{manifest::AssetManifest, RomFS};
+///
+/// let romfs = RomFS::new()?;
+/// let manifest = AssetManifest::load(&romfs)?;
+///
+/// // Fail early, with a meaningful message, if the install is broken.
+/// manifest.verify()?;
+///
+/// let logo = manifest.read("logo")?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AssetManifest {
+    assets: HashMap<String, Asset>,
+}
+
+impl AssetManifest {
+    /// Parses a manifest from its JSON representation.
+    pub fn parse(json: &str) -> Result<Self, ManifestError> {
+        let root = Parser { json, position: 0 }.document()?;
+
+        let Some(Value::Object(entries)) = root.get("assets") else {
+            return Err(schema_error("missing \"assets\" object"));
+        };
+
+        let mut assets = HashMap::with_capacity(entries.len());
+        for (name, value) in entries {
+            let asset = Asset::from_value(value)
+                .map_err(|message| schema_error(&format!("asset \"{name}\": {message}")))?;
+            assets.insert(name.clone(), asset);
+        }
+
+        Ok(Self { assets })
+    }
+
+    /// Loads the manifest stored at [`DEFAULT_PATH`] in the RomFS.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the manifest couldn't be read or parsed.
+    pub fn load(romfs: &RomFS) -> Result<Self, ManifestError> {
+        Self::load_from(romfs, DEFAULT_PATH)
+    }
+
+    /// Loads the manifest stored at the specified path.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the manifest couldn't be read or parsed.
+    pub fn load_from(_romfs: &RomFS, path: &str) -> Result<Self, ManifestError> {
+        let json = fs::read_to_string(path).map_err(|error| ManifestError::Io {
+            path: path.to_owned(),
+            error,
+        })?;
+
+        Self::parse(&json)
+    }
+
+    /// Returns the asset with the specified name, if listed in the manifest.
+    pub fn get(&self, name: &str) -> Option<&Asset> {
+        self.assets.get(name)
+    }
+
+    /// Returns an iterator over the names and descriptions of all assets.
+    pub fn assets(&self) -> impl Iterator<Item = (&str, &Asset)> {
+        self.assets
+            .iter()
+            .map(|(name, asset)| (name.as_str(), asset))
+    }
+
+    /// Returns the full path (including the `romfs:/` prefix) of the asset with the specified name.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`ManifestError::UnknownAsset`] if the asset isn't listed in the manifest.
+    pub fn path(&self, name: &str) -> Result<String, ManifestError> {
+        self.asset(name).map(Asset::full_path)
+    }
+
+    /// Reads the contents of the asset with the specified name, checking its size and hash.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the asset isn't listed in the manifest, couldn't be read or doesn't match its description.
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, ManifestError> {
+        self.asset(name)?.read(name)
+    }
+
+    /// Reads the contents of the asset with the specified name as UTF-8 text, checking its size and hash.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the asset isn't listed in the manifest, couldn't be read, doesn't match its description,
+    /// or isn't valid UTF-8.
+    pub fn read_to_string(&self, name: &str) -> Result<String, ManifestError> {
+        let asset = self.asset(name)?;
+
+        String::from_utf8(asset.read(name)?).map_err(|_| ManifestError::Io {
+            path: asset.full_path(),
+            error: io::Error::new(io::ErrorKind::InvalidData, "asset isn't valid UTF-8"),
+        })
+    }
+
+    /// Checks that all critical assets are present and match their description.
+    ///
+    /// This is meant to be called at startup, before any asset is used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the error of the first critical asset found missing or corrupted.
+    pub fn verify(&self) -> Result<(), ManifestError> {
+        self.verify_matching(|asset| asset.critical)
+    }
+
+    /// Checks that all assets (critical or not) are present and match their description.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the error of the first asset found missing or corrupted.
+    pub fn verify_all(&self) -> Result<(), ManifestError> {
+        self.verify_matching(|_| true)
+    }
+
+    fn verify_matching(&self, filter: impl Fn(&Asset) -> bool) -> Result<(), ManifestError> {
+        // Check the assets in a stable order, so that the same install always reports the same error.
+        let mut names: Vec<_> = self
+            .assets
+            .iter()
+            .filter(|(_, asset)| filter(asset))
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+
+        for name in names {
+            self.assets[name].read(name)?;
+        }
+
+        Ok(())
+    }
+
+    fn asset(&self, name: &str) -> Result<&Asset, ManifestError> {
+        self.get(name)
+            .ok_or_else(|| ManifestError::UnknownAsset(name.to_owned()))
+    }
+}
+
+impl Asset {
+    /// Returns the path of the asset, relative to the RomFS root.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the full path of the asset, including the `romfs:/` prefix.
+    pub fn full_path(&self) -> String {
+        format!("romfs:/{}", self.path)
+    }
+
+    /// Returns the expected size of the asset, if listed.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Returns the expected SHA-256 hash of the asset, if listed.
+    pub fn sha256(&self) -> Option<&[u8; 32]> {
+        self.sha256.as_ref()
+    }
+
+    /// Returns `true` if the asset is checked by [`AssetManifest::verify()`].
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>, ManifestError> {
+        let path = self.full_path();
+
+        let data = fs::read(&path).map_err(|error| match error.kind() {
+            io::ErrorKind::NotFound => ManifestError::Missing {
+                name: name.to_owned(),
+                path: path.clone(),
+            },
+            _ => ManifestError::Io {
+                path: path.clone(),
+                error,
+            },
+        })?;
+
+        self.check(name, &data)?;
+
+        Ok(data)
+    }
+
+    fn check(&self, name: &str, data: &[u8]) -> Result<(), ManifestError> {
+        if let Some(expected) = self.size {
+            if data.len() as u64 != expected {
+                return Err(ManifestError::SizeMismatch {
+                    name: name.to_owned(),
+                    expected,
+                    actual: data.len() as u64,
+                });
+            }
+        }
+
+        if let Some(expected) = self.sha256 {
+            let actual = sha256(data);
+
+            if actual != expected {
+                return Err(ManifestError::HashMismatch {
+                    name: name.to_owned(),
+                    expected: to_hex(&expected),
+                    actual: to_hex(&actual),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn from_value(value: &Value) -> Result<Self, String> {
+        let Value::Object(_) = value else {
+            return Err("expected an object".into());
+        };
+
+        let path = match value.get("path") {
+            Some(Value::String(path)) => path.trim_start_matches('/').to_owned(),
+            _ => return Err("missing \"path\" string".into()),
+        };
+
+        let size = match value.get("size") {
+            None => None,
+            Some(Value::Number(size)) if size.fract() == 0.0 && *size >= 0.0 => Some(*size as u64),
+            Some(_) => return Err("\"size\" must be a non-negative integer".into()),
+        };
+
+        let sha256 = match value.get("sha256") {
+            None => None,
+            Some(Value::String(hash)) => {
+                Some(from_hex(hash).ok_or("\"sha256\" must be 64 hexadecimal characters")?)
+            }
+            Some(_) => return Err("\"sha256\" must be a string".into()),
+        };
+
+        let critical = match value.get("critical") {
+            None => false,
+            Some(Value::Bool(critical)) => *critical,
+            Some(_) => return Err("\"critical\" must be a boolean".into()),
+        };
+
+        Ok(Self {
+            path,
+            size,
+            sha256,
+            critical,
+        })
+    }
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, message } => {
+                write!(f, "invalid asset manifest (line {line}): {message}")
+            }
+            Self::UnknownAsset(name) => write!(f, "asset \"{name}\" isn't listed in the manifest"),
+            Self::Missing { name, path } => write!(
+                f,
+                "asset \"{name}\" is missing ({path} not found), the application may not have been copied completely"
+            ),
+            Self::SizeMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "asset \"{name}\" is {actual} bytes long instead of {expected}, the application may not have been copied completely"
+            ),
+            Self::HashMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "asset \"{name}\" is corrupted (expected SHA-256 {expected}, found {actual}), try reinstalling the application"
+            ),
+            Self::Io { path, error } => write!(f, "couldn't read {path}: {error}"),
+        }
+    }
+}
+
+impl StdError for ManifestError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut bytes = [0; 32];
+    for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+// Minimal JSON document model, covering what's needed to read manifests.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    // Arrays aren't used by manifests, their contents are only validated.
+    Array,
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    json: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn document(&mut self) -> Result<Value, ManifestError> {
+        let value = self.value()?;
+
+        self.skip_whitespace();
+        if self.position != self.json.len() {
+            return Err(self.error("unexpected data after the document"));
+        }
+
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, ManifestError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of document")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, ManifestError> {
+        self.expect(b'{')?;
+
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Value::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            entries.push((key, self.value()?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b'}') => return Ok(Value::Object(entries)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ManifestError> {
+        self.expect(b'[')?;
+
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Value::Array);
+        }
+
+        loop {
+            self.value()?;
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b']') => return Ok(Value::Array),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ManifestError> {
+        self.expect(b'"')?;
+
+        let mut string = String::new();
+        loop {
+            let rest = &self.json[self.position..];
+            let Some(end) = rest.find(['"', '\\']) else {
+                return Err(self.error("unterminated string"));
+            };
+
+            string.push_str(&rest[..end]);
+            self.position += end + 1;
+
+            if rest.as_bytes()[end] == b'"' {
+                return Ok(string);
+            }
+
+            let escaped = match self.next() {
+                Some(b'"') => '"',
+                Some(b'\\') => '\\',
+                Some(b'/') => '/',
+                Some(b'b') => '\u{8}',
+                Some(b'f') => '\u{c}',
+                Some(b'n') => '\n',
+                Some(b'r') => '\r',
+                Some(b't') => '\t',
+                Some(b'u') => {
+                    let code = self
+                        .json
+                        .get(self.position..self.position + 4)
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .ok_or_else(|| self.error("invalid unicode escape"))?;
+                    self.position += 4;
+
+                    // Surrogate pairs aren't needed for manifests, they're replaced instead.
+                    char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                }
+                _ => return Err(self.error("invalid escape sequence")),
+            };
+            string.push(escaped);
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ManifestError> {
+        let start = self.position;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+
+        self.json[start..self.position]
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| self.error_at(start, "invalid number"))
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Value, ManifestError> {
+        if !self.json[self.position..].starts_with(literal) {
+            return Err(self.error("expected a value"));
+        }
+
+        self.position += literal.len();
+        Ok(value)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), ManifestError> {
+        if self.next() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.as_bytes().get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn error(&self, message: &str) -> ManifestError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: &str) -> ManifestError {
+        let line = self.json.as_bytes()[..position.min(self.json.len())]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+
+        ManifestError::Parse {
+            line: line + 1,
+            message: message.to_owned(),
+        }
+    }
+}
+
+// Errors in the manifest structure aren't tied to a position in the document.
+fn schema_error(message: &str) -> ManifestError {
+    ManifestError::Parse {
+        line: 0,
+        message: message.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = AssetManifest::parse(
+            r#"{
+                "assets": {
+                    "logo": {
+                        "path": "/gfx/logo.bin",
+                        "size": 3,
+                        "sha256": "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD",
+                        "critical": true
+                    },
+                    "music": { "path": "audio/theme.pcm" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let logo = manifest.get("logo").unwrap();
+        assert_eq!(logo.full_path(), "romfs:/gfx/logo.bin");
+        assert!(logo.is_critical());
+        assert!(logo.check("logo", b"abc").is_ok());
+        assert!(matches!(
+            logo.check("logo", b"abd"),
+            Err(ManifestError::HashMismatch { .. })
+        ));
+        assert!(matches!(
+            logo.check("logo", b"ab"),
+            Err(ManifestError::SizeMismatch {
+                expected: 3,
+                actual: 2,
+                ..
+            })
+        ));
+
+        let music = manifest.get("music").unwrap();
+        assert_eq!(music.path(), "audio/theme.pcm");
+        assert!(!music.is_critical());
+        assert!(manifest.get("missing").is_none());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            AssetManifest::parse("{\n  \"assets\": {\n    \"logo\": }\n}"),
+            Err(ManifestError::Parse { line: 3, .. })
+        ));
+        assert!(matches!(
+            AssetManifest::parse(r#"{ "assets": { "logo": { "sha256": "00" } } }"#),
+            Err(ManifestError::Parse { line: 0, .. })
+        ));
+        assert!(matches!(
+            AssetManifest::parse(r#"{ "files": [] }"#),
+            Err(ManifestError::Parse { .. })
+        ));
+    }
+}
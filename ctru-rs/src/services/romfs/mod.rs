@@ -27,6 +27,8 @@
 #![doc(alias = "embed")]
 #![doc(alias = "filesystem")]
 
+pub mod manifest;
+
 use crate::error::ResultCode;
 use std::ffi::CStr;
 use std::sync::Mutex;
@@ -2,14 +2,37 @@
 //!
 //! By using this service the program enables the use of network sockets and utilities such as those found in `std::net`, which are completely inaccessible by default.
 //! As such, remember to hold a handle to this service handle while using any network functionality, or else the `std::net` methods will return generic OS errors.
+//!
+//! # Servers
+//!
+//! The socket implementation of the system has a few limitations which servers written against the defaults of the standard library run into:
+//! the backlog of pending connections is much smaller than on desktop systems, and a port can't be bound again while the closed sockets
+//! which used it linger, unless the address is explicitly marked as reusable. [`ListenerOptions`] creates listeners with settings which work
+//! with the system, and [`serve()`] runs an accept loop which can be stopped from another thread via a [`Shutdown`] handle.
+//!
+//! # IPv6
+//!
+//! The network stack of the system only supports IPv4. Using an IPv6 address with `std::net` fails with generic OS errors,
+//! and resolving a host name may return IPv6 addresses which can't be connected to.
+//! [`resolve()`] and [`connect()`] only keep the usable addresses, and [`to_ipv4()`] converts a single address,
+//! reporting an [`AddressError`] when no IPv4 address is available.
 #![doc(alias = "socket")]
 #![doc(alias = "network")]
 
 use libc::memalign;
-use std::net::Ipv4Addr;
-use std::sync::Mutex;
+use std::fmt;
+use std::io;
+use std::net::{
+    Ipv4Addr, SocketAddr, SocketAddrV4, SocketAddrV6, TcpListener, TcpStream, ToSocketAddrs,
+};
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::error::ResultCode;
+use crate::linear::LinearAllocator;
+use crate::os::WifiStrength;
 use crate::services::ServiceReference;
 use crate::Error;
 
@@ -21,8 +44,118 @@ pub struct Soc {
 
 static SOC_ACTIVE: Mutex<()> = Mutex::new(());
 
+// Socket buffer provided by the caller of `Soc::with_linear_buffer()`, freed once the service has exited.
+static SOC_LINEAR_BUFFER: Mutex<Option<Box<[BufferPage], LinearAllocator>>> = Mutex::new(None);
+
+/// Alignment of the socket buffer, whose size must also be a multiple of it (in bytes).
+pub const BUFFER_ALIGNMENT: usize = 0x1000;
+
+/// Size of the socket buffer used by [`Soc::new()`] (in bytes).
+pub const DEFAULT_BUFFER_SIZE: usize = 0x100000;
+
+/// Largest backlog of pending connections reliably accepted by the system.
+///
+/// Higher values are sometimes accepted, but make `listen` fail at other times.
+pub const MAX_LISTEN_BACKLOG: u32 = 20;
+
+// Interval between the checks of the shutdown flag in `serve()`.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Maximum number of entries read from the routing and DNS tables.
+const MAX_TABLE_ENTRIES: usize = 8;
+
+/// Page of a socket buffer provided via [`Soc::with_linear_buffer()`], ensuring its alignment and size.
+#[derive(Clone)]
+#[repr(C, align(0x1000))]
+pub struct BufferPage([u8; BUFFER_ALIGNMENT]);
+
+impl BufferPage {
+    /// Page filled with zeros.
+    pub const ZEROED: Self = Self([0; BUFFER_ALIGNMENT]);
+}
+
+// Source of the socket buffer.
+enum SocBuffer {
+    Heap(usize),
+    Linear(Box<[BufferPage], LinearAllocator>),
+}
+
+/// Options to create a [`TcpListener`] with settings supported by the system.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::net::{Ipv4Addr, SocketAddrV4};
+///
+/// use ctru::services::soc::{ListenerOptions, Soc};
+///
+/// let soc = Soc::new()?;
+///
+/// let listener = ListenerOptions::new()
+///     .backlog(4)
+///     .bind(&soc, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 8080))?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ListenerOptions {
+    reuse_address: bool,
+    backlog: u32,
+}
+
+/// Error returned when an address can't be used by the IPv4-only network stack of the system.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AddressError {
+    /// The address is an IPv6 address, which isn't supported by the system.
+    Ipv6(SocketAddrV6),
+    /// The host name didn't resolve to any IPv4 address.
+    NoIpv4Address,
+}
+
+/// Handle to stop an accept loop started via [`serve()`].
+///
+/// Clones of the handle control the same loop, so one of them can be moved to another thread (or to an exit handler).
+#[derive(Clone, Debug, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+}
+
+/// Statistics about the current network connection.
+///
+/// This struct can be retrieved via [`Soc::link_stats()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Current WiFi signal strength.
+    pub strength: WifiStrength,
+    /// Maximum Transmission Unit of the network interface (in bytes).
+    pub mtu: u32,
+    /// Number of open TCP sockets.
+    pub tcp_sockets: u32,
+    /// Number of open UDP sockets.
+    pub udp_sockets: u32,
+}
+
+/// Addresses of the network interface of the console.
+///
+/// This struct can be retrieved via [`Soc::ip_info()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IpInfo {
+    /// IP address of the console.
+    pub address: Ipv4Addr,
+    /// Subnet mask of the local network.
+    pub subnet_mask: Ipv4Addr,
+    /// Broadcast address of the local network.
+    pub broadcast: Ipv4Addr,
+}
+
 impl Soc {
-    /// Initialize a new service handle using a socket buffer size of `0x100000` bytes.
+    /// Initialize a new service handle using a socket buffer size of [`DEFAULT_BUFFER_SIZE`] bytes.
     ///
     /// # Errors
     ///
@@ -44,16 +177,18 @@ impl Soc {
     /// ```
     #[doc(alias = "socInit")]
     pub fn new() -> crate::Result<Self> {
-        Self::init_with_buffer_size(0x100000)
+        Self::with_buffer_size(DEFAULT_BUFFER_SIZE)
     }
 
     /// Initialize a new service handle using a custom socket buffer size.
     ///
-    /// The size should be `0x100000` bytes or greater.
+    /// The buffer holds the data of all the sockets, so applications doing large transfers benefit from a bigger buffer.
+    /// The size must be a multiple of [`BUFFER_ALIGNMENT`], and should be [`DEFAULT_BUFFER_SIZE`] bytes or greater.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the [`Soc`] service is already being used.
+    /// This function will return an error if the size isn't a non-zero multiple of [`BUFFER_ALIGNMENT`],
+    /// if the buffer couldn't be allocated, or if the [`Soc`] service is already being used.
     ///
     /// # Example
     ///
@@ -64,18 +199,89 @@ impl Soc {
     /// #
     /// use ctru::services::soc::Soc;
     ///
-    /// let soc = Soc::init_with_buffer_size(0x100000)?;
+    /// let soc = Soc::with_buffer_size(0x400000)?;
     /// #
     /// # Ok(())
     /// # }
     /// ```
     #[doc(alias = "socInit")]
+    pub fn with_buffer_size(num_bytes: usize) -> crate::Result<Self> {
+        check_buffer_size(num_bytes)?;
+
+        Self::init(SocBuffer::Heap(num_bytes))
+    }
+
+    /// Initialize a new service handle using a custom socket buffer size.
+    ///
+    /// # Errors
+    ///
+    /// See [`Soc::with_buffer_size()`].
+    #[deprecated(note = "use `Soc::with_buffer_size()` instead")]
     pub fn init_with_buffer_size(num_bytes: usize) -> crate::Result<Self> {
+        Self::with_buffer_size(num_bytes)
+    }
+
+    /// Initialize a new service handle using a socket buffer in LINEAR memory, provided by the caller.
+    ///
+    /// This lets applications choose where the (possibly large) buffer is allocated, e.g. to keep the regular heap for their own data.
+    /// The buffer is kept alive until the service exits, and freed afterwards.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the buffer is empty, or if the [`Soc`] service is already being used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #![feature(allocator_api)]
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::linear::LinearAllocator;
+    /// use ctru::services::soc::{BufferPage, Soc, DEFAULT_BUFFER_SIZE};
+    ///
+    /// let pages = DEFAULT_BUFFER_SIZE / std::mem::size_of::<BufferPage>();
+    /// let mut buffer = Vec::with_capacity_in(pages, LinearAllocator);
+    /// buffer.resize(pages, BufferPage::ZEROED);
+    ///
+    /// let soc = Soc::with_linear_buffer(buffer.into_boxed_slice())?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "socInit")]
+    pub fn with_linear_buffer(buffer: Box<[BufferPage], LinearAllocator>) -> crate::Result<Self> {
+        check_buffer_size(std::mem::size_of_val(&*buffer))?;
+
+        Self::init(SocBuffer::Linear(buffer))
+    }
+
+    fn init(buffer: SocBuffer) -> crate::Result<Self> {
         let _service_handler = ServiceReference::new(
             &SOC_ACTIVE,
             || {
-                let soc_mem = unsafe { memalign(0x1000, num_bytes) } as *mut u32;
-                ResultCode(unsafe { ctru_sys::socInit(soc_mem, num_bytes as u32) })?;
+                match buffer {
+                    SocBuffer::Heap(num_bytes) => {
+                        let soc_mem = unsafe { memalign(BUFFER_ALIGNMENT, num_bytes) } as *mut u32;
+                        if soc_mem.is_null() {
+                            return Err(Error::Other(format!(
+                                "failed to allocate a socket buffer of {num_bytes:#x} bytes"
+                            )));
+                        }
+
+                        ResultCode(unsafe { ctru_sys::socInit(soc_mem, num_bytes as u32) })?;
+                    }
+                    SocBuffer::Linear(mut buffer) => {
+                        let num_bytes = std::mem::size_of_val(&*buffer);
+                        ResultCode(unsafe {
+                            ctru_sys::socInit(buffer.as_mut_ptr().cast(), num_bytes as u32)
+                        })?;
+
+                        // The system keeps using the buffer until the service exits.
+                        *SOC_LINEAR_BUFFER.lock().unwrap_or_else(|e| e.into_inner()) = Some(buffer);
+                    }
+                }
 
                 Ok(())
             },
@@ -83,8 +289,12 @@ impl Soc {
             // but we wouldn't be able to handle them in the `Drop` implementation anyways.
             // Surely nothing bad will happens :D
             || unsafe {
-                // The socket buffer is freed automatically by `socExit`
+                // The socket buffer is freed automatically by `socExit`, unless it was provided by the caller.
                 let _ = ctru_sys::socExit();
+                SOC_LINEAR_BUFFER
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .take();
             },
         )?;
 
@@ -117,6 +327,217 @@ impl Soc {
         Ipv4Addr::from(raw_id.to_ne_bytes())
     }
 
+    /// Returns the current WiFi signal strength.
+    ///
+    /// Useful to display signal bars, or to warn the user about a weak connection before starting a large transfer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::os::WifiStrength;
+    /// use ctru::services::soc::Soc;
+    /// let soc = Soc::new()?;
+    ///
+    /// if soc.wifi_strength() < WifiStrength::Decent {
+    ///     println!("The connection is weak, the download may take a while.");
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "osGetWifiStrength")]
+    pub fn wifi_strength(&self) -> WifiStrength {
+        WifiStrength::current()
+    }
+
+    /// Returns statistics about the current network connection.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::soc::Soc;
+    /// let soc = Soc::new()?;
+    ///
+    /// let stats = soc.link_stats()?;
+    /// println!("Signal: {:?}, MTU: {} bytes", stats.strength, stats.mtu);
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "SOCU_GetNetworkOpt")]
+    pub fn link_stats(&self) -> crate::Result<LinkStats> {
+        Ok(LinkStats {
+            strength: self.wifi_strength(),
+            mtu: self.network_opt(ctru_sys::NETOPT_IP_MTU)?,
+            tcp_sockets: self.network_opt(ctru_sys::NETOPT_TCP_NUMBER)?,
+            udp_sockets: self.network_opt(ctru_sys::NETOPT_UDP_NUMBER)?,
+        })
+    }
+
+    /// Returns the IP address, subnet mask and broadcast address of the console.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::soc::Soc;
+    /// let soc = Soc::new()?;
+    ///
+    /// let info = soc.ip_info()?;
+    /// println!("IP: {}, subnet mask: {}", info.address, info.subnet_mask);
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "SOCU_GetNetworkOpt", alias = "NETOPT_IP_INFO")]
+    pub fn ip_info(&self) -> crate::Result<IpInfo> {
+        // SAFETY: the struct only contains integers.
+        let mut info = [unsafe { std::mem::zeroed::<ctru_sys::SOCU_IPInfo>() }];
+        self.network_opt_items(ctru_sys::NETOPT_IP_INFO, &mut info)?;
+
+        Ok(IpInfo {
+            address: to_ip(info[0].ip),
+            subnet_mask: to_ip(info[0].netmask),
+            broadcast: to_ip(info[0].broadcast),
+        })
+    }
+
+    /// Returns the address of the default gateway, or `None` if there is no default route (e.g. while disconnected).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::soc::Soc;
+    /// let soc = Soc::new()?;
+    ///
+    /// if let Some(gateway) = soc.gateway()? {
+    ///     println!("Gateway: {gateway}");
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "SOCU_GetNetworkOpt", alias = "NETOPT_ROUTING_TABLE")]
+    pub fn gateway(&self) -> crate::Result<Option<Ipv4Addr>> {
+        // SAFETY: the struct only contains integers.
+        let mut routes =
+            [unsafe { std::mem::zeroed::<ctru_sys::SOCU_RoutingTableEntry>() }; MAX_TABLE_ENTRIES];
+        let len = self.network_opt_items(ctru_sys::NETOPT_ROUTING_TABLE, &mut routes)?;
+
+        Ok(routes[..len]
+            .iter()
+            .find(|route| route.dest_ip.s_addr == 0 && route.gateway.s_addr != 0)
+            .map(|route| to_ip(route.gateway)))
+    }
+
+    /// Returns the addresses of the DNS servers, in order of preference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::soc::Soc;
+    /// let soc = Soc::new()?;
+    ///
+    /// for server in soc.dns_servers()? {
+    ///     println!("DNS: {server}");
+    /// }
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "SOCU_GetNetworkOpt", alias = "NETOPT_DNS_TABLE")]
+    pub fn dns_servers(&self) -> crate::Result<Vec<Ipv4Addr>> {
+        // SAFETY: the struct only contains integers.
+        let mut servers =
+            [unsafe { std::mem::zeroed::<ctru_sys::SOCU_DNSTableEntry>() }; MAX_TABLE_ENTRIES];
+        let len = self.network_opt_items(ctru_sys::NETOPT_DNS_TABLE, &mut servers)?;
+
+        Ok(servers[..len]
+            .iter()
+            .filter(|server| server.family == libc::AF_INET as u32)
+            .map(|server| to_ip(server.ip))
+            .collect())
+    }
+
+    /// Returns the MAC address of the Wi-Fi module of the console.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let _runner = test_runner::GdbRunner::default();
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use ctru::services::soc::Soc;
+    /// let soc = Soc::new()?;
+    ///
+    /// let mac = soc.mac_address()?.map(|byte| format!("{byte:02X}")).join(":");
+    /// println!("MAC: {mac}");
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "SOCU_GetNetworkOpt", alias = "NETOPT_MAC_ADDRESS")]
+    pub fn mac_address(&self) -> crate::Result<[u8; 6]> {
+        let mut mac = [0u8; 6];
+        self.network_opt_items(ctru_sys::NETOPT_MAC_ADDRESS, &mut mac)?;
+
+        Ok(mac)
+    }
+
+    // Reads a `u32` network configuration option.
+    fn network_opt(&self, option: ctru_sys::NetworkOpt) -> crate::Result<u32> {
+        let mut value = [0u32];
+        self.network_opt_items(option, &mut value)?;
+
+        Ok(value[0])
+    }
+
+    // Reads a network configuration option made of up to `values.len()` items, returning the number of items read.
+    //
+    // `T` must be a plain C struct (or integer) matching the layout of the option.
+    fn network_opt_items<T: Copy>(
+        &self,
+        option: ctru_sys::NetworkOpt,
+        values: &mut [T],
+    ) -> crate::Result<usize> {
+        let mut len = std::mem::size_of_val(values) as ctru_sys::socklen_t;
+
+        let result = unsafe {
+            ctru_sys::SOCU_GetNetworkOpt(
+                ctru_sys::SOL_CONFIG as i32,
+                option,
+                values.as_mut_ptr().cast(),
+                &mut len,
+            )
+        };
+
+        if result < 0 {
+            Err(Error::from_errno())
+        } else {
+            Ok(len as usize / std::mem::size_of::<T>())
+        }
+    }
+
     /// Redirect output streams (i.e. `stdout` and `stderr`) to the `3dslink` server.
     ///
     /// With this redirection it is possible to send (and view in real time) the output of `stdout` operations,
@@ -167,6 +588,305 @@ impl Soc {
     }
 }
 
+impl ListenerOptions {
+    /// Creates options which reuse the address and use a backlog of [`MAX_LISTEN_BACKLOG`] connections.
+    pub fn new() -> Self {
+        Self {
+            reuse_address: true,
+            backlog: MAX_LISTEN_BACKLOG,
+        }
+    }
+
+    /// Sets whether the address can be bound again while the sockets which used it linger after being closed (`SO_REUSEADDR`).
+    ///
+    /// Without it, a server which is restarted often fails to bind its port for a while.
+    pub fn reuse_address(&mut self, reuse_address: bool) -> &mut Self {
+        self.reuse_address = reuse_address;
+        self
+    }
+
+    /// Sets the maximum number of pending connections, clamped between 1 and [`MAX_LISTEN_BACKLOG`].
+    pub fn backlog(&mut self, backlog: u32) -> &mut Self {
+        self.backlog = backlog.clamp(1, MAX_LISTEN_BACKLOG);
+        self
+    }
+
+    /// Creates a listener bound to `address`.
+    ///
+    /// Only IPv4 addresses are supported by the system.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the socket couldn't be created, or if the address is already in use.
+    #[doc(alias = "SO_REUSEADDR", alias = "listen")]
+    pub fn bind(&self, _soc: &Soc, address: SocketAddrV4) -> io::Result<TcpListener> {
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // The socket is closed if any of the next steps fails.
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        if self.reuse_address {
+            let enable: libc::c_int = 1;
+            let result = unsafe {
+                libc::setsockopt(
+                    fd,
+                    libc::SOL_SOCKET,
+                    libc::SO_REUSEADDR,
+                    (&enable as *const libc::c_int).cast(),
+                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+                )
+            };
+            if result < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        let mut raw_address: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+        raw_address.sin_family = libc::AF_INET as libc::sa_family_t;
+        raw_address.sin_port = address.port().to_be();
+        raw_address.sin_addr.s_addr = u32::from_ne_bytes(address.ip().octets());
+
+        let result = unsafe {
+            libc::bind(
+                fd,
+                (&raw_address as *const libc::sockaddr_in).cast(),
+                std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if unsafe { libc::listen(fd, self.backlog as libc::c_int) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(TcpListener::from(socket))
+    }
+}
+
+impl Default for ListenerOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    /// Creates a new handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the accept loop to stop.
+    ///
+    /// Connections being handled are not interrupted: the loop returns once the current handler returns.
+    pub fn trigger(&self) {
+        self.requested.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if the accept loop was requested to stop.
+    pub fn is_triggered(&self) -> bool {
+        self.requested.load(Ordering::Acquire)
+    }
+}
+
+/// Accepts connections from `listener` until `shutdown` is triggered, calling `handler` for each of them.
+///
+/// The listener is switched to non-blocking mode, so that the shutdown is noticed within a few milliseconds
+/// even if no client connects. The accepted streams are blocking.
+/// Transient errors (a client aborting the connection before it's accepted) are ignored.
+///
+/// # Errors
+///
+/// This function will return an error if the listener fails for any other reason (e.g. the console lost its connection).
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use std::io::Write;
+/// use std::net::{Ipv4Addr, SocketAddrV4};
+///
+/// use ctru::services::soc::{self, ListenerOptions, Shutdown, Soc};
+///
+/// let soc = Soc::new()?;
+/// let listener = ListenerOptions::new().bind(&soc, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 8080))?;
+///
+/// let shutdown = Shutdown::new();
+/// soc::serve(&listener, &shutdown, |mut stream, _| {
+///     let _ = stream.write_all(b"Hello from the 3DS!\n");
+///
+///     // Serve a single client.
+///     shutdown.trigger();
+/// })?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub fn serve(
+    listener: &TcpListener,
+    shutdown: &Shutdown,
+    mut handler: impl FnMut(TcpStream, SocketAddr),
+) -> io::Result<()> {
+    listener.set_nonblocking(true)?;
+
+    while !shutdown.is_triggered() {
+        match listener.accept() {
+            Ok((stream, address)) => {
+                // Sockets accepted by the system inherit the non-blocking mode of the listener.
+                stream.set_nonblocking(false)?;
+                handler(stream, address);
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                std::thread::sleep(SHUTDOWN_POLL_INTERVAL);
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::Interrupted | io::ErrorKind::ConnectionAborted
+                ) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts a socket address to an IPv4 address usable by the system.
+///
+/// IPv4-mapped IPv6 addresses (e.g. `[::ffff:192.168.1.2]:80`) are converted to the IPv4 address they represent.
+///
+/// # Errors
+///
+/// This function will return an [`AddressError::Ipv6`] error for any other IPv6 address.
+///
+/// # Example
+///
+/// ```
+/// use std::net::{Ipv4Addr, SocketAddrV4};
+///
+/// use ctru::services::soc::{self, AddressError};
+///
+/// let mapped = "[::ffff:192.168.1.2]:80".parse().unwrap();
+/// assert_eq!(
+///     soc::to_ipv4(mapped),
+///     Ok(SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 80))
+/// );
+///
+/// let ipv6 = "[2001:db8::1]:80".parse().unwrap();
+/// assert!(matches!(soc::to_ipv4(ipv6), Err(AddressError::Ipv6(_))));
+/// ```
+pub fn to_ipv4(address: SocketAddr) -> Result<SocketAddrV4, AddressError> {
+    match address {
+        SocketAddr::V4(address) => Ok(address),
+        SocketAddr::V6(address) => match address.ip().to_ipv4_mapped() {
+            Some(ip) => Ok(SocketAddrV4::new(ip, address.port())),
+            None => Err(AddressError::Ipv6(address)),
+        },
+    }
+}
+
+/// Resolves a host name (or parses an address), keeping only the IPv4 addresses usable by the system.
+///
+/// # Errors
+///
+/// This function will return an error if the name couldn't be resolved, or an [`AddressError`]
+/// (with the [`AddrNotAvailable`](io::ErrorKind::AddrNotAvailable) kind) if none of the resolved addresses is an IPv4 address.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::soc::{self, Soc};
+///
+/// let soc = Soc::new()?;
+///
+/// for address in soc::resolve("example.com:80")? {
+///     println!("{address}");
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "getaddrinfo")]
+pub fn resolve(address: impl ToSocketAddrs) -> io::Result<Vec<SocketAddrV4>> {
+    let mut addresses = Vec::new();
+    let mut ipv6 = None;
+
+    for address in address.to_socket_addrs()? {
+        match to_ipv4(address) {
+            Ok(address) => addresses.push(address),
+            Err(e) => {
+                ipv6.get_or_insert(e);
+            }
+        }
+    }
+
+    if addresses.is_empty() {
+        Err(ipv6.unwrap_or(AddressError::NoIpv4Address).into())
+    } else {
+        Ok(addresses)
+    }
+}
+
+/// Opens a TCP connection, like [`TcpStream::connect()`], trying only the IPv4 addresses `address` resolves to.
+///
+/// # Errors
+///
+/// This function will return the same errors as [`resolve()`], or the error of the last connection attempt if all of them failed.
+pub fn connect(address: impl ToSocketAddrs) -> io::Result<TcpStream> {
+    let mut last_error = None;
+
+    for address in resolve(address)? {
+        match TcpStream::connect(address) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    // `resolve()` never returns an empty list.
+    Err(last_error.unwrap())
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ipv6(address) => write!(
+                f,
+                "the IPv6 address {address} can't be used, since the system only supports IPv4"
+            ),
+            Self::NoIpv4Address => write!(f, "the host has no IPv4 address"),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+impl From<AddressError> for io::Error {
+    fn from(e: AddressError) -> Self {
+        io::Error::new(io::ErrorKind::AddrNotAvailable, e)
+    }
+}
+
+fn check_buffer_size(num_bytes: usize) -> crate::Result<()> {
+    if num_bytes == 0 || num_bytes % BUFFER_ALIGNMENT != 0 {
+        return Err(Error::Other(format!(
+            "the socket buffer size ({num_bytes:#x}) must be a non-zero multiple of {BUFFER_ALIGNMENT:#x}"
+        )));
+    }
+
+    Ok(())
+}
+
 impl Drop for Soc {
     #[doc(alias = "socExit")]
     fn drop(&mut self) {
@@ -178,6 +898,11 @@ impl Drop for Soc {
     }
 }
 
+// The addresses are stored in network byte order.
+fn to_ip(address: libc::in_addr) -> Ipv4Addr {
+    Ipv4Addr::from(address.s_addr.to_ne_bytes())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,4 +913,75 @@ mod tests {
 
         assert!(matches!(Soc::new(), Err(Error::ServiceAlreadyActive)))
     }
+
+    #[test]
+    fn soc_buffer_size() {
+        assert!(matches!(Soc::with_buffer_size(0), Err(Error::Other(_))));
+        assert!(matches!(
+            Soc::with_buffer_size(DEFAULT_BUFFER_SIZE + 0x10),
+            Err(Error::Other(_))
+        ));
+    }
+
+    #[test]
+    fn ipv4_only_resolution() {
+        let addresses: [SocketAddr; 3] = [
+            "[2001:db8::1]:80".parse().unwrap(),
+            "192.168.1.2:80".parse().unwrap(),
+            "[::ffff:10.0.0.1]:443".parse().unwrap(),
+        ];
+        let resolved = resolve(&addresses[..]).unwrap();
+
+        assert_eq!(
+            resolved,
+            [
+                SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 80),
+                SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 443),
+            ]
+        );
+
+        let error = resolve("[2001:db8::1]:80").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AddrNotAvailable);
+        assert!(matches!(
+            error.get_ref().unwrap().downcast_ref(),
+            Some(AddressError::Ipv6(_))
+        ));
+    }
+
+    #[test]
+    fn accept_loop() {
+        let soc = Soc::new().unwrap();
+
+        let mut options = ListenerOptions::new();
+        assert_eq!(options.backlog(1000).backlog, MAX_LISTEN_BACKLOG);
+
+        let listener = options
+            .bind(&soc, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // The address can be bound again right after the listener is closed.
+        drop(listener);
+        let listener = options
+            .bind(&soc, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))
+            .unwrap();
+
+        let client = std::thread::spawn(move || {
+            TcpStream::connect(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port)).unwrap()
+        });
+
+        let shutdown = Shutdown::new();
+        let mut accepted = 0;
+        serve(&listener, &shutdown, |_, _| {
+            accepted += 1;
+            shutdown.trigger();
+        })
+        .unwrap();
+
+        client.join().unwrap();
+        assert_eq!(accepted, 1);
+
+        // A triggered handle stops the loop right away.
+        serve(&listener, &shutdown, |_, _| unreachable!()).unwrap();
+    }
 }
@@ -1,12 +1,73 @@
 //! SSLC (TLS) service.
+//!
+//! The root certificate store of the system is outdated, and shared WiFi networks make connections from the console
+//! easy targets for man-in-the-middle attacks. [`SslC::connect_pinned()`] opens TLS connections which are only trusted
+//! if the server's certificate chain is anchored to a set of pinned certificates, identified by the SHA-256 hash of their
+//! Subject Public Key Info (SPKI), in the same format used by HPKP and most pinning libraries (`pin-sha256`).
 
 // TODO: Implement remaining functions
 
+use std::error::Error as StdError;
+use std::ffi::CString;
+use std::fmt::Display;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::ops::FromResidual;
+use std::os::fd::AsRawFd;
+
+use crate::base64;
 use crate::error::ResultCode;
+use crate::hash::sha256;
 
 /// Handle to the SSLC service.
 pub struct SslC(());
 
+/// Set of certificates trusted by a pinned TLS connection.
+///
+/// Every certificate is added together with the expected hash of its public key, which protects against bundling the wrong certificate.
+/// The hashes can be retrieved with `openssl x509 -in cert.pem -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64`.
+#[derive(Clone, Debug, Default)]
+pub struct PinSet {
+    certificates: Vec<(Vec<u8>, String)>,
+}
+
+/// TLS connection created via [`SslC::connect_pinned()`].
+pub struct TlsStream {
+    context: ctru_sys::sslcContext,
+    root_chain: u32,
+    stream: TcpStream,
+}
+
+/// Error enum for failures of certificate pinning.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum PinError {
+    /// The certificate is not a valid DER-encoded X.509 certificate.
+    InvalidCertificate,
+    /// The public key of the certificate doesn't match the expected pin.
+    PinMismatch {
+        /// Pin which was expected for the certificate.
+        expected: String,
+        /// Actual pin of the certificate.
+        actual: String,
+    },
+    /// No certificates were pinned.
+    NoPins,
+    /// The provided hostname contained a NULL byte.
+    HostnameContainsNull(usize),
+    /// The server's certificate chain is not anchored to any of the pinned certificates, or the TLS handshake failed.
+    Rejected {
+        /// Result code returned by the handshake.
+        result: ctru_sys::Result,
+        /// Internal result code of the certificate verification.
+        verification_result: i32,
+        /// Pins which were trusted for the connection.
+        pins: Vec<String>,
+    },
+    /// ctru-rs error
+    Lib(crate::Error),
+}
+
 impl SslC {
     /// Initialize a new service handle.
     ///
@@ -31,6 +92,191 @@ impl SslC {
             Ok(SslC(()))
         }
     }
+
+    /// Starts a TLS connection over `stream`, trusting only the certificates of `pins`.
+    ///
+    /// The connection succeeds only if the server's certificate chain is signed by one of the pinned certificates (or is one of them),
+    /// and if it's valid for `hostname`. The system root certificate store is not used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`PinError::Rejected`] if the handshake failed, listing the pins which were trusted to help diagnose the failure.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use std::io::Write;
+    /// use std::net::TcpStream;
+    ///
+    /// use ctru::services::soc::Soc;
+    /// use ctru::services::sslc::{PinSet, SslC};
+    ///
+    /// let _soc = Soc::new()?;
+    /// let sslc = SslC::new()?;
+    ///
+    /// let mut pins = PinSet::new();
+    /// let certificate = std::fs::read("romfs:/ca.der")?;
+    /// pins.add(&certificate, "r/mIkG3eEpVdm+u/ko/cwxzOMo1bk4TyHIlByibiA5E=")?;
+    ///
+    /// let stream = TcpStream::connect("example.com:443")?;
+    /// let mut tls = sslc.connect_pinned(stream, "example.com", &pins)?;
+    ///
+    /// tls.write_all(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")?;
+    /// #
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "sslcCreateContext", alias = "sslcStartConnection")]
+    pub fn connect_pinned(
+        &self,
+        stream: TcpStream,
+        hostname: &str,
+        pins: &PinSet,
+    ) -> Result<TlsStream, PinError> {
+        if pins.certificates.is_empty() {
+            return Err(PinError::NoPins);
+        }
+
+        let hostname =
+            CString::new(hostname).map_err(|e| PinError::HostnameContainsNull(e.nul_position()))?;
+
+        let mut root_chain = 0;
+        ResultCode(unsafe { ctru_sys::sslcCreateRootCertChain(&mut root_chain) })?;
+
+        // From now on, the stream takes care of releasing the resources on failure.
+        let mut tls = TlsStream {
+            context: Default::default(),
+            root_chain,
+            stream,
+        };
+
+        for (certificate, _) in &pins.certificates {
+            let mut cert_handle = 0;
+            ResultCode(unsafe {
+                ctru_sys::sslcAddTrustedRootCA(
+                    root_chain,
+                    certificate.as_ptr(),
+                    certificate.len() as u32,
+                    &mut cert_handle,
+                )
+            })?;
+        }
+
+        ResultCode(unsafe {
+            ctru_sys::sslcCreateContext(
+                &mut tls.context,
+                tls.stream.as_raw_fd(),
+                ctru_sys::SSLCOPT_Default,
+                hostname.as_ptr(),
+            )
+        })?;
+        ResultCode(unsafe { ctru_sys::sslcContextSetRootCertChain(&mut tls.context, root_chain) })?;
+
+        let mut verification_result = 0;
+        let mut out = 0;
+        let result = unsafe {
+            ctru_sys::sslcStartConnection(&mut tls.context, &mut verification_result, &mut out)
+        };
+
+        if ctru_sys::R_FAILED(result) {
+            return Err(PinError::Rejected {
+                result,
+                verification_result,
+                pins: pins.pins().map(String::from).collect(),
+            });
+        }
+
+        Ok(tls)
+    }
+}
+
+impl PinSet {
+    /// Creates a new empty set of pins.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a DER-encoded certificate to the set, checking that the SHA-256 hash of its public key matches `pin` (encoded in base64).
+    ///
+    /// Both root and intermediate certificates can be pinned.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`PinError::PinMismatch`] if the hash doesn't match,
+    /// or [`PinError::InvalidCertificate`] if the certificate couldn't be parsed.
+    pub fn add(&mut self, certificate: &[u8], pin: &str) -> Result<(), PinError> {
+        let actual = spki_pin(certificate).ok_or(PinError::InvalidCertificate)?;
+
+        if actual != pin.trim_start_matches("sha256/") {
+            return Err(PinError::PinMismatch {
+                expected: pin.to_owned(),
+                actual,
+            });
+        }
+
+        self.certificates.push((certificate.to_vec(), actual));
+        Ok(())
+    }
+
+    /// Returns an iterator over the pins of the set, encoded in base64.
+    pub fn pins(&self) -> impl Iterator<Item = &str> {
+        self.certificates.iter().map(|(_, pin)| pin.as_str())
+    }
+}
+
+impl TlsStream {
+    /// Returns a reference to the underlying TCP stream.
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.stream
+    }
+}
+
+impl Read for TlsStream {
+    #[doc(alias = "sslcRead")]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = unsafe {
+            ctru_sys::sslcRead(&mut self.context, buf.as_mut_ptr().cast(), buf.len(), false)
+        };
+
+        if ctru_sys::R_FAILED(result) {
+            Err(io::Error::other(crate::Error::Os(result)))
+        } else {
+            Ok(result as usize)
+        }
+    }
+}
+
+impl Write for TlsStream {
+    #[doc(alias = "sslcWrite")]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result =
+            unsafe { ctru_sys::sslcWrite(&mut self.context, buf.as_ptr().cast(), buf.len()) };
+
+        if ctru_sys::R_FAILED(result) {
+            Err(io::Error::other(crate::Error::Os(result)))
+        } else {
+            Ok(result as usize)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for TlsStream {
+    #[doc(alias = "sslcDestroyContext", alias = "sslcDestroyRootCertChain")]
+    fn drop(&mut self) {
+        unsafe {
+            if self.context.sslchandle != 0 {
+                ctru_sys::sslcDestroyContext(&mut self.context);
+            }
+            ctru_sys::sslcDestroyRootCertChain(self.root_chain);
+        }
+    }
 }
 
 impl Drop for SslC {
@@ -39,3 +285,131 @@ impl Drop for SslC {
         unsafe { ctru_sys::sslcExit() };
     }
 }
+
+impl From<crate::Error> for PinError {
+    fn from(value: crate::Error) -> Self {
+        PinError::Lib(value)
+    }
+}
+
+impl<T> FromResidual<crate::Error> for Result<T, PinError> {
+    fn from_residual(residual: crate::Error) -> Self {
+        Err(residual.into())
+    }
+}
+
+impl Display for PinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidCertificate => write!(f, "certificate is not a valid DER-encoded X.509 certificate"),
+            Self::PinMismatch { expected, actual } => {
+                write!(f, "certificate public key pin is {actual}, expected {expected}")
+            }
+            Self::NoPins => write!(f, "no certificates were pinned"),
+            Self::HostnameContainsNull(pos) => {
+                write!(f, "provided hostname contained a NULL byte at position {pos}")
+            }
+            Self::Rejected {
+                result,
+                verification_result,
+                pins,
+            } => write!(
+                f,
+                "TLS handshake failed ({}, verification result {verification_result:#X}): the server's certificate chain may not be anchored to any of the pins [{}]",
+                crate::Error::Os(*result),
+                pins.join(", ")
+            ),
+            Self::Lib(e) => write!(f, "ctru-rs error: {e}"),
+        }
+    }
+}
+
+impl StdError for PinError {}
+
+// Returns the base64-encoded SHA-256 hash of the Subject Public Key Info of a DER-encoded X.509 certificate.
+fn spki_pin(certificate: &[u8]) -> Option<String> {
+    Some(base64::encode(&sha256(spki(certificate)?)))
+}
+
+// Reads a DER element, returning its tag, its contents, the whole element and the data following it.
+fn read_der(data: &[u8]) -> Option<(u8, &[u8], &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first = *data.get(1)?;
+
+    let (len, header_len) = if first < 0x80 {
+        (usize::from(first), 2)
+    } else {
+        let count = usize::from(first & 0x7F);
+        if count == 0 || count > 4 {
+            return None;
+        }
+
+        let bytes = data.get(2..2 + count)?;
+        (
+            bytes.iter().fold(0, |len, &b| (len << 8) | usize::from(b)),
+            2 + count,
+        )
+    };
+
+    let element = data.get(..header_len.checked_add(len)?)?;
+    Some((tag, &element[header_len..], element, &data[element.len()..]))
+}
+
+// Extracts the whole SubjectPublicKeyInfo element of a DER-encoded X.509 certificate.
+fn spki(certificate: &[u8]) -> Option<&[u8]> {
+    const SEQUENCE: u8 = 0x30;
+
+    let (SEQUENCE, certificate, _, _) = read_der(certificate)? else {
+        return None;
+    };
+    let (SEQUENCE, mut fields, _, _) = read_der(certificate)? else {
+        return None;
+    };
+
+    // Skip the optional version.
+    if fields.first() == Some(&0xA0) {
+        fields = read_der(fields)?.3;
+    }
+
+    // Skip the serial number, signature algorithm, issuer, validity and subject.
+    for _ in 0..5 {
+        fields = read_der(fields)?.3;
+    }
+
+    match read_der(fields)? {
+        (SEQUENCE, _, spki, _) => Some(spki),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_digest() {
+        assert_eq!(
+            base64::encode(&sha256(b"abc")),
+            "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+        );
+    }
+
+    #[test]
+    fn extract_spki() {
+        #[rustfmt::skip]
+        let certificate = [
+            0x30, 0x17,
+                0x30, 0x15,
+                    0xA0, 0x03, 0x02, 0x01, 0x02,
+                    0x02, 0x01, 0x01,
+                    0x30, 0x00,
+                    0x30, 0x00,
+                    0x30, 0x00,
+                    0x30, 0x00,
+                    0x30, 0x03, 0x03, 0x01, 0x00,
+        ];
+
+        assert_eq!(spki(&certificate), Some(&certificate[20..]));
+        assert_eq!(spki(&certificate[..10]), None);
+    }
+}
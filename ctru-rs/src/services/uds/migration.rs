@@ -0,0 +1,353 @@
+//! Host migration.
+//!
+//! A local network only exists as long as its host is connected: when the host leaves (or runs out of battery),
+//! every client is disconnected. [`HostMigration`] keeps the session alive by recreating the network on one of the clients.
+//!
+//! While connected, every console tracks the nodes of the network. When the host disconnects, the remaining client
+//! with the lowest node ID is elected as the new host, and creates a new network with the same settings and app data.
+//! The other clients (and spectators) then scan for that network and rejoin it automatically.
+//! The election doesn't need any communication between the consoles, since all of them know the same list of nodes.
+//!
+//! # Notes
+//!
+//! Node IDs are reassigned when joining the new network, so any game state indexed by node ID must be remapped
+//! (e.g. using [`NodeInfo::uds_friendcodeseed()`](super::NodeInfo::uds_friendcodeseed)).
+#![doc(alias = "migration")]
+
+use std::time::{Duration, Instant};
+
+use super::{
+    ConnectionStatus, ConnectionStatusInfo, ConnectionType, Error, NetworkScanInfo, NodeID, Uds,
+};
+
+/// Settings of a network, used to create it and to recreate it after a host migration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkConfig {
+    /// Communication ID of the application, as passed to [`Uds::create_network()`].
+    pub comm_id: [u8; 4],
+    /// Additional ID, to tell apart different kinds of networks of the same application.
+    pub additional_id: Option<u8>,
+    /// Maximum number of nodes in the network.
+    pub max_nodes: Option<u8>,
+    /// Passphrase of the network.
+    pub passphrase: Vec<u8>,
+    /// Data channel used by the application.
+    pub channel: u8,
+    /// App data of the network.
+    ///
+    /// Clients keep this value up to date with the app data of the network they are connected to.
+    pub appdata: Vec<u8>,
+}
+
+/// Event reported by [`HostMigration::poll()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationEvent {
+    /// The host disconnected: the network is unavailable until this console rejoins it.
+    HostLost,
+    /// The host disconnected, and this console now hosts the recreated network.
+    BecameHost,
+    /// This console rejoined the recreated network.
+    Rejoined,
+    /// The recreated network couldn't be found before the [rejoin timeout](HostMigration::with_rejoin_timeout).
+    /// The console is now disconnected.
+    Failed,
+}
+
+/// Host migration state of a [`Uds`] session.
+///
+/// Networks must be created and joined via [`HostMigration::create_network()`] and [`HostMigration::connect_network()`],
+/// and [`HostMigration::poll()`] must be called once per frame.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::uds::migration::{HostMigration, MigrationEvent, NetworkConfig};
+/// use ctru::services::uds::{ConnectionType, Uds};
+///
+/// let mut uds = Uds::new(None)?;
+/// let mut migration = HostMigration::new(NetworkConfig {
+///     comm_id: *b"HBW\x10",
+///     additional_id: None,
+///     max_nodes: None,
+///     passphrase: b"udsdemo passphrase c186093cd2652741\0".to_vec(),
+///     channel: 1,
+///     appdata: Vec::new(),
+/// });
+///
+/// let networks = uds.scan(b"HBW\x10", None, None)?;
+/// migration.connect_network(&mut uds, &networks[0], ConnectionType::Client)?;
+///
+/// loop {
+///     match migration.poll(&mut uds)? {
+///         Some(MigrationEvent::BecameHost) => println!("We are the host now"),
+///         Some(MigrationEvent::Failed) => break,
+///         _ => {}
+///     }
+///
+///     // Game logic...
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HostMigration {
+    config: NetworkConfig,
+    rejoin_timeout: Duration,
+    state: State,
+    own_node: NodeID,
+    node_mask: u16,
+    // Friend code seeds of the nodes, which identify the consoles across networks.
+    peers: [Option<u64>; 16],
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Disconnected,
+    Hosting,
+    Connected(ConnectionType),
+    Rejoining {
+        connection_type: ConnectionType,
+        // `None` if any known peer is accepted as the new host.
+        host: Option<u64>,
+        // `None` if the rejoin timeout is too long to be represented, in which case there's no deadline.
+        deadline: Option<Instant>,
+    },
+}
+
+impl HostMigration {
+    /// Creates a new host migration state for the network described by `config`.
+    pub fn new(config: NetworkConfig) -> Self {
+        Self {
+            config,
+            rejoin_timeout: Duration::from_secs(10),
+            state: State::Disconnected,
+            own_node: NodeID::None,
+            node_mask: 0,
+            peers: [None; 16],
+        }
+    }
+
+    /// Sets how long to look for the recreated network after the host disconnected. The default is 10 seconds.
+    pub fn with_rejoin_timeout(mut self, timeout: Duration) -> Self {
+        self.rejoin_timeout = timeout;
+        self
+    }
+
+    /// Returns the settings of the network.
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    /// Returns `true` if this console is looking for the recreated network.
+    pub fn is_migrating(&self) -> bool {
+        matches!(self.state, State::Rejoining { .. })
+    }
+
+    /// Creates the network and sets its app data.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the network couldn't be created.
+    #[doc(alias = "udsCreateNetwork")]
+    pub fn create_network(&mut self, uds: &mut Uds) -> Result<(), Error> {
+        let config = &self.config;
+
+        uds.create_network(
+            &config.comm_id,
+            config.additional_id,
+            config.max_nodes,
+            &config.passphrase,
+            config.channel,
+        )?;
+        uds.set_appdata(&config.appdata)?;
+
+        self.state = State::Hosting;
+        self.refresh(uds)
+    }
+
+    /// Connects to a network with the settings of this host migration state.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the connection failed.
+    #[doc(alias = "udsConnectNetwork")]
+    pub fn connect_network(
+        &mut self,
+        uds: &mut Uds,
+        network: &NetworkScanInfo,
+        connection_type: ConnectionType,
+    ) -> Result<(), Error> {
+        uds.connect_network(
+            network,
+            &self.config.passphrase,
+            connection_type,
+            self.config.channel,
+        )?;
+
+        self.state = State::Connected(connection_type);
+        self.refresh(uds)
+    }
+
+    /// Sets the app data of the network, which is also used when recreating it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if this console is hosting the network and the app data couldn't be set.
+    #[doc(alias = "udsSetApplicationData")]
+    pub fn set_appdata(&mut self, uds: &Uds, data: &[u8]) -> Result<(), Error> {
+        if matches!(self.state, State::Hosting) {
+            uds.set_appdata(data)?;
+        }
+
+        self.config.appdata = data.to_vec();
+
+        Ok(())
+    }
+
+    /// Tracks the nodes of the network, and migrates the network if its host disconnected.
+    ///
+    /// While looking for the recreated network, this function scans for networks once per call, which blocks for a short while.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the connection status couldn't be read or if the network couldn't be recreated.
+    pub fn poll(&mut self, uds: &mut Uds) -> Result<Option<MigrationEvent>, Error> {
+        match self.state {
+            State::Disconnected => Ok(None),
+            State::Hosting => {
+                if uds.wait_status_event(false, false)? {
+                    self.refresh(uds)?;
+                }
+
+                Ok(None)
+            }
+            State::Connected(connection_type) => {
+                if !uds.wait_status_event(false, false)? {
+                    return Ok(None);
+                }
+
+                let status = uds.connection_status()?;
+                if !host_lost(&status) {
+                    self.refresh(uds)?;
+                    return Ok(None);
+                }
+
+                // The link is already gone, so errors while disconnecting don't matter.
+                let _ = uds.disconnect_network();
+                self.start_migration(uds, connection_type)
+            }
+            State::Rejoining {
+                connection_type,
+                host,
+                deadline,
+            } => {
+                let networks = uds.scan(&self.config.comm_id, self.config.additional_id, None)?;
+
+                let network = networks.iter().find(|network| {
+                    let Some(seed) = network.nodes()[0].map(|node| node.uds_friendcodeseed())
+                    else {
+                        return false;
+                    };
+
+                    match host {
+                        Some(host) => seed == host,
+                        None => self.peers.contains(&Some(seed)),
+                    }
+                });
+
+                if let Some(network) = network {
+                    self.connect_network(uds, network, connection_type)?;
+                    return Ok(Some(MigrationEvent::Rejoined));
+                }
+
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    self.state = State::Disconnected;
+                    return Ok(Some(MigrationEvent::Failed));
+                }
+
+                Ok(None)
+            }
+        }
+    }
+
+    fn start_migration(
+        &mut self,
+        uds: &mut Uds,
+        connection_type: ConnectionType,
+    ) -> Result<Option<MigrationEvent>, Error> {
+        let new_host = elect(self.node_mask);
+
+        if connection_type == ConnectionType::Client && new_host == Some(self.own_node) {
+            self.create_network(uds)?;
+            return Ok(Some(MigrationEvent::BecameHost));
+        }
+
+        let host = new_host.and_then(|node| match node {
+            NodeID::Node(node) => self.peers[usize::from(node) - 1],
+            _ => None,
+        });
+
+        self.state = State::Rejoining {
+            connection_type,
+            host,
+            deadline: Instant::now().checked_add(self.rejoin_timeout),
+        };
+
+        Ok(Some(MigrationEvent::HostLost))
+    }
+
+    // Updates the list of nodes and, for clients, the app data of the network.
+    fn refresh(&mut self, uds: &Uds) -> Result<(), Error> {
+        let status = uds.connection_status()?;
+
+        self.own_node = status.cur_node_id();
+        self.node_mask = status.node_bitmask();
+
+        for (index, peer) in self.peers.iter_mut().enumerate() {
+            *peer = if self.node_mask & (1 << index) != 0 {
+                uds.node_info(NodeID::Node(index as u8 + 1))
+                    .ok()
+                    .map(|info| info.uds_friendcodeseed())
+            } else {
+                None
+            };
+        }
+
+        if let State::Connected(_) = self.state {
+            self.config.appdata = uds.appdata(None)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn host_lost(status: &ConnectionStatus) -> bool {
+    status.node_bitmask() & 1 == 0
+        || matches!(status.status(), Some(ConnectionStatusInfo::Disconnected))
+}
+
+// Elects the client with the lowest node ID as the new host.
+fn elect(node_mask: u16) -> Option<NodeID> {
+    let clients = node_mask & !1;
+
+    (clients != 0).then(|| NodeID::Node(clients.trailing_zeros() as u8 + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_election() {
+        assert_eq!(elect(0b0000_0001), None);
+        assert_eq!(elect(0b0000_0011), Some(NodeID::Node(2)));
+        assert_eq!(elect(0b0001_0101), Some(NodeID::Node(3)));
+        // The host is already gone.
+        assert_eq!(elect(0b1000_0000_0000_0000), Some(NodeID::Node(16)));
+    }
+}
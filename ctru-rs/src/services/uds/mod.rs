@@ -19,6 +19,8 @@ use crate::services::ServiceReference;
 use bitflags::bitflags;
 use macaddr::MacAddr6;
 
+pub mod migration;
+
 bitflags! {
     /// Flags used for sending packets to a network.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -96,33 +98,16 @@ impl Display for Error {
 
 impl StdError for Error {}
 
-/// Possible types of connection to a network.
-#[doc(alias = "udsConnectionType")]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[repr(u8)]
-pub enum ConnectionType {
-    /// A normal client. Can push packets to the network.
-    Client = ctru_sys::UDSCONTYPE_Client,
-    /// A spectator. Cannot push packets to the network,
-    /// but doesn't need the passphrase to join.
-    Spectator = ctru_sys::UDSCONTYPE_Spectator,
-}
-
-impl From<ConnectionType> for u8 {
-    fn from(value: ConnectionType) -> Self {
-        value as Self
-    }
-}
-
-impl TryFrom<u8> for ConnectionType {
-    type Error = ();
-
-    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
-        match value {
-            ctru_sys::UDSCONTYPE_Client => Ok(Self::Client),
-            ctru_sys::UDSCONTYPE_Spectator => Ok(Self::Spectator),
-            _ => Err(()),
-        }
+ffi_enum! {
+    /// Possible types of connection to a network.
+    #[doc(alias = "udsConnectionType")]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ConnectionType: u8 from ctru_sys::udsConnectionType_VALUES {
+        /// A normal client. Can push packets to the network.
+        Client = ctru_sys::UDSCONTYPE_Client,
+        /// A spectator. Cannot push packets to the network,
+        /// but doesn't need the passphrase to join.
+        Spectator = ctru_sys::UDSCONTYPE_Spectator,
     }
 }
 
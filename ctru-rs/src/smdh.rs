@@ -0,0 +1,294 @@
+//! Title metadata (SMDH).
+//!
+//! Every title comes with an SMDH file, containing its name and publisher in 16 languages, its icons and a few settings
+//! used by the HOME Menu. [`Smdh`] parses this data, and can read it from the running title or from another installed title,
+//! which is useful for launchers or "about" screens.
+//!
+//! # Additional Resources
+//!
+//! - <https://www.3dbrew.org/wiki/SMDH>
+#![doc(alias = "icon")]
+
+use std::io::Read;
+
+use crate::error::Error;
+use crate::services::cfgu::Language;
+use crate::services::fs::{Archive, ArchiveID, File, FsPath, MediaType};
+use crate::services::gfx::pixel::{Pixel, Rgb565, Rgba8};
+use crate::services::gfx::texture::{self, Image};
+
+const MAGIC: &[u8; 4] = b"SMDH";
+const TITLES_OFFSET: usize = 0x8;
+const TITLE_SIZE: usize = 0x200;
+const SETTINGS_OFFSET: usize = 0x2008;
+const SMALL_ICON_OFFSET: usize = 0x2040;
+const LARGE_ICON_OFFSET: usize = 0x24C0;
+
+// Path of the icon in the ExeFS of a title, as used with the archives giving access to the contents of titles.
+const EXEFS_ICON_PATH: [u32; 5] = [0, 0, 2, u32::from_le_bytes(*b"icon"), 0];
+
+/// Number of languages of the titles of an SMDH.
+pub const LANGUAGE_COUNT: usize = 16;
+
+/// Side of the small icon (in pixels), shown in the HOME Menu when the icons are small and in the system notifications.
+pub const SMALL_ICON_SIZE: usize = 24;
+
+/// Side of the large icon (in pixels), shown in the HOME Menu.
+pub const LARGE_ICON_SIZE: usize = 48;
+
+/// Name and publisher of a title, in a single language.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Title {
+    /// Short name of the title.
+    pub short_description: String,
+    /// Full name of the title.
+    pub long_description: String,
+    /// Publisher of the title.
+    pub publisher: String,
+}
+
+/// Parsed SMDH data of a title.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::cfgu::Cfgu;
+/// use ctru::smdh::Smdh;
+///
+/// let cfgu = Cfgu::new()?;
+/// let smdh = Smdh::current()?;
+///
+/// let title = smdh.title(cfgu.language()?);
+/// println!("{} by {}", title.long_description, title.publisher);
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Smdh {
+    version: u16,
+    titles: Vec<Title>,
+    region_lockout: u32,
+    flags: u32,
+    small_icon: Image,
+    large_icon: Image,
+}
+
+impl Smdh {
+    /// Size of SMDH data (in bytes).
+    pub const SIZE: usize = 0x36C0;
+
+    /// Parses SMDH data.
+    ///
+    /// Returns [`None`] if the data is too short or doesn't start with the SMDH magic.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || !data.starts_with(MAGIC) {
+            return None;
+        }
+
+        let titles = (0..LANGUAGE_COUNT)
+            .map(|i| {
+                let title = &data[TITLES_OFFSET + i * TITLE_SIZE..][..TITLE_SIZE];
+
+                Title {
+                    short_description: utf16_string(&title[..0x80]),
+                    long_description: utf16_string(&title[0x80..0x180]),
+                    publisher: utf16_string(&title[0x180..]),
+                }
+            })
+            .collect();
+
+        Some(Self {
+            version: u16::from_le_bytes([data[4], data[5]]),
+            titles,
+            region_lockout: read_u32(data, SETTINGS_OFFSET + 0x10),
+            flags: read_u32(data, SETTINGS_OFFSET + 0x20),
+            small_icon: decode_icon(&data[SMALL_ICON_OFFSET..], SMALL_ICON_SIZE),
+            large_icon: decode_icon(&data[LARGE_ICON_OFFSET..], LARGE_ICON_SIZE),
+        })
+    }
+
+    /// Reads the SMDH of the running title.
+    ///
+    /// The SMDH is read from the ExeFS of the title when it's installed, or from the `.3dsx` file it was launched from otherwise.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the title has no SMDH (e.g. a `.3dsx` file built without one),
+    /// or if it couldn't be read.
+    pub fn current() -> crate::Result<Self> {
+        let romfs = Archive::open(ArchiveID::RomFS, &FsPath::empty());
+
+        match romfs.and_then(|archive| read_exefs_icon(&archive)) {
+            Ok(smdh) => Ok(smdh),
+            Err(e) => match std::env::args().next() {
+                Some(path) if path.ends_with(".3dsx") => read_3dsx(&path),
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Reads the SMDH of an installed title.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the title isn't installed, or if the application isn't allowed to access its contents.
+    pub fn for_title(media_type: MediaType, title_id: u64) -> crate::Result<Self> {
+        let archive = Archive::open(
+            ArchiveID::SaveDataAndContent,
+            &FsPath::from_words(&[
+                title_id as u32,
+                (title_id >> 32) as u32,
+                media_type as u32,
+                0,
+            ]),
+        )?;
+
+        read_exefs_icon(&archive)
+    }
+
+    /// Returns the version of the SMDH format.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Returns the name and publisher of the title in a language.
+    ///
+    /// Falls back to English when the title doesn't have a name in `language`, like the HOME Menu.
+    pub fn title(&self, language: Language) -> &Title {
+        let title = &self.titles[language as i8 as usize];
+
+        if title.short_description.is_empty() {
+            &self.titles[Language::English as i8 as usize]
+        } else {
+            title
+        }
+    }
+
+    /// Returns the names and publishers of the title in all languages, in the order of the [`Language`] values.
+    ///
+    /// The last 4 entries aren't used by the system.
+    pub fn titles(&self) -> &[Title] {
+        &self.titles
+    }
+
+    /// Returns the bit mask of the regions the title can run in (bit 0 for Japan, 1 for North America, 2 for Europe,
+    /// 3 for Australia, 4 for China, 5 for Korea and 6 for Taiwan).
+    pub fn region_lockout(&self) -> u32 {
+        self.region_lockout
+    }
+
+    /// Returns the flags of the title (e.g. whether it's visible in the HOME Menu, or whether it uses the save data backup feature).
+    ///
+    /// See <https://www.3dbrew.org/wiki/SMDH#Flags> for the meaning of each bit.
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns the small icon, of [`SMALL_ICON_SIZE`] pixels per side.
+    pub fn small_icon(&self) -> &Image {
+        &self.small_icon
+    }
+
+    /// Returns the large icon, of [`LARGE_ICON_SIZE`] pixels per side.
+    pub fn large_icon(&self) -> &Image {
+        &self.large_icon
+    }
+}
+
+fn read_exefs_icon(archive: &Archive) -> crate::Result<Smdh> {
+    let mut data = Vec::with_capacity(Smdh::SIZE);
+
+    File::open(archive, &FsPath::from_words(&EXEFS_ICON_PATH))?
+        .take(Smdh::SIZE as u64)
+        .read_to_end(&mut data)
+        .map_err(|e| Error::Other(format!("failed to read the SMDH: {e}")))?;
+
+    Smdh::from_bytes(&data).ok_or_else(|| Error::Other("invalid SMDH data".into()))
+}
+
+// Reads the SMDH embedded in the extended header of a `.3dsx` file.
+fn read_3dsx(path: &str) -> crate::Result<Smdh> {
+    let data =
+        std::fs::read(path).map_err(|e| Error::Other(format!("failed to read {path}: {e}")))?;
+
+    let header_size = data
+        .get(4..6)
+        .map_or(0, |size| u16::from_le_bytes([size[0], size[1]]));
+    if !data.starts_with(b"3DSX") || header_size <= 0x20 {
+        return Err(Error::Other(format!("{path} doesn't contain an SMDH")));
+    }
+
+    let offset = read_u32(&data, 0x20) as usize;
+    let size = read_u32(&data, 0x24) as usize;
+
+    offset
+        .checked_add(size)
+        .and_then(|end| data.get(offset..end))
+        .and_then(Smdh::from_bytes)
+        .ok_or_else(|| Error::Other(format!("{path} contains invalid SMDH data")))
+}
+
+// Decodes an RGB565 icon, stored in tiles of 8x8 pixels in Morton order (top to bottom, unlike textures).
+fn decode_icon(data: &[u8], size: usize) -> Image {
+    let pixels = (0..size * size)
+        .map(|i| {
+            let (x, y) = (i % size, i / size);
+            let tile = (y / 8) * (size / 8) + x / 8;
+            let index = tile * 64 + texture::morton(x % 8, y % 8);
+
+            Rgba8::from(Rgb565::read(&data[index * 2..]))
+        })
+        .collect();
+
+    Image::new(size, size, pixels).unwrap()
+}
+
+fn utf16_string(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+
+    String::from_utf16_lossy(&units)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    offset
+        .checked_add(4)
+        .and_then(|end| data.get(offset..end))
+        .map_or(0, |bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let mut data = vec![0; Smdh::SIZE];
+        data[..4].copy_from_slice(MAGIC);
+
+        let english = TITLES_OFFSET + TITLE_SIZE;
+        for (i, unit) in "Demo".encode_utf16().enumerate() {
+            data[english + i * 2..][..2].copy_from_slice(&unit.to_le_bytes());
+        }
+
+        // The second pixel of the first tile is the top row, second column.
+        data[LARGE_ICON_OFFSET + 2..][..2].copy_from_slice(&0xF800u16.to_le_bytes());
+
+        let smdh = Smdh::from_bytes(&data).unwrap();
+        assert_eq!(smdh.title(Language::French).short_description, "Demo");
+        assert_eq!(smdh.large_icon().get(1, 0), Some(Rgba8::opaque(255, 0, 0)));
+        assert_eq!(smdh.large_icon().get(0, 1), Some(Rgba8::opaque(0, 0, 0)));
+        assert_eq!(smdh.small_icon().size(), (24, 24));
+
+        assert!(Smdh::from_bytes(&data[..100]).is_none());
+        assert_eq!(read_u32(&data, usize::MAX - 1), 0);
+    }
+}
@@ -0,0 +1,310 @@
+//! Splash screen.
+//!
+//! Large applications may need several seconds to load their assets (e.g. from the [RomFS](crate::services::romfs)) before showing anything.
+//! A [`Splash`] keeps the application responsive during that time: the assets are loaded on a worker thread,
+//! while the main thread shows an image or a short animation (and optionally a progress bar) on the [primary screen](Gfx::primary_screen).
+#![doc(alias = "boot")]
+#![doc(alias = "loading")]
+
+use std::panic;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::services::apt::Apt;
+use crate::services::gfx::pixel::{Bgr8, Pixel, Rgb565, Rgb5A1, Rgba4, Rgba8};
+use crate::services::gfx::{Flush, Gfx, Screen, ScreenMut, Swap};
+use crate::services::gspgpu::FramebufferFormat;
+
+/// Image shown by a [`Splash`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgba8>,
+}
+
+/// Splash screen shown while the assets of the application are loading.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::prelude::*;
+/// use ctru::services::gfx::pixel::Rgba8;
+/// use ctru::splash::{Image, Splash};
+///
+/// let apt = Apt::new()?;
+/// let gfx = Gfx::new()?;
+///
+/// let _romfs = ctru::services::romfs::RomFS::new()?;
+///
+/// // Generated with `magick logo.png logo.rgb`.
+/// let logo = Image::from_rgb(128, 128, &std::fs::read("romfs:/logo.rgb")?);
+///
+/// let splash = Splash::new(logo).with_progress_bar(Rgba8::opaque(0xFF, 0xFF, 0xFF));
+///
+/// let Some(levels) = splash.show(&apt, &gfx, |progress| {
+///     (0..10)
+///         .map(|i| {
+///             progress.set(i as f32 / 10.0);
+///             std::fs::read(format!("romfs:/levels/{i}.bin"))
+///         })
+///         .collect::<Vec<_>>()
+/// }) else {
+///     // The user closed the application while loading.
+///     return Ok(());
+/// };
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Splash {
+    frames: Vec<Image>,
+    frame_duration: Duration,
+    background: Rgba8,
+    progress_bar: Option<Rgba8>,
+}
+
+/// Progress of the loading, which can be reported by the loader of a [`Splash`].
+#[derive(Clone, Debug, Default)]
+pub struct Progress {
+    // Bits of the `f32` value, since there is no atomic float.
+    value: Arc<AtomicU32>,
+}
+
+impl Image {
+    /// Creates a new image from its pixels, in row-major order.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the number of pixels doesn't match the size of the image.
+    pub fn new(width: usize, height: usize, pixels: Vec<Rgba8>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width * height,
+            "a {width}x{height} image needs {} pixels",
+            width * height
+        );
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Creates a new image from raw RGB data (3 bytes per pixel, in row-major order).
+    ///
+    /// This is the format produced by tools such as ImageMagick for `.rgb` files.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the length of `data` doesn't match the size of the image.
+    pub fn from_rgb(width: usize, height: usize, data: &[u8]) -> Self {
+        assert_eq!(
+            data.len(),
+            width * height * 3,
+            "a {width}x{height} RGB image needs {} bytes",
+            width * height * 3
+        );
+
+        let pixels = data
+            .chunks_exact(3)
+            .map(|rgb| Rgba8::opaque(rgb[0], rgb[1], rgb[2]))
+            .collect();
+
+        Self::new(width, height, pixels)
+    }
+
+    /// Returns the width of the image (in pixels).
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the image (in pixels).
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl Splash {
+    /// Creates a new splash screen showing a still image.
+    pub fn new(image: Image) -> Self {
+        Self::animated(vec![image], Duration::MAX)
+    }
+
+    /// Creates a new splash screen looping over the frames of an animation.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `frames` is empty or `frame_duration` is zero.
+    pub fn animated(frames: Vec<Image>, frame_duration: Duration) -> Self {
+        assert!(!frames.is_empty(), "an animation needs at least one frame");
+        assert!(
+            !frame_duration.is_zero(),
+            "frames can't have a zero duration"
+        );
+
+        Self {
+            frames,
+            frame_duration,
+            background: Rgba8::opaque(0, 0, 0),
+            progress_bar: None,
+        }
+    }
+
+    /// Sets the color around the image. The default is black.
+    pub fn with_background(mut self, color: Rgba8) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Shows a progress bar of the specified color at the bottom of the screen, filled as reported via [`Progress::set()`].
+    pub fn with_progress_bar(mut self, color: Rgba8) -> Self {
+        self.progress_bar = Some(color);
+        self
+    }
+
+    /// Runs `load` on a worker thread, showing the splash screen until it returns.
+    ///
+    /// The splash screen is drawn on [`Gfx::primary_screen()`], once per frame.
+    ///
+    /// Returns the value returned by `load`, or [`None`] if the application was closed before the loading ended
+    /// (i.e. [`Apt::main_loop()`] returned `false`). In the latter case, the worker thread is left running in the background.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `load` panics, or if the primary screen is already borrowed.
+    pub fn show<T: Send + 'static>(
+        &self,
+        apt: &Apt,
+        gfx: &Gfx,
+        load: impl FnOnce(&Progress) -> T + Send + 'static,
+    ) -> Option<T> {
+        let progress = Progress::default();
+        let worker = thread::spawn({
+            let progress = progress.clone();
+            move || load(&progress)
+        });
+
+        let start = Instant::now();
+
+        while !worker.is_finished() {
+            if !apt.main_loop() {
+                return None;
+            }
+
+            let elapsed = start.elapsed().as_nanos() / self.frame_duration.as_nanos();
+            let frame = &self.frames[(elapsed % self.frames.len() as u128) as usize];
+
+            let mut screen = gfx.primary_screen();
+            self.draw(&mut screen, frame, progress.get());
+            screen.flush_buffers();
+            screen.swap_buffers();
+            drop(screen);
+
+            gfx.wait_for_vblank();
+        }
+
+        match worker.join() {
+            Ok(value) => Some(value),
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    fn draw(&self, screen: &mut ScreenMut<'_>, frame: &Image, progress: f32) {
+        match screen.framebuffer_format() {
+            FramebufferFormat::Rgba8 => self.draw_with::<Rgba8>(screen, frame, progress),
+            FramebufferFormat::Bgr8 => self.draw_with::<Bgr8>(screen, frame, progress),
+            FramebufferFormat::Rgb565 => self.draw_with::<Rgb565>(screen, frame, progress),
+            FramebufferFormat::Rgb5A1 => self.draw_with::<Rgb5A1>(screen, frame, progress),
+            FramebufferFormat::Rgba4 => self.draw_with::<Rgba4>(screen, frame, progress),
+        }
+    }
+
+    fn draw_with<P: Pixel>(&self, screen: &mut ScreenMut<'_>, frame: &Image, progress: f32) {
+        let Some(mut view) = screen.framebuffer_view::<P>() else {
+            return;
+        };
+
+        view.fill(self.background.into());
+
+        // Center the image, cropping it if it's larger than the screen.
+        let left = view.width().saturating_sub(frame.width) / 2;
+        let top = view.height().saturating_sub(frame.height) / 2;
+        let skip_x = frame.width.saturating_sub(view.width()) / 2;
+        let skip_y = frame.height.saturating_sub(view.height()) / 2;
+
+        for (y, mut row) in view.rows_mut().skip(top).take(frame.height).enumerate() {
+            let pixels = &frame.pixels[(y + skip_y) * frame.width..][..frame.width];
+
+            for (x, &pixel) in pixels
+                .iter()
+                .skip(skip_x)
+                .take(row.len() - left)
+                .enumerate()
+            {
+                // Fully transparent pixels show the background.
+                if pixel.a != 0 {
+                    row.set(left + x, pixel.into());
+                }
+            }
+        }
+
+        if let Some(color) = self.progress_bar {
+            const MARGIN: usize = 16;
+            const HEIGHT: usize = 4;
+
+            let width = view.width().saturating_sub(2 * MARGIN);
+            let filled = (width as f32 * progress) as usize;
+
+            view.fill_rect(
+                MARGIN,
+                view.height().saturating_sub(MARGIN + HEIGHT),
+                filled,
+                HEIGHT,
+                color.into(),
+            );
+        }
+    }
+}
+
+impl Progress {
+    /// Sets the progress of the loading, between `0.0` and `1.0`.
+    pub fn set(&self, progress: f32) {
+        self.value
+            .store(progress.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns the progress of the loading, between `0.0` and `1.0`.
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.value.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_from_rgb() {
+        let image = Image::from_rgb(2, 1, &[1, 2, 3, 4, 5, 6]);
+
+        assert_eq!((image.width(), image.height()), (2, 1));
+        assert_eq!(
+            image.pixels,
+            [Rgba8::opaque(1, 2, 3), Rgba8::opaque(4, 5, 6)]
+        );
+
+        let progress = Progress::default();
+        assert_eq!(progress.get(), 0.0);
+        progress.set(1.5);
+        assert_eq!(progress.get(), 1.0);
+    }
+}
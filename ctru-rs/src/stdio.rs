@@ -0,0 +1,108 @@
+//! Standard I/O redirection.
+//!
+//! By default, the output of `println!` and `eprintln!` is discarded, unless a [`Console`](crate::console::Console) is shown on screen.
+//! The functions in this module redirect it to a PC instead, which is more practical to read long outputs or logs:
+//!
+//! - [`redirect_to_3dslink()`] sends it over the network to `3dslink`, when the application was launched with `3dslink --server`
+//!   (or `cargo 3ds run --server`).
+//! - [`redirect_to_gdb()`] sends it to the GDB console, when the application is being debugged via the GDB stub of Luma3DS.
+#![doc(alias = "stdout")]
+#![doc(alias = "stderr")]
+
+use std::sync::Mutex;
+
+use crate::error::{Error, ResultCode};
+#[cfg(feature = "network")]
+use crate::services::soc::Soc;
+use crate::services::ServiceReference;
+
+static GDB_HIO_ACTIVE: Mutex<()> = Mutex::new(());
+
+/// Redirection of the standard streams to GDB, active until dropped.
+///
+/// This struct can be created via [`redirect_to_gdb()`].
+pub struct GdbRedirection {
+    _service_handler: ServiceReference,
+}
+
+/// Redirect `stdout` and `stderr` to the `3dslink` server which sent the application.
+///
+/// The redirection lasts for as long as `soc` is active. See [`Soc::redirect_to_3dslink()`] for more information.
+///
+/// # Errors
+///
+/// This function will return an error if a connection couldn't be established to the server,
+/// or if the output was already being redirected.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::soc::Soc;
+///
+/// let mut soc = Soc::new()?;
+/// ctru::stdio::redirect_to_3dslink(&mut soc)?;
+///
+/// println!("I'm visible from a PC!");
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "network")]
+#[doc(alias = "link3dsStdio")]
+pub fn redirect_to_3dslink(soc: &mut Soc) -> crate::Result<()> {
+    soc.redirect_to_3dslink(true, true)
+}
+
+/// Redirect `stdin`, `stdout` and `stderr` to GDB, using the File I/O protocol (also known as HIO).
+///
+/// The output is printed in the GDB console once GDB is attached to the application (e.g. via the GDB stub of Luma3DS).
+/// It's discarded until then. Likewise, input is read from the GDB console.
+///
+/// # Errors
+///
+/// This function will return an error if the redirection couldn't be set up, or if the output is already redirected to GDB.
+///
+/// # Example
+///
+/// ```
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// let _gdb = ctru::stdio::redirect_to_gdb()?;
+///
+/// println!("I'm visible from GDB!");
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[doc(alias = "gdbHioDevInit", alias = "gdbHioDevRedirectStdStreams")]
+pub fn redirect_to_gdb() -> crate::Result<GdbRedirection> {
+    let _service_handler = ServiceReference::new(
+        &GDB_HIO_ACTIVE,
+        || {
+            ResultCode(unsafe { ctru_sys::gdbHioDevInit() })?;
+
+            let result = unsafe { ctru_sys::gdbHioDevRedirectStdStreams(true, true, true) };
+            if ctru_sys::R_FAILED(result) {
+                // The exit function only runs for services which started successfully.
+                unsafe { ctru_sys::gdbHioDevExit() };
+                return Err(Error::from(result));
+            }
+
+            Ok(())
+        },
+        || unsafe {
+            ctru_sys::gdbHioDevExit();
+        },
+    )
+    .map_err(|e| match e {
+        Error::ServiceAlreadyActive => Error::OutputAlreadyRedirected,
+        e => e,
+    })?;
+
+    Ok(GdbRedirection { _service_handler })
+}
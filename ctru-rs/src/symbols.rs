@@ -0,0 +1,223 @@
+//! On-device symbolication.
+//!
+//! Release builds of 3DS applications don't carry any debug information, so crash reports and backtraces only contain raw addresses.
+//! A [`SymbolTable`] maps these addresses back to function names, using the output of `nm` stored alongside the application
+//! (usually in the RomFS). It can be generated from the ELF file after building:
+//!
+//! ```sh
+//! arm-none-eabi-nm --numeric-sort --print-size --demangle --defined-only target/armv6k-nintendo-3ds/release/my-app.elf > romfs/symbols.txt
+//! ```
+//!
+//! Since the table is generated after linking, it must be regenerated whenever the executable changes.
+//!
+//! Once [installed](SymbolTable::install), the table is used by the [crash reporter](crate::crash) and by the panic hook set via [`set_panic_hook()`].
+//!
+//! This module is only compiled if the `symbols` feature is enabled.
+#![doc(alias = "addr2line")]
+#![doc(alias = "backtrace")]
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+static INSTALLED: OnceLock<SymbolTable> = OnceLock::new();
+
+/// Table of function symbols, sorted by address.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::services::romfs::RomFS;
+/// use ctru::symbols::SymbolTable;
+///
+/// let _romfs = RomFS::new()?;
+///
+/// SymbolTable::load("romfs:/symbols.txt")?.install();
+/// ctru::symbols::set_panic_hook();
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Clone, Debug)]
+struct Symbol {
+    address: u32,
+    size: Option<u32>,
+    name: String,
+}
+
+/// Function containing an address, as found by [`SymbolTable::resolve()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location<'a> {
+    /// Name of the function.
+    pub name: &'a str,
+    /// Offset of the address from the start of the function.
+    pub offset: u32,
+}
+
+impl SymbolTable {
+    /// Loads a symbol table from a file in the format produced by `nm`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file couldn't be read.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parses a symbol table in the format produced by `nm` (with or without `--print-size`).
+    ///
+    /// Only code symbols are kept, and lines which can't be parsed are skipped.
+    pub fn parse(nm_output: &str) -> Self {
+        let mut symbols: Vec<Symbol> = nm_output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(4, ' ');
+                let address = u32::from_str_radix(fields.next()?, 16).ok()?;
+
+                let second = fields.next()?;
+                let (size, kind, name) = if second.len() == 1 {
+                    // Demangled names may contain spaces: re-join the remaining fields.
+                    let rest = line.splitn(3, ' ').nth(2)?;
+                    (None, second, rest)
+                } else {
+                    let size = u32::from_str_radix(second, 16).ok()?;
+                    (Some(size), fields.next()?, fields.next()?)
+                };
+
+                matches!(kind, "T" | "t" | "W" | "w").then(|| Symbol {
+                    address,
+                    size,
+                    name: name.to_owned(),
+                })
+            })
+            .collect();
+
+        symbols.sort_by_key(|symbol| symbol.address);
+
+        Self { symbols }
+    }
+
+    /// Returns the number of symbols in the table.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Returns `true` if the table contains no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns the function containing the specified address, if any.
+    pub fn resolve(&self, address: u32) -> Option<Location<'_>> {
+        let index = self
+            .symbols
+            .partition_point(|symbol| symbol.address <= address)
+            .checked_sub(1)?;
+        let symbol = &self.symbols[index];
+        let offset = address - symbol.address;
+
+        // Without a size, the symbol is assumed to extend up to the next one.
+        let end = match symbol.size {
+            Some(size) => symbol.address.checked_add(size),
+            None => self.symbols.get(index + 1).map(|next| next.address),
+        };
+        if end.is_some_and(|end| address >= end) {
+            return None;
+        }
+
+        Some(Location {
+            name: &symbol.name,
+            offset,
+        })
+    }
+
+    /// Installs this table for use by the crash reporter and the panic hook.
+    ///
+    /// Only the first table installed is used.
+    pub fn install(self) {
+        let _ = INSTALLED.set(self);
+    }
+
+    /// Returns the installed symbol table, if any.
+    pub fn installed() -> Option<&'static SymbolTable> {
+        INSTALLED.get()
+    }
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{:#x}", self.name, self.offset)
+    }
+}
+
+/// Sets a [panic hook](std::panic::set_hook) which prints the backtrace of the panicking thread, resolved with the installed symbol table.
+///
+/// The previously registered hook is called first.
+pub fn set_panic_hook() {
+    let old_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |panic_info| {
+        old_hook(panic_info);
+
+        let Some(table) = SymbolTable::installed() else {
+            return;
+        };
+
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        eprintln!("symbolicated backtrace:");
+
+        for address in backtrace
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter_map(|word| u32::from_str_radix(word.strip_prefix("0x")?, 16).ok())
+        {
+            match table.resolve(address) {
+                Some(location) => eprintln!("  {address:#010x} {location}"),
+                None => eprintln!("  {address:#010x} <unknown>"),
+            }
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_symbols() {
+        let table = SymbolTable::parse(
+            "00100010 00000010 T main\n\
+             00100000 T _start\n\
+             00100030 00000008 t core::fmt::write\n\
+             00200000 D some_data\n\
+             garbage line\n",
+        );
+
+        assert_eq!(table.len(), 3);
+        assert_eq!(
+            table.resolve(0x0010_0004),
+            Some(Location {
+                name: "_start",
+                offset: 4
+            })
+        );
+        assert_eq!(table.resolve(0x0010_001c).unwrap().name, "main");
+        // Between `main` and `core::fmt::write`.
+        assert_eq!(table.resolve(0x0010_0024), None);
+        assert_eq!(
+            table.resolve(0x0010_0030).unwrap().to_string(),
+            "core::fmt::write+0x0"
+        );
+        assert_eq!(table.resolve(0x0000_1000), None);
+    }
+}
@@ -0,0 +1,583 @@
+//! Time zones and local time.
+//!
+//! The system has no notion of time zones: the clock of the console holds the local time as set by the user in the System Settings,
+//! and is never adjusted for daylight saving time (DST). This module bundles the rules of a subset of the
+//! [tz database](https://www.iana.org/time-zones) (see [`ZONES`]), and converts times to the local time of a [`TimeZone`],
+//! taking DST into account.
+//!
+//! Zones are described with [POSIX `TZ` strings](https://pubs.opengroup.org/onlinepubs/9699919799/basedefs/V1_chap08.html#tag_08_03),
+//! which hold the current rules of a zone (e.g. `CET-1CEST,M3.5.0,M10.5.0/3` for Central Europe).
+//! Historical changes of the rules aren't supported.
+//!
+//! # Console clock
+//!
+//! Since the clock of the console can't hold UTC, [`TimeZone::now()`] assumes that it's set to the *standard* time of the zone
+//! (i.e. the time outside of DST), and applies DST on top of it. Applications showing the local time should ask the user
+//! to set the clock that way once, instead of changing it twice a year.
+//!
+//! # Example
+//!
+//! ```
+//! # let _runner = test_runner::GdbRunner::default();
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #
+//! use ctru::tz::TimeZone;
+//!
+//! let zone = TimeZone::named("Europe/Paris")?;
+//! let now = zone.now();
+//!
+//! println!(
+//!     "{:02}:{:02} {}",
+//!     now.hour,
+//!     now.minute,
+//!     zone.abbreviation(now.is_dst)
+//! );
+//! #
+//! # Ok(())
+//! # }
+//! ```
+#![doc(alias = "timezone")]
+#![doc(alias = "dst")]
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use crate::services::cfgu::Region;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+// Time of the transitions when the rule doesn't specify it (02:00:00).
+const DEFAULT_TRANSITION_TIME: i32 = 2 * 60 * 60;
+
+// Maximum hours of UTC offsets, and of transition times (which may be up to a week after the transition day).
+const MAX_OFFSET_HOURS: i32 = 24;
+const MAX_TRANSITION_HOURS: i32 = 167;
+
+/// Names and POSIX `TZ` strings of the bundled time zones.
+#[rustfmt::skip]
+pub const ZONES: &[(&str, &str)] = &[
+    ("UTC", "UTC0"),
+    // Europe
+    ("Europe/London", "GMT0BST,M3.5.0/1,M10.5.0"),
+    ("Europe/Dublin", "GMT0IST,M3.5.0/1,M10.5.0"),
+    ("Europe/Lisbon", "WET0WEST,M3.5.0/1,M10.5.0"),
+    ("Europe/Amsterdam", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Berlin", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Brussels", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Copenhagen", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Madrid", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Oslo", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Paris", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Prague", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Rome", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Stockholm", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Vienna", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Warsaw", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Zurich", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Athens", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Helsinki", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Istanbul", "<+03>-3"),
+    ("Europe/Moscow", "MSK-3"),
+    // Africa
+    ("Africa/Cairo", "EET-2EEST,M4.5.5/0,M10.5.4/24"),
+    ("Africa/Johannesburg", "SAST-2"),
+    ("Africa/Lagos", "WAT-1"),
+    // Americas
+    ("America/St_Johns", "NST3:30NDT,M3.2.0,M11.1.0"),
+    ("America/Halifax", "AST4ADT,M3.2.0,M11.1.0"),
+    ("America/New_York", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Toronto", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Chicago", "CST6CDT,M3.2.0,M11.1.0"),
+    ("America/Mexico_City", "CST6"),
+    ("America/Denver", "MST7MDT,M3.2.0,M11.1.0"),
+    ("America/Phoenix", "MST7"),
+    ("America/Los_Angeles", "PST8PDT,M3.2.0,M11.1.0"),
+    ("America/Vancouver", "PST8PDT,M3.2.0,M11.1.0"),
+    ("America/Anchorage", "AKST9AKDT,M3.2.0,M11.1.0"),
+    ("America/Sao_Paulo", "<-03>3"),
+    ("America/Argentina/Buenos_Aires", "<-03>3"),
+    ("America/Santiago", "<-04>4<-03>,M9.1.6/24,M4.1.6/24"),
+    ("Pacific/Honolulu", "HST10"),
+    // Asia
+    ("Asia/Dubai", "<+04>-4"),
+    ("Asia/Kolkata", "IST-5:30"),
+    ("Asia/Bangkok", "<+07>-7"),
+    ("Asia/Jakarta", "WIB-7"),
+    ("Asia/Singapore", "<+08>-8"),
+    ("Asia/Shanghai", "CST-8"),
+    ("Asia/Hong_Kong", "HKT-8"),
+    ("Asia/Taipei", "CST-8"),
+    ("Asia/Manila", "PST-8"),
+    ("Asia/Seoul", "KST-9"),
+    ("Asia/Tokyo", "JST-9"),
+    // Oceania
+    ("Australia/Perth", "AWST-8"),
+    ("Australia/Darwin", "ACST-9:30"),
+    ("Australia/Adelaide", "ACST-9:30ACDT,M10.1.0,M4.1.0/3"),
+    ("Australia/Brisbane", "AEST-10"),
+    ("Australia/Melbourne", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+    ("Australia/Sydney", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+    ("Pacific/Auckland", "NZST-12NZDT,M9.5.0,M4.1.0/3"),
+];
+
+/// Error returned when building a [`TimeZone`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The string isn't a valid POSIX `TZ` string, or uses rules which aren't supported (only the `Mm.w.d` format is).
+    InvalidRules(String),
+    /// The zone isn't part of the bundled [`ZONES`].
+    UnknownZone(String),
+}
+
+/// Time zone, with its offset from UTC and its DST rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeZone {
+    name: String,
+    abbreviation: String,
+    // Offsets are in seconds east of UTC, i.e. `local = utc + offset`.
+    offset: i32,
+    dst: Option<Dst>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Dst {
+    abbreviation: String,
+    offset: i32,
+    start: Rule,
+    end: Rule,
+}
+
+// Transition on the `week`-th `weekday` (0 being Sunday) of `month`, the 5th week being the last one of the month.
+// The time is in the local time in effect before the transition.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Rule {
+    month: u8,
+    week: u8,
+    weekday: u8,
+    time: i32,
+}
+
+/// Date and time in a [`TimeZone`].
+///
+/// This struct can be retrieved via [`TimeZone::to_local()`] and [`TimeZone::now()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LocalTime {
+    /// Year.
+    pub year: i32,
+    /// Month, from 1 (January) to 12.
+    pub month: u8,
+    /// Day of the month, from 1.
+    pub day: u8,
+    /// Day of the week, from 0 (Sunday) to 6.
+    pub weekday: u8,
+    /// Hour, from 0 to 23.
+    pub hour: u8,
+    /// Minute, from 0 to 59.
+    pub minute: u8,
+    /// Second, from 0 to 59.
+    pub second: u8,
+    /// Offset from UTC (in seconds east of UTC), including DST.
+    pub utc_offset: i32,
+    /// Whether DST is in effect.
+    pub is_dst: bool,
+}
+
+impl TimeZone {
+    /// Returns the bundled time zone named `name` (e.g. `Europe/Paris`), as listed in [`ZONES`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the zone isn't bundled.
+    pub fn named(name: &str) -> Result<Self, Error> {
+        let (name, rules) = ZONES
+            .iter()
+            .find(|(zone, _)| *zone == name)
+            .ok_or_else(|| Error::UnknownZone(name.to_owned()))?;
+
+        let mut zone: Self = rules.parse()?;
+        zone.name = (*name).to_owned();
+
+        Ok(zone)
+    }
+
+    /// Returns the most common time zone of a console region, as a default until the user chooses theirs.
+    ///
+    /// # Notes
+    ///
+    /// Some regions (USA, Europe, Australia) span many zones, so this should only be used as a starting point.
+    pub fn for_region(region: Region) -> Self {
+        let name = match region {
+            Region::Japan => "Asia/Tokyo",
+            Region::USA => "America/New_York",
+            Region::Europe => "Europe/London",
+            Region::Australia => "Australia/Sydney",
+            Region::China => "Asia/Shanghai",
+            Region::Korea => "Asia/Seoul",
+            Region::Taiwan => "Asia/Taipei",
+        };
+
+        Self::named(name).expect("bundled zones should be valid")
+    }
+
+    /// Returns the name of the zone: its tz database name for bundled zones, or the `TZ` string it was parsed from.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the abbreviation of the zone (e.g. `CET`, or `CEST` during DST).
+    pub fn abbreviation(&self, is_dst: bool) -> &str {
+        match &self.dst {
+            Some(dst) if is_dst => &dst.abbreviation,
+            _ => &self.abbreviation,
+        }
+    }
+
+    /// Returns `true` if the zone observes DST.
+    pub fn has_dst(&self) -> bool {
+        self.dst.is_some()
+    }
+
+    /// Converts a UTC time to the local time of the zone.
+    ///
+    /// Use this with times coming from an accurate source (e.g. a server), rather than from the clock of the console.
+    pub fn to_local(&self, time: SystemTime) -> LocalTime {
+        let utc = unix_seconds(time);
+        let is_dst = self.is_dst(utc);
+        let utc_offset = match &self.dst {
+            Some(dst) if is_dst => dst.offset,
+            _ => self.offset,
+        };
+
+        let local = utc + i64::from(utc_offset);
+        let days = local.div_euclid(SECONDS_PER_DAY);
+        let seconds = local.rem_euclid(SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+
+        LocalTime {
+            year: year as i32,
+            month,
+            day,
+            weekday: weekday(days),
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+            utc_offset,
+            is_dst,
+        }
+    }
+
+    /// Returns the current local time, assuming that the clock of the console is set to the standard time of the zone.
+    ///
+    /// See the [module documentation](self#console-clock) for more information.
+    pub fn now(&self) -> LocalTime {
+        let console = SystemTime::now();
+        let offset = Duration::from_secs(u64::from(self.offset.unsigned_abs()));
+
+        let utc = if self.offset >= 0 {
+            console - offset
+        } else {
+            console + offset
+        };
+
+        self.to_local(utc)
+    }
+
+    fn is_dst(&self, utc: i64) -> bool {
+        let Some(dst) = &self.dst else {
+            return false;
+        };
+
+        // The transition of a year can't fall in another year in UTC by more than a day, so checking the year
+        // of the local standard time is enough.
+        let (year, _, _) =
+            civil_from_days((utc + i64::from(self.offset)).div_euclid(SECONDS_PER_DAY));
+
+        // The start is expressed in standard time, and the end in daylight time.
+        let start = dst.start.local_seconds(year) - i64::from(self.offset);
+        let end = dst.end.local_seconds(year) - i64::from(dst.offset);
+
+        if start < end {
+            (start..end).contains(&utc)
+        } else {
+            // Southern hemisphere: DST spans the new year.
+            !(end..start).contains(&utc)
+        }
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = Error;
+
+    /// Parses a POSIX `TZ` string (e.g. `EST5EDT,M3.2.0,M11.1.0`).
+    fn from_str(rules: &str) -> Result<Self, Self::Err> {
+        parse_rules(rules).ok_or_else(|| Error::InvalidRules(rules.to_owned()))
+    }
+}
+
+impl Rule {
+    // Returns the time of the transition in `year`, in seconds since the epoch in the local time before the transition.
+    fn local_seconds(&self, year: i64) -> i64 {
+        let first = days_from_civil(year, self.month, 1);
+        let mut day = first
+            + i64::from((7 + self.weekday - weekday(first)) % 7)
+            + i64::from(self.week - 1) * 7;
+
+        if self.week == 5 {
+            let (next_year, next_month) = if self.month == 12 {
+                (year + 1, 1)
+            } else {
+                (year, self.month + 1)
+            };
+
+            if day >= days_from_civil(next_year, next_month, 1) {
+                day -= 7;
+            }
+        }
+
+        day * SECONDS_PER_DAY + i64::from(self.time)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRules(rules) => {
+                write!(f, "invalid or unsupported time zone rules `{rules}`")
+            }
+            Self::UnknownZone(name) => write!(f, "unknown time zone `{name}`"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+fn parse_rules(rules: &str) -> Option<TimeZone> {
+    let mut parser = Parser(rules);
+
+    let abbreviation = parser.abbreviation()?;
+    // POSIX offsets are west of UTC.
+    let offset = -parser.time(MAX_OFFSET_HOURS)?;
+
+    let dst = if parser.is_empty() {
+        None
+    } else {
+        let abbreviation = parser.abbreviation()?;
+        let dst_offset = if parser.0.starts_with(',') {
+            offset + 3600
+        } else {
+            -parser.time(MAX_OFFSET_HOURS)?
+        };
+
+        parser.expect(',')?;
+        let start = parser.rule()?;
+        parser.expect(',')?;
+        let end = parser.rule()?;
+
+        Some(Dst {
+            abbreviation,
+            offset: dst_offset,
+            start,
+            end,
+        })
+    };
+
+    if !parser.is_empty() {
+        return None;
+    }
+
+    Some(TimeZone {
+        name: rules.to_owned(),
+        abbreviation,
+        offset,
+        dst,
+    })
+}
+
+struct Parser<'a>(&'a str);
+
+impl Parser<'_> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn expect(&mut self, c: char) -> Option<()> {
+        self.0 = self.0.strip_prefix(c)?;
+        Some(())
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &str {
+        let end = self.0.find(|c| !predicate(c)).unwrap_or(self.0.len());
+        let (taken, rest) = self.0.split_at(end);
+        self.0 = rest;
+        taken
+    }
+
+    // Either at least 3 letters, or any text between angle brackets (e.g. `<+03>`).
+    fn abbreviation(&mut self) -> Option<String> {
+        let abbreviation = if self.expect('<').is_some() {
+            let abbreviation = self.take_while(|c| c != '>').to_owned();
+            self.expect('>')?;
+            abbreviation
+        } else {
+            self.take_while(|c| c.is_ascii_alphabetic()).to_owned()
+        };
+
+        (abbreviation.len() >= 3).then_some(abbreviation)
+    }
+
+    fn number(&mut self) -> Option<i32> {
+        self.take_while(|c| c.is_ascii_digit()).parse().ok()
+    }
+
+    // `[+-]hh[:mm[:ss]]`, in seconds, with at most `max_hours` hours.
+    fn time(&mut self, max_hours: i32) -> Option<i32> {
+        let sign = if self.expect('-').is_some() {
+            -1
+        } else {
+            let _ = self.expect('+');
+            1
+        };
+
+        let mut seconds = self.number().filter(|hours| *hours <= max_hours)? * 3600;
+        if self.expect(':').is_some() {
+            seconds += self.number().filter(|minutes| *minutes < 60)? * 60;
+            if self.expect(':').is_some() {
+                seconds += self.number().filter(|seconds| *seconds < 60)?;
+            }
+        }
+
+        Some(sign * seconds)
+    }
+
+    // `Mm.w.d[/time]`
+    fn rule(&mut self) -> Option<Rule> {
+        self.expect('M')?;
+        let month = self.number()?;
+        self.expect('.')?;
+        let week = self.number()?;
+        self.expect('.')?;
+        let weekday = self.number()?;
+
+        let time = if self.expect('/').is_some() {
+            self.time(MAX_TRANSITION_HOURS)?
+        } else {
+            DEFAULT_TRANSITION_TIME
+        };
+
+        ((1..=12).contains(&month) && (1..=5).contains(&week) && (0..=6).contains(&weekday))
+            .then_some(Rule {
+                month: month as u8,
+                week: week as u8,
+                weekday: weekday as u8,
+                time,
+            })
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    }
+}
+
+// Day of the week (0 being Sunday) of a number of days since the epoch, which was a Thursday.
+fn weekday(days: i64) -> u8 {
+    (days + 4).rem_euclid(7) as u8
+}
+
+// Conversions between days since the epoch and proleptic Gregorian dates, from
+// <https://howardhinnant.github.io/date_algorithms.html>.
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u8;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u8;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn bundled_zones() {
+        for (name, _) in ZONES {
+            assert_eq!(TimeZone::named(name).unwrap().name(), *name);
+        }
+
+        assert_eq!(
+            TimeZone::named("Mars/Olympus_Mons"),
+            Err(Error::UnknownZone("Mars/Olympus_Mons".to_owned()))
+        );
+        assert!("EST5EDT,J60,J300".parse::<TimeZone>().is_err());
+        assert!("AAA999999".parse::<TimeZone>().is_err());
+        assert!("AAA5BBB,M3.2.0/168,M11.1.0".parse::<TimeZone>().is_err());
+    }
+
+    #[test]
+    fn local_time() {
+        let paris = TimeZone::named("Europe/Paris").unwrap();
+
+        // 2024-03-31 00:59:59 UTC, right before the switch to CEST.
+        let winter = paris.to_local(at(1_711_846_799));
+        assert_eq!(
+            (winter.month, winter.day, winter.hour, winter.minute),
+            (3, 31, 1, 59)
+        );
+        assert_eq!(
+            (winter.utc_offset, winter.is_dst, winter.weekday),
+            (3600, false, 0)
+        );
+
+        let summer = paris.to_local(at(1_711_846_800));
+        assert_eq!((summer.hour, summer.minute), (3, 0));
+        assert_eq!(paris.abbreviation(summer.is_dst), "CEST");
+
+        // 2024-10-27 00:59:59 UTC, right before the switch back to CET.
+        assert!(paris.to_local(at(1_729_990_799)).is_dst);
+        assert!(!paris.to_local(at(1_729_990_800)).is_dst);
+
+        // DST spans the new year in the southern hemisphere.
+        let sydney = TimeZone::named("Australia/Sydney").unwrap();
+        let new_year = sydney.to_local(at(1_704_067_200));
+        assert_eq!(
+            (new_year.year, new_year.hour, new_year.is_dst),
+            (2024, 11, true)
+        );
+        assert!(!sydney.to_local(at(1_719_792_000)).is_dst);
+
+        let kolkata = TimeZone::named("Asia/Kolkata").unwrap();
+        assert_eq!(kolkata.to_local(at(0)).utc_offset, 5 * 3600 + 30 * 60);
+        assert_eq!(kolkata.to_local(at(0)).minute, 30);
+    }
+}
@@ -0,0 +1,379 @@
+//! Voice chat building blocks.
+//!
+//! A voice chat is made of three stages:
+//!
+//! 1. A [`VoiceSender`] splits the samples recorded by the [microphone](crate::services::mic) into frames,
+//!    and encodes them into packets while the push-to-talk button is held.
+//! 2. The packets are sent over any transport (e.g. a local network or a UDP socket). Packets may be lost or arrive out of order.
+//! 3. A [`JitterBuffer`] reorders the received packets and decodes them into frames, ready to be played via [`Ndsp`](crate::services::ndsp::Ndsp).
+//!
+//! The compression of the frames is handled by a [`VoiceCodec`]. [`MuLaw`] is provided as a simple built-in codec,
+//! but more efficient codecs (e.g. Opus) can be plugged in by implementing the trait.
+#![doc(alias = "microphone")]
+#![doc(alias = "push to talk")]
+
+use std::collections::VecDeque;
+
+use crate::codec::{Decode, Decoder, Encode, Encoder, Error};
+use crate::services::mic::Mic;
+
+// Packets further ahead than this are treated as a new stream.
+const MAX_GAP: usize = 64;
+
+/// Compression of the audio frames of a voice chat.
+pub trait VoiceCodec {
+    /// Appends the encoded `frame` to `out`.
+    fn encode(&mut self, frame: &[i16], out: &mut Vec<u8>);
+
+    /// Decodes a frame encoded via [`VoiceCodec::encode()`].
+    fn decode(&mut self, data: &[u8]) -> Vec<i16>;
+
+    /// Returns a frame of `len` samples to play in place of a lost packet.
+    ///
+    /// The default implementation returns silence.
+    fn conceal(&mut self, len: usize) -> Vec<i16> {
+        vec![0; len]
+    }
+}
+
+/// G.711 μ-law codec, which halves the size of the samples with no noticeable loss for speech.
+#[doc(alias = "g711")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MuLaw;
+
+/// Packet of a voice chat, holding a single encoded frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoicePacket {
+    /// Sequence number of the frame, which wraps around.
+    pub sequence: u16,
+    /// Encoded frame.
+    pub payload: Vec<u8>,
+}
+
+/// Push-to-talk sender of a voice chat.
+///
+/// # Example
+///
+/// ```no_run
+/// # let _runner = test_runner::GdbRunner::default();
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use ctru::prelude::*;
+/// use ctru::services::mic::{Mic, SampleRate};
+/// use ctru::voice::{MuLaw, VoiceSender};
+///
+/// let apt = Apt::new()?;
+/// let mut hid = Hid::new()?;
+/// let mut mic = Mic::new()?;
+///
+/// mic.start_sampling(SampleRate::Hz8180)?;
+///
+/// // 20 ms frames.
+/// let mut sender = VoiceSender::new(MuLaw, 164);
+///
+/// while apt.main_loop() {
+///     hid.scan_input();
+///
+///     for packet in sender.capture(&mut mic, hid.keys_held().contains(KeyPad::L)) {
+///         // Send the packet over the network...
+///     }
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct VoiceSender<C> {
+    codec: C,
+    frame_len: usize,
+    pending: Vec<i16>,
+    sequence: u16,
+}
+
+/// Receiver of a voice chat, which smooths out the timing of the packets.
+///
+/// Packets are held until [`depth`](JitterBuffer::new) of them are buffered, then one frame is played per [`JitterBuffer::pop()`].
+/// Lost packets are [concealed](VoiceCodec::conceal), while late and duplicate packets are dropped.
+#[derive(Clone, Debug)]
+pub struct JitterBuffer<C> {
+    codec: C,
+    frame_len: usize,
+    depth: usize,
+    next: Option<u16>,
+    slots: VecDeque<Option<Vec<u8>>>,
+    buffering: bool,
+}
+
+impl MuLaw {
+    const BIAS: i32 = 0x84;
+
+    fn encode_sample(sample: i16) -> u8 {
+        let sign = if sample < 0 { 0x80 } else { 0 };
+        let magnitude = (i32::from(sample).abs() + Self::BIAS).min(0x7FFF);
+
+        let exponent = 15 - (magnitude as u16).leading_zeros() as i32 - 7;
+        let mantissa = (magnitude >> (exponent + 3)) & 0x0F;
+
+        !(sign | (exponent << 4) as u8 | mantissa as u8)
+    }
+
+    fn decode_sample(byte: u8) -> i16 {
+        let byte = !byte;
+        let exponent = i32::from((byte >> 4) & 0x07);
+        let mantissa = i32::from(byte & 0x0F);
+
+        let magnitude = (((mantissa << 3) + Self::BIAS) << exponent) - Self::BIAS;
+
+        if byte & 0x80 != 0 {
+            -magnitude as i16
+        } else {
+            magnitude as i16
+        }
+    }
+}
+
+impl VoiceCodec for MuLaw {
+    fn encode(&mut self, frame: &[i16], out: &mut Vec<u8>) {
+        out.extend(frame.iter().map(|&sample| Self::encode_sample(sample)));
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Vec<i16> {
+        data.iter().map(|&byte| Self::decode_sample(byte)).collect()
+    }
+}
+
+impl VoicePacket {
+    /// Encodes the packet, ready to be sent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        self.encode(&mut encoder);
+        encoder.into_bytes()
+    }
+
+    /// Decodes a packet encoded via [`VoicePacket::to_bytes()`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `bytes` doesn't contain a valid packet.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode(&mut Decoder::new(bytes))
+    }
+}
+
+impl Encode for VoicePacket {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.sequence.encode(encoder);
+        self.payload.encode(encoder);
+    }
+}
+
+impl Decode for VoicePacket {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        Ok(Self {
+            sequence: u16::decode(decoder)?,
+            payload: Vec::decode(decoder)?,
+        })
+    }
+}
+
+impl<C: VoiceCodec> VoiceSender<C> {
+    /// Creates a new sender, encoding frames of `frame_len` samples with `codec`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `frame_len` is zero.
+    pub fn new(codec: C, frame_len: usize) -> Self {
+        assert_ne!(frame_len, 0, "frames can't be empty");
+
+        Self {
+            codec,
+            frame_len,
+            pending: Vec::with_capacity(frame_len),
+            sequence: 0,
+        }
+    }
+
+    /// Adds recorded samples, returning the encoded packets of every completed frame.
+    ///
+    /// While `talking` is `false` the samples are discarded, along with any incomplete frame.
+    pub fn push(&mut self, samples: &[i16], talking: bool) -> Vec<Vec<u8>> {
+        if !talking {
+            self.pending.clear();
+            return Vec::new();
+        }
+
+        self.pending.extend_from_slice(samples);
+
+        let mut packets = Vec::new();
+        let mut start = 0;
+
+        while self.pending.len() - start >= self.frame_len {
+            let frame = &self.pending[start..][..self.frame_len];
+            start += self.frame_len;
+
+            let mut payload = Vec::new();
+            self.codec.encode(frame, &mut payload);
+
+            packets.push(
+                VoicePacket {
+                    sequence: self.sequence,
+                    payload,
+                }
+                .to_bytes(),
+            );
+            self.sequence = self.sequence.wrapping_add(1);
+        }
+
+        self.pending.drain(..start);
+
+        packets
+    }
+
+    /// Reads the samples recorded by `mic`, returning the encoded packets of every completed frame.
+    ///
+    /// See [`VoiceSender::push()`].
+    pub fn capture(&mut self, mic: &mut Mic, talking: bool) -> Vec<Vec<u8>> {
+        let samples = mic.read_samples();
+        self.push(&samples, talking)
+    }
+}
+
+impl<C: VoiceCodec> JitterBuffer<C> {
+    /// Creates a new jitter buffer decoding frames of `frame_len` samples with `codec`,
+    /// which starts playing once `depth` packets are buffered.
+    pub fn new(codec: C, frame_len: usize, depth: usize) -> Self {
+        Self {
+            codec,
+            frame_len,
+            depth: depth.max(1),
+            next: None,
+            slots: VecDeque::new(),
+            buffering: true,
+        }
+    }
+
+    /// Returns the number of frames waiting to be played, including lost ones.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns `true` if there are no frames waiting to be played.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Adds a received packet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `packet` isn't a valid [`VoicePacket`].
+    pub fn push(&mut self, packet: &[u8]) -> Result<(), Error> {
+        let packet = VoicePacket::from_bytes(packet)?;
+
+        let next = *self.next.get_or_insert(packet.sequence);
+        let offset = packet.sequence.wrapping_sub(next) as i16;
+
+        // The frame was already played (or concealed).
+        if offset < 0 {
+            return Ok(());
+        }
+
+        let mut offset = offset as usize;
+        if offset >= MAX_GAP {
+            // The sender restarted, or too many packets were lost to catch up.
+            self.slots.clear();
+            self.next = Some(packet.sequence);
+            self.buffering = true;
+            offset = 0;
+        }
+
+        if self.slots.len() <= offset {
+            self.slots.resize(offset + 1, None);
+        }
+        self.slots[offset].get_or_insert(packet.payload);
+
+        Ok(())
+    }
+
+    /// Returns the next frame to play, or [`None`] if the buffer is waiting for more packets.
+    pub fn pop(&mut self) -> Option<Vec<i16>> {
+        if self.buffering {
+            if self.slots.len() < self.depth {
+                return None;
+            }
+
+            self.buffering = false;
+        }
+
+        let Some(slot) = self.slots.pop_front() else {
+            // The buffer ran dry: wait for it to fill up again.
+            self.buffering = true;
+            return None;
+        };
+
+        self.next = self.next.map(|next| next.wrapping_add(1));
+
+        Some(match slot {
+            Some(payload) => self.codec.decode(&payload),
+            None => self.codec.conceal(self.frame_len),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mulaw_roundtrip() {
+        for sample in [
+            0,
+            1,
+            -1,
+            100,
+            -100,
+            1000,
+            -1000,
+            8000,
+            -8000,
+            i16::MAX,
+            i16::MIN,
+        ] {
+            let decoded = MuLaw::decode_sample(MuLaw::encode_sample(sample));
+            let error = (i32::from(decoded) - i32::from(sample)).abs();
+
+            assert!(
+                error <= i32::from(sample).abs() / 16 + 8,
+                "{sample} -> {decoded}"
+            );
+        }
+    }
+
+    #[test]
+    fn jitter_buffer() {
+        let mut sender = VoiceSender::new(MuLaw, 2);
+        let packets = sender.push(&[0, 0, 1000, 1000, 2000, 2000, 3000, 3000], true);
+        assert_eq!(packets.len(), 4);
+
+        let mut buffer = JitterBuffer::new(MuLaw, 2, 2);
+
+        // Out of order, with the third packet lost.
+        buffer.push(&packets[0]).unwrap();
+        assert_eq!(buffer.pop(), None);
+        buffer.push(&packets[3]).unwrap();
+        buffer.push(&packets[1]).unwrap();
+
+        assert_eq!(buffer.pop(), Some(vec![0, 0]));
+        assert!(buffer.pop().unwrap()[0].abs_diff(1000) < 64);
+        assert_eq!(buffer.pop(), Some(vec![0, 0]));
+
+        // Late and duplicate packets are dropped.
+        buffer.push(&packets[2]).unwrap();
+        buffer.push(&packets[3]).unwrap();
+        assert_eq!(buffer.len(), 1);
+
+        assert!(buffer.pop().is_some());
+        assert_eq!(buffer.pop(), None);
+    }
+}
@@ -0,0 +1,67 @@
+//! VRAM memory allocator.
+//!
+//! VRAM is the dedicated memory of the GPU. Allocating GPU resources (such as textures, render targets or, via [`Gfx::with_framebuffer_location()`](crate::services::gfx::Gfx::with_framebuffer_location),
+//! the framebuffers of the screens) in VRAM leaves more LINEAR memory available for data which must be shared with other hardware components,
+//! such as audio buffers.
+//!
+//! # Notes
+//!
+//! VRAM is accessible by the CPU, but reading from or writing to it is considerably slower than using the other memory sectors.
+//!
+//! # Additional Resources
+//!
+//! - <https://github.com/devkitPro/libctru/blob/master/libctru/source/allocator/vram.cpp>
+//! - <https://www.3dbrew.org/wiki/Memory_layout>
+
+use std::alloc::{AllocError, Allocator, Layout};
+use std::ptr::NonNull;
+
+/// [`Allocator`] struct for VRAM memory.
+///
+/// To use this struct the main crate must activate the `allocator_api` unstable feature.
+///
+/// # Example
+///
+/// ```
+/// # #![feature(allocator_api)]
+/// # fn main() {
+/// # let _runner = test_runner::GdbRunner::default();
+/// #
+/// use ctru::vram::VramAllocator;
+///
+/// let free_space = VramAllocator::free_space();
+///
+/// // Zeroed texture allocated in VRAM.
+/// let texture: Box<[_], _> = Box::new_in([0u8; 64 * 64 * 4], VramAllocator);
+///
+/// assert!(VramAllocator::free_space() < free_space);
+/// # }
+/// ```
+#[derive(Copy, Clone, Default, Debug)]
+pub struct VramAllocator;
+
+impl VramAllocator {
+    /// Returns the amount of free space left in the VRAM memory sector.
+    #[doc(alias = "vramSpaceFree")]
+    pub fn free_space() -> u32 {
+        unsafe { ctru_sys::vramSpaceFree() }
+    }
+}
+
+unsafe impl Allocator for VramAllocator {
+    #[doc(alias = "vramAlloc", alias = "vramMemAlign")]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let pointer = unsafe { ctru_sys::vramMemAlign(layout.size(), layout.align()) };
+
+        NonNull::new(pointer.cast())
+            .map(|ptr| NonNull::slice_from_raw_parts(ptr, layout.size()))
+            .ok_or(AllocError)
+    }
+
+    #[doc(alias = "vramFree")]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
+        unsafe {
+            ctru_sys::vramFree(ptr.as_ptr().cast());
+        }
+    }
+}
@@ -7,13 +7,6 @@ use std::error::Error;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 
-// This allows us to have a directory layout of build/*.rs which is a little
-// cleaner than having all the submodules as siblings to build.rs.
-mod build {
-    #[cfg(feature = "layout-tests")]
-    pub mod test_gen;
-}
-
 #[derive(Debug)]
 struct CustomCallbacks;
 
@@ -73,6 +66,7 @@ fn main() {
     let sysroot = Path::new(&devkitarm).join("arm-none-eabi");
     let system_include = sysroot.join("include");
     let errno_header = system_include.join("errno.h");
+    let iosupport_header = system_include.join("sys/iosupport.h");
 
     let gcc_version = get_gcc_version(&cc);
     let gcc_include = Path::new(&devkitarm)
@@ -119,6 +113,7 @@ fn main() {
     let binding_builder = Builder::default()
         .header(ctru_header.to_str().unwrap())
         .header(errno_header.to_str().unwrap())
+        .header(iosupport_header.to_str().unwrap())
         .rust_target(RustTarget::Nightly)
         .use_core()
         .trust_clang_mangling(false)
@@ -129,6 +124,12 @@ fn main() {
         .allowlist_file(include_path.join("3ds[.]h").to_string_lossy())
         .allowlist_file(include_path.join("3ds/.*").to_string_lossy())
         .allowlist_function("__errno")
+        // newlib device table, used to register custom filesystem devices.
+        .allowlist_type("devoptab_t")
+        .allowlist_var("devoptab_list")
+        .allowlist_function("(Add|Remove|Find)Device")
+        .allowlist_function("GetDeviceOpTab")
+        .allowlist_function("setDefaultDevice")
         .blocklist_function("gethost(id|name)")
         .blocklist_type("u(8|16|32|64)")
         .blocklist_type("__builtin_va_list")
@@ -138,6 +139,10 @@ fn main() {
         .blocklist_type("sockaddr_storage")
         .blocklist_type("(in_addr|wchar|socklen|suseconds|sa_family|time)_t")
         .blocklist_item("SOL_CONFIG")
+        // Types used by the device callbacks which are already provided by `libc`.
+        .blocklist_type("stat(vfs)?")
+        .blocklist_type("(off|ssize|mode)_t")
+        .opaque_type("_reent")
         .opaque_type("MiiData")
         .derive_default(true)
         .wrap_static_fns(true)
@@ -145,8 +150,11 @@ fn main() {
         .clang_args(clang.args().iter().map(|s| s.to_str().unwrap()))
         .parse_callbacks(Box::new(CustomCallbacks));
 
+    let (enum_callbacks, enum_generator) = binding_helpers::EnumValuesCallbacks::new();
+    let binding_builder = binding_builder.parse_callbacks(Box::new(enum_callbacks));
+
     #[cfg(feature = "layout-tests")]
-    let (test_callbacks, test_generator) = build::test_gen::LayoutTestCallbacks::new();
+    let (test_callbacks, test_generator) = binding_helpers::LayoutTestCallbacks::new();
     #[cfg(feature = "layout-tests")]
     let binding_builder = binding_builder.parse_callbacks(Box::new(test_callbacks));
 
@@ -156,6 +164,18 @@ fn main() {
         .write_to_file(out_dir.join("bindings.rs"))
         .expect("Couldn't write bindings!");
 
+    // Values of the enums mirrored by safe wrappers in `ctru-rs`, to check that they handle all of them.
+    enum_generator
+        .allowlist_enum("CFG_(Region|Language|SystemModel)")
+        .allowlist_enum("udsConnectionType")
+        .allowlist_enum("acSecurityMode")
+        .constant_table("RL_", "RESULT_LEVELS")
+        .constant_table("RS_", "RESULT_SUMMARIES")
+        .constant_table("RM_", "RESULT_MODULES")
+        .constant_table("RD_", "RESULT_DESCRIPTIONS")
+        .generate_values(out_dir.join("enum_values.rs"))
+        .expect("Couldn't write enum values!");
+
     cc_build
         .file(out_dir.join("libctru_statics_wrapper.c"))
         .compile("ctru_statics_wrapper");
@@ -275,7 +295,7 @@ fn track_libctru_files(pacman: &Path) -> Result<(), String> {
 #[cfg(feature = "layout-tests")]
 fn generate_layout_tests(
     output_file: &Path,
-    test_generator: &build::test_gen::LayoutTestGenerator,
+    test_generator: &binding_helpers::LayoutTestGenerator,
 ) -> Result<(), Box<dyn Error>> {
     // There are several bindgen-generated types/fields that we can't check:
     test_generator
@@ -304,9 +324,6 @@ fn generate_layout_tests(
             )
             // Variable-length arrays:
             .blocklist_field("romfs_(dir|file)", "name")
-            // Bindgen anonymous types (and their associated fields):
-            .blocklist_type(".*__bindgen.*")
-            .blocklist_field(".*", "__bindgen.*")
             // Bindgen mangles `type` (a Rust keyword) to `type_`:
             .rename_field("type", "type_")
             .generate_layout_tests(output_file)
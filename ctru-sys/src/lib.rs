@@ -33,6 +33,7 @@ mod bindings {
     use libc::*;
 
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+    include!(concat!(env!("OUT_DIR"), "/enum_values.rs"));
 }
 
 pub use bindings::*;
@@ -17,9 +17,12 @@ fn size_of_ret<T, U>(_f: impl Fn(U) -> T) -> usize {
     ::std::mem::size_of::<T>()
 }
 
+// Fields of anonymous members are accessed through a nested path, which may go through a union.
 macro_rules! size_of {
-    ($ty:ident::$field:ident) => {{
-        $crate::size_of_ret(|x: $ty| x.$field)
+    ($ty:ident::$($field:ident).+) => {{
+        #[allow(unused_unsafe)]
+        let field = |x: $ty| unsafe { x.$($field).+ };
+        $crate::size_of_ret(field)
     }};
     ($ty:ty) => {
         ::std::mem::size_of::<$ty>()
@@ -34,11 +37,13 @@ fn align_of_ret<T, U>(_f: impl Fn(U) -> T) -> usize {
 }
 
 macro_rules! align_of {
-    ($ty:ident::$field:ident) => {{
+    ($ty:ident::$($field:ident).+) => {{
         // This matches the semantics of C++ alignof when it is applied to a struct
         // member. Packed structs may under-align fields, so we take the minimum
         // of the align of the struct and the type of the field itself.
-        $crate::align_of_ret(|x: $ty| x.$field).min(align_of!($ty))
+        #[allow(unused_unsafe)]
+        let field = |x: $ty| unsafe { x.$($field).+ };
+        $crate::align_of_ret(field).min(align_of!($ty))
     }};
     ($ty:ty) => {
         ::std::mem::align_of::<$ty>()
@@ -0,0 +1,98 @@
+//! System configuration.
+//!
+//! See `ctru::services::cfgu` for the service reading the configuration of the console.
+#![doc(alias = "configuration")]
+
+ffi_enum! {
+    /// Console region.
+    #[doc(alias = "CFG_Region")]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum Region: u8 {
+        /// Japan.
+        Japan = 0,
+        /// USA.
+        USA = 1,
+        /// Europe.
+        Europe = 2,
+        /// Australia.
+        Australia = 3,
+        /// China.
+        China = 4,
+        /// Korea.
+        Korea = 5,
+        /// Taiwan.
+        Taiwan = 6,
+    }
+}
+
+ffi_enum! {
+    /// Language set for the console's OS.
+    #[doc(alias = "CFG_Language")]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum Language: i8 {
+        /// Japanese.
+        Japanese = 0,
+        /// English.
+        English = 1,
+        /// French.
+        French = 2,
+        /// German.
+        German = 3,
+        /// Italian.
+        Italian = 4,
+        /// Spanish.
+        Spanish = 5,
+        /// Korean.
+        Korean = 7,
+        /// Dutch.
+        Dutch = 8,
+        /// Portuguese.
+        Portuguese = 9,
+        /// Russian.
+        Russian = 10,
+        /// Simplified Chinese.
+        SimplifiedChinese = 6,
+        /// Traditional Chinese.
+        TraditionalChinese = 11,
+    }
+}
+
+ffi_enum! {
+    /// Specific model of the console.
+    #[doc(alias = "CFG_SystemModel")]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub enum SystemModel: u8 {
+        /// Old Nintendo 3DS.
+        Old3DS = 0,
+        /// Old Nintendo 3DS XL.
+        Old3DSXL = 1,
+        /// New Nintendo 3DS.
+        New3DS = 2,
+        /// Old Nintendo 2DS.
+        Old2DS = 3,
+        /// New Nintendo 3DS XL.
+        New3DSXL = 4,
+        /// New Nintendo 2DS XL.
+        New2DSXL = 5,
+    }
+}
+
+/// Source of the console's system configuration.
+///
+/// Implemented by `ctru::services::cfgu::Cfgu`, which reads it from the CFG service.
+pub trait SystemInfo {
+    /// Error returned when the configuration couldn't be read.
+    type Error;
+
+    /// Returns the console's region.
+    fn region(&self) -> Result<Region, Self::Error>;
+
+    /// Returns the console's model.
+    fn model(&self) -> Result<SystemModel, Self::Error>;
+
+    /// Returns the language set for the console's OS.
+    fn language(&self) -> Result<Language, Self::Error>;
+
+    /// Returns `true` if the console is a model of the Nintendo 2DS family.
+    fn is_2ds_family(&self) -> Result<bool, Self::Error>;
+}
@@ -0,0 +1,40 @@
+//! File system access.
+//!
+//! See `ctru::services::fs` for the archives of the FS service.
+#![doc(alias = "filesystem")]
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Storage of files, addressed by their path.
+///
+/// Files are always read and written as a whole. Implemented by [`StdFs`] and `ctru::services::fs::Archive`.
+pub trait Fs {
+    /// Reads the whole contents of a file.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Writes the contents of a file, creating it if it doesn't exist and replacing its previous contents otherwise.
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Removes a file.
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+}
+
+/// Implementation of [`Fs`] over the file systems mounted in the standard library (e.g. `sdmc:/` and `romfs:/`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StdFs;
+
+impl Fs for StdFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+}
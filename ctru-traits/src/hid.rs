@@ -0,0 +1,250 @@
+//! Input state.
+//!
+//! See `ctru::services::hid` for the service reading the input state of the console.
+#![doc(alias = "input")]
+
+use std::fmt;
+
+use bitflags::bitflags;
+
+use crate::cfgu::Language;
+
+bitflags! {
+    /// A set of flags corresponding to the button and directional pad inputs present on the 3DS.
+    ///
+    /// Sets of buttons can be composed in constants, and iterated over one button at a time via [`KeyPad::keys()`].
+    /// With the `serde` feature, sets are serialized as the names of their flags (e.g. `"A | DPAD_UP"`) in human-readable formats,
+    /// and as the raw mask otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ctru_traits::hid::KeyPad;
+    ///
+    /// const CONFIRM: KeyPad = KeyPad::A.union(KeyPad::START);
+    ///
+    /// let names: Vec<String> = CONFIRM.keys().map(|key| key.to_string()).collect();
+    /// assert_eq!(names, ["A", "Start"]);
+    /// ```
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct KeyPad: u32 {
+        /// A button.
+        const A             = 1 << 0;
+        /// B button.
+        const B             = 1 << 1;
+        /// Select button.
+        const SELECT        = 1 << 2;
+        /// Start button.
+        const START         = 1 << 3;
+        /// D-Pad Right.
+        const DPAD_RIGHT        = 1 << 4;
+        /// D-Pad Left.
+        const DPAD_LEFT         = 1 << 5;
+        /// D-Pad Up.
+        const DPAD_UP           = 1 << 6;
+        /// D-Pad Down.
+        const DPAD_DOWN         = 1 << 7;
+        /// R button.
+        const R             = 1 << 8;
+        /// L button.
+        const L             = 1 << 9;
+        /// X button.
+        const X             = 1 << 10;
+        /// Y button.
+        const Y             = 1 << 11;
+        /// ZL button.
+        const ZL            = 1 << 14;
+        /// ZR button.
+        const ZR            = 1 << 15;
+        /// Touchscreen.
+        const TOUCH         = 1 << 20;
+        /// C-Stick Right.
+        const CSTICK_RIGHT  = 1 << 24;
+        /// C-Stick Left.
+        const CSTICK_LEFT   = 1 << 25;
+        /// C-Stick Up.
+        const CSTICK_UP     = 1 << 26;
+        /// C-Stick Down.
+        const CSTICK_DOWN   = 1 << 27;
+        /// CirclePad Right.
+        const CPAD_RIGHT    = 1 << 28;
+        /// CirclePad Left.
+        const CPAD_LEFT     = 1 << 29;
+        /// CirclePad Up.
+        const CPAD_UP       = 1 << 30;
+        /// CirclePad Down.
+        const CPAD_DOWN     = 1 << 31;
+
+        // Convenience catch-all for the D-Pad and the CirclePad
+
+        /// Direction Up (either D-Pad or CirclePad).
+        const UP    = KeyPad::DPAD_UP.bits()    | KeyPad::CPAD_UP.bits();
+        /// Direction Down (either D-Pad or CirclePad).
+        const DOWN  = KeyPad::DPAD_DOWN.bits()  | KeyPad::CPAD_DOWN.bits();
+        /// Direction Left (either D-Pad or CirclePad).
+        const LEFT  = KeyPad::DPAD_LEFT.bits()  | KeyPad::CPAD_LEFT.bits();
+        /// Direction Right (either D-Pad or CirclePad).
+        const RIGHT = KeyPad::DPAD_RIGHT.bits() | KeyPad::CPAD_RIGHT.bits();
+    }
+}
+
+// Names of the inputs, as shown to users.
+struct InputNames {
+    // D-Pad, Circle Pad and C-Stick.
+    inputs: [&'static str; 3],
+    // Up, down, left and right.
+    directions: [&'static str; 4],
+    touch: &'static str,
+    // Separator between the name of an input and a direction.
+    separator: &'static str,
+}
+
+// Indexed by the raw values of `Language`.
+#[rustfmt::skip]
+const INPUT_NAMES: [InputNames; 12] = [
+    InputNames { inputs: ["十字ボタン", "スライドパッド", "Cスティック"], directions: ["上", "下", "左", "右"], touch: "タッチスクリーン", separator: "" },
+    InputNames { inputs: ["D-Pad", "Circle Pad", "C-Stick"], directions: ["Up", "Down", "Left", "Right"], touch: "Touch Screen", separator: " " },
+    InputNames { inputs: ["Croix directionnelle", "Pad circulaire", "Stick C"], directions: ["haut", "bas", "gauche", "droite"], touch: "Écran tactile", separator: " " },
+    InputNames { inputs: ["Steuerkreuz", "Schiebepad", "C-Stick"], directions: ["oben", "unten", "links", "rechts"], touch: "Touchscreen", separator: " " },
+    InputNames { inputs: ["Pulsantiera +", "Pad scorrevole", "Stick C"], directions: ["su", "giù", "sinistra", "destra"], touch: "Touch screen", separator: " " },
+    InputNames { inputs: ["Cruceta", "Botón deslizante", "Palanca C"], directions: ["arriba", "abajo", "izquierda", "derecha"], touch: "Pantalla táctil", separator: " " },
+    InputNames { inputs: ["十字键", "滑动板", "C摇杆"], directions: ["上", "下", "左", "右"], touch: "触摸屏", separator: "" },
+    InputNames { inputs: ["십자 버튼", "슬라이드 패드", "C스틱"], directions: ["위", "아래", "왼쪽", "오른쪽"], touch: "터치스크린", separator: " " },
+    InputNames { inputs: ["+Bedieningsknop", "Circle Pad", "C-stick"], directions: ["omhoog", "omlaag", "links", "rechts"], touch: "Aanraakscherm", separator: " " },
+    InputNames { inputs: ["Botão de direção", "Botão deslizante", "Stick C"], directions: ["cima", "baixo", "esquerda", "direita"], touch: "Ecrã tátil", separator: " " },
+    InputNames { inputs: ["Крестовина", "Круговая панель", "C-стик"], directions: ["вверх", "вниз", "влево", "вправо"], touch: "Сенсорный экран", separator: " " },
+    InputNames { inputs: ["十字鈕", "類比搖桿", "C搖桿"], directions: ["上", "下", "左", "右"], touch: "觸控螢幕", separator: "" },
+];
+
+impl KeyPad {
+    /// Returns an iterator over the individual buttons of the set, in the order of their flags.
+    ///
+    /// Unlike [`KeyPad::iter()`], the convenience combinations (e.g. [`KeyPad::UP`]) are never yielded, and neither are unknown bits.
+    pub fn keys(self) -> impl Iterator<Item = KeyPad> {
+        (0..u32::BITS)
+            .map(|bit| KeyPad::from_bits_retain(1 << bit))
+            .filter(move |&key| KeyPad::all().contains(key) && self.contains(key))
+    }
+
+    /// Returns the name of a single button, as shown to users in the given language (e.g. "D-Pad Up" in English).
+    ///
+    /// Returns [`None`] if the set doesn't contain exactly one button.
+    /// The names of the buttons marked with letters (A, B, L, Start...) aren't translated, like in the system menus.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ctru_traits::cfgu::Language;
+    /// use ctru_traits::hid::KeyPad;
+    ///
+    /// let name = KeyPad::DPAD_UP.display_name(Language::German).unwrap();
+    /// assert_eq!(name, "Steuerkreuz oben");
+    /// ```
+    pub fn display_name(self, language: Language) -> Option<String> {
+        let names = &INPUT_NAMES[language as i8 as usize];
+        let directional = |input: usize, direction: usize| {
+            Some(format!(
+                "{}{}{}",
+                names.inputs[input], names.separator, names.directions[direction]
+            ))
+        };
+
+        match self {
+            KeyPad::A => Some("A".into()),
+            KeyPad::B => Some("B".into()),
+            KeyPad::X => Some("X".into()),
+            KeyPad::Y => Some("Y".into()),
+            KeyPad::L => Some("L".into()),
+            KeyPad::R => Some("R".into()),
+            KeyPad::ZL => Some("ZL".into()),
+            KeyPad::ZR => Some("ZR".into()),
+            KeyPad::START => Some("Start".into()),
+            KeyPad::SELECT => Some("Select".into()),
+            KeyPad::TOUCH => Some(names.touch.into()),
+            KeyPad::DPAD_UP => directional(0, 0),
+            KeyPad::DPAD_DOWN => directional(0, 1),
+            KeyPad::DPAD_LEFT => directional(0, 2),
+            KeyPad::DPAD_RIGHT => directional(0, 3),
+            KeyPad::CPAD_UP => directional(1, 0),
+            KeyPad::CPAD_DOWN => directional(1, 1),
+            KeyPad::CPAD_LEFT => directional(1, 2),
+            KeyPad::CPAD_RIGHT => directional(1, 3),
+            KeyPad::CSTICK_UP => directional(2, 0),
+            KeyPad::CSTICK_DOWN => directional(2, 1),
+            KeyPad::CSTICK_LEFT => directional(2, 2),
+            KeyPad::CSTICK_RIGHT => directional(2, 3),
+            _ => None,
+        }
+    }
+}
+
+/// Displays the English names of the buttons of the set, separated by `+` (e.g. "L + D-Pad Up").
+///
+/// See [`KeyPad::display_name()`] to display them in the language of the console.
+impl fmt::Display for KeyPad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut separator = "";
+
+        for key in self.keys() {
+            // Every single known button has a name.
+            let name = key.display_name(Language::English).unwrap_or_default();
+            write!(f, "{separator}{name}")?;
+            separator = " + ";
+        }
+
+        let unknown = self.bits() & !KeyPad::all().bits();
+        if unknown != 0 {
+            write!(f, "{separator}{unknown:#x}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Source of the input state read by the application each frame.
+///
+/// Implemented by `ctru::services::hid::Hid`, which must be updated via `Hid::scan_input()` every frame.
+pub trait InputSource {
+    /// Returns the buttons which have just been pressed on the current frame.
+    fn keys_down(&self) -> KeyPad;
+
+    /// Returns the buttons which have been held down during the current frame.
+    fn keys_held(&self) -> KeyPad;
+
+    /// Returns the buttons which have just been released on the current frame.
+    fn keys_up(&self) -> KeyPad;
+
+    /// Returns the current touch position in pixels (x, y).
+    fn touch_position(&self) -> (u16, u16);
+
+    /// Returns the current circle pad position in relative (x, y).
+    fn circlepad_position(&self) -> (i16, i16);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names() {
+        let keys = KeyPad::UP | KeyPad::L;
+        assert_eq!(
+            keys.keys().collect::<Vec<_>>(),
+            [KeyPad::DPAD_UP, KeyPad::L, KeyPad::CPAD_UP]
+        );
+        assert_eq!(keys.to_string(), "D-Pad Up + L + Circle Pad Up");
+
+        assert_eq!(
+            KeyPad::CSTICK_LEFT
+                .display_name(Language::French)
+                .as_deref(),
+            Some("Stick C gauche")
+        );
+        assert_eq!(
+            KeyPad::DPAD_UP.display_name(Language::Japanese).as_deref(),
+            Some("十字ボタン上")
+        );
+        assert_eq!(KeyPad::UP.display_name(Language::English), None);
+    }
+}
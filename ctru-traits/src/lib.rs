@@ -0,0 +1,81 @@
+//! Service traits and data types shared by [`ctru-rs`](https://rust3ds.github.io/ctru-rs/crates/ctru) and its mock implementations.
+//!
+//! Logic which only needs to read the state of a service (e.g. the input state, the system configuration or files)
+//! can be made generic over the traits of this crate instead of depending on the service handles of `ctru-rs` directly.
+//! Unlike `ctru-rs`, this crate doesn't link to `libctru`, so such logic can be unit tested on the host
+//! with the mock implementations enabled by the `mock` feature.
+//!
+//! All the items of this crate are re-exported by `ctru-rs`, in the modules of the matching services.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "mock")]
+//! # {
+//! use ctru_traits::hid::{InputSource, KeyPad};
+//! use ctru_traits::mock::MockHid;
+//!
+//! fn wants_to_jump(input: &impl InputSource) -> bool {
+//!     input.keys_down().contains(KeyPad::A)
+//! }
+//!
+//! let mut input = MockHid::default();
+//! assert!(!wants_to_jump(&input));
+//!
+//! input.press(KeyPad::A);
+//! assert!(wants_to_jump(&input));
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+#![doc(
+    html_favicon_url = "https://user-images.githubusercontent.com/11131775/225929072-2fa1741c-93ae-4b47-9bdf-af70f3d59910.png"
+)]
+#![doc(
+    html_logo_url = "https://user-images.githubusercontent.com/11131775/225929072-2fa1741c-93ae-4b47-9bdf-af70f3d59910.png"
+)]
+#![doc(html_root_url = "https://rust3ds.github.io/ctru-rs/crates")]
+
+// Defines an enum mirroring a C enum of libctru, with the conversions from and into its raw representation.
+//
+// The values are checked against the ones generated by `ctru-sys` when building `ctru-rs`.
+macro_rules! ffi_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident: $repr:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:expr),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr($repr)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant = $value),+
+        }
+
+        impl From<$name> for $repr {
+            fn from(v: $name) -> Self {
+                v as $repr
+            }
+        }
+
+        impl TryFrom<$repr> for $name {
+            type Error = ();
+
+            fn try_from(value: $repr) -> ::core::result::Result<Self, Self::Error> {
+                $(
+                    if value == $name::$variant as $repr {
+                        return Ok($name::$variant);
+                    }
+                )+
+
+                Err(())
+            }
+        }
+    };
+}
+
+pub mod cfgu;
+pub mod fs;
+pub mod hid;
+#[cfg(feature = "mock")]
+pub mod mock;
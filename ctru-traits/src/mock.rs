@@ -0,0 +1,203 @@
+//! Mock implementations of the service traits.
+//!
+//! The types in this module hold plain data instead of communicating with the system services,
+//! so that logic which is generic over traits such as [`InputSource`], [`SystemInfo`] or [`Fs`] can be tested with predefined inputs.
+//!
+//! This module is only compiled if the `mock` feature is enabled. See the [crate documentation](crate) for an example.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::cfgu::{Language, Region, SystemInfo, SystemModel};
+use crate::fs::Fs;
+use crate::hid::{InputSource, KeyPad};
+
+/// Mock implementation of [`InputSource`], returning the values stored in its fields.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MockHid {
+    /// Value returned by [`InputSource::keys_down()`].
+    pub keys_down: KeyPad,
+    /// Value returned by [`InputSource::keys_held()`].
+    pub keys_held: KeyPad,
+    /// Value returned by [`InputSource::keys_up()`].
+    pub keys_up: KeyPad,
+    /// Value returned by [`InputSource::touch_position()`].
+    pub touch_position: (u16, u16),
+    /// Value returned by [`InputSource::circlepad_position()`].
+    pub circlepad_position: (i16, i16),
+}
+
+/// Mock implementation of [`SystemInfo`], returning the values stored in its fields.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MockCfgu {
+    /// Value returned by [`SystemInfo::region()`].
+    pub region: Region,
+    /// Value returned by [`SystemInfo::model()`].
+    pub model: SystemModel,
+    /// Value returned by [`SystemInfo::language()`].
+    pub language: Language,
+}
+
+/// Mock implementation of [`Fs`], storing the files in memory.
+///
+/// Paths are compared as-is, without being normalized.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MockFs {
+    /// Contents of the stored files.
+    pub files: RefCell<BTreeMap<PathBuf, Vec<u8>>>,
+}
+
+impl Default for MockHid {
+    /// Returns a [`MockHid`] with no buttons pressed, no touch input and a centered circle pad.
+    fn default() -> Self {
+        Self {
+            keys_down: KeyPad::empty(),
+            keys_held: KeyPad::empty(),
+            keys_up: KeyPad::empty(),
+            touch_position: (0, 0),
+            circlepad_position: (0, 0),
+        }
+    }
+}
+
+impl MockHid {
+    /// Simulates the transition to a new frame in which only the buttons in `held` are pressed.
+    ///
+    /// `keys_down` and `keys_up` are computed from the difference with the previously held buttons.
+    pub fn press(&mut self, held: KeyPad) {
+        self.keys_down = held.difference(self.keys_held);
+        self.keys_up = self.keys_held.difference(held);
+        self.keys_held = held;
+    }
+}
+
+impl InputSource for MockHid {
+    fn keys_down(&self) -> KeyPad {
+        self.keys_down
+    }
+
+    fn keys_held(&self) -> KeyPad {
+        self.keys_held
+    }
+
+    fn keys_up(&self) -> KeyPad {
+        self.keys_up
+    }
+
+    fn touch_position(&self) -> (u16, u16) {
+        self.touch_position
+    }
+
+    fn circlepad_position(&self) -> (i16, i16) {
+        self.circlepad_position
+    }
+}
+
+impl Default for MockCfgu {
+    /// Returns a [`MockCfgu`] describing an European Old 3DS set to English.
+    fn default() -> Self {
+        Self {
+            region: Region::Europe,
+            model: SystemModel::Old3DS,
+            language: Language::English,
+        }
+    }
+}
+
+impl SystemInfo for MockCfgu {
+    type Error = Infallible;
+
+    fn region(&self) -> Result<Region, Infallible> {
+        Ok(self.region)
+    }
+
+    fn model(&self) -> Result<SystemModel, Infallible> {
+        Ok(self.model)
+    }
+
+    fn language(&self) -> Result<Language, Infallible> {
+        Ok(self.language)
+    }
+
+    fn is_2ds_family(&self) -> Result<bool, Infallible> {
+        Ok(matches!(
+            self.model,
+            SystemModel::Old2DS | SystemModel::New2DSXL
+        ))
+    }
+}
+
+impl MockFs {
+    /// Creates a file system holding the given files.
+    pub fn with_files<P: Into<PathBuf>>(files: impl IntoIterator<Item = (P, Vec<u8>)>) -> Self {
+        Self {
+            files: RefCell::new(
+                files
+                    .into_iter()
+                    .map(|(path, contents)| (path.into(), contents))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Fs for MockFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files
+            .borrow()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.files
+            .borrow_mut()
+            .insert(path.to_owned(), contents.to_vec());
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.files
+            .borrow_mut()
+            .remove(path)
+            .map(drop)
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_tracks_transitions() {
+        let mut input = MockHid::default();
+
+        input.press(KeyPad::A | KeyPad::B);
+        assert_eq!(input.keys_down, KeyPad::A | KeyPad::B);
+        assert_eq!(input.keys_up, KeyPad::empty());
+
+        input.press(KeyPad::B);
+        assert_eq!(input.keys_down, KeyPad::empty());
+        assert_eq!(input.keys_up, KeyPad::A);
+        assert_eq!(input.keys_held, KeyPad::B);
+    }
+
+    #[test]
+    fn files_in_memory() {
+        let fs = MockFs::with_files([("sdmc:/save.bin", vec![1, 2, 3])]);
+        let path = Path::new("sdmc:/save.bin");
+
+        assert_eq!(fs.read(path).unwrap(), [1, 2, 3]);
+
+        fs.write(path, &[4]).unwrap();
+        assert_eq!(fs.read(path).unwrap(), [4]);
+
+        fs.remove_file(path).unwrap();
+        assert_eq!(fs.read(path).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,182 @@
+//! Collection of test artifacts.
+//!
+//! Tests running on a console can't leave files behind for the CI to inspect, so artifacts (screenshots, logs, dumps of
+//! failing states...) are recorded with [`record()`] or [`record_file()`], and sent to the host through the output channel
+//! of the runner (GDB or the `3dslink` socket) once all the tests have run.
+//!
+//! # Protocol
+//!
+//! Every artifact is printed as a block of lines:
+//!
+//! ```text
+//! ==== ARTIFACT BEGIN <size> <name> ====
+//! <data, encoded in base64, in lines of at most 76 characters>
+//! ==== ARTIFACT END <name> ====
+//! ```
+//!
+//! `<size>` is the size of the decoded data, in bytes. Names never contain line breaks.
+//! Host tools can extract the artifacts from the captured output via [`decode()`].
+//!
+//! # Example
+//!
+//! ```
+//! let _runner = test_runner::GdbRunner::default();
+//!
+//! test_runner::artifacts::record("state.txt", b"frame 42: player at (10, 20)".to_vec());
+//! ```
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ctru::base64;
+
+const BEGIN_MARKER: &str = "==== ARTIFACT BEGIN ";
+const END_MARKER: &str = "==== ARTIFACT END ";
+const MARKER_SUFFIX: &str = " ====";
+const LINE_LENGTH: usize = 76;
+
+static ARTIFACTS: Mutex<Vec<(String, Source)>> = Mutex::new(Vec::new());
+
+enum Source {
+    Memory(Vec<u8>),
+    File(PathBuf),
+}
+
+/// Records an artifact, to be sent to the host at the end of the test run.
+///
+/// Line breaks in `name` are replaced by spaces.
+pub fn record(name: impl Into<String>, data: Vec<u8>) {
+    push(name.into(), Source::Memory(data));
+}
+
+/// Records a file as an artifact (e.g. a screenshot saved on the SD card), to be sent to the host at the end of the test run.
+///
+/// The file is read when the artifacts are sent, so it can still be written until then.
+/// The artifact is named after the file name of `path`.
+pub fn record_file(path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    push(name, Source::File(path.to_owned()));
+}
+
+/// Extracts the artifacts sent by a test run from its captured output, returning their names and contents.
+///
+/// Blocks which are truncated or corrupted (e.g. because the connection dropped) are skipped.
+pub fn decode(output: &str) -> Vec<(String, Vec<u8>)> {
+    let mut artifacts = Vec::new();
+    let mut lines = output.lines();
+
+    while let Some(line) = lines.next() {
+        let Some(header) = line
+            .trim_end()
+            .strip_prefix(BEGIN_MARKER)
+            .and_then(|header| header.strip_suffix(MARKER_SUFFIX))
+        else {
+            continue;
+        };
+
+        let Some((size, name)) = header.split_once(' ') else {
+            continue;
+        };
+        let Ok(size) = size.parse::<usize>() else {
+            continue;
+        };
+
+        let end = format!("{END_MARKER}{name}{MARKER_SUFFIX}");
+        let mut encoded = String::new();
+        let mut complete = false;
+
+        for line in lines.by_ref() {
+            let line = line.trim_end();
+            if line == end {
+                complete = true;
+                break;
+            }
+            encoded.push_str(line);
+        }
+
+        if let Some(data) = complete.then(|| base64::decode(&encoded)).flatten() {
+            if data.len() == size {
+                artifacts.push((name.to_owned(), data));
+            }
+        }
+    }
+
+    artifacts
+}
+
+/// Sends the recorded artifacts through `output` and forgets them.
+///
+/// Artifacts whose file can't be read are reported as such, and the others are still sent.
+pub(crate) fn transfer(output: &mut impl Write) -> io::Result<()> {
+    let artifacts = std::mem::take(&mut *ARTIFACTS.lock().unwrap_or_else(|e| e.into_inner()));
+
+    for (name, source) in artifacts {
+        let data = match source {
+            Source::Memory(data) => data,
+            Source::File(path) => match fs::read(&path) {
+                Ok(data) => data,
+                Err(e) => {
+                    writeln!(output, "failed to read artifact {}: {e}", path.display())?;
+                    continue;
+                }
+            },
+        };
+
+        write_artifact(output, &name, &data)?;
+    }
+
+    output.flush()
+}
+
+fn push(name: String, source: Source) {
+    let name = name.replace(['\r', '\n'], " ");
+
+    ARTIFACTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((name, source));
+}
+
+fn write_artifact(output: &mut impl Write, name: &str, data: &[u8]) -> io::Result<()> {
+    writeln!(output, "{BEGIN_MARKER}{} {name}{MARKER_SUFFIX}", data.len())?;
+
+    let encoded = base64::encode(data);
+    for line in encoded.as_bytes().chunks(LINE_LENGTH) {
+        output.write_all(line)?;
+        output.write_all(b"\n")?;
+    }
+
+    writeln!(output, "{END_MARKER}{name}{MARKER_SUFFIX}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+        let mut output = b"test foo ... ok\n".to_vec();
+        write_artifact(&mut output, "data.bin", &data).unwrap();
+        write_artifact(&mut output, "empty.txt", &[]).unwrap();
+        // A truncated block is skipped.
+        output.extend_from_slice(b"==== ARTIFACT BEGIN 3 lost.txt ====\nYWJj\n");
+
+        let artifacts = decode(std::str::from_utf8(&output).unwrap());
+        assert_eq!(
+            artifacts,
+            [
+                ("data.bin".to_owned(), data),
+                ("empty.txt".to_owned(), Vec::new())
+            ]
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Lightweight benchmark harness, to measure the crate's fast paths on real hardware.
+//!
+//! Benchmarks are regular tests calling [`bench()`] (or [`Bencher::run()`]), so they run with any of the test runners
+//! and their results are printed through the same channel as the test output (GDB, socket or console).
+//! Timings are measured with the system tick counter of the ARM11, which is much more precise than [`std::time::Instant`].
+//!
+//! Every benchmark goes through three phases:
+//!
+//! 1. Warmup, to fill the caches and settle any lazy initialization.
+//! 2. Calibration, to find how many iterations make a sample long enough to be measured accurately.
+//! 3. Sampling. Samples outside the [Tukey fences](https://en.wikipedia.org/wiki/Outlier#Tukey's_fences)
+//!    (e.g. interrupted by a thread switch or a system event) are rejected before computing the statistics.
+//!
+//! Results are printed in the same format as `cargo bench`, so that they can be compared with the usual tools.
+//!
+//! # Example
+//!
+//! ```
+//! use std::hint::black_box;
+//!
+//! use test_runner::bench::bench;
+//!
+//! let _runner = test_runner::GdbRunner::default();
+//!
+//! let data = vec![1u32; 1024];
+//! let summary = bench("sum_1024", || black_box(&data).iter().sum::<u32>());
+//!
+//! assert!(summary.median_ns > 0.0);
+//! ```
+
+use std::fmt;
+use std::hint::black_box;
+
+// Frequency of the system tick counter (`SYSCLOCK_ARM11` in `libctru`).
+const TICKS_PER_SECOND: u64 = 268_111_856;
+
+/// Configuration of a benchmark.
+#[derive(Clone, Debug)]
+pub struct Bencher {
+    warmup_iterations: u64,
+    samples: usize,
+    sample_ticks: u64,
+}
+
+/// Statistics of a benchmark, in nanoseconds per iteration.
+#[derive(Clone, Debug)]
+pub struct Summary {
+    /// Name of the benchmark.
+    pub name: String,
+    /// Number of iterations measured by each sample.
+    pub iterations_per_sample: u64,
+    /// Number of samples rejected as outliers.
+    pub outliers: usize,
+    /// Fastest sample.
+    pub min_ns: f64,
+    /// Slowest sample (excluding outliers).
+    pub max_ns: f64,
+    /// Median of the samples.
+    pub median_ns: f64,
+    /// Mean of the samples (excluding outliers).
+    pub mean_ns: f64,
+    /// Median absolute deviation of the samples.
+    pub deviation_ns: f64,
+}
+
+/// Runs a benchmark with the default configuration and prints its results.
+///
+/// See [`Bencher::run()`] for more information.
+pub fn bench<T>(name: &str, routine: impl FnMut() -> T) -> Summary {
+    Bencher::new().run(name, routine)
+}
+
+impl Bencher {
+    /// Creates a configuration with 100 warmup iterations and 50 samples of at least 1 ms each.
+    pub fn new() -> Self {
+        Self {
+            warmup_iterations: 100,
+            samples: 50,
+            sample_ticks: TICKS_PER_SECOND / 1000,
+        }
+    }
+
+    /// Sets the number of iterations run before measuring.
+    pub fn warmup_iterations(mut self, iterations: u64) -> Self {
+        self.warmup_iterations = iterations;
+        self
+    }
+
+    /// Sets the number of samples measured.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `samples` is lower than 4, which isn't enough to reject outliers.
+    pub fn samples(mut self, samples: usize) -> Self {
+        assert!(samples >= 4, "at least 4 samples are needed");
+        self.samples = samples;
+        self
+    }
+
+    /// Sets the minimum duration of a sample (in microseconds).
+    ///
+    /// Longer samples are less sensitive to the overhead of the measurement, but make the benchmark slower.
+    pub fn sample_time_us(mut self, micros: u64) -> Self {
+        self.sample_ticks = (TICKS_PER_SECOND * micros / 1_000_000).max(1);
+        self
+    }
+
+    /// Measures `routine` and prints the results.
+    ///
+    /// The value returned by `routine` is passed to [`black_box()`], so that the computation isn't optimized out.
+    pub fn run<T>(&self, name: &str, mut routine: impl FnMut() -> T) -> Summary {
+        for _ in 0..self.warmup_iterations {
+            black_box(routine());
+        }
+
+        // Double the iterations until a sample is long enough.
+        let mut iterations = 1;
+        while measure(iterations, &mut routine) < self.sample_ticks && iterations < 1 << 30 {
+            iterations *= 2;
+        }
+
+        let mut samples: Vec<f64> = (0..self.samples)
+            .map(|_| ticks_to_ns(measure(iterations, &mut routine)) / iterations as f64)
+            .collect();
+
+        let summary = Summary::from_samples(name, iterations, &mut samples);
+        println!("{summary}");
+
+        summary
+    }
+}
+
+impl Default for Bencher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Summary {
+    fn from_samples(name: &str, iterations_per_sample: u64, samples: &mut [f64]) -> Self {
+        samples.sort_by(f64::total_cmp);
+
+        let q1 = percentile(samples, 0.25);
+        let q3 = percentile(samples, 0.75);
+        let fence = 1.5 * (q3 - q1);
+
+        let kept: Vec<f64> = samples
+            .iter()
+            .copied()
+            .filter(|&s| s >= q1 - fence && s <= q3 + fence)
+            .collect();
+
+        let median = percentile(&kept, 0.5);
+        let mut deviations: Vec<f64> = kept.iter().map(|s| (s - median).abs()).collect();
+        deviations.sort_by(f64::total_cmp);
+
+        Self {
+            name: name.to_owned(),
+            iterations_per_sample,
+            outliers: samples.len() - kept.len(),
+            min_ns: kept[0],
+            max_ns: kept[kept.len() - 1],
+            median_ns: median,
+            mean_ns: kept.iter().sum::<f64>() / kept.len() as f64,
+            deviation_ns: percentile(&deviations, 0.5),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "test {} ... bench: {:>12.0} ns/iter (+/- {:.0})",
+            self.name, self.median_ns, self.deviation_ns
+        )?;
+
+        if self.outliers > 0 {
+            write!(f, " [{} outliers rejected]", self.outliers)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn measure<T>(iterations: u64, routine: &mut impl FnMut() -> T) -> u64 {
+    let start = unsafe { ctru_sys::svcGetSystemTick() };
+
+    for _ in 0..iterations {
+        black_box(routine());
+    }
+
+    unsafe { ctru_sys::svcGetSystemTick() }.wrapping_sub(start)
+}
+
+fn ticks_to_ns(ticks: u64) -> f64 {
+    ticks as f64 * 1e9 / TICKS_PER_SECOND as f64
+}
+
+// Linear interpolation between the closest ranks of a sorted slice.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outlier_rejection() {
+        let mut samples = vec![10.0, 11.0, 9.0, 10.0, 12.0, 10.0, 8.0, 500.0];
+        let summary = Summary::from_samples("test", 1, &mut samples);
+
+        assert_eq!(summary.outliers, 1);
+        assert_eq!(summary.max_ns, 12.0);
+        assert_eq!(summary.median_ns, 10.0);
+        assert_eq!(summary.deviation_ns, 1.0);
+    }
+}
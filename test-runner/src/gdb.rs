@@ -1,6 +1,6 @@
 use std::process::Termination;
 
-use ctru::error::ResultCode;
+use ctru::stdio::GdbRedirection;
 
 use super::TestRunner;
 
@@ -34,28 +34,11 @@ use super::TestRunner;
 /// assert_eq!(2 + 2, 5);
 /// ```
 #[cfg_attr(not(doctest), doc = "````")]
-pub struct GdbRunner(());
+pub struct GdbRunner(GdbRedirection);
 
 impl Default for GdbRunner {
     fn default() -> Self {
-        || -> ctru::Result<()> {
-            // TODO: `ctru` expose safe API to do this and call that instead
-            unsafe {
-                ResultCode(ctru_sys::gdbHioDevInit())?;
-                // TODO: should we actually redirect stdin or nah?
-                ResultCode(ctru_sys::gdbHioDevRedirectStdStreams(true, true, true))?;
-            }
-            Ok(())
-        }()
-        .expect("failed to redirect I/O streams to GDB");
-
-        Self(())
-    }
-}
-
-impl Drop for GdbRunner {
-    fn drop(&mut self) {
-        unsafe { ctru_sys::gdbHioDevExit() }
+        Self(ctru::stdio::redirect_to_gdb().expect("failed to redirect I/O streams to GDB"))
     }
 }
 
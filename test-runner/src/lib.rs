@@ -11,6 +11,8 @@
 
 extern crate test;
 
+pub mod artifacts;
+pub mod bench;
 mod console;
 mod gdb;
 mod socket;
@@ -63,6 +65,9 @@ fn run<Runner: TestRunner>(tests: &[&TestDescAndFn]) {
     let tests = tests.iter().map(|t| make_owned_test(t)).collect();
     let result = test::run_tests_console(&opts, tests);
 
+    // The output is still redirected to the host at this point.
+    let _ = artifacts::transfer(&mut std::io::stdout());
+
     drop(ctx);
 
     let reportable_result = match result {